@@ -0,0 +1,106 @@
+//! Searching a large history: a single-threaded scan that parses every
+//! record against `Storage::search_commands`, and filtering on owned records
+//! against the borrowed ones `Query` filters on
+//!
+//! Run with `cargo bench -p shelltape-core`.
+
+use chrono::{Duration, TimeZone, Utc};
+use criterion::{Criterion, criterion_group, criterion_main};
+use shelltape_core::models::Command;
+use shelltape_core::query::Query;
+use shelltape_core::storage::Storage;
+use std::hint::black_box;
+use std::io::{BufRead, BufReader, Write};
+
+const COMMANDS: usize = 50_000;
+
+const PROGRAMS: [&str; 6] = [
+    "git status",
+    "cargo build --release",
+    "ls -la",
+    "docker compose up -d",
+    "kubectl get pods",
+    "vim src/main.rs",
+];
+
+fn history() -> (tempfile::TempDir, Storage) {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+    let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+
+    let mut file = std::fs::File::create(storage.commands_file()).unwrap();
+    for i in 0..COMMANDS {
+        let cmd = Command {
+            id: format!("{:08}", i),
+            command: format!("{} {}", PROGRAMS[i % PROGRAMS.len()], i),
+            output: "line of build output\n".repeat(20),
+            exit_code: (i % 7 == 0) as i32,
+            cwd: format!("/home/user/project-{}", i % 40),
+            started_at: start + Duration::seconds(i as i64),
+            duration_ms: 120,
+            session_id: format!("session-{}", i / 200),
+            shell: "zsh".to_string(),
+            hostname: "laptop".to_string(),
+            username: "user".to_string(),
+            timing: Vec::new(),
+            tags: Vec::new(),
+            note: None,
+            bookmarked: false,
+            rerun_of: None,
+        };
+        writeln!(file, "{}", serde_json::to_string(&cmd).unwrap()).unwrap();
+    }
+    (dir, storage)
+}
+
+/// The search as it was: parse every line, then filter
+fn sequential_search(storage: &Storage, query: &str, limit: usize) -> Vec<Command> {
+    let file = std::fs::File::open(storage.commands_file()).unwrap();
+    let mut results: Vec<Command> = BufReader::new(file)
+        .lines()
+        .map(|line| serde_json::from_str::<Command>(&line.unwrap()).unwrap())
+        .filter(|cmd| cmd.matches_query(query))
+        .collect();
+    results.sort_by_key(|c| std::cmp::Reverse(c.started_at));
+    results.truncate(limit);
+    results
+}
+
+fn bench_search(c: &mut Criterion) {
+    let (_dir, storage) = history();
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+
+    for query in ["kubectl", "no-such-command"] {
+        group.bench_function(format!("sequential/{}", query), |b| {
+            b.iter(|| sequential_search(&storage, black_box(query), 50))
+        });
+        group.bench_function(format!("parallel/{}", query), |b| {
+            b.iter(|| storage.search_commands(black_box(query), 50).unwrap())
+        });
+    }
+    group.bench_function("read_all", |b| {
+        b.iter(|| storage.read_all_commands().unwrap())
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("filter");
+    group.sample_size(10);
+    group.bench_function("owned/failed", |b| {
+        b.iter(|| {
+            storage
+                .read_all_commands()
+                .unwrap()
+                .into_iter()
+                .filter(|c| c.exit_code != 0)
+                .count()
+        })
+    });
+    group.bench_function("borrowed/failed", |b| {
+        b.iter(|| Query::new().failed().run(&storage).unwrap().len())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_search);
+criterion_main!(benches);
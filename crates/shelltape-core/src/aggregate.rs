@@ -0,0 +1,370 @@
+//! Running totals behind `shelltape stats` and `shelltape status`
+//!
+//! An [`Aggregate`] only holds tallies that can be added to one record at a
+//! time: counts per command, program and subcommand, exact duration
+//! distributions and activity per quarter hour. [`Stats`] is derived from it,
+//! so a whole history, a filtered slice or a cached aggregate extended with
+//! new records all give the same figures.
+//!
+//! `commands.stats` keeps the aggregate of the whole commands file together
+//! with how much of the file it has seen. Reading it folds in the records
+//! appended since, so the cost follows what is new rather than the size of
+//! the history. It is rebuilt when the file no longer fits, like the other
+//! caches next to the commands file.
+
+use crate::index::starts_line;
+use crate::mapped;
+use crate::models::{Command, DURATION_BUCKETS, DurationPercentiles, Stats};
+use crate::normalize;
+use crate::scan;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Seconds per activity bucket; every time zone offset is a multiple of it,
+/// so local hours and days can be derived exactly
+const BUCKET_SECS: i64 = 15 * 60;
+
+/// Bump when the cache layout changes, so old caches are rebuilt
+pub(crate) const CACHE_VERSION: u32 = 1;
+
+/// Tallies over a set of commands
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Aggregate {
+    pub total: usize,
+    pub failed: usize,
+    /// Sum of all durations, in ms
+    pub total_duration_ms: u64,
+    /// The slowest command, without its output and timing
+    pub longest: Option<Command>,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+    /// Runs and durations of each distinct command line
+    commands: HashMap<String, Tally>,
+    programs: HashMap<String, usize>,
+    subcommands: HashMap<String, usize>,
+    /// Commands and failures per quarter hour, keyed by its start in
+    /// quarter hours since the epoch
+    activity: BTreeMap<i64, (usize, usize)>,
+    /// Number of commands with each duration, in ms
+    durations: BTreeMap<u64, usize>,
+}
+
+/// How often a command line ran, and how long it took
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Tally {
+    count: usize,
+    durations: BTreeMap<u64, usize>,
+}
+
+impl Aggregate {
+    /// Tally `commands`
+    pub fn from_commands<'a>(commands: impl IntoIterator<Item = &'a Command>) -> Self {
+        let mut aggregate = Self::default();
+        for cmd in commands {
+            aggregate.add(cmd);
+        }
+        aggregate
+    }
+
+    /// Count one more command
+    pub fn add(&mut self, cmd: &Command) {
+        self.total += 1;
+        if cmd.exit_code != 0 {
+            self.failed += 1;
+        }
+        self.total_duration_ms += cmd.duration_ms;
+        if self
+            .longest
+            .as_ref()
+            .is_none_or(|l| cmd.duration_ms > l.duration_ms)
+        {
+            self.longest = Some(Command {
+                output: String::new(),
+                timing: Vec::new(),
+                ..cmd.clone()
+            });
+        }
+        self.oldest = Some(
+            self.oldest
+                .map_or(cmd.started_at, |t| t.min(cmd.started_at)),
+        );
+        self.newest = Some(
+            self.newest
+                .map_or(cmd.started_at, |t| t.max(cmd.started_at)),
+        );
+
+        let tally = self.commands.entry(cmd.command.clone()).or_default();
+        tally.count += 1;
+        *tally.durations.entry(cmd.duration_ms).or_insert(0) += 1;
+        if let Some(program) = normalize::program(&cmd.command) {
+            *self.programs.entry(program).or_insert(0) += 1;
+        }
+        if let Some(subcommand) = normalize::program_and_subcommand(&cmd.command) {
+            *self.subcommands.entry(subcommand).or_insert(0) += 1;
+        }
+
+        let bucket = cmd.started_at.timestamp().div_euclid(BUCKET_SECS);
+        let activity = self.activity.entry(bucket).or_insert((0, 0));
+        activity.0 += 1;
+        if cmd.exit_code != 0 {
+            activity.1 += 1;
+        }
+        *self.durations.entry(cmd.duration_ms).or_insert(0) += 1;
+    }
+
+    /// Runs of each distinct command line
+    pub fn command_counts(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.commands.iter().map(|(c, t)| (c.as_str(), t.count))
+    }
+
+    /// Statistics over the tallied commands
+    pub fn stats(&self, total_sessions: usize) -> Stats {
+        let success_rate = if self.total > 0 {
+            ((self.total - self.failed) as f64 / self.total as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        let most_used_commands = top_counts(self.command_counts());
+        let command_durations = most_used_commands
+            .iter()
+            .map(|(command, _)| {
+                (
+                    command.clone(),
+                    DurationPercentiles::from_counts(&self.commands[command].durations),
+                )
+            })
+            .collect();
+
+        // Activity in local time
+        let mut by_hour = [0; 24];
+        let mut by_weekday = [0; 7];
+        let mut daily = BTreeMap::new();
+        let mut daily_failed = BTreeMap::new();
+        for (&bucket, &(count, failed)) in &self.activity {
+            let Some(start) = Utc.timestamp_opt(bucket * BUCKET_SECS, 0).single() else {
+                continue;
+            };
+            let local = start.with_timezone(&Local);
+            by_hour[local.hour() as usize] += count;
+            by_weekday[local.weekday().num_days_from_monday() as usize] += count;
+            *daily.entry(local.date_naive()).or_insert(0) += count;
+            if failed > 0 {
+                *daily_failed.entry(local.date_naive()).or_insert(0) += failed;
+            }
+        }
+
+        let mut duration_histogram = vec![0; DURATION_BUCKETS.len()];
+        for (&ms, &count) in &self.durations {
+            let bucket = DURATION_BUCKETS
+                .iter()
+                .position(|&(max, _)| ms < max)
+                .unwrap_or(DURATION_BUCKETS.len() - 1);
+            duration_histogram[bucket] += count;
+        }
+
+        Stats {
+            total_commands: self.total,
+            total_sessions,
+            success_rate,
+            most_used_commands,
+            most_used_programs: top_counts(self.programs.iter().map(|(p, &n)| (p.as_str(), n))),
+            most_used_subcommands: top_counts(
+                self.subcommands.iter().map(|(s, &n)| (s.as_str(), n)),
+            ),
+            by_hour,
+            by_weekday,
+            durations: DurationPercentiles::from_counts(&self.durations),
+            command_durations,
+            duration_histogram,
+            daily,
+            daily_failed,
+        }
+    }
+}
+
+/// The ten most frequent values, most frequent first (ties alphabetically)
+fn top_counts<'a>(counts: impl Iterator<Item = (&'a str, usize)>) -> Vec<(String, usize)> {
+    let mut top: Vec<(&str, usize)> = counts.collect();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    top.truncate(10);
+    top.into_iter().map(|(v, n)| (v.to_string(), n)).collect()
+}
+
+/// The cached aggregate of a whole commands file
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    version: u32,
+    /// Bytes of the commands file tallied
+    covered: u64,
+    /// Lines in those bytes, for error messages about the ones after
+    lines: usize,
+    aggregate: Aggregate,
+}
+
+/// The cache file next to a commands file
+#[derive(Debug, Clone)]
+pub(crate) struct StatsCache {
+    path: PathBuf,
+}
+
+impl StatsCache {
+    pub fn for_file(commands_file: &Path) -> Self {
+        Self {
+            path: commands_file.with_extension("stats"),
+        }
+    }
+
+    /// The aggregate of every record in `commands_file`, caught up with
+    /// the records appended since it was last saved
+    pub fn load(&self, commands_file: &Path) -> Result<Aggregate> {
+        let file_len = match std::fs::metadata(commands_file) {
+            Ok(meta) => meta.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Aggregate::default());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let cached = match self.read() {
+            Some(cached)
+                if cached.covered <= file_len && starts_line(commands_file, cached.covered)? =>
+            {
+                cached
+            }
+            _ => {
+                tracing::debug!(path = %self.path.display(), "Stats cache is stale, rebuilding");
+                Cached {
+                    version: CACHE_VERSION,
+                    covered: 0,
+                    lines: 0,
+                    aggregate: Aggregate::default(),
+                }
+            }
+        };
+        if cached.covered == file_len {
+            return Ok(cached.aggregate);
+        }
+
+        let contents = mapped::open(commands_file)?.unwrap_or_else(mapped::empty);
+        let data = contents.get(cached.covered as usize..).unwrap_or_default();
+        // A line without its newline is still being written
+        let data = &data[..memchr::memrchr(b'\n', data).map_or(0, |i| i + 1)];
+
+        let new = scan::scan_at(
+            data,
+            cached.covered,
+            cached.lines + 1,
+            None,
+            &|mut cmd, _| {
+                cmd.output = Cow::Borrowed("");
+                Some(cmd.into_owned())
+            },
+        )?;
+        let mut cached = cached;
+        for cmd in &new {
+            cached.aggregate.add(cmd);
+        }
+        cached.covered += data.len() as u64;
+        cached.lines += memchr::memchr_iter(b'\n', data).count();
+        tracing::debug!(count = new.len(), "Folded new commands into stats cache");
+
+        if let Err(e) = self.write(&cached) {
+            tracing::warn!("Failed to save stats cache: {:#}", e);
+        }
+        Ok(cached.aggregate)
+    }
+
+    /// Drop the cache; the next read rebuilds it
+    pub fn invalidate(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn read(&self) -> Option<Cached> {
+        let data = std::fs::read(&self.path).ok()?;
+        serde_json::from_slice::<Cached>(&data)
+            .ok()
+            .filter(|c| c.version == CACHE_VERSION)
+    }
+
+    fn write(&self, cached: &Cached) -> Result<()> {
+        let tmp = self.path.with_extension("stats.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(cached)?)
+            .with_context(|| format!("Failed to write stats cache: {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use tempfile::TempDir;
+
+    fn cmd(id: usize, command: &str, exit_code: i32, duration_ms: u64) -> Command {
+        Command {
+            id: id.to_string(),
+            command: command.to_string(),
+            output: "output".to_string(),
+            exit_code,
+            cwd: "/tmp".to_string(),
+            started_at: Utc
+                .timestamp_opt(1_760_000_000 + id as i64 * 600, 0)
+                .unwrap(),
+            duration_ms,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cache_catches_up() {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        assert_eq!(storage.aggregate().unwrap().total, 0);
+
+        let commands: Vec<_> = (0..6)
+            .map(|i| {
+                cmd(
+                    i,
+                    ["ls", "cargo test"][i % 2],
+                    (i % 3) as i32,
+                    i as u64 * 10,
+                )
+            })
+            .collect();
+        storage.append_commands(&commands[..4]).unwrap();
+        assert_eq!(storage.aggregate().unwrap().total, 4);
+
+        // Folded into the saved aggregate, and the same as counting afresh
+        storage.append_commands(&commands[4..]).unwrap();
+        let cached = storage.aggregate().unwrap();
+        let fresh = Aggregate::from_commands(&commands);
+        assert_eq!(cached.total, 6);
+        assert_eq!(cached.longest.as_ref().unwrap().id, "5");
+        assert!(cached.longest.as_ref().unwrap().output.is_empty());
+        assert_eq!(cached.oldest, fresh.oldest);
+        let (cached, fresh) = (cached.stats(1), fresh.stats(1));
+        assert_eq!(cached.most_used_commands, fresh.most_used_commands);
+        assert_eq!(cached.command_durations, fresh.command_durations);
+        assert_eq!(cached.daily_failed, fresh.daily_failed);
+        assert_eq!(cached.by_hour, fresh.by_hour);
+
+        // Rewrites start over
+        storage.update_command("1", |c| c.exit_code = 0).unwrap();
+        storage.append_command(&cmd(9, "make", 0, 5)).unwrap();
+        let rebuilt = storage.aggregate().unwrap();
+        assert_eq!((rebuilt.total, rebuilt.failed), (7, 3));
+    }
+}
@@ -0,0 +1,98 @@
+//! Where the commands are kept
+//!
+//! [`Storage`](crate::storage::Storage) handles sessions, running markers
+//! and the data directory itself, and hands everything about commands to a
+//! [`StorageBackend`]: the JSONL file by default ([`crate::jsonl`]), or an
+//! SQLite database with the `sqlite` feature ([`crate::sqlite`]).
+
+use crate::aggregate::Aggregate;
+use crate::models::{Command, CommandView};
+use crate::storage::{CommandPages, RecordPos};
+use anyhow::Result;
+use std::collections::HashSet;
+
+/// Operations on the commands of one history
+///
+/// Order is the same for every backend: "newest first" sorts by start
+/// time, and ties keep the order the commands were added in.
+pub trait StorageBackend: Send + Sync {
+    /// Add commands in one write
+    fn append_commands(&self, cmds: &[Command]) -> Result<()>;
+
+    /// The commands `keep` accepts, in the order they were added
+    ///
+    /// With `text`, records that cannot contain it (case-insensitively) may
+    /// be skipped without calling `keep`; `keep` still has to check for it.
+    fn scan_commands(
+        &self,
+        text: Option<&str>,
+        keep: &(dyn Fn(&CommandView) -> bool + Sync),
+    ) -> Result<Vec<Command>>;
+
+    /// Every command without its output and timing, with where it is kept
+    fn read_command_metadata(&self) -> Result<Vec<(Command, RecordPos)>>;
+
+    /// IDs of the commands whose line, directory or output contains `query`
+    /// (case-insensitive)
+    fn search_command_ids(&self, query: &str) -> Result<HashSet<String>>;
+
+    /// The full command at `pos`, as given by
+    /// [`read_command_metadata`](Self::read_command_metadata) or pages
+    fn read_command_at(&self, pos: RecordPos) -> Result<Command>;
+
+    /// Up to `limit` of the newest commands that `keep` accepts
+    fn recent_commands(
+        &self,
+        limit: usize,
+        keep: &mut dyn FnMut(&Command) -> bool,
+    ) -> Result<Vec<Command>>;
+
+    /// Page through the commands, newest first
+    fn command_pages(&self) -> Result<CommandPages>;
+
+    /// Every command whose ID starts with `prefix`
+    fn commands_with_id_prefix(&self, prefix: &str) -> Result<Vec<Command>>;
+
+    /// Apply `update` to the command with this ID or unique ID prefix,
+    /// returning the updated command
+    fn update_command(
+        &self,
+        id: &str,
+        update: Box<dyn FnOnce(&mut Command) + '_>,
+    ) -> Result<Command>;
+
+    /// Add the commands whose IDs are not present yet, returning how many
+    fn merge_commands(&self, incoming: Vec<Command>) -> Result<usize>;
+
+    /// Keep only the commands `keep` accepts, returning how many were removed
+    fn retain_commands(&self, keep: &mut dyn FnMut(&Command) -> bool) -> Result<usize>;
+
+    /// Remove the commands with these IDs, returning how many were removed
+    fn delete_commands(&self, ids: &HashSet<String>) -> Result<usize>;
+
+    /// A cursor at the end of the history, for
+    /// [`commands_since`](Self::commands_since)
+    fn end_cursor(&self) -> Result<u64>;
+
+    /// Commands added after `cursor` with their positions, in the order they
+    /// were added, and the cursor to continue from
+    ///
+    /// Cursors are opaque: a file length for JSONL, a row for SQLite. One
+    /// left pointing past the end by a rewrite starts over from the top.
+    fn commands_since(&self, cursor: u64) -> Result<(Vec<(Command, RecordPos)>, u64)>;
+
+    /// Flush appended commands to disk, so they survive a crash
+    fn sync(&self) -> Result<()>;
+
+    /// Tallies over every command
+    fn aggregate(&self) -> Result<Aggregate>;
+}
+
+/// Source of [`CommandPages`]
+pub trait Pager {
+    /// Up to `limit` more commands, without their output and timing
+    fn next_page(&mut self, limit: usize) -> Result<Vec<(Command, RecordPos)>>;
+
+    /// Whether every command has been handed out
+    fn is_done(&self) -> bool;
+}
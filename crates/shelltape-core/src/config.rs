@@ -0,0 +1,626 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// User configuration loaded from `config.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Settings used when recording commands
+    pub recording: RecordingConfig,
+    /// Settings for the storage layer
+    pub storage: StorageConfig,
+    /// Settings for the list and TUI views
+    pub ui: UiConfig,
+    /// Settings for diagnostic logging
+    pub logging: LoggingConfig,
+    /// Settings for the `serve` HTTP API
+    pub serve: ServeConfig,
+    /// Forwarding of recorded commands to syslog or the journal
+    pub forward: ForwardConfig,
+    /// Export of recorded commands as OpenTelemetry spans
+    pub otlp: OtlpConfig,
+    /// Notifications when long-running commands finish
+    pub notify: NotifyConfig,
+    /// Settings for `sync`
+    pub sync: SyncConfig,
+    /// Extra command categories: name to programs or `program subcommand` patterns
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub categories: BTreeMap<String, Vec<String>>,
+    /// Webhooks fired after matching commands are recorded
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookConfig>,
+    /// External programs run after each command is recorded
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<PluginConfig>,
+}
+
+/// Settings used when recording commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingConfig {
+    /// Maximum output size in bytes before truncation
+    pub max_output_size: usize,
+    /// Commands starting with any of these prefixes are not recorded
+    pub exclude_patterns: Vec<String>,
+    /// Mask secrets (keys, tokens, passwords) in commands and output
+    pub redact: bool,
+    /// Extra regexes to mask, besides the built-in ones; only the first
+    /// capture group is masked when there is one
+    pub redact_patterns: Vec<String>,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            max_output_size: 100_000,
+            exclude_patterns: Vec::new(),
+            redact: true,
+            redact_patterns: Vec::new(),
+        }
+    }
+}
+
+/// Settings for the storage layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Data directory (defaults to ~/.shelltape/)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<PathBuf>,
+    /// Default age in days used by `shelltape clean`
+    pub retention_days: u64,
+    /// `jsonl` or `sqlite`; change it with `shelltape migrate`
+    pub backend: Backend,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: None,
+            retention_days: 90,
+            backend: Backend::Jsonl,
+        }
+    }
+}
+
+/// Where recorded commands are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// One JSON record per line in `commands.jsonl`
+    #[default]
+    Jsonl,
+    /// An SQLite database, `commands.db`
+    Sqlite,
+}
+
+/// Settings for the list and TUI views
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Default number of commands shown by `shelltape list`
+    pub default_limit: usize,
+    /// Highlight color for the selected row in the TUI
+    pub highlight_color: String,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: 20,
+            highlight_color: "darkgray".to_string(),
+        }
+    }
+}
+
+/// Settings for diagnostic logging
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Also write logs to a daily rotating file in `<data_dir>/logs/`
+    pub file: bool,
+    /// Number of rotated log files to keep
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file: false,
+            max_files: 7,
+        }
+    }
+}
+
+/// Settings for the `serve` HTTP API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServeConfig {
+    /// Address to listen on
+    pub listen: String,
+    /// Bearer token clients must send; a random one is generated when empty
+    pub token: String,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self {
+            listen: "127.0.0.1:7070".to_string(),
+            token: String::new(),
+        }
+    }
+}
+
+/// Where recorded commands are forwarded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardTarget {
+    #[default]
+    Off,
+    Syslog,
+    Journald,
+}
+
+/// Forwarding of recorded commands to syslog or the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForwardConfig {
+    /// `off`, `syslog` or `journald`
+    pub target: ForwardTarget,
+    /// Program name the messages are logged under
+    pub identifier: String,
+    /// Also send the captured output
+    pub include_output: bool,
+}
+
+impl Default for ForwardConfig {
+    fn default() -> Self {
+        Self {
+            target: ForwardTarget::Off,
+            identifier: "shelltape".to_string(),
+            include_output: false,
+        }
+    }
+}
+
+/// Notifications when commands finish
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Show a desktop notification when a long command finishes
+    pub desktop: bool,
+    /// Shortest run, in seconds, that triggers a desktop notification
+    pub min_duration_secs: u64,
+    /// Push notifications to ntfy or Slack for matching commands
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub push: Vec<PushConfig>,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            desktop: false,
+            min_duration_secs: 60,
+            push: Vec::new(),
+        }
+    }
+}
+
+/// Settings for `sync`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// Target used when `sync` is run without one (directory,
+    /// `[user@]host:path`, or URL)
+    pub target: String,
+}
+
+/// Services push notifications can be sent to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushService {
+    #[default]
+    Ntfy,
+    Slack,
+}
+
+/// A push notification sent when a matching command is recorded
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PushConfig {
+    /// `ntfy` or `slack`
+    pub service: PushService,
+    /// ntfy topic URL (e.g. `https://ntfy.sh/my-topic`) or Slack incoming webhook URL
+    pub url: String,
+    /// ntfy access token, for protected topics
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub token: String,
+    #[serde(flatten)]
+    pub filter: CommandFilter,
+}
+
+/// Export of recorded commands as OpenTelemetry spans
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OtlpConfig {
+    /// OTLP/HTTP collector to send each command to as it is recorded
+    /// (e.g. `http://localhost:4318`); off when empty
+    pub endpoint: String,
+    /// Extra request headers, e.g. `Authorization: Bearer ...`
+    pub headers: Vec<String>,
+    /// `service.name` of the exported spans
+    pub service_name: String,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            headers: Vec::new(),
+            service_name: "shelltape".to_string(),
+        }
+    }
+}
+
+/// Which recorded commands a webhook or notifier fires for
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandFilter {
+    /// Only fire for commands that exited with a non-zero code
+    pub failed_only: bool,
+    /// Only fire for commands run in this directory or below (`~` is expanded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    /// Only fire for command lines matching this regular expression
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+/// A webhook fired after a matching command is recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// URL the payload is POSTed to
+    pub url: String,
+    #[serde(flatten)]
+    pub filter: CommandFilter,
+    /// Payload with `{{field}}` placeholders; the whole command as JSON when empty
+    pub template: String,
+    /// Extra request headers, e.g. `Authorization: Bearer ...`
+    pub headers: Vec<String>,
+    /// Attempts after a failed delivery, with exponential backoff
+    pub retries: u32,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            filter: CommandFilter::default(),
+            template: String::new(),
+            headers: Vec::new(),
+            retries: 3,
+        }
+    }
+}
+
+/// An external program run with each recorded command as JSON on stdin
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginConfig {
+    /// Name used in log messages
+    pub name: String,
+    /// Program to run (`~/` is expanded)
+    pub command: String,
+    /// Arguments passed to the program
+    pub args: Vec<String>,
+}
+
+impl Config {
+    /// Get the config file path
+    ///
+    /// This is `$XDG_CONFIG_HOME/shelltape/config.toml`, where `config init`
+    /// creates it. A file at the older `~/.shelltape/config.toml` is still
+    /// used when there is none at the XDG path.
+    pub fn path() -> Result<PathBuf> {
+        let legacy = dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".shelltape")
+            .join("config.toml");
+        let Some(config_dir) = dirs::config_dir() else {
+            return Ok(legacy);
+        };
+
+        let xdg_path = config_dir.join("shelltape").join("config.toml");
+        if !xdg_path.exists() && legacy.exists() {
+            return Ok(legacy);
+        }
+        Ok(xdg_path)
+    }
+
+    /// Load the configuration, falling back to defaults if no file exists
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::path()?)
+    }
+
+    /// Load the configuration from a specific file
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))
+    }
+
+    /// Save the configuration to a specific file
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let content =
+            toml::to_string_pretty(self).with_context(|| "Failed to serialize config to TOML")?;
+
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write config file: {}", path.display()))
+    }
+
+    /// Set a value by its dotted key in the file at `path`, like [`Config::set`]
+    ///
+    /// Only that value is rewritten; the rest of the file, comments
+    /// included, is kept as it is. A missing file is created with the
+    /// defaults. Returns the updated configuration.
+    pub fn set_in_file(path: &Path, key: &str, raw: &str) -> Result<Self> {
+        let mut config = Self::load_from(path)?;
+        config.set(key, raw)?;
+
+        let content = if path.exists() {
+            fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config file: {}", path.display()))?
+        } else {
+            toml::to_string_pretty(&Self::default())?
+        };
+        let mut doc: toml_edit::DocumentMut = content
+            .parse()
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+
+        // `set` checked that the key has this form
+        let (section, field) = key.split_once('.').unwrap_or((key, ""));
+        let value: toml_edit::Value = config.get(key)?.to_string().parse()?;
+        if !doc.contains_key(section) {
+            doc.insert(section, toml_edit::table());
+        }
+        let item = &mut doc[section][field];
+        match item.as_value_mut() {
+            // Keep the spacing and any comment after the old value
+            Some(old) => {
+                let decor = old.decor().clone();
+                *old = value;
+                *old.decor_mut() = decor;
+            }
+            None => *item = toml_edit::value(value),
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        fs::write(path, doc.to_string())
+            .with_context(|| format!("Failed to write config file: {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Resolve the data directory, expanding a leading `~`
+    pub fn data_dir(&self) -> Result<PathBuf> {
+        let home = || dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"));
+
+        match &self.storage.data_dir {
+            Some(dir) => match dir.strip_prefix("~") {
+                Ok(rest) => Ok(home()?.join(rest)),
+                Err(_) => Ok(dir.clone()),
+            },
+            None => Ok(home()?.join(".shelltape")),
+        }
+    }
+
+    /// Get a value by its dotted key (e.g. `recording.max_output_size`)
+    pub fn get(&self, key: &str) -> Result<toml::Value> {
+        let mut value = toml::Value::try_from(self)?;
+
+        for part in key.split('.') {
+            value = value
+                .get(part)
+                .cloned()
+                .ok_or_else(|| anyhow!("Unknown config key: {}", key))?;
+        }
+
+        Ok(value)
+    }
+
+    /// Set a value by its dotted key, parsing `raw` as a TOML value when possible
+    pub fn set(&mut self, key: &str, raw: &str) -> Result<()> {
+        let (section, field) = key
+            .split_once('.')
+            .ok_or_else(|| anyhow!("Config keys have the form <section>.<field>: {}", key))?;
+
+        let new_value = parse_value(raw);
+
+        let mut root = toml::Value::try_from(&*self)?;
+        let table = root
+            .get_mut(section)
+            .and_then(|v| v.as_table_mut())
+            .ok_or_else(|| anyhow!("Unknown config section: {}", section))?;
+
+        // Optional keys are omitted from the serialized table when unset
+        let known = table.contains_key(field) || key == "storage.data_dir";
+        if !known {
+            bail!("Unknown config key: {}", key);
+        }
+        table.insert(field.to_string(), new_value);
+
+        *self = root
+            .try_into()
+            .with_context(|| format!("Invalid value for {}: {}", key, raw))?;
+
+        Ok(())
+    }
+
+    /// Flatten the configuration into `(key, value)` pairs
+    pub fn entries(&self) -> Result<Vec<(String, toml::Value)>> {
+        let root = toml::Value::try_from(self)?;
+        let mut entries = Vec::new();
+
+        if let Some(sections) = root.as_table() {
+            for (section, fields) in sections {
+                if let Some(fields) = fields.as_table() {
+                    for (field, value) in fields {
+                        entries.push((format!("{}.{}", section, field), value.clone()));
+                    }
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Expand a leading `~` in a configured path to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Parse a raw CLI value as TOML, treating anything unparsable as a string
+fn parse_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("v = {}", raw))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let dir = tempdir().unwrap();
+        let config = Config::load_from(&dir.path().join("config.toml")).unwrap();
+
+        assert_eq!(config.recording.max_output_size, 100_000);
+        assert_eq!(config.storage.retention_days, 90);
+        assert!(config.storage.data_dir.is_none());
+    }
+
+    #[test]
+    fn test_set_get_and_save() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let mut config = Config::default();
+        config.set("recording.max_output_size", "500").unwrap();
+        config
+            .set("recording.exclude_patterns", r#"["ls", "cd"]"#)
+            .unwrap();
+        config.set("storage.data_dir", "/tmp/shelltape").unwrap();
+        config.save_to(&path).unwrap();
+
+        let loaded = Config::load_from(&path).unwrap();
+        assert_eq!(loaded.recording.max_output_size, 500);
+        assert_eq!(loaded.recording.exclude_patterns, vec!["ls", "cd"]);
+        assert_eq!(
+            loaded.get("storage.data_dir").unwrap().as_str(),
+            Some("/tmp/shelltape")
+        );
+    }
+
+    #[test]
+    fn test_set_in_file_keeps_comments() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "# My settings\n[recording]\n# Keep outputs small\nmax_output_size = 1000 # bytes\n",
+        )
+        .unwrap();
+
+        let config = Config::set_in_file(&path, "recording.max_output_size", "500").unwrap();
+        assert_eq!(config.recording.max_output_size, 500);
+        Config::set_in_file(&path, "storage.retention_days", "30").unwrap();
+        assert!(Config::set_in_file(&path, "recording.nope", "1").is_err());
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "# My settings\n[recording]\n# Keep outputs small\nmax_output_size = 500 # bytes\n\n[storage]\nretention_days = 30\n"
+        );
+        let loaded = Config::load_from(&path).unwrap();
+        assert_eq!(loaded.storage.retention_days, 30);
+        assert_eq!(loaded.recording.max_output_size, 500);
+
+        // A new file starts from the defaults
+        let fresh = dir.path().join("new").join("config.toml");
+        Config::set_in_file(&fresh, "storage.data_dir", "/tmp/shelltape").unwrap();
+        let loaded = Config::load_from(&fresh).unwrap();
+        assert_eq!(
+            loaded.storage.data_dir,
+            Some(PathBuf::from("/tmp/shelltape"))
+        );
+        assert_eq!(loaded.storage.retention_days, 90);
+    }
+
+    #[test]
+    fn test_filters_in_lists() {
+        let config: Config = toml::from_str(
+            r#"
+            [[webhooks]]
+            url = "http://localhost/hook"
+            failed_only = true
+
+            [[notify.push]]
+            service = "slack"
+            url = "https://hooks.slack.com/services/x"
+            dir = "~/deployments"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.webhooks[0].filter.failed_only);
+        assert_eq!(config.webhooks[0].retries, 3);
+        assert_eq!(config.notify.push[0].service, PushService::Slack);
+        assert_eq!(
+            config.notify.push[0].filter.dir.as_deref(),
+            Some("~/deployments")
+        );
+        assert!(!config.notify.desktop);
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("~/bin/hook"), home.join("bin/hook"));
+        assert_eq!(expand_home("~other/x"), PathBuf::from("~other/x"));
+        assert_eq!(expand_home("/usr/bin/jq"), PathBuf::from("/usr/bin/jq"));
+    }
+
+    #[test]
+    fn test_set_rejects_invalid() {
+        let mut config = Config::default();
+        assert!(config.set("recording.nope", "1").is_err());
+        assert!(config.set("recording.max_output_size", "lots").is_err());
+    }
+}
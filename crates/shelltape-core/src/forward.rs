@@ -0,0 +1,191 @@
+use crate::config::{ForwardConfig, ForwardTarget};
+use crate::models::Command;
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde_json::json;
+use std::path::Path;
+
+/// Socket of the local syslog daemon
+const SYSLOG_SOCKET: &str = if cfg!(target_os = "macos") {
+    "/var/run/syslog"
+} else {
+    "/dev/log"
+};
+
+/// Socket of the systemd journal's native protocol
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// The `user` syslog facility
+const FACILITY_USER: u8 = 1;
+const SEVERITY_WARNING: u8 = 4;
+const SEVERITY_INFO: u8 = 6;
+
+/// Forward a summary of a freshly recorded command to syslog or the journal
+///
+/// Failures are logged, not returned, so recording never depends on the
+/// log daemon being up.
+pub fn forward(config: &ForwardConfig, cmd: &Command) {
+    let result = match config.target {
+        ForwardTarget::Off => return,
+        ForwardTarget::Syslog => send(
+            Path::new(SYSLOG_SOCKET),
+            syslog_message(config, cmd).as_bytes(),
+        ),
+        ForwardTarget::Journald => send(Path::new(JOURNAL_SOCKET), &journal_message(config, cmd)),
+    };
+    if let Err(e) = result {
+        tracing::warn!(target = ?config.target, "Failed to forward command: {:#}", e);
+    }
+}
+
+/// Failed commands are logged as warnings, the rest as info
+fn severity(cmd: &Command) -> u8 {
+    if cmd.exit_code == 0 {
+        SEVERITY_INFO
+    } else {
+        SEVERITY_WARNING
+    }
+}
+
+/// One-line summary used as the log message
+fn summary(config: &ForwardConfig, cmd: &Command) -> String {
+    let mut summary = json!({
+        "id": cmd.id,
+        "command": cmd.command,
+        "exit_code": cmd.exit_code,
+        "cwd": cmd.cwd,
+        "started_at": cmd.started_at,
+        "duration_ms": cmd.duration_ms,
+        "session_id": cmd.session_id,
+        "user": cmd.username,
+        "host": cmd.hostname,
+    });
+    if config.include_output {
+        summary["output"] = json!(cmd.output);
+    }
+    summary.to_string()
+}
+
+/// An RFC 3164 message for the local syslog socket
+fn syslog_message(config: &ForwardConfig, cmd: &Command) -> String {
+    format!(
+        "<{}>{} {}[{}]: {}",
+        FACILITY_USER * 8 + severity(cmd),
+        Local::now().format("%b %e %H:%M:%S"),
+        config.identifier,
+        std::process::id(),
+        summary(config, cmd)
+    )
+}
+
+/// A datagram in the journal's native protocol, with one field per attribute
+fn journal_message(config: &ForwardConfig, cmd: &Command) -> Vec<u8> {
+    let mut fields = vec![
+        ("MESSAGE", summary(config, cmd)),
+        ("PRIORITY", severity(cmd).to_string()),
+        ("SYSLOG_FACILITY", FACILITY_USER.to_string()),
+        ("SYSLOG_IDENTIFIER", config.identifier.clone()),
+        ("SHELLTAPE_ID", cmd.id.clone()),
+        ("SHELLTAPE_COMMAND", cmd.command.clone()),
+        ("SHELLTAPE_EXIT_CODE", cmd.exit_code.to_string()),
+        ("SHELLTAPE_CWD", cmd.cwd.clone()),
+        ("SHELLTAPE_DURATION_MS", cmd.duration_ms.to_string()),
+        ("SHELLTAPE_SESSION_ID", cmd.session_id.clone()),
+    ];
+    if config.include_output {
+        fields.push(("SHELLTAPE_OUTPUT", cmd.output.clone()));
+    }
+
+    let mut message = Vec::new();
+    for (name, value) in fields {
+        message.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            // Multi-line values are sent as a little-endian length and raw bytes
+            message.push(b'\n');
+            message.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            message.push(b'=');
+        }
+        message.extend_from_slice(value.as_bytes());
+        message.push(b'\n');
+    }
+    message
+}
+
+#[cfg(unix)]
+fn send(socket: &Path, message: &[u8]) -> Result<()> {
+    let sock = std::os::unix::net::UnixDatagram::unbound()?;
+    sock.send_to(message, socket)
+        .with_context(|| format!("Failed to write to: {}", socket.display()))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_socket: &Path, _message: &[u8]) -> Result<()> {
+    anyhow::bail!("Forwarding to syslog or the journal is only supported on Unix")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn cmd(exit_code: i32, output: &str) -> Command {
+        Command {
+            id: "abc".to_string(),
+            command: "rm -rf build".to_string(),
+            output: output.to_string(),
+            exit_code,
+            cwd: "/src".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 42,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_syslog_message() {
+        let config = ForwardConfig::default();
+        let message = syslog_message(&config, &cmd(1, "secret"));
+
+        assert!(message.starts_with("<12>"));
+        assert!(message.contains(&format!(" shelltape[{}]: {{", std::process::id())));
+        assert!(message.contains(r#""command":"rm -rf build""#));
+        assert!(!message.contains("secret"));
+    }
+
+    #[test]
+    fn test_journal_message() {
+        let config = ForwardConfig {
+            include_output: true,
+            ..ForwardConfig::default()
+        };
+        let message = journal_message(&config, &cmd(0, "a\nb"));
+        let text = String::from_utf8_lossy(&message);
+
+        assert!(text.contains("\nPRIORITY=6\n"));
+        assert!(text.contains("\nSHELLTAPE_COMMAND=rm -rf build\n"));
+
+        let mut expected = b"SHELLTAPE_OUTPUT\n".to_vec();
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        expected.extend_from_slice(b"a\nb\n");
+        assert!(message.ends_with(&expected));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_send() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("log.sock");
+        let server = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        send(&path, b"hello").unwrap();
+        let mut buf = [0; 16];
+        let n = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+    }
+}
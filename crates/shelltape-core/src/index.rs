@@ -0,0 +1,359 @@
+//! Sidecar index of where each command sits in `commands.jsonl`
+//!
+//! `commands.idx` holds a short header, then one fixed-size entry per
+//! record: its byte offset, length, start time and the latest start time
+//! of it and every record before it. That last field lets reads that want
+//! the newest commands walk the file backwards and stop as soon as nothing
+//! earlier can be newer (see [`crate::recent`]).
+//!
+//! The index is only ever a cache. It is extended on append, caught up when
+//! the commands file grew behind its back (older versions, concurrent
+//! writers) and rebuilt from scratch when it no longer fits the file.
+
+use crate::mapped;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Marks the current layout; files without it are rebuilt
+const MAGIC: &[u8; HEADER_SIZE] = b"stidx\0\0\x02";
+const HEADER_SIZE: usize = 8;
+
+/// Bytes per entry: offset, length, start time and latest start time,
+/// little-endian
+const ENTRY_SIZE: usize = 32;
+
+/// Where one record sits in the commands file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Entry {
+    pub offset: u64,
+    /// Length of the line, including its newline
+    pub len: u64,
+    /// Start time in nanoseconds since the epoch
+    pub started_at: i64,
+    /// Latest start time of this record and every one before it
+    pub latest: i64,
+}
+
+impl Entry {
+    fn end(&self) -> u64 {
+        self.offset.saturating_add(self.len)
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[..8].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.len.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.started_at.to_le_bytes());
+        bytes[24..].copy_from_slice(&self.latest.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let field = |i: usize| bytes[i..i + 8].try_into().unwrap();
+        Self {
+            offset: u64::from_le_bytes(field(0)),
+            len: u64::from_le_bytes(field(8)),
+            started_at: i64::from_le_bytes(field(16)),
+            latest: i64::from_le_bytes(field(24)),
+        }
+    }
+}
+
+/// Only the field the index needs; the rest of the record is skipped
+#[derive(Deserialize)]
+struct Stamp {
+    started_at: DateTime<Utc>,
+}
+
+/// How the index file ends
+enum Last {
+    /// No entries yet
+    Empty,
+    Entry(Entry),
+    /// Torn or in another layout: rebuilt on the next read
+    Unusable,
+}
+
+/// The index file next to a commands file
+#[derive(Debug, Clone)]
+pub(crate) struct CommandIndex {
+    path: PathBuf,
+}
+
+impl CommandIndex {
+    pub fn for_file(commands_file: &Path) -> Self {
+        Self {
+            path: commands_file.with_extension("idx"),
+        }
+    }
+
+    /// Record a line just appended at `offset`
+    ///
+    /// Skipped unless the index ends exactly where the line starts; the next
+    /// read then catches up instead.
+    pub fn append(&self, offset: u64, line: &str) -> Result<()> {
+        let latest = match self.last()? {
+            Last::Empty if offset == 0 => i64::MIN,
+            Last::Entry(last) if last.end() == offset => last.latest,
+            _ => return Ok(()),
+        };
+        let started_at = stamp(line.trim_end())?;
+        let entry = Entry {
+            offset,
+            len: line.len() as u64,
+            started_at,
+            latest: latest.max(started_at),
+        };
+        self.write_entries(&[entry], true)
+    }
+
+    /// Bring the index up to date with `commands_file` and map it
+    ///
+    /// Only where the index ends is checked, so this costs nothing per
+    /// record when the index is already current.
+    pub fn open(&self, commands_file: &Path) -> Result<Bounds> {
+        let file_len = match std::fs::metadata(commands_file) {
+            Ok(meta) => meta.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+        let (covered, latest) = match self.last()? {
+            Last::Empty => (0, i64::MIN),
+            Last::Entry(last) => (last.end(), last.latest),
+            Last::Unusable => (u64::MAX, i64::MIN),
+        };
+        if covered > file_len || !starts_line(commands_file, covered)? {
+            tracing::debug!(path = %self.path.display(), "Index is stale, rebuilding");
+            self.rebuild(commands_file)?;
+        } else if covered < file_len {
+            let new = scan(commands_file, covered, latest)?;
+            if !new.is_empty() {
+                tracing::debug!(count = new.len(), "Catching up index");
+                self.write_entries(&new, true)?;
+            }
+        }
+
+        let data = mapped::open(&self.path)?.unwrap_or_else(mapped::empty);
+        Ok(Bounds { data })
+    }
+
+    /// Index `commands_file` from scratch
+    fn rebuild(&self, commands_file: &Path) -> Result<()> {
+        let entries = if commands_file.exists() {
+            scan(commands_file, 0, i64::MIN)?
+        } else {
+            Vec::new()
+        };
+        self.write_entries(&entries, false)
+    }
+
+    /// Drop the index; the next read rebuilds it
+    pub fn invalidate(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// The final entry, which says how much of the commands file is indexed
+    fn last(&self) -> Result<Last> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Last::Empty),
+            Err(e) => return Err(e.into()),
+        };
+        let len = file.metadata()?.len();
+        if len == 0 {
+            return Ok(Last::Empty);
+        }
+        let mut header = [0; HEADER_SIZE];
+        if len < HEADER_SIZE as u64
+            || !(len - HEADER_SIZE as u64).is_multiple_of(ENTRY_SIZE as u64)
+            || file.read_exact(&mut header).is_err()
+            || &header != MAGIC
+        {
+            return Ok(Last::Unusable);
+        }
+        if len == HEADER_SIZE as u64 {
+            return Ok(Last::Empty);
+        }
+        let mut last = [0; ENTRY_SIZE];
+        file.seek(SeekFrom::Start(len - ENTRY_SIZE as u64))?;
+        file.read_exact(&mut last)?;
+        Ok(Last::Entry(Entry::from_bytes(&last)))
+    }
+
+    fn write_entries(&self, entries: &[Entry], append: bool) -> Result<()> {
+        let mut bytes: Vec<u8> = entries.iter().flat_map(|e| e.to_bytes()).collect();
+        if append {
+            // Cut a torn final entry first so the new ones stay aligned
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .with_context(|| format!("Failed to open index: {}", self.path.display()))?;
+            let len = file.metadata()?.len();
+            if len < HEADER_SIZE as u64 {
+                file.set_len(0)?;
+                bytes.splice(0..0, *MAGIC);
+            } else {
+                file.set_len(len - (len - HEADER_SIZE as u64) % ENTRY_SIZE as u64)?;
+            }
+            (&file).write_all(&bytes)?;
+        } else {
+            bytes.splice(0..0, *MAGIC);
+            let tmp = self.path.with_extension("idx.tmp");
+            std::fs::write(&tmp, &bytes)
+                .with_context(|| format!("Failed to write index: {}", tmp.display()))?;
+            std::fs::rename(&tmp, &self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// The latest start times from a mapped index, for bounding reverse reads
+pub(crate) struct Bounds {
+    data: mapped::Contents,
+}
+
+impl Bounds {
+    /// Bytes of the commands file the index accounts for
+    pub fn covered(&self) -> u64 {
+        self.len()
+            .checked_sub(1)
+            .map_or(0, |last| self.entry(last).end())
+    }
+
+    /// Latest start time of the records before `offset`, or `None` if there
+    /// are none
+    pub fn latest_before(&self, offset: u64) -> Option<i64> {
+        // Entries are in file order, so the ones before `offset` are a prefix
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.entry(mid).offset < offset {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo.checked_sub(1).map(|i| self.entry(i).latest)
+    }
+
+    fn len(&self) -> usize {
+        self.data.len().saturating_sub(HEADER_SIZE) / ENTRY_SIZE
+    }
+
+    fn entry(&self, i: usize) -> Entry {
+        let start = HEADER_SIZE + i * ENTRY_SIZE;
+        Entry::from_bytes(&self.data[start..start + ENTRY_SIZE])
+    }
+}
+
+/// Whether a line starts at `offset`
+pub(crate) fn starts_line(path: &Path, offset: u64) -> Result<bool> {
+    if offset == 0 {
+        return Ok(true);
+    }
+    let mut file = File::open(path)?;
+    let mut previous = [0];
+    file.seek(SeekFrom::Start(offset - 1))?;
+    file.read_exact(&mut previous)?;
+    Ok(previous[0] == b'\n')
+}
+
+/// Index the complete lines of `path` from `offset` on, continuing from
+/// the `latest` start time before it
+fn scan(path: &Path, offset: u64, mut latest: i64) -> Result<Vec<Entry>> {
+    let data =
+        mapped::open(path)?.ok_or_else(|| anyhow!("No commands file: {}", path.display()))?;
+    let data = data.get(offset as usize..).unwrap_or_default();
+
+    let mut entries = Vec::new();
+    let mut start = 0;
+    // A line without its newline is still being written
+    for end in memchr::memchr_iter(b'\n', data) {
+        let line = &data[start..end];
+        let line_offset = offset + start as u64;
+        if !line.trim_ascii().is_empty() {
+            let started_at = std::str::from_utf8(line)
+                .map_err(anyhow::Error::from)
+                .and_then(stamp)
+                .with_context(|| format!("Failed to index line at offset {}", line_offset))?;
+            latest = latest.max(started_at);
+            entries.push(Entry {
+                offset: line_offset,
+                len: (end + 1 - start) as u64,
+                started_at,
+                latest,
+            });
+        }
+        start = end + 1;
+    }
+    Ok(entries)
+}
+
+/// Start time of a record line, in nanoseconds
+fn stamp(line: &str) -> Result<i64> {
+    let stamp: Stamp = serde_json::from_str(line)?;
+    stamp
+        .started_at
+        .timestamp_nanos_opt()
+        .ok_or_else(|| anyhow!("Start time out of range: {}", stamp.started_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn line(minute: u32) -> String {
+        format!(
+            "{{\"id\":\"{}\",\"started_at\":\"2026-10-17T09:{:02}:00Z\"}}\n",
+            minute, minute
+        )
+    }
+
+    #[test]
+    fn test_catch_up_and_rebuild() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("commands.jsonl");
+        let index = CommandIndex::for_file(&path);
+
+        std::fs::write(&path, format!("{}\n{}", line(2), line(1))).unwrap();
+        let bounds = index.open(&path).unwrap();
+        assert_eq!(bounds.len(), 2);
+        assert_eq!(bounds.entry(1).offset, line(2).len() as u64 + 1);
+        // The latest start before a record, not the start of the one before it
+        let latest = |offset| {
+            bounds
+                .latest_before(offset)
+                .map(|t| t / 60_000_000_000 % 60)
+        };
+        assert_eq!(latest(0), None);
+        assert_eq!(latest(bounds.covered()), Some(2));
+
+        // Appended elsewhere, plus a line still being written
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{}{{\"id\":", line(3)).unwrap();
+        let bounds = index.open(&path).unwrap();
+        assert_eq!(bounds.len(), 3);
+        assert_eq!(bounds.entry(2).latest / 60_000_000_000 % 60, 3);
+
+        // Rewritten shorter, or left in another layout
+        std::fs::write(&path, line(4)).unwrap();
+        assert_eq!(index.open(&path).unwrap().covered(), line(4).len() as u64);
+        std::fs::write(&index.path, [0; 24]).unwrap();
+        assert_eq!(index.open(&path).unwrap().len(), 1);
+
+        // Extended on append only when it lines up
+        index.append(line(4).len() as u64, &line(5)).unwrap();
+        index.append(0, &line(6)).unwrap();
+        let len = std::fs::metadata(&index.path).unwrap().len() as usize;
+        assert_eq!(len, HEADER_SIZE + 2 * ENTRY_SIZE);
+    }
+}
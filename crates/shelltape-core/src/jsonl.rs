@@ -0,0 +1,490 @@
+//! The default backend: one JSON record per line in `commands.jsonl`
+//!
+//! Appends are single writes, so concurrent shells never interleave within
+//! a line. Sidecar caches next to the file (`commands.idx`, `commands.tri`,
+//! `commands.stats`) keep reads from parsing the whole history; every
+//! rewrite drops them and they are rebuilt on the next read.
+
+use crate::aggregate::{Aggregate, StatsCache};
+use crate::backend::{Pager, StorageBackend};
+use crate::index::CommandIndex;
+use crate::mapped;
+use crate::models::{Command, CommandView};
+use crate::recent::Recent;
+use crate::scan;
+use crate::storage::{CommandPages, RecordPos, merge_records, unique_match};
+use crate::trigram::{self, TrigramIndex};
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Commands kept in a JSONL file
+pub struct JsonlBackend {
+    commands_file: PathBuf,
+    index: CommandIndex,
+    trigrams: TrigramIndex,
+    stats: StatsCache,
+}
+
+impl JsonlBackend {
+    pub fn new(commands_file: PathBuf) -> Self {
+        Self {
+            index: CommandIndex::for_file(&commands_file),
+            trigrams: TrigramIndex::for_file(&commands_file),
+            stats: StatsCache::for_file(&commands_file),
+            commands_file,
+        }
+    }
+
+    fn scan<T: Send>(
+        &self,
+        text: Option<&str>,
+        f: &(dyn Fn(CommandView<'_>, RecordPos) -> Option<T> + Sync),
+    ) -> Result<Vec<T>> {
+        // Only read the blocks that may hold the text
+        let spans = match text {
+            Some(text) => self
+                .trigrams
+                .candidates(&self.commands_file, text)
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to read trigram filters: {:#}", e);
+                    None
+                }),
+            None => None,
+        };
+        if let Some(spans) = spans {
+            return self.scan_spans(&spans, text, f);
+        }
+
+        let Some(data) = self.map_commands()? else {
+            return Ok(Vec::new());
+        };
+
+        let records = scan::scan(&data, text, f)?;
+        tracing::debug!(
+            count = records.len(),
+            bytes = data.len(),
+            "Read commands file"
+        );
+        Ok(records)
+    }
+
+    fn scan_spans<T: Send>(
+        &self,
+        spans: &[trigram::Span],
+        text: Option<&str>,
+        f: &(dyn Fn(CommandView<'_>, RecordPos) -> Option<T> + Sync),
+    ) -> Result<Vec<T>> {
+        let Some(data) = self.map_commands()? else {
+            return Ok(Vec::new());
+        };
+        let mut records = Vec::new();
+        let mut bytes = 0;
+        for span in spans {
+            let start = (span.offset as usize).min(data.len());
+            let end = span
+                .len
+                .map_or(data.len(), |len| (start + len as usize).min(data.len()));
+            let data = &data[start..end];
+            bytes += data.len();
+            records.extend(scan::scan_at(data, span.offset, span.first_line, text, f)?);
+        }
+        tracing::debug!(count = records.len(), bytes, "Read parts of commands file");
+        Ok(records)
+    }
+
+    /// The commands file's contents, or `None` if nothing was recorded yet
+    fn map_commands(&self) -> Result<Option<mapped::Contents>> {
+        mapped::open(&self.commands_file).with_context(|| {
+            format!(
+                "Failed to read commands file: {}",
+                self.commands_file.display()
+            )
+        })
+    }
+
+    fn read_all_commands(&self) -> Result<Vec<Command>> {
+        self.scan_commands(None, &|_| true)
+    }
+
+    fn read_recent(
+        &self,
+        limit: usize,
+        keep: &mut dyn FnMut(&Command) -> bool,
+    ) -> Result<Vec<Command>> {
+        let mut commands = Vec::new();
+        if limit == 0 {
+            return Ok(commands);
+        }
+        let mut recent = self.recent(keep)?;
+        while let Some((cmd, _)) = recent.next()? {
+            commands.push(cmd);
+            if commands.len() == limit {
+                break;
+            }
+        }
+        tracing::debug!(count = commands.len(), "Read recent commands");
+        Ok(commands)
+    }
+
+    /// Newest-first reader over the commands file, as far as it is indexed
+    fn recent<K: FnMut(&Command) -> bool>(&self, keep: K) -> Result<Recent<K>> {
+        let bounds = self.index.open(&self.commands_file)?;
+        let data = self.map_commands()?.unwrap_or_else(mapped::empty);
+        Recent::new(data, bounds, keep)
+    }
+
+    /// Records appended at or after `offset` that are not in `seen`
+    ///
+    /// Appends are whole lines, so `offset`, a length the file had earlier,
+    /// is at the start of a line.
+    fn appended_since(&self, offset: u64, seen: &HashSet<String>) -> Result<Vec<Command>> {
+        let Some(data) = self.map_commands()? else {
+            return Ok(Vec::new());
+        };
+        let start = (offset as usize).min(data.len());
+        let mut appended = Vec::new();
+        for line in data[start..].split(|b| *b == b'\n') {
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            match serde_json::from_slice::<Command>(line) {
+                Ok(cmd) if !seen.contains(&cmd.id) => appended.push(cmd),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Skipping unreadable appended record: {}", e),
+            }
+        }
+        if !appended.is_empty() {
+            tracing::debug!(count = appended.len(), "Keeping records appended meanwhile");
+        }
+        Ok(appended)
+    }
+
+    /// Rewrite the commands file with what `edit` makes of its records
+    ///
+    /// `edit` returns its result and whether it changed anything; the file is
+    /// left alone when it did not. Records shells append while this runs are
+    /// kept after the edited ones, or, with `sort`, put in place by start
+    /// time along with them.
+    fn rewrite_commands<R>(
+        &self,
+        sort: bool,
+        edit: impl FnOnce(&mut Vec<Command>) -> Result<(R, bool)>,
+    ) -> Result<R> {
+        let read_len = std::fs::metadata(&self.commands_file).map_or(0, |m| m.len());
+        let mut commands = self.read_all_commands()?;
+        let mut seen: HashSet<String> = commands.iter().map(|c| c.id.clone()).collect();
+
+        let (result, changed) = edit(&mut commands)?;
+        if !changed {
+            return Ok(result);
+        }
+        seen.extend(commands.iter().map(|c| c.id.clone()));
+        commands.extend(self.appended_since(read_len, &seen)?);
+        if sort {
+            commands.sort_by_key(|c| c.started_at);
+        }
+
+        replace_lines(&self.commands_file, &commands)?;
+        self.invalidate_caches()?;
+        tracing::debug!(count = commands.len(), "Rewrote commands file");
+        Ok(result)
+    }
+
+    /// Drop lines (1-based) from the commands file, copying it to `backup`
+    /// first; see [`drop_lines`]
+    pub(crate) fn drop_lines(&self, lines: &[usize], backup: &Path) -> Result<()> {
+        drop_lines(&self.commands_file, lines, backup)?;
+        self.invalidate_caches()
+    }
+
+    /// Remove the sidecar caches, after the commands file was rewritten
+    fn invalidate_caches(&self) -> Result<()> {
+        self.index.invalidate()?;
+        self.trigrams.invalidate()?;
+        self.stats.invalidate()
+    }
+}
+
+impl StorageBackend for JsonlBackend {
+    fn append_commands(&self, cmds: &[Command]) -> Result<()> {
+        if cmds.is_empty() {
+            return Ok(());
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.commands_file)
+            .with_context(|| {
+                format!(
+                    "Failed to open commands file: {}",
+                    self.commands_file.display()
+                )
+            })?;
+
+        let mut lines = Vec::with_capacity(cmds.len());
+        for cmd in cmds {
+            let mut line = serde_json::to_string(cmd)
+                .with_context(|| "Failed to serialize command to JSON")?;
+            line.push('\n');
+            lines.push(line);
+        }
+        let data = lines.concat();
+
+        // One write, so concurrent shells never interleave within a line
+        let offset = file.metadata()?.len();
+        file.write_all(data.as_bytes())
+            .with_context(|| "Failed to write command to file")?;
+        tracing::debug!(path = %self.commands_file.display(), count = cmds.len(), bytes = data.len(), "Appended commands");
+
+        // Another writer may have slipped in; the next read catches up then
+        if file.metadata()?.len() == offset + data.len() as u64 {
+            let mut at = offset;
+            for line in &lines {
+                if let Err(e) = self.index.append(at, line) {
+                    tracing::warn!("Failed to update command index: {:#}", e);
+                    break;
+                }
+                at += line.len() as u64;
+            }
+        }
+        if let Err(e) = self.trigrams.update(&self.commands_file) {
+            tracing::warn!("Failed to update trigram filters: {:#}", e);
+        }
+
+        Ok(())
+    }
+
+    fn scan_commands(
+        &self,
+        text: Option<&str>,
+        keep: &(dyn Fn(&CommandView) -> bool + Sync),
+    ) -> Result<Vec<Command>> {
+        self.scan(text, &|cmd, _| keep(&cmd).then(|| cmd.into_owned()))
+    }
+
+    fn read_command_metadata(&self) -> Result<Vec<(Command, RecordPos)>> {
+        self.scan(None, &|mut cmd, pos| {
+            cmd.output = Cow::Borrowed("");
+            cmd.timing = Cow::Borrowed(&[]);
+            Some((cmd.into_owned(), pos))
+        })
+    }
+
+    fn search_command_ids(&self, query: &str) -> Result<HashSet<String>> {
+        Ok(self
+            .scan(Some(query), &|cmd, _| {
+                cmd.matches_query(query).then(|| cmd.id.into_owned())
+            })?
+            .into_iter()
+            .collect())
+    }
+
+    fn read_command_at(&self, pos: RecordPos) -> Result<Command> {
+        let mut file = File::open(&self.commands_file).with_context(|| {
+            format!(
+                "Failed to open commands file: {}",
+                self.commands_file.display()
+            )
+        })?;
+        let mut line = vec![0; pos.len as usize];
+        file.seek(SeekFrom::Start(pos.offset))?;
+        file.read_exact(&mut line)?;
+        serde_json::from_slice(&line)
+            .with_context(|| format!("No record at offset {} of commands file", pos.offset))
+    }
+
+    fn recent_commands(
+        &self,
+        limit: usize,
+        keep: &mut dyn FnMut(&Command) -> bool,
+    ) -> Result<Vec<Command>> {
+        match self.read_recent(limit, keep) {
+            Ok(commands) => Ok(commands),
+            Err(e) => {
+                // The index no longer fits the file (e.g. edited by hand)
+                tracing::debug!("Rebuilding command index: {:#}", e);
+                self.index.invalidate()?;
+                self.read_recent(limit, keep)
+            }
+        }
+    }
+
+    fn command_pages(&self) -> Result<CommandPages> {
+        let keep_all: fn(&Command) -> bool = |_| true;
+        let recent = match self.recent(keep_all) {
+            Ok(recent) => recent,
+            Err(e) => {
+                tracing::debug!("Rebuilding command index: {:#}", e);
+                self.index.invalidate()?;
+                self.recent(keep_all)?
+            }
+        };
+        Ok(CommandPages::new(Box::new(recent)))
+    }
+
+    fn commands_with_id_prefix(&self, prefix: &str) -> Result<Vec<Command>> {
+        self.scan_commands(None, &|cmd| cmd.id.starts_with(prefix))
+    }
+
+    fn update_command(
+        &self,
+        id: &str,
+        update: Box<dyn FnOnce(&mut Command) + '_>,
+    ) -> Result<Command> {
+        self.rewrite_commands(false, |commands| {
+            let matches = (0..commands.len())
+                .filter(|&i| commands[i].id.starts_with(id))
+                .collect();
+            let slot = &mut commands[unique_match(id, matches)?];
+            update(slot);
+            Ok((slot.clone(), true))
+        })
+    }
+
+    fn merge_commands(&self, incoming: Vec<Command>) -> Result<usize> {
+        self.rewrite_commands(true, |commands| {
+            let (merged, added) = merge_records(std::mem::take(commands), incoming, |c| &c.id);
+            *commands = merged;
+            Ok((added, added > 0))
+        })
+    }
+
+    fn retain_commands(&self, keep: &mut dyn FnMut(&Command) -> bool) -> Result<usize> {
+        self.rewrite_commands(false, |commands| {
+            let before = commands.len();
+            commands.retain(|c| keep(c));
+            let removed = before - commands.len();
+            Ok((removed, removed > 0))
+        })
+    }
+
+    fn delete_commands(&self, ids: &HashSet<String>) -> Result<usize> {
+        self.retain_commands(&mut |cmd| !ids.contains(&cmd.id))
+    }
+
+    fn end_cursor(&self) -> Result<u64> {
+        Ok(self.map_commands()?.map_or(0, |data| data.len() as u64))
+    }
+
+    fn commands_since(&self, cursor: u64) -> Result<(Vec<(Command, RecordPos)>, u64)> {
+        let Some(data) = self.map_commands()? else {
+            return Ok((Vec::new(), 0));
+        };
+        let start = if cursor > data.len() as u64 {
+            0
+        } else {
+            cursor as usize
+        };
+        // A line still being written is left for the next call
+        let Some(end) = data[start..].iter().rposition(|&b| b == b'\n') else {
+            return Ok((Vec::new(), start as u64));
+        };
+        let end = start + end + 1;
+
+        let mut commands = Vec::new();
+        let mut offset = start;
+        for line in data[start..end].split(|&b| b == b'\n') {
+            let pos = RecordPos {
+                offset: offset as u64,
+                len: line.len() as u64,
+            };
+            offset += line.len() + 1;
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            match serde_json::from_slice::<Command>(line) {
+                Ok(cmd) => commands.push((cmd, pos)),
+                Err(e) => tracing::warn!("Skipping unreadable record: {}", e),
+            }
+        }
+        Ok((commands, end as u64))
+    }
+
+    fn sync(&self) -> Result<()> {
+        match File::open(&self.commands_file) {
+            Ok(file) => file.sync_data().with_context(|| {
+                format!(
+                    "Failed to sync commands file: {}",
+                    self.commands_file.display()
+                )
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn aggregate(&self) -> Result<Aggregate> {
+        self.stats.load(&self.commands_file)
+    }
+}
+
+impl<K: FnMut(&Command) -> bool> Pager for Recent<K> {
+    fn next_page(&mut self, limit: usize) -> Result<Vec<(Command, RecordPos)>> {
+        let mut page = Vec::new();
+        while page.len() < limit
+            && let Some((mut cmd, pos)) = self.next()?
+        {
+            cmd.output = String::new();
+            cmd.timing = Vec::new();
+            page.push((cmd, pos));
+        }
+        Ok(page)
+    }
+
+    fn is_done(&self) -> bool {
+        Recent::is_done(self)
+    }
+}
+
+/// Replace a JSONL file with `records`, written to a temporary file first
+///
+/// Renaming it into place never leaves a half-written history behind, and
+/// readers that mapped the old file keep seeing it whole.
+pub(crate) fn replace_lines<T: serde::Serialize>(path: &Path, records: &[T]) -> Result<()> {
+    let mut data = String::new();
+    for record in records {
+        data.push_str(
+            &serde_json::to_string(record).with_context(|| "Failed to serialize record to JSON")?,
+        );
+        data.push('\n');
+    }
+    replace_file(path, data.as_bytes())
+}
+
+/// Drop lines (1-based) from a JSONL file, copying it to `backup` first
+///
+/// Lines appended while it runs are kept.
+pub(crate) fn drop_lines(path: &Path, lines: &[usize], backup: &Path) -> Result<()> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open: {}", path.display()))?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    std::fs::write(backup, &content)
+        .with_context(|| format!("Failed to write backup: {}", backup.display()))?;
+
+    let mut kept = Vec::with_capacity(content.len());
+    for (i, line) in content.split_inclusive(|&b| b == b'\n').enumerate() {
+        if !lines.contains(&(i + 1)) {
+            kept.extend_from_slice(line);
+        }
+    }
+    file.read_to_end(&mut kept)?;
+    replace_file(path, &kept)
+}
+
+/// Replace a file with `data` by renaming a temporary file over it
+fn replace_file(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("jsonl.tmp");
+    std::fs::write(&tmp, data).with_context(|| format!("Failed to write: {}", tmp.display()))?;
+    // Keep the permissions of the file being replaced
+    if let Ok(meta) = std::fs::metadata(path) {
+        std::fs::set_permissions(&tmp, meta.permissions())?;
+    }
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("Failed to replace: {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,66 @@
+//! Storage, recording and querying of shelltape command history.
+//!
+//! This is the library behind the `shelltape` CLI. It reads and writes the
+//! same data directory as the CLI, so tools built on it see every recorded
+//! command and their own records show up in `shelltape list` and the TUI.
+//!
+//! - [`storage::Storage`] opens a data directory and reads or rewrites its
+//!   commands and sessions. Commands go through a
+//!   [`backend::StorageBackend`]: JSONL by default, or SQLite with the
+//!   `sqlite` feature.
+//! - [`recorder::Recorder`] records a finished command, applying exclude
+//!   patterns, secret redaction and output truncation and running webhooks,
+//!   forwarding, plugins, span export and notifications.
+//! - [`query::Query`] selects commands by text, tag, session, status or time.
+//! - [`models`] holds the records themselves; [`config`] the user settings.
+//!
+//! ```
+//! use shelltape_core::query::Query;
+//! use shelltape_core::recorder::Recorder;
+//! use shelltape_core::storage::Storage;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! # let dir = tempfile::tempdir()?;
+//! let storage = Storage::with_dir(dir.path().to_path_buf())?;
+//! let recorder = Recorder::with_storage(storage.clone());
+//!
+//! let start = 1_760_000_000_000_000_000; // nanoseconds since the epoch
+//! recorder.record(
+//!     "cargo test".to_string(),
+//!     "test result: FAILED".to_string(),
+//!     101,
+//!     start,
+//!     start + 2_000_000_000,
+//!     "/src/app".to_string(),
+//!     "my-session".to_string(),
+//!     Vec::new(),
+//! )?;
+//!
+//! let failed = Query::new().text("cargo").failed().run(&storage)?;
+//! assert_eq!(failed[0].duration_ms, 2000);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod aggregate;
+pub mod backend;
+pub mod config;
+pub mod forward;
+mod index;
+pub mod jsonl;
+mod mapped;
+pub mod models;
+pub mod normalize;
+pub mod notify;
+pub mod otlp;
+pub mod plugin;
+pub mod query;
+mod recent;
+pub mod recorder;
+pub mod redact;
+mod scan;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod storage;
+mod trigram;
+pub mod webhook;
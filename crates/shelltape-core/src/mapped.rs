@@ -0,0 +1,101 @@
+//! Whole-file reads, memory-mapped where the platform allows
+//!
+//! Mapping the commands file lets scans and record lookups work on the
+//! page cache directly instead of copying the file through read calls.
+//! Platforms without mmap, empty files and files that fail to map are read
+//! into memory instead, behind the same `&[u8]`.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Read;
+use std::ops::Deref;
+use std::path::Path;
+
+/// The bytes of a file
+pub(crate) struct Contents(Inner);
+
+enum Inner {
+    #[cfg(any(unix, windows))]
+    Mapped(memmap2::Mmap),
+    Read(Vec<u8>),
+}
+
+impl Deref for Contents {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.0 {
+            #[cfg(any(unix, windows))]
+            Inner::Mapped(map) => map,
+            Inner::Read(data) => data,
+        }
+    }
+}
+
+/// No bytes, for files that do not exist yet
+pub(crate) fn empty() -> Contents {
+    Contents(Inner::Read(Vec::new()))
+}
+
+/// The contents of `path`, or `None` if it does not exist
+pub(crate) fn open(path: &Path) -> Result<Option<Contents>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to open file: {}", path.display()));
+        }
+    };
+    map(&file)
+        .with_context(|| format!("Failed to read file: {}", path.display()))
+        .map(Some)
+}
+
+/// The contents of an open file
+pub(crate) fn map(file: &File) -> Result<Contents> {
+    #[cfg(any(unix, windows))]
+    if file.metadata()?.len() > 0 {
+        // SAFETY: the history files are only appended to in place, which
+        // leaves the mapped range intact; rewrites replace the file by
+        // renaming a new one over it, so the mapped inode never shrinks.
+        // `purge --shred` renames a file away before zeroing it, which
+        // changes bytes under an earlier mapping but never its length.
+        match unsafe { memmap2::Mmap::map(file) } {
+            Ok(map) => return Ok(Contents(Inner::Mapped(map))),
+            Err(e) => tracing::debug!("Failed to map file, reading it instead: {}", e),
+        }
+    }
+
+    let mut data = Vec::new();
+    (&*file).read_to_end(&mut data)?;
+    Ok(Contents(Inner::Read(data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_open() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("commands.jsonl");
+        assert!(open(&path).unwrap().is_none());
+
+        std::fs::write(&path, "").unwrap();
+        assert!(open(&path).unwrap().unwrap().is_empty());
+
+        std::fs::write(&path, "{}\n").unwrap();
+        let contents = open(&path).unwrap().unwrap();
+        // Appending leaves what was already mapped in place
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"[]\n")
+            .unwrap();
+        assert_eq!(&contents[..], b"{}\n");
+        assert_eq!(&open(&path).unwrap().unwrap()[..], b"{}\n[]\n");
+    }
+}
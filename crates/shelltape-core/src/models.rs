@@ -0,0 +1,506 @@
+use crate::aggregate::Aggregate;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// A single command execution record
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Command {
+    /// Unique identifier (UUID)
+    pub id: String,
+    /// The command that was executed
+    pub command: String,
+    /// Output from the command (may be truncated)
+    pub output: String,
+    /// Exit code from the command
+    pub exit_code: i32,
+    /// Working directory when command was executed
+    pub cwd: String,
+    /// Timestamp when command started
+    pub started_at: DateTime<Utc>,
+    /// Duration of command execution in milliseconds
+    pub duration_ms: u64,
+    /// Session ID this command belongs to
+    pub session_id: String,
+    /// Shell type (bash, zsh, fish, etc.)
+    pub shell: String,
+    /// Hostname where command was executed
+    pub hostname: String,
+    /// Username who executed the command
+    pub username: String,
+    /// Output timing as `(milliseconds since start, bytes)` chunks, if captured
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timing: Vec<(u64, usize)>,
+    /// User-assigned tags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Free-form user note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Whether the user bookmarked this command
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bookmarked: bool,
+    /// ID of the command this run repeated, for runs made by `rerun`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerun_of: Option<String>,
+}
+
+impl Command {
+    /// Check whether the command carries a tag (case-insensitive)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Check whether the command line, directory or output contains `query`
+    /// (case-insensitive)
+    pub fn matches_query(&self, query: &str) -> bool {
+        matches_query(query, [&self.command, &self.cwd, &self.output])
+    }
+
+    /// Borrow the record as a [`CommandView`]
+    pub fn view(&self) -> CommandView<'_> {
+        CommandView {
+            id: Cow::Borrowed(&self.id),
+            command: Cow::Borrowed(&self.command),
+            output: Cow::Borrowed(&self.output),
+            exit_code: self.exit_code,
+            cwd: Cow::Borrowed(&self.cwd),
+            started_at: self.started_at,
+            duration_ms: self.duration_ms,
+            session_id: Cow::Borrowed(&self.session_id),
+            shell: Cow::Borrowed(&self.shell),
+            hostname: Cow::Borrowed(&self.hostname),
+            username: Cow::Borrowed(&self.username),
+            timing: Cow::Borrowed(&self.timing),
+            tags: self
+                .tags
+                .iter()
+                .map(|t| Cow::Borrowed(t.as_str()))
+                .collect(),
+            note: self.note.as_deref().map(Cow::Borrowed),
+            bookmarked: self.bookmarked,
+            rerun_of: self.rerun_of.as_deref().map(Cow::Borrowed),
+        }
+    }
+}
+
+/// A command record borrowing its text from the line it was parsed from
+///
+/// Scans filter on views, so only the records they keep pay for owned
+/// strings. Fields with JSON escapes (newlines in output, quotes) are
+/// unescaped into owned strings; the rest point into the line.
+#[derive(Debug, Deserialize)]
+pub struct CommandView<'a> {
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+    #[serde(borrow)]
+    pub command: Cow<'a, str>,
+    #[serde(borrow)]
+    pub output: Cow<'a, str>,
+    pub exit_code: i32,
+    #[serde(borrow)]
+    pub cwd: Cow<'a, str>,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    #[serde(borrow)]
+    pub session_id: Cow<'a, str>,
+    #[serde(borrow)]
+    pub shell: Cow<'a, str>,
+    #[serde(borrow)]
+    pub hostname: Cow<'a, str>,
+    #[serde(borrow)]
+    pub username: Cow<'a, str>,
+    #[serde(default)]
+    pub timing: Cow<'a, [(u64, usize)]>,
+    #[serde(default, borrow)]
+    pub tags: Vec<Cow<'a, str>>,
+    #[serde(default, borrow)]
+    pub note: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub bookmarked: bool,
+    #[serde(default, borrow)]
+    pub rerun_of: Option<Cow<'a, str>>,
+}
+
+impl CommandView<'_> {
+    /// Check whether the command carries a tag (case-insensitive)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Check whether the command line, directory or output contains `query`
+    /// (case-insensitive)
+    pub fn matches_query(&self, query: &str) -> bool {
+        matches_query(query, [&self.command, &self.cwd, &self.output])
+    }
+
+    /// Copy the record into an owned [`Command`]
+    pub fn into_owned(self) -> Command {
+        Command {
+            id: self.id.into_owned(),
+            command: self.command.into_owned(),
+            output: self.output.into_owned(),
+            exit_code: self.exit_code,
+            cwd: self.cwd.into_owned(),
+            started_at: self.started_at,
+            duration_ms: self.duration_ms,
+            session_id: self.session_id.into_owned(),
+            shell: self.shell.into_owned(),
+            hostname: self.hostname.into_owned(),
+            username: self.username.into_owned(),
+            timing: self.timing.into_owned(),
+            tags: self.tags.into_iter().map(Cow::into_owned).collect(),
+            note: self.note.map(Cow::into_owned),
+            bookmarked: self.bookmarked,
+            rerun_of: self.rerun_of.map(Cow::into_owned),
+        }
+    }
+}
+
+/// Whether any of `fields` contains `query`, compared in lowercase
+///
+/// ASCII fields are compared in place; only other text is lowercased into a
+/// new string.
+fn matches_query(query: &str, fields: [&str; 3]) -> bool {
+    let query = query.to_lowercase();
+    fields.iter().any(|field| {
+        if field.is_ascii() {
+            query.is_ascii()
+                && (query.is_empty()
+                    || field
+                        .as_bytes()
+                        .windows(query.len())
+                        .any(|w| w.eq_ignore_ascii_case(query.as_bytes())))
+        } else {
+            field.to_lowercase().contains(&query)
+        }
+    })
+}
+
+/// A command that is currently executing under `shelltape exec`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunningCommand {
+    /// Unique identifier (UUID)
+    pub id: String,
+    /// The command being executed
+    pub command: String,
+    /// Working directory of the command
+    pub cwd: String,
+    /// Timestamp when command started
+    pub started_at: DateTime<Utc>,
+    /// Session ID this command belongs to
+    pub session_id: String,
+    /// Process ID of the shelltape wrapper
+    pub pid: u32,
+}
+
+/// A shell session record
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    /// Unique identifier (UUID)
+    pub id: String,
+    /// Timestamp when session started
+    pub started_at: DateTime<Utc>,
+    /// Timestamp when session ended (None if still active)
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Hostname where session was started
+    pub hostname: String,
+    /// Shell type (bash, zsh, fish, etc.)
+    pub shell: String,
+    /// Number of commands executed in this session
+    pub command_count: u32,
+    /// Optional label for the session (e.g. from `exec --name`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Terminal the session was started from, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tty: Option<String>,
+}
+
+/// Optional search index for fast queries
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchIndex {
+    /// Total number of commands in the index
+    pub commands_count: usize,
+    /// When the index was last updated
+    pub last_updated: DateTime<Utc>,
+    /// List of all session IDs
+    pub sessions: Vec<String>,
+}
+
+/// Statistics about command history
+#[derive(Debug, Clone)]
+pub struct Stats {
+    /// Total number of commands recorded
+    pub total_commands: usize,
+    /// Total number of sessions
+    pub total_sessions: usize,
+    /// Success rate (percentage of commands with exit code 0)
+    pub success_rate: f64,
+    /// Most frequently used commands
+    pub most_used_commands: Vec<(String, usize)>,
+    /// Most frequently used programs (`git`, `cargo`, ...)
+    pub most_used_programs: Vec<(String, usize)>,
+    /// Most frequently used programs with their subcommand (`git status`, ...)
+    pub most_used_subcommands: Vec<(String, usize)>,
+    /// Commands started in each hour of the day (local time, 0-23)
+    pub by_hour: [usize; 24],
+    /// Commands started on each day of the week (local time, Monday first)
+    pub by_weekday: [usize; 7],
+    /// Duration percentiles across all commands
+    pub durations: DurationPercentiles,
+    /// Duration percentiles for each of the most used commands (same order)
+    pub command_durations: Vec<(String, DurationPercentiles)>,
+    /// Number of commands per duration bucket (see `DURATION_BUCKETS`)
+    pub duration_histogram: Vec<usize>,
+    /// Commands per day (local time); days without commands are absent
+    pub daily: BTreeMap<NaiveDate, usize>,
+    /// Failed commands per day (local time); days without failures are absent
+    pub daily_failed: BTreeMap<NaiveDate, usize>,
+}
+
+/// Upper bounds (exclusive, in ms) and labels of the duration histogram buckets
+pub const DURATION_BUCKETS: [(u64, &str); 6] = [
+    (100, "<100ms"),
+    (1_000, "<1s"),
+    (10_000, "<10s"),
+    (60_000, "<1m"),
+    (600_000, "<10m"),
+    (u64::MAX, "≥10m"),
+];
+
+/// Duration percentiles in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DurationPercentiles {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+}
+
+impl DurationPercentiles {
+    /// Compute percentiles (nearest-rank) from unsorted durations
+    pub fn from_durations(durations: Vec<u64>) -> Self {
+        let mut counts = BTreeMap::new();
+        for ms in durations {
+            *counts.entry(ms).or_insert(0) += 1;
+        }
+        Self::from_counts(&counts)
+    }
+
+    /// Compute percentiles (nearest-rank) from the number of commands with
+    /// each duration
+    pub fn from_counts(counts: &BTreeMap<u64, usize>) -> Self {
+        let total: usize = counts.values().sum();
+        if total == 0 {
+            return Self::default();
+        }
+
+        let rank = |p: usize| {
+            let idx = (p * total).div_ceil(100).max(1) - 1;
+            let mut seen = 0;
+            for (&ms, &count) in counts {
+                seen += count;
+                if seen > idx {
+                    return ms;
+                }
+            }
+            0
+        };
+        Self {
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+        }
+    }
+}
+
+impl Stats {
+    /// Compute statistics over a set of commands
+    pub fn from_commands(commands: &[Command], total_sessions: usize) -> Self {
+        Aggregate::from_commands(commands).stats(total_sessions)
+    }
+
+    /// Success rate (0-100) for each of the `days` days ending with `end`
+    ///
+    /// Days without commands are `None` rather than 0%.
+    pub fn success_rate_series(&self, end: NaiveDate, days: usize) -> Vec<Option<f64>> {
+        (0..days as i64)
+            .rev()
+            .map(|ago| {
+                let day = end - chrono::Duration::days(ago);
+                let total = self.daily.get(&day).copied().unwrap_or(0);
+                let failed = self.daily_failed.get(&day).copied().unwrap_or(0);
+                (total > 0).then(|| (total - failed) as f64 / total as f64 * 100.0)
+            })
+            .collect()
+    }
+
+    /// Current and longest runs of consecutive active days
+    ///
+    /// The current streak ends today, or yesterday if nothing has been run
+    /// yet today.
+    pub fn streaks(&self, today: NaiveDate) -> (usize, usize) {
+        let mut longest = 0;
+        let mut run = 0;
+        let mut prev: Option<NaiveDate> = None;
+        for &day in self.daily.keys() {
+            run = match prev {
+                Some(p) if p.succ_opt() == Some(day) => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            prev = Some(day);
+        }
+
+        let mut day = if self.daily.contains_key(&today) {
+            today
+        } else {
+            today.pred_opt().unwrap_or(today)
+        };
+        let mut current = 0;
+        while self.daily.contains_key(&day) {
+            current += 1;
+            match day.pred_opt() {
+                Some(p) => day = p,
+                None => break,
+            }
+        }
+
+        (current, longest)
+    }
+
+    /// The day with the most commands (the earliest one on ties)
+    pub fn busiest_day(&self) -> Option<(NaiveDate, usize)> {
+        self.daily
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(&day, &count)| (day, count))
+    }
+}
+
+/// Format a duration in milliseconds as a compact human-readable string
+pub fn format_duration(ms: u64) -> String {
+    match ms {
+        0..1000 => format!("{}ms", ms),
+        1000..60_000 => format!("{:.1}s", ms as f64 / 1000.0),
+        60_000..3_600_000 => format!("{}m {:02}s", ms / 60_000, ms / 1000 % 60),
+        _ => format!("{}h {:02}m", ms / 3_600_000, ms / 60_000 % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn cmd(command: &str, exit_code: i32, started_at: DateTime<Utc>) -> Command {
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: "/tmp".to_string(),
+            started_at,
+            duration_ms: 0,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stats_from_commands() {
+        // 2026-10-12 is a Monday
+        let monday_9am = Local
+            .with_ymd_and_hms(2026, 10, 12, 9, 15, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let commands = vec![
+            cmd("ls", 0, monday_9am),
+            cmd("ls", 1, monday_9am),
+            cmd("git status", 0, monday_9am + chrono::Duration::days(2)),
+        ];
+
+        let stats = Stats::from_commands(&commands, 1);
+        assert_eq!(stats.total_commands, 3);
+        assert!((stats.success_rate - 66.666).abs() < 0.01);
+        assert_eq!(stats.most_used_commands[0], ("ls".to_string(), 2));
+        assert_eq!(stats.most_used_programs[1], ("git".to_string(), 1));
+        assert_eq!(
+            stats.most_used_subcommands[1],
+            ("git status".to_string(), 1)
+        );
+        assert_eq!(stats.by_hour[9], 3);
+        assert_eq!(stats.by_weekday, [2, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(stats.duration_histogram, vec![3, 0, 0, 0, 0, 0]);
+
+        let monday = monday_9am.with_timezone(&Local).date_naive();
+        let series = stats.success_rate_series(monday + chrono::Duration::days(2), 4);
+        assert_eq!(series, vec![None, Some(50.0), None, Some(100.0)]);
+    }
+
+    #[test]
+    fn test_streaks() {
+        let day = |d| {
+            Local
+                .with_ymd_and_hms(2026, 10, d, 12, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let commands: Vec<_> = [1, 2, 3, 3, 6, 7, 9, 10]
+            .into_iter()
+            .map(|d| cmd("ls", 0, day(d)))
+            .collect();
+        let stats = Stats::from_commands(&commands, 1);
+        let date = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+
+        assert_eq!(stats.streaks(date(10)), (2, 3));
+        assert_eq!(stats.streaks(date(11)), (2, 3));
+        assert_eq!(stats.streaks(date(12)), (0, 3));
+        assert_eq!(stats.busiest_day(), Some((date(3), 2)));
+    }
+
+    #[test]
+    fn test_duration_percentiles() {
+        let p = DurationPercentiles::from_durations((1..=100).rev().collect());
+        assert_eq!((p.p50, p.p90, p.p99), (50, 90, 99));
+
+        let p = DurationPercentiles::from_durations(vec![7]);
+        assert_eq!((p.p50, p.p90, p.p99), (7, 7, 7));
+
+        assert_eq!(
+            DurationPercentiles::from_durations(Vec::new()),
+            DurationPercentiles::default()
+        );
+    }
+
+    #[test]
+    fn test_matches_query() {
+        let mut c = cmd("cargo test", 0, Utc::now());
+        c.output = "test result: FAILED".to_string();
+
+        assert!(c.matches_query("CARGO"));
+        assert!(c.matches_query("/tmp"));
+        assert!(c.matches_query("failed"));
+        assert!(!c.matches_query("docker"));
+
+        // Lowercased the same way whether a field is ASCII or not
+        c.cwd = "/home/ÉLODIE".to_string();
+        assert!(c.matches_query("élodie"));
+        c.command = "make".to_string();
+        assert!(c.matches_query("MA\u{212a}E"));
+        assert!(c.matches_query(""));
+        assert!(c.view().matches_query("RESULT"));
+        assert!(!c.view().matches_query("ÿ"));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(250), "250ms");
+        assert_eq!(format_duration(1500), "1.5s");
+        assert_eq!(format_duration(125_000), "2m 05s");
+        assert_eq!(format_duration(3_720_000), "1h 02m");
+    }
+}
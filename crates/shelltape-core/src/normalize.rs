@@ -0,0 +1,207 @@
+/// Programs whose first argument is a subcommand (`git status`, `cargo build`, ...)
+const SUBCOMMAND_PROGRAMS: &[&str] = &[
+    "apt",
+    "brew",
+    "bun",
+    "cargo",
+    "conda",
+    "deno",
+    "dnf",
+    "docker",
+    "gh",
+    "git",
+    "go",
+    "helm",
+    "kubectl",
+    "nix",
+    "npm",
+    "pip",
+    "pip3",
+    "pnpm",
+    "podman",
+    "poetry",
+    "rustup",
+    "shelltape",
+    "systemctl",
+    "terraform",
+    "uv",
+    "yarn",
+];
+
+/// Wrappers that run another command, which is the one worth counting
+const WRAPPERS: &[&str] = &[
+    "builtin", "command", "doas", "env", "exec", "nice", "nohup", "sudo", "time",
+];
+
+/// Split a command line into words, honouring simple quoting
+///
+/// Only the first line is considered. This is not a full shell parser:
+/// it is meant for grouping and statistics, not for execution.
+pub fn words(command: &str) -> Vec<String> {
+    let line = command.lines().next().unwrap_or("");
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            // Stop at the end of the first command in a pipeline or list
+            (None, '|' | ';' | '&') => break,
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    words
+}
+
+/// The words of the command proper, starting at the program name
+///
+/// Skips leading `VAR=value` assignments and wrappers such as `sudo`
+/// (including their flags), and reduces the program path to its file name.
+pub fn program_words(command: &str) -> Vec<String> {
+    let mut words = words(command).into_iter().peekable();
+
+    while let Some(word) = words.peek() {
+        let is_assignment = word
+            .split_once('=')
+            .is_some_and(|(name, _)| is_identifier(name));
+        if is_assignment || WRAPPERS.contains(&word.as_str()) || word.starts_with('-') {
+            words.next();
+        } else {
+            break;
+        }
+    }
+
+    let mut words: Vec<String> = words.collect();
+    if let Some(program) = words.first_mut()
+        && let Some((_, name)) = program.rsplit_once('/')
+        && !name.is_empty()
+    {
+        *program = name.to_string();
+    }
+    words
+}
+
+/// The program a command runs, e.g. `git` for `sudo git -C repo status`
+pub fn program(command: &str) -> Option<String> {
+    program_words(command).into_iter().next()
+}
+
+/// The program plus its subcommand for tools that have them
+///
+/// `git -C repo status -sb` becomes `git status`, `ls -la src` stays `ls`.
+pub fn program_and_subcommand(command: &str) -> Option<String> {
+    let words = program_words(command);
+    let program = words.first()?;
+
+    if !SUBCOMMAND_PROGRAMS.contains(&program.as_str()) {
+        return Some(program.clone());
+    }
+
+    match subcommand(&words[1..]) {
+        Some(sub) => Some(format!("{} {}", program, sub)),
+        None => Some(program.clone()),
+    }
+}
+
+/// First argument that looks like a subcommand, skipping leading flags
+///
+/// Known global flags that take a value (`-C repo`) skip that value too.
+pub fn subcommand(args: &[String]) -> Option<&str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            return None;
+        }
+        // `+nightly` selects a toolchain (cargo, rustup)
+        if arg.starts_with('+') {
+            continue;
+        }
+        if arg.starts_with('-') {
+            // Skip the value of known flags like `-C repo`
+            if !arg.contains('=') && FLAGS_WITH_VALUES.contains(&arg.as_str()) {
+                args.next();
+            }
+            continue;
+        }
+        return is_identifier(arg).then_some(arg.as_str());
+    }
+    None
+}
+
+/// Global flags of subcommand programs that take a separate value
+const FLAGS_WITH_VALUES: &[&str] = &[
+    "-C",
+    "-c",
+    "--git-dir",
+    "--work-tree",
+    "-H",
+    "--host",
+    "--context",
+    "-n",
+    "--namespace",
+    "--manifest-path",
+    "-f",
+    "--file",
+    "--prefix",
+    "--filter",
+    "-p",
+    "--project",
+];
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == ':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program() {
+        assert_eq!(program("git status -sb").as_deref(), Some("git"));
+        assert_eq!(
+            program("sudo -E /usr/bin/apt install jq").as_deref(),
+            Some("apt")
+        );
+        assert_eq!(
+            program("RUST_LOG=debug cargo run").as_deref(),
+            Some("cargo")
+        );
+        assert_eq!(program("  ").as_deref(), None);
+    }
+
+    #[test]
+    fn test_program_and_subcommand() {
+        let norm = |c| program_and_subcommand(c).unwrap();
+        assert_eq!(norm("git status"), "git status");
+        assert_eq!(norm("git status -sb"), "git status");
+        assert_eq!(norm("git -C ~/repo log --oneline"), "git log");
+        assert_eq!(norm("cargo +nightly build"), "cargo build");
+        assert_eq!(norm("docker compose up -d"), "docker compose");
+        assert_eq!(norm("ls -la src"), "ls");
+        assert_eq!(norm("git commit -m 'fix: a | b'"), "git commit");
+        assert_eq!(norm("kubectl -n prod get pods | grep api"), "kubectl get");
+    }
+}
@@ -0,0 +1,232 @@
+use crate::config::{NotifyConfig, PushConfig, PushService};
+use crate::models::{Command, format_duration};
+use crate::webhook;
+use anyhow::{Context, Result, anyhow};
+use serde_json::json;
+use std::process::Stdio;
+
+/// Attempts after a failed push delivery
+const PUSH_RETRIES: u32 = 3;
+
+/// Send the notifications a freshly recorded command calls for
+///
+/// A desktop notification is shown by the platform's own tool
+/// (`notify-send`, `osascript` or PowerShell) if the command ran long
+/// enough, and pushes go to every matching ntfy or Slack target. Everything
+/// runs in the background; failures are logged.
+pub fn notify(config: &NotifyConfig, cmd: &Command) {
+    let (title, body) = message(cmd);
+
+    if should_notify(config, cmd)
+        && let Err(e) = show(&title, &body, cmd.exit_code != 0)
+    {
+        tracing::warn!("Desktop notification failed: {:#}", e);
+    }
+
+    for target in &config.push {
+        let result = webhook::matches(&target.filter, cmd).and_then(|matched| {
+            if matched {
+                push(target, &title, &body, cmd.exit_code != 0)
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(e) = result {
+            tracing::warn!(url = %target.url, "Push notification failed: {:#}", e);
+        }
+    }
+}
+
+/// Send a push notification with a background `curl`
+fn push(target: &PushConfig, title: &str, body: &str, failed: bool) -> Result<()> {
+    let (url, headers, payload) = push_request(target, title, body, failed)?;
+    let mut child = webhook::post(&url, &headers, PUSH_RETRIES, &payload)?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// URL, headers and JSON body of a push notification
+fn push_request(
+    target: &PushConfig,
+    title: &str,
+    body: &str,
+    failed: bool,
+) -> Result<(String, Vec<String>, String)> {
+    match target.service {
+        PushService::Slack => Ok((
+            target.url.clone(),
+            Vec::new(),
+            json!({ "text": format!("{}\n{}", title, body) }).to_string(),
+        )),
+        PushService::Ntfy => {
+            // JSON messages are published to the server root, naming the topic
+            let (server, topic) = target
+                .url
+                .trim_end_matches('/')
+                .rsplit_once('/')
+                .filter(|(server, _)| server.contains("://"))
+                .ok_or_else(|| anyhow!("ntfy URL needs a topic: {}", target.url))?;
+            let headers = if target.token.is_empty() {
+                Vec::new()
+            } else {
+                vec![format!("Authorization: Bearer {}", target.token)]
+            };
+            let payload = json!({
+                "topic": topic,
+                "title": title,
+                "message": body,
+                "priority": if failed { 4 } else { 3 },
+                "tags": [if failed { "x" } else { "white_check_mark" }],
+            });
+            Ok((server.to_string(), headers, payload.to_string()))
+        }
+    }
+}
+
+/// Whether `cmd` took long enough for a desktop notification
+fn should_notify(config: &NotifyConfig, cmd: &Command) -> bool {
+    config.desktop && cmd.duration_ms >= config.min_duration_secs.saturating_mul(1000)
+}
+
+/// Title and body of the notification
+fn message(cmd: &Command) -> (String, String) {
+    let title = if cmd.exit_code == 0 {
+        format!("✓ {}", cmd.command.trim())
+    } else {
+        format!("✗ {} (exit {})", cmd.command.trim(), cmd.exit_code)
+    };
+    let body = format!("Took {} in {}", format_duration(cmd.duration_ms), cmd.cwd);
+    (title, body)
+}
+
+fn show(title: &str, body: &str, failed: bool) -> Result<()> {
+    let mut notifier = if cfg!(target_os = "macos") {
+        let mut osascript = std::process::Command::new("osascript");
+        osascript.args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display notification (item 2 of argv) with title (item 1 of argv)",
+            "-e",
+            "end run",
+            title,
+            body,
+        ]);
+        osascript
+    } else if cfg!(windows) {
+        let mut powershell = std::process::Command::new("powershell");
+        powershell
+            .args(["-NoProfile", "-Command", WINDOWS_BALLOON])
+            .env("SHELLTAPE_TITLE", title)
+            .env("SHELLTAPE_BODY", body);
+        powershell
+    } else {
+        let mut notify_send = std::process::Command::new("notify-send");
+        notify_send.args(["--app-name", "shelltape"]);
+        if failed {
+            notify_send.args(["--urgency", "critical"]);
+        }
+        notify_send.args([title, body]);
+        notify_send
+    };
+
+    let mut child = notifier
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run the notification tool")?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Balloon tip from the tray, available on every Windows version
+const WINDOWS_BALLOON: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+    $n = New-Object System.Windows.Forms.NotifyIcon; \
+    $n.Icon = [System.Drawing.SystemIcons]::Information; \
+    $n.Visible = $true; \
+    $n.ShowBalloonTip(10000, $env:SHELLTAPE_TITLE, $env:SHELLTAPE_BODY, 'Info'); \
+    Start-Sleep -Seconds 10; \
+    $n.Dispose()";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn cmd(exit_code: i32, duration_ms: u64) -> Command {
+        Command {
+            id: "abc".to_string(),
+            command: "cargo build --release ".to_string(),
+            exit_code,
+            cwd: "/src/app".to_string(),
+            started_at: Utc::now(),
+            duration_ms,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_should_notify() {
+        let config = NotifyConfig {
+            desktop: true,
+            min_duration_secs: 60,
+            push: Vec::new(),
+        };
+        assert!(should_notify(&config, &cmd(0, 60_000)));
+        assert!(!should_notify(&config, &cmd(0, 59_999)));
+
+        let off = NotifyConfig {
+            desktop: false,
+            ..config
+        };
+        assert!(!should_notify(&off, &cmd(0, 600_000)));
+    }
+
+    #[test]
+    fn test_push_request() {
+        let ntfy = PushConfig {
+            service: PushService::Ntfy,
+            url: "https://ntfy.example.com/deploys/".to_string(),
+            token: "tk_secret".to_string(),
+            ..PushConfig::default()
+        };
+        let (url, headers, payload) = push_request(&ntfy, "✗ make", "Took 2s", true).unwrap();
+        assert_eq!(url, "https://ntfy.example.com");
+        assert_eq!(headers, vec!["Authorization: Bearer tk_secret"]);
+        let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(payload["topic"], "deploys");
+        assert_eq!(payload["priority"], 4);
+
+        let no_topic = PushConfig {
+            url: "https://ntfy.sh".to_string(),
+            ..ntfy
+        };
+        assert!(push_request(&no_topic, "t", "b", false).is_err());
+
+        let slack = PushConfig {
+            service: PushService::Slack,
+            url: "https://hooks.slack.com/services/T/B/X".to_string(),
+            ..PushConfig::default()
+        };
+        let (url, _, payload) = push_request(&slack, "✓ make", "Took 2s", false).unwrap();
+        assert_eq!(url, slack.url);
+        assert_eq!(payload, r#"{"text":"✓ make\nTook 2s"}"#);
+    }
+
+    #[test]
+    fn test_message() {
+        assert_eq!(
+            message(&cmd(2, 1_203_000)),
+            (
+                "✗ cargo build --release (exit 2)".to_string(),
+                "Took 20m 03s in /src/app".to_string()
+            )
+        );
+        assert_eq!(message(&cmd(0, 1000)).0, "✓ cargo build --release");
+    }
+}
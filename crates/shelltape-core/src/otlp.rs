@@ -0,0 +1,217 @@
+use crate::config::OtlpConfig;
+use crate::models::Command;
+use crate::normalize;
+use crate::webhook;
+use anyhow::{Result, bail};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+/// Path OTLP/HTTP collectors accept traces on
+const TRACES_PATH: &str = "/v1/traces";
+
+/// `SPAN_KIND_INTERNAL`
+const SPAN_KIND: u8 = 1;
+/// `STATUS_CODE_OK` and `STATUS_CODE_ERROR`
+const STATUS_OK: u8 = 1;
+const STATUS_ERROR: u8 = 2;
+
+/// Send a freshly recorded command to the configured collector as a span
+///
+/// Delivery runs in a detached `curl` like webhooks; failures to start it
+/// are logged, not returned. Does nothing without an endpoint.
+pub fn export_live(config: &OtlpConfig, cmd: &Command) {
+    if config.endpoint.is_empty() {
+        return;
+    }
+    let payload = traces(config, std::slice::from_ref(cmd)).to_string();
+    match webhook::post(&traces_url(&config.endpoint), &config.headers, 3, &payload) {
+        Ok(mut child) => {
+            tracing::debug!(pid = child.id(), "OTLP span dispatched");
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => tracing::warn!(endpoint = %config.endpoint, "OTLP export failed: {:#}", e),
+    }
+}
+
+/// Send a batch of commands to a collector and wait for it to accept them
+pub fn export_batch(config: &OtlpConfig, endpoint: &str, commands: &[Command]) -> Result<()> {
+    let payload = traces(config, commands).to_string();
+    let mut child = webhook::post(&traces_url(endpoint), &config.headers, 3, &payload)?;
+
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    if !child.wait()?.success() {
+        bail!("Failed to send spans: {}", stderr.trim());
+    }
+    Ok(())
+}
+
+/// The collector URL for traces, given a base URL or the full traces URL
+pub fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with(TRACES_PATH) {
+        endpoint.to_string()
+    } else {
+        format!("{}{}", endpoint, TRACES_PATH)
+    }
+}
+
+/// An OTLP/JSON `ExportTraceServiceRequest` with one span per command
+///
+/// Each session becomes a trace, and each host a resource.
+pub fn traces(config: &OtlpConfig, commands: &[Command]) -> Value {
+    let mut by_host: BTreeMap<&str, Vec<&Command>> = BTreeMap::new();
+    for cmd in commands {
+        by_host.entry(&cmd.hostname).or_default().push(cmd);
+    }
+
+    let resource_spans: Vec<Value> = by_host
+        .into_iter()
+        .map(|(host, cmds)| {
+            json!({
+                "resource": {
+                    "attributes": [
+                        attribute("service.name", json!({ "stringValue": config.service_name })),
+                        attribute("host.name", json!({ "stringValue": host })),
+                    ]
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "shelltape", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": cmds.into_iter().map(span).collect::<Vec<_>>(),
+                }]
+            })
+        })
+        .collect();
+
+    json!({ "resourceSpans": resource_spans })
+}
+
+fn span(cmd: &Command) -> Value {
+    let start = cmd.started_at.timestamp_nanos_opt().unwrap_or_default();
+    let end = start + cmd.duration_ms as i64 * 1_000_000;
+    let name = normalize::program_and_subcommand(&cmd.command)
+        .unwrap_or_else(|| cmd.command.trim().to_string());
+    let string = |s: &str| json!({ "stringValue": s });
+
+    json!({
+        "traceId": hex_id(&cmd.session_id, 32),
+        "spanId": hex_id(&cmd.id, 16),
+        "name": name,
+        "kind": SPAN_KIND,
+        // 64-bit integers are strings in OTLP/JSON
+        "startTimeUnixNano": start.to_string(),
+        "endTimeUnixNano": end.to_string(),
+        "attributes": [
+            attribute("process.command_line", string(&cmd.command)),
+            attribute("process.exit_code", json!({ "intValue": cmd.exit_code.to_string() })),
+            attribute("process.owner", string(&cmd.username)),
+            attribute("shelltape.cwd", string(&cmd.cwd)),
+            attribute("shelltape.shell", string(&cmd.shell)),
+            attribute("shelltape.session_id", string(&cmd.session_id)),
+            attribute("shelltape.command_id", string(&cmd.id)),
+        ],
+        "status": if cmd.exit_code == 0 {
+            json!({ "code": STATUS_OK })
+        } else {
+            json!({ "code": STATUS_ERROR, "message": format!("exit code {}", cmd.exit_code) })
+        },
+    })
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+/// A trace or span ID of `len` hex digits taken from a UUID
+///
+/// IDs that are not UUIDs (e.g. imported session names) are hashed instead,
+/// so the same ID always maps to the same trace.
+fn hex_id(id: &str, len: usize) -> String {
+    let hex: String = id.chars().filter(|c| *c != '-').collect();
+    if hex.len() >= len && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return hex[..len].to_ascii_lowercase();
+    }
+
+    // FNV-1a, once per 16 digits with a different seed
+    let mut out = String::new();
+    for seed in 0..len.div_ceil(16) as u64 {
+        let mut hash = 0xcbf29ce484222325u64 ^ seed;
+        for byte in id.bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+        out.push_str(&format!("{:016x}", hash));
+    }
+    out.truncate(len);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn cmd(command: &str, exit_code: i32, session_id: &str) -> Command {
+        Command {
+            id: "0190f3a2-b7c4-7d1e-8a9b-0c1d2e3f4a5b".to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: "/src".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap(),
+            duration_ms: 1500,
+            session_id: session_id.to_string(),
+            shell: "bash".to_string(),
+            hostname: "ci".to_string(),
+            username: "runner".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_traces() {
+        let commands = vec![
+            cmd(
+                "cargo test --all",
+                101,
+                "0190f3a2-0000-7000-8000-000000000001",
+            ),
+            cmd("make", 0, "job-42"),
+        ];
+        let traces = traces(&OtlpConfig::default(), &commands);
+        let resource = &traces["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][1]["value"]["stringValue"],
+            "ci"
+        );
+
+        let spans = resource["scopeSpans"][0]["spans"].as_array().unwrap();
+        assert_eq!(spans.len(), 2);
+        let failed = &spans[0];
+        assert_eq!(failed["name"], "cargo test");
+        assert_eq!(failed["traceId"], "0190f3a2000070008000000000000001");
+        assert_eq!(failed["spanId"], "0190f3a2b7c47d1e");
+        assert_eq!(failed["startTimeUnixNano"], "1792227600000000000");
+        assert_eq!(failed["endTimeUnixNano"], "1792227601500000000");
+        assert_eq!(failed["status"]["code"], STATUS_ERROR);
+        assert_eq!(failed["attributes"][1]["value"]["intValue"], "101");
+
+        let hashed = spans[1]["traceId"].as_str().unwrap();
+        assert_eq!(hashed.len(), 32);
+        assert_eq!(hashed, hex_id("job-42", 32));
+        assert_ne!(hashed, hex_id("job-43", 32));
+    }
+
+    #[test]
+    fn test_traces_url() {
+        assert_eq!(
+            traces_url("http://localhost:4318/"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("http://collector/v1/traces"),
+            "http://collector/v1/traces"
+        );
+    }
+}
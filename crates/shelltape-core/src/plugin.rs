@@ -0,0 +1,109 @@
+use crate::config::{PluginConfig, expand_home};
+use crate::models::Command;
+use anyhow::{Context, Result};
+use std::io::{Seek, Write};
+use std::path::Path;
+use std::process::{Child, Stdio};
+
+/// Run every configured plugin for a freshly appended command
+///
+/// Each plugin is started in the background with the record as JSON on
+/// stdin, so a slow plugin never holds up the shell; failures to start one
+/// are logged, not returned.
+pub fn run(plugins: &[PluginConfig], data_dir: &Path, cmd: &Command) {
+    if plugins.is_empty() {
+        return;
+    }
+    let record = serde_json::to_string(cmd).unwrap_or_default();
+
+    for plugin in plugins {
+        match spawn(plugin, data_dir, &record) {
+            Ok(mut child) => {
+                tracing::debug!(plugin = %plugin.name, pid = child.id(), "Plugin started");
+                // Reap the child in long-running processes like `serve`; a
+                // short-lived recorder exits first and leaves it running
+                std::thread::spawn(move || child.wait());
+            }
+            Err(e) => tracing::warn!(plugin = %plugin.name, "Plugin failed: {:#}", e),
+        }
+    }
+}
+
+/// Start a plugin and hand it the record
+///
+/// The record is read from an unlinked temporary file rather than a pipe, so
+/// a large one never blocks the recorder on a plugin that reads slowly, and
+/// outlives a recorder that exits first.
+fn spawn(plugin: &PluginConfig, data_dir: &Path, record: &str) -> Result<Child> {
+    let mut input = tempfile::tempfile().context("Failed to create the plugin's input")?;
+    input.write_all(record.as_bytes())?;
+    input.rewind()?;
+
+    let program = expand_home(&plugin.command);
+    std::process::Command::new(&program)
+        .args(&plugin.args)
+        .env("SHELLTAPE_EVENT", "record")
+        .env("SHELLTAPE_DATA_DIR", data_dir)
+        .stdin(input)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run plugin: {}", program.display()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_spawn() {
+        let dir = TempDir::new().unwrap();
+        let out = dir.path().join("out");
+        let plugin = PluginConfig {
+            name: "capture".to_string(),
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!(
+                    r#"printf "$SHELLTAPE_EVENT " > {0}; cat >> {0}"#,
+                    out.display()
+                ),
+            ],
+        };
+        let cmd = Command {
+            id: "abc".to_string(),
+            command: "make".to_string(),
+            exit_code: 0,
+            cwd: "/src".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 1,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        };
+
+        let record = serde_json::to_string(&cmd).unwrap();
+        let status = spawn(&plugin, dir.path(), &record).unwrap().wait().unwrap();
+        assert!(status.success());
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            format!("record {}", record)
+        );
+
+        // A record bigger than a pipe buffer does not wait for the plugin
+        let slow = PluginConfig {
+            name: "slow".to_string(),
+            command: "sleep".to_string(),
+            args: vec!["5".to_string()],
+        };
+        let started = std::time::Instant::now();
+        let mut child = spawn(&slow, dir.path(), &"x".repeat(1 << 20)).unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
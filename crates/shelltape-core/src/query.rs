@@ -0,0 +1,199 @@
+use crate::models::{Command, CommandView};
+use crate::storage::Storage;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+/// Criteria for selecting commands from the history
+///
+/// Every criterion is optional; an empty query matches everything.
+///
+/// ```
+/// use chrono::{Duration, Utc};
+/// use shelltape_core::query::Query;
+///
+/// let query = Query::new()
+///     .text("cargo")
+///     .failed()
+///     .since(Utc::now() - Duration::days(7))
+///     .limit(10);
+/// # let _ = query;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// Substring of the command line, directory or output (case-insensitive)
+    pub text: Option<String>,
+    /// Tag the command must carry (case-insensitive)
+    pub tag: Option<String>,
+    /// Session ID or ID prefix
+    pub session: Option<String>,
+    /// Only commands that exited with a non-zero code
+    pub failed: bool,
+    /// Only commands started at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only commands started before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Most commands to return
+    pub limit: Option<usize>,
+}
+
+impl Query {
+    /// A query matching every command
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match commands whose line, directory or output contains `text`
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Match commands carrying `tag`
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Match commands from the session with this ID or ID prefix
+    pub fn session(mut self, session: impl Into<String>) -> Self {
+        self.session = Some(session.into());
+        self
+    }
+
+    /// Match only failed commands
+    pub fn failed(mut self) -> Self {
+        self.failed = true;
+        self
+    }
+
+    /// Match commands started at or after `since`
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Match commands started before `until`
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Return at most `limit` commands
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Check whether a command passes every criterion (the limit aside)
+    pub fn matches(&self, cmd: &Command) -> bool {
+        self.matches_view(&cmd.view())
+    }
+
+    /// Like [`Query::matches`], for a record still borrowed from the file
+    pub fn matches_view(&self, cmd: &CommandView) -> bool {
+        if self.failed && cmd.exit_code == 0 {
+            return false;
+        }
+        if self.since.is_some_and(|t| cmd.started_at < t)
+            || self.until.is_some_and(|t| cmd.started_at >= t)
+        {
+            return false;
+        }
+        if let Some(session) = &self.session
+            && !cmd.session_id.starts_with(session.as_str())
+        {
+            return false;
+        }
+        if let Some(tag) = &self.tag
+            && !cmd.has_tag(tag)
+        {
+            return false;
+        }
+        self.text.as_ref().is_none_or(|t| cmd.matches_query(t))
+    }
+
+    /// Run the query against the history, most recent first
+    pub fn run(&self, storage: &Storage) -> Result<Vec<Command>> {
+        // Without text to prefilter on, the newest records are read first
+        if let (Some(limit), None) = (self.limit, &self.text) {
+            return storage.recent_commands_matching(limit, |cmd| self.matches(cmd));
+        }
+
+        let mut commands =
+            storage.scan_commands(self.text.as_deref(), &|cmd| self.matches_view(cmd))?;
+        commands.sort_by_key(|c| std::cmp::Reverse(c.started_at));
+        if let Some(limit) = self.limit {
+            commands.truncate(limit);
+        }
+        Ok(commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use tempfile::TempDir;
+
+    fn cmd(command: &str, exit_code: i32, minute: i64, tags: &[&str]) -> Command {
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: "/src".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap()
+                + Duration::minutes(minute),
+            duration_ms: 10,
+            session_id: "abc123".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        for c in [
+            cmd("cargo build", 0, 0, &[]),
+            cmd("cargo test", 1, 1, &["ci"]),
+            cmd("cargo test", 1, 2, &[]),
+            cmd("ls", 2, 3, &["ci"]),
+        ] {
+            storage.append_command(&c).unwrap();
+        }
+        let start = Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap();
+
+        let lines = |query: Query| -> Vec<(String, i64)> {
+            query
+                .run(&storage)
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.command, (c.started_at - start).num_minutes()))
+                .collect()
+        };
+
+        assert_eq!(Query::new().run(&storage).unwrap().len(), 4);
+        assert_eq!(
+            lines(Query::new().text("CARGO").failed()),
+            vec![("cargo test".to_string(), 2), ("cargo test".to_string(), 1)]
+        );
+        assert_eq!(
+            lines(Query::new().tag("CI").limit(1)),
+            vec![("ls".to_string(), 3)]
+        );
+        assert_eq!(
+            lines(
+                Query::new()
+                    .session("abc")
+                    .since(start + Duration::minutes(1))
+                    .until(start + Duration::minutes(3))
+            ),
+            vec![("cargo test".to_string(), 2), ("cargo test".to_string(), 1)]
+        );
+        assert!(lines(Query::new().session("zzz")).is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+//! Reading the commands file backwards, newest records first
+//!
+//! Records are appended as commands finish, so the end of `commands.jsonl`
+//! holds the newest ones, but not strictly in start-time order: a long
+//! build that started an hour ago lands after everything run meanwhile,
+//! and imports can add older history at any point. [`Recent`] walks the
+//! lines from the end, holds what it has read in a heap, and hands a
+//! record out once the index says nothing earlier in the file started
+//! later. Tail queries then read about as many records as they return.
+
+use crate::index::Bounds;
+use crate::mapped::Contents;
+use crate::models::Command;
+use crate::storage::RecordPos;
+use anyhow::{Context, Result, anyhow};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// Commands in start-time order, newest first, that `keep` accepts
+///
+/// Ties keep file order, matching a stable sort of the whole file.
+pub(crate) struct Recent<K> {
+    data: Contents,
+    bounds: Bounds,
+    /// Start of the last line read; everything before it is still unread
+    pos: usize,
+    pending: BinaryHeap<Pending>,
+    keep: K,
+}
+
+struct Pending {
+    key: (i64, Reverse<u64>),
+    cmd: Command,
+    len: u64,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl<K: FnMut(&Command) -> bool> Recent<K> {
+    /// Read `data`, the commands file's contents, up to where `bounds` ends
+    pub fn new(data: Contents, bounds: Bounds, keep: K) -> Result<Self> {
+        let pos = usize::try_from(bounds.covered())?;
+        if pos > data.len() || (pos > 0 && data[pos - 1] != b'\n') {
+            return Err(anyhow!("Command index runs past the commands file"));
+        }
+        Ok(Self {
+            data,
+            bounds,
+            pos,
+            pending: BinaryHeap::new(),
+            keep,
+        })
+    }
+
+    /// The next newest command, with where it sits in the file
+    pub fn next(&mut self) -> Result<Option<(Command, RecordPos)>> {
+        loop {
+            if let Some(top) = self.pending.peek() {
+                let earlier = self.bounds.latest_before(self.pos as u64);
+                // Equal start times could still be an earlier line, which goes first
+                if earlier.is_none_or(|latest| top.key.0 > latest) {
+                    break;
+                }
+            }
+            if !self.read_line()? {
+                break;
+            }
+        }
+        Ok(self.pending.pop().map(|p| {
+            let pos = RecordPos {
+                offset: (p.key.1).0,
+                len: p.len,
+            };
+            (p.cmd, pos)
+        }))
+    }
+
+    /// Whether every accepted command has been handed out
+    pub fn is_done(&self) -> bool {
+        self.pos == 0 && self.pending.is_empty()
+    }
+
+    /// Read the line ending just before `pos`, or return false at the start
+    fn read_line(&mut self) -> Result<bool> {
+        while self.pos > 0 {
+            let end = self.pos - 1;
+            let start = memchr::memrchr(b'\n', &self.data[..end]).map_or(0, |i| i + 1);
+            self.pos = start;
+
+            let line = &self.data[start..end];
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            let cmd: Command = serde_json::from_slice(line).with_context(|| {
+                format!(
+                    "Failed to parse command at offset {} in commands file",
+                    start
+                )
+            })?;
+            if (self.keep)(&cmd) {
+                let started_at = cmd.started_at.timestamp_nanos_opt().unwrap_or(i64::MIN);
+                self.pending.push(Pending {
+                    key: (started_at, Reverse(start as u64)),
+                    cmd,
+                    len: line.len() as u64,
+                });
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::CommandIndex;
+    use crate::mapped;
+    use chrono::{Duration, TimeZone, Utc};
+    use std::cell::Cell;
+    use tempfile::TempDir;
+
+    fn line(id: &str, minute: i64) -> String {
+        let cmd = Command {
+            id: id.to_string(),
+            command: "make".to_string(),
+            exit_code: 0,
+            cwd: "/tmp".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap()
+                + Duration::minutes(minute),
+            duration_ms: 1,
+            session_id: "session-1".to_string(),
+            shell: "bash".to_string(),
+            hostname: "localhost".to_string(),
+            username: "testuser".to_string(),
+            ..Default::default()
+        };
+        serde_json::to_string(&cmd).unwrap() + "\n"
+    }
+
+    #[test]
+    fn test_reads_only_the_tail() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("commands.jsonl");
+        let mut data: String = (0..100).map(|i| line(&i.to_string(), i)).collect();
+        // A long command that started earlier but finished last, and a tie
+        data += &line("early", 97);
+        data += &line("tie", 99);
+        std::fs::write(&path, data).unwrap();
+
+        let read = Cell::new(0);
+        let bounds = CommandIndex::for_file(&path).open(&path).unwrap();
+        let contents = mapped::open(&path).unwrap().unwrap();
+        let mut recent = Recent::new(contents, bounds, |_: &Command| {
+            read.set(read.get() + 1);
+            true
+        })
+        .unwrap();
+
+        let mut ids = Vec::new();
+        for _ in 0..4 {
+            ids.push(recent.next().unwrap().unwrap().0.id);
+        }
+        assert_eq!(ids, ["99", "tie", "98", "97"]);
+        assert!(read.get() <= 7, "read {} lines", read.get());
+        assert!(!recent.is_done());
+    }
+}
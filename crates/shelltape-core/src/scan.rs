@@ -0,0 +1,215 @@
+//! Parallel parsing of the commands file
+//!
+//! The file is split into chunks at line boundaries and parsed on all cores,
+//! into [`CommandView`]s that borrow from the file where they can. When
+//! looking for a substring, lines that cannot contain it are skipped with a
+//! byte search before any JSON is parsed.
+
+use crate::models::CommandView;
+use crate::storage::RecordPos;
+use anyhow::{Context, Result};
+use memchr::memmem::Finder;
+use rayon::prelude::*;
+
+/// Smallest chunk handed to one thread
+const MIN_CHUNK: usize = 256 * 1024;
+
+/// Parse the records in `data`, keeping what `f` makes of them, in file order
+///
+/// `f` sees each record borrowed from `data`, and copies out what it keeps.
+///
+/// With `text`, lines are first checked for it case-insensitively; only
+/// possible matches are parsed. `f` must still do the exact check.
+pub(crate) fn scan<T: Send>(
+    data: &[u8],
+    text: Option<&str>,
+    f: &(dyn Fn(CommandView<'_>, RecordPos) -> Option<T> + Sync),
+) -> Result<Vec<T>> {
+    scan_at(data, 0, 1, text, f)
+}
+
+/// Like [`scan`], for `data` read from `offset` of the file, where line
+/// `first_line` starts
+pub(crate) fn scan_at<T: Send>(
+    data: &[u8],
+    offset: u64,
+    first_line: usize,
+    text: Option<&str>,
+    f: &(dyn Fn(CommandView<'_>, RecordPos) -> Option<T> + Sync),
+) -> Result<Vec<T>> {
+    let prefilter = text.and_then(Prefilter::new);
+    let chunks = chunks(data);
+
+    // Line numbers for error messages, from the newlines before each chunk
+    let mut first_lines = Vec::with_capacity(chunks.len());
+    let mut line = first_line;
+    for chunk in &chunks {
+        first_lines.push(line);
+        line += memchr::memchr_iter(b'\n', chunk).count();
+    }
+
+    let parsed = chunks
+        .par_iter()
+        .zip(first_lines)
+        .map(|(chunk, first_line)| {
+            // Chunks are slices of `data`, so this is where the chunk starts
+            let base = offset + (chunk.as_ptr() as usize - data.as_ptr() as usize) as u64;
+            scan_chunk(chunk, base, first_line, prefilter.as_ref(), f)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(parsed.into_iter().flatten().collect())
+}
+
+fn scan_chunk<T>(
+    chunk: &[u8],
+    base: u64,
+    first_line: usize,
+    prefilter: Option<&Prefilter>,
+    f: &(dyn Fn(CommandView<'_>, RecordPos) -> Option<T> + Sync),
+) -> Result<Vec<T>> {
+    let lowered = prefilter.map(|_| chunk.to_ascii_lowercase());
+    let mut kept = Vec::new();
+    let mut start = 0;
+
+    for (i, end) in memchr::memchr_iter(b'\n', chunk)
+        .chain(std::iter::once(chunk.len()))
+        .enumerate()
+    {
+        let line_start = start;
+        let line = &chunk[line_start..end];
+        start = end + 1;
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        if let (Some(prefilter), Some(lowered)) = (prefilter, &lowered)
+            && !prefilter.may_match(line, &lowered[line_start..end])
+        {
+            continue;
+        }
+
+        let cmd: CommandView = serde_json::from_slice(line).with_context(|| {
+            format!(
+                "Failed to parse command from line {} in commands file",
+                first_line + i
+            )
+        })?;
+        let pos = RecordPos {
+            offset: base + line_start as u64,
+            len: line.len() as u64,
+        };
+        kept.extend(f(cmd, pos));
+    }
+    Ok(kept)
+}
+
+/// Split `data` into chunks ending at newlines, enough to keep every core busy
+fn chunks(data: &[u8]) -> Vec<&[u8]> {
+    let target = (data.len() / (rayon::current_num_threads() * 4)).max(MIN_CHUNK);
+    let mut chunks = Vec::new();
+    let mut rest = data;
+    while rest.len() > target {
+        match memchr::memchr(b'\n', &rest[target..]) {
+            Some(i) => {
+                let (chunk, tail) = rest.split_at(target + i + 1);
+                chunks.push(chunk);
+                rest = tail;
+            }
+            None => break,
+        }
+    }
+    if !rest.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+/// Whether `text` is spelled the same in a record's line as in its fields
+///
+/// Not so for JSON escapes (quotes, backslashes, control characters) and for
+/// non-ASCII text, whose case folding bytes cannot follow.
+pub(crate) fn byte_searchable(text: &str) -> bool {
+    text.bytes()
+        .all(|b| b.is_ascii() && !b.is_ascii_control() && b != b'"' && b != b'\\')
+}
+
+/// A byte search that rules out lines which cannot contain the text
+struct Prefilter {
+    finder: Finder<'static>,
+}
+
+impl Prefilter {
+    /// `None` when the raw line may spell the text differently than the
+    /// record does
+    fn new(text: &str) -> Option<Self> {
+        (!text.is_empty() && byte_searchable(text)).then(|| Self {
+            finder: Finder::new(text.to_ascii_lowercase().as_bytes()).into_owned(),
+        })
+    }
+
+    /// Whether `line` may hold the text; `lowered` is the line in ASCII lowercase
+    fn may_match(&self, line: &[u8], lowered: &[u8]) -> bool {
+        // Non-ASCII characters can lowercase to ASCII ones (e.g. the Kelvin sign)
+        !line.is_ascii() || self.finder.find(lowered).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Command;
+    use std::borrow::Cow;
+
+    fn line(id: usize, command: &str) -> String {
+        format!(
+            r#"{{"id":"{}","command":"{}","output":"","exit_code":0,"cwd":"/","started_at":"2026-10-17T09:00:00Z","duration_ms":1,"session_id":"s","shell":"bash","hostname":"h","username":"u"}}"#,
+            id, command
+        )
+    }
+
+    #[test]
+    fn test_scan() {
+        let mut data = String::new();
+        for i in 0..20_000 {
+            let command = match i % 3 {
+                0 => "cargo BUILD",
+                1 => "ls -la",
+                _ => "echo \\\"quoted\\\" K\u{212a}",
+            };
+            data.push_str(&line(i, command));
+            data.push('\n');
+        }
+        assert!(chunks(data.as_bytes()).len() > 1);
+
+        // Plain strings point into the data, escaped ones are unescaped
+        let borrowed = scan(data.as_bytes(), None, &|c, _| {
+            Some(matches!(c.command, Cow::Borrowed(_)))
+        })
+        .unwrap();
+        assert_eq!(&borrowed[..3], [true, true, false]);
+
+        let all = scan(data.as_bytes(), None, &|c, pos| Some((c.into_owned(), pos))).unwrap();
+        assert_eq!(all.len(), 20_000);
+        assert!(all.windows(2).all(|w| {
+            w[0].0.id.parse::<usize>().unwrap() < w[1].0.id.parse::<usize>().unwrap()
+        }));
+        let (last, pos) = &all[19_999];
+        let raw = &data[pos.offset as usize..(pos.offset + pos.len) as usize];
+        assert_eq!(serde_json::from_str::<Command>(raw).unwrap().id, last.id);
+
+        let search = |text: &str| {
+            scan(data.as_bytes(), Some(text), &|c, _| {
+                c.matches_query(text).then_some(())
+            })
+            .unwrap()
+            .len()
+        };
+        assert_eq!(search("build"), 6667);
+        assert_eq!(search("\"quoted\""), 6666);
+        // Only found through Unicode case folding
+        assert_eq!(search("kk"), 6666);
+
+        let bad = format!("{}\n\n{{oops\n", line(1, "ls"));
+        let err = scan(bad.as_bytes(), None, &|_, _| Some(())).unwrap_err();
+        assert!(format!("{:#}", err).contains("line 3"));
+    }
+}
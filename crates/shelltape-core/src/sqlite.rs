@@ -0,0 +1,638 @@
+//! SQLite backend: the commands in `commands.db`
+//!
+//! Each command is a row holding its JSON record, next to the columns the
+//! lookups need: the ID (unique), the start time (indexed, for newest-first
+//! reads and pages) and whether the record is plain ASCII (so `LIKE` can
+//! rule out records for text searches). `seq` counts rows in the order they
+//! were added, which is what ties in start time fall back to, like file
+//! order does for JSONL.
+//!
+//! Text searches go through `commands_text`, an FTS5 table with the
+//! trigram tokenizer over each command's line, directory and output. It
+//! stores no copy of the text, only the index, and triggers keep it in step
+//! with `commands`. A quoted phrase finds the rows containing a substring
+//! of three or more characters, case-insensitively; shorter or
+//! non-ASCII queries fall back to a `LIKE` scan.
+//!
+//! The stats aggregate is kept in the `meta` table with the last `seq` it
+//! covers, and catches up with newer rows when read.
+
+use crate::aggregate::{Aggregate, CACHE_VERSION};
+use crate::backend::{Pager, StorageBackend};
+use crate::models::{Command, CommandView};
+use crate::scan::byte_searchable;
+use crate::storage::{CommandPages, RecordPos, unique_match};
+use anyhow::{Context, Result, anyhow};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS commands (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        id TEXT NOT NULL UNIQUE,
+        started_at INTEGER NOT NULL,
+        ascii INTEGER NOT NULL,
+        record TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS commands_started_at ON commands (started_at);
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+
+/// Added in schema version 2, and filled from the rows already there
+const TEXT_INDEX: &str = "
+    CREATE INDEX commands_non_ascii ON commands (seq) WHERE NOT ascii;
+    CREATE VIRTUAL TABLE commands_text USING fts5(
+        command, cwd, output,
+        content = '', contentless_delete = 1, tokenize = 'trigram'
+    );
+    CREATE TRIGGER commands_text_insert AFTER INSERT ON commands BEGIN
+        INSERT INTO commands_text (rowid, command, cwd, output) VALUES (
+            new.seq,
+            json_extract(new.record, '$.command'),
+            json_extract(new.record, '$.cwd'),
+            json_extract(new.record, '$.output')
+        );
+    END;
+    CREATE TRIGGER commands_text_delete AFTER DELETE ON commands BEGIN
+        DELETE FROM commands_text WHERE rowid = old.seq;
+    END;
+    CREATE TRIGGER commands_text_update AFTER UPDATE OF record ON commands BEGIN
+        DELETE FROM commands_text WHERE rowid = old.seq;
+        INSERT INTO commands_text (rowid, command, cwd, output) VALUES (
+            new.seq,
+            json_extract(new.record, '$.command'),
+            json_extract(new.record, '$.cwd'),
+            json_extract(new.record, '$.output')
+        );
+    END;
+    INSERT INTO commands_text (rowid, command, cwd, output)
+        SELECT seq,
+            json_extract(record, '$.command'),
+            json_extract(record, '$.cwd'),
+            json_extract(record, '$.output')
+        FROM commands;
+";
+
+/// Kept in `PRAGMA user_version`
+const SCHEMA_VERSION: i64 = 2;
+
+/// Records without their output and timing, for listings
+const METADATA: &str = "json_set(json_remove(record, '$.timing'), '$.output', '')";
+
+/// The aggregate as kept in `meta`
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    version: u32,
+    /// Last `seq` tallied
+    covered: i64,
+    aggregate: Aggregate,
+}
+
+/// Commands kept in an SQLite database
+pub struct SqliteBackend {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteBackend {
+    /// Open (or create) the database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut conn = Connection::open(path)
+            .with_context(|| format!("Failed to open database: {}", path.display()))?;
+        // Shells record concurrently; wait for each other instead of failing
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        migrate(&mut conn)
+            .with_context(|| format!("Failed to set up database: {}", path.display()))?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        lock(&self.conn)
+    }
+
+    /// Call `f` on every record `sql` selects, with its `seq`
+    fn each_record(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+        mut f: impl FnMut(i64, &str) -> Result<()>,
+    ) -> Result<()> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(sql)?;
+        let mut rows = stmt.query(params)?;
+        while let Some(row) = rows.next()? {
+            let seq: i64 = row.get(0)?;
+            let record = row.get_ref(1)?.as_str()?;
+            f(seq, record).with_context(|| format!("Failed to read command in row {}", seq))?;
+        }
+        Ok(())
+    }
+
+    /// Tally the rows after `covered` into `aggregate`, returning the last
+    fn fold(&self, covered: i64, aggregate: &mut Aggregate) -> Result<i64> {
+        let mut last = covered;
+        self.each_record(
+            "SELECT seq, record FROM commands WHERE seq > ?1 ORDER BY seq",
+            [covered],
+            |seq, record| {
+                aggregate.add(&serde_json::from_str(record)?);
+                last = seq;
+                Ok(())
+            },
+        )?;
+        Ok(last)
+    }
+
+    fn invalidate_stats(conn: &Connection) -> Result<()> {
+        conn.execute("DELETE FROM meta WHERE key = 'stats'", [])?;
+        Ok(())
+    }
+}
+
+impl StorageBackend for SqliteBackend {
+    fn append_commands(&self, cmds: &[Command]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO commands (id, started_at, ascii, record) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for cmd in cmds {
+                let record = serde_json::to_string(cmd)
+                    .with_context(|| "Failed to serialize command to JSON")?;
+                stmt.execute(params![cmd.id, nanos(cmd)?, record.is_ascii(), record])
+                    .with_context(|| format!("Failed to insert command {}", cmd.id))?;
+            }
+        }
+        tx.commit()?;
+        tracing::debug!(count = cmds.len(), "Inserted commands");
+        Ok(())
+    }
+
+    fn scan_commands(
+        &self,
+        text: Option<&str>,
+        keep: &(dyn Fn(&CommandView) -> bool + Sync),
+    ) -> Result<Vec<Command>> {
+        let mut commands = Vec::new();
+        let mut add = |_: i64, record: &str| {
+            let cmd: CommandView = serde_json::from_str(record)?;
+            if keep(&cmd) {
+                commands.push(cmd.into_owned());
+            }
+            Ok(())
+        };
+        match text.and_then(phrase) {
+            Some(phrase) => self.each_record(
+                "SELECT seq, record FROM commands WHERE seq IN (
+                     SELECT rowid FROM commands_text WHERE commands_text MATCH ?1
+                     UNION SELECT seq FROM commands WHERE NOT ascii
+                 ) ORDER BY seq",
+                [phrase],
+                &mut add,
+            )?,
+            None => self.each_record(
+                "SELECT seq, record FROM commands
+                 WHERE ?1 IS NULL OR NOT ascii OR record LIKE ?1 ESCAPE '\\'
+                 ORDER BY seq",
+                [text.and_then(like_pattern)],
+                &mut add,
+            )?,
+        }
+        Ok(commands)
+    }
+
+    fn read_command_metadata(&self) -> Result<Vec<(Command, RecordPos)>> {
+        let mut commands = Vec::new();
+        self.each_record(
+            &format!("SELECT seq, {} FROM commands ORDER BY seq", METADATA),
+            params![],
+            |seq, record| {
+                commands.push((serde_json::from_str(record)?, row_pos(seq)));
+                Ok(())
+            },
+        )?;
+        Ok(commands)
+    }
+
+    fn search_command_ids(&self, query: &str) -> Result<HashSet<String>> {
+        Ok(self
+            .scan_commands(Some(query), &|cmd| cmd.matches_query(query))?
+            .into_iter()
+            .map(|cmd| cmd.id)
+            .collect())
+    }
+
+    fn read_command_at(&self, pos: RecordPos) -> Result<Command> {
+        let record: Option<String> = self
+            .conn()?
+            .query_row(
+                "SELECT record FROM commands WHERE seq = ?1",
+                [pos.offset as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let record = record.ok_or_else(|| anyhow!("No command in row {}", pos.offset))?;
+        Ok(serde_json::from_str(&record)?)
+    }
+
+    fn recent_commands(
+        &self,
+        limit: usize,
+        keep: &mut dyn FnMut(&Command) -> bool,
+    ) -> Result<Vec<Command>> {
+        let mut commands = Vec::new();
+        if limit == 0 {
+            return Ok(commands);
+        }
+        let conn = self.conn()?;
+        let mut stmt =
+            conn.prepare_cached("SELECT record FROM commands ORDER BY started_at DESC, seq ASC")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let cmd: Command = serde_json::from_str(row.get_ref(0)?.as_str()?)?;
+            if keep(&cmd) {
+                commands.push(cmd);
+                if commands.len() == limit {
+                    break;
+                }
+            }
+        }
+        tracing::debug!(count = commands.len(), "Read recent commands");
+        Ok(commands)
+    }
+
+    fn command_pages(&self) -> Result<CommandPages> {
+        Ok(CommandPages::new(Box::new(RowPager {
+            conn: Arc::clone(&self.conn),
+            after: None,
+            done: false,
+        })))
+    }
+
+    fn commands_with_id_prefix(&self, prefix: &str) -> Result<Vec<Command>> {
+        // A range on the unique index: every ID with the prefix sorts in it
+        let end = format!("{}\u{10ffff}", prefix);
+        let mut commands = Vec::new();
+        self.each_record(
+            "SELECT seq, record FROM commands WHERE id >= ?1 AND id < ?2 ORDER BY seq",
+            [prefix, end.as_str()],
+            |_, record| {
+                commands.push(serde_json::from_str(record)?);
+                Ok(())
+            },
+        )?;
+        Ok(commands)
+    }
+
+    fn update_command(
+        &self,
+        id: &str,
+        update: Box<dyn FnOnce(&mut Command) + '_>,
+    ) -> Result<Command> {
+        let mut cmd = unique_match(id, self.commands_with_id_prefix(id)?)?;
+        update(&mut cmd);
+
+        let conn = self.conn()?;
+        let record =
+            serde_json::to_string(&cmd).with_context(|| "Failed to serialize command to JSON")?;
+        let changed = conn.execute(
+            "UPDATE commands SET started_at = ?2, ascii = ?3, record = ?4 WHERE id = ?1",
+            params![cmd.id, nanos(&cmd)?, record.is_ascii(), record],
+        )?;
+        if changed == 0 {
+            return Err(anyhow!("No command found with ID: {}", cmd.id));
+        }
+        Self::invalidate_stats(&conn)?;
+        Ok(cmd)
+    }
+
+    fn merge_commands(&self, mut incoming: Vec<Command>) -> Result<usize> {
+        incoming.sort_by_key(|c| c.started_at);
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut added = 0;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR IGNORE INTO commands (id, started_at, ascii, record)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for cmd in &incoming {
+                let record = serde_json::to_string(cmd)
+                    .with_context(|| "Failed to serialize command to JSON")?;
+                added += stmt.execute(params![cmd.id, nanos(cmd)?, record.is_ascii(), record])?;
+            }
+        }
+        tx.commit()?;
+        Ok(added)
+    }
+
+    fn retain_commands(&self, keep: &mut dyn FnMut(&Command) -> bool) -> Result<usize> {
+        let mut remove = Vec::new();
+        self.each_record(
+            "SELECT seq, record FROM commands ORDER BY seq",
+            params![],
+            |seq, record| {
+                if !keep(&serde_json::from_str(record)?) {
+                    remove.push(seq);
+                }
+                Ok(())
+            },
+        )?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached("DELETE FROM commands WHERE seq = ?1")?;
+            for seq in &remove {
+                stmt.execute([seq])?;
+            }
+        }
+        if !remove.is_empty() {
+            Self::invalidate_stats(&tx)?;
+        }
+        tx.commit()?;
+        Ok(remove.len())
+    }
+
+    fn delete_commands(&self, ids: &HashSet<String>) -> Result<usize> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        {
+            let mut stmt = tx.prepare_cached("DELETE FROM commands WHERE id = ?1")?;
+            for id in ids {
+                removed += stmt.execute([id])?;
+            }
+        }
+        if removed > 0 {
+            Self::invalidate_stats(&tx)?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    fn end_cursor(&self) -> Result<u64> {
+        let last: i64 =
+            self.conn()?
+                .query_row("SELECT COALESCE(MAX(seq), 0) FROM commands", [], |row| {
+                    row.get(0)
+                })?;
+        Ok(last as u64)
+    }
+
+    fn commands_since(&self, cursor: u64) -> Result<(Vec<(Command, RecordPos)>, u64)> {
+        let mut commands = Vec::new();
+        let mut last = cursor as i64;
+        self.each_record(
+            "SELECT seq, record FROM commands WHERE seq > ?1 ORDER BY seq",
+            [cursor as i64],
+            |seq, record| {
+                commands.push((serde_json::from_str(record)?, row_pos(seq)));
+                last = seq;
+                Ok(())
+            },
+        )?;
+        Ok((commands, last as u64))
+    }
+
+    fn sync(&self) -> Result<()> {
+        // With `synchronous = NORMAL` commits reach the disk at checkpoints
+        self.conn()?
+            .execute_batch("PRAGMA wal_checkpoint(PASSIVE)")
+            .with_context(|| "Failed to checkpoint the database")
+    }
+
+    fn aggregate(&self) -> Result<Aggregate> {
+        let cached: Option<String> = self
+            .conn()?
+            .query_row("SELECT value FROM meta WHERE key = 'stats'", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        let mut cached = cached
+            .and_then(|value| serde_json::from_str::<Cached>(&value).ok())
+            .filter(|c| c.version == CACHE_VERSION)
+            .unwrap_or_else(|| Cached {
+                version: CACHE_VERSION,
+                covered: 0,
+                aggregate: Aggregate::default(),
+            });
+
+        let covered = self.fold(cached.covered, &mut cached.aggregate)?;
+        if covered != cached.covered {
+            tracing::debug!(from = cached.covered, to = covered, "Caught up stats");
+            cached.covered = covered;
+            self.conn()?.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('stats', ?1)",
+                [serde_json::to_string(&cached)?],
+            )?;
+        }
+        Ok(cached.aggregate)
+    }
+}
+
+/// Pages newest first, each picking up after the last row handed out
+struct RowPager {
+    conn: Arc<Mutex<Connection>>,
+    /// Start time and `seq` of the last row handed out
+    after: Option<(i64, i64)>,
+    done: bool,
+}
+
+impl Pager for RowPager {
+    fn next_page(&mut self, limit: usize) -> Result<Vec<(Command, RecordPos)>> {
+        if self.done || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let (started_at, seq) = self.after.unwrap_or((i64::MAX, i64::MIN));
+        let conn = lock(&self.conn)?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT seq, started_at, {} FROM commands
+             WHERE started_at < ?1 OR (started_at = ?1 AND seq > ?2)
+             ORDER BY started_at DESC, seq ASC LIMIT ?3",
+            METADATA
+        ))?;
+        let mut rows = stmt.query(params![started_at, seq, limit as i64])?;
+
+        let mut page = Vec::new();
+        while let Some(row) = rows.next()? {
+            let seq: i64 = row.get(0)?;
+            self.after = Some((row.get(1)?, seq));
+            page.push((
+                serde_json::from_str(row.get_ref(2)?.as_str()?)?,
+                row_pos(seq),
+            ));
+        }
+        self.done = page.len() < limit;
+        Ok(page)
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+/// Bring the schema of an older (or new) database up to date
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+    if version < 2 {
+        tracing::debug!("Building text index");
+        tx.execute_batch(TEXT_INDEX)?;
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn lock(conn: &Mutex<Connection>) -> Result<MutexGuard<'_, Connection>> {
+    conn.lock()
+        .map_err(|_| anyhow!("Database connection poisoned"))
+}
+
+fn row_pos(seq: i64) -> RecordPos {
+    RecordPos {
+        offset: seq as u64,
+        len: 0,
+    }
+}
+
+/// Start time in nanoseconds, as the index is kept
+fn nanos(cmd: &Command) -> Result<i64> {
+    cmd.started_at
+        .timestamp_nanos_opt()
+        .ok_or_else(|| anyhow!("Start time out of range: {}", cmd.started_at))
+}
+
+/// An FTS5 query for the rows containing `text`, or `None` when the text
+/// index cannot answer it
+fn phrase(text: &str) -> Option<String> {
+    // Trigrams need three characters; quotes are ruled out with the rest of
+    // what records may spell differently
+    (text.len() >= 3 && byte_searchable(text)).then(|| format!("\"{}\"", text))
+}
+
+/// A `LIKE` pattern ruling out ASCII records without `text`, or `None`
+/// when the record may spell it differently
+fn like_pattern(text: &str) -> Option<String> {
+    byte_searchable(text).then(|| {
+        let escaped = text.replace('%', "\\%").replace('_', "\\_");
+        format!("%{}%", escaped)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Backend;
+    use crate::storage::Storage;
+    use chrono::{TimeZone, Utc};
+    use tempfile::TempDir;
+
+    fn command(id: &str, minute: u32, output: &str) -> Command {
+        Command {
+            id: id.to_string(),
+            command: format!("make {}", id),
+            output: output.to_string(),
+            exit_code: 0,
+            cwd: "/tmp".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, minute, 0).unwrap(),
+            duration_ms: 1,
+            session_id: "session-1".to_string(),
+            shell: "bash".to_string(),
+            hostname: "localhost".to_string(),
+            username: "testuser".to_string(),
+            timing: vec![(0, output.len())],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backend() {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::open(dir.path().to_path_buf(), Backend::Sqlite).unwrap();
+        storage
+            .append_commands(&[
+                command("a1", 3, "first 100%"),
+                command("b2", 1, "second"),
+                command("b3", 3, "third"),
+            ])
+            .unwrap();
+
+        let ids = |cmds: Vec<Command>| cmds.into_iter().map(|c| c.id).collect::<Vec<_>>();
+        let pos_ids =
+            |cmds: Vec<(Command, RecordPos)>| ids(cmds.into_iter().map(|(c, _)| c).collect());
+        assert_eq!(ids(storage.get_recent_commands(2).unwrap()), ["a1", "b3"]);
+
+        let cursor = storage.end_cursor().unwrap();
+        storage.append_command(&command("c4", 0, "older")).unwrap();
+        let (added, next) = storage.commands_since(cursor).unwrap();
+        assert_eq!(storage.read_command_at(added[0].1).unwrap().output, "older");
+        assert_eq!(pos_ids(added), ["c4"]);
+        assert!(storage.commands_since(next).unwrap().0.is_empty());
+        storage
+            .delete_commands(&HashSet::from(["c4".to_string()]))
+            .unwrap();
+        assert_eq!(ids(storage.search_commands("100%", 5).unwrap()), ["a1"]);
+        assert_eq!(ids(storage.search_commands("SECOND", 5).unwrap()), ["b2"]);
+        assert_eq!(ids(storage.search_commands("b", 5).unwrap()).len(), 2);
+        assert_eq!(storage.find_command("a").unwrap().id, "a1");
+        assert!(storage.find_command("b").is_err());
+
+        let mut pages = storage.command_pages().unwrap();
+        let page = pages.next_page(2).unwrap();
+        assert_eq!(
+            (page[1].0.id.as_str(), page[1].0.output.as_str()),
+            ("b3", "")
+        );
+        assert_eq!(storage.read_command_at(page[1].1).unwrap().output, "third");
+        assert_eq!(pages.next_page(2).unwrap()[0].0.id, "b2");
+        assert!(pages.is_done());
+
+        // Stats catch up with new rows and start over after removals
+        assert_eq!(storage.aggregate().unwrap().total, 3);
+        assert_eq!(
+            storage
+                .merge_commands(vec![command("a1", 0, ""), command("c4", 4, "")])
+                .unwrap(),
+            1
+        );
+        assert_eq!(storage.aggregate().unwrap().total, 4);
+        storage
+            .update_command("c4", |c| {
+                c.exit_code = 1;
+                c.output = "fourth".to_string();
+            })
+            .unwrap();
+        assert_eq!(ids(storage.search_commands("fourth", 5).unwrap()), ["c4"]);
+        assert_eq!(storage.retain_commands(|c| c.id != "b2").unwrap(), 1);
+        let aggregate = storage.aggregate().unwrap();
+        assert_eq!((aggregate.total, aggregate.failed), (3, 1));
+        assert!(storage.search_commands("second", 5).unwrap().is_empty());
+
+        // Non-ASCII records are checked whatever the text index says
+        storage
+            .append_command(&command("d5", 5, "273.15 \u{212a}elvin"))
+            .unwrap();
+        assert_eq!(ids(storage.search_commands("kelvin", 5).unwrap()), ["d5"]);
+
+        let delete = HashSet::from(["d5".to_string(), "zz".to_string()]);
+        assert_eq!(storage.delete_commands(&delete).unwrap(), 1);
+        assert_eq!(storage.aggregate().unwrap().total, 3);
+    }
+}
@@ -0,0 +1,950 @@
+use crate::aggregate::Aggregate;
+use crate::backend::{Pager, StorageBackend};
+use crate::config::{Backend, Config};
+use crate::jsonl::{self, JsonlBackend};
+use crate::mapped;
+use crate::models::{Command, CommandView, RunningCommand, Session};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Where a record sits in the backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordPos {
+    /// Byte offset of the line in the commands file, or the row ID in SQLite
+    pub offset: u64,
+    /// Length of the line, without its newline; unused by SQLite
+    pub len: u64,
+}
+
+/// Pages of commands, newest first, for lists filled in as they scroll
+///
+/// Created by [`Storage::command_pages`]. Each page reads little more than
+/// its own records.
+pub struct CommandPages {
+    pager: Box<dyn Pager>,
+}
+
+impl CommandPages {
+    pub fn new(pager: Box<dyn Pager>) -> Self {
+        Self { pager }
+    }
+
+    /// Whether every command has been read
+    pub fn is_done(&self) -> bool {
+        self.pager.is_done()
+    }
+
+    /// Read up to `limit` more commands, without their output and timing
+    pub fn next_page(&mut self, limit: usize) -> Result<Vec<(Command, RecordPos)>> {
+        self.pager.next_page(limit)
+    }
+}
+
+/// Storage manager for shelltape data
+#[derive(Clone)]
+pub struct Storage {
+    data_dir: PathBuf,
+    commands_file: PathBuf,
+    sessions_file: PathBuf,
+    kind: Backend,
+    backend: Arc<dyn StorageBackend>,
+}
+
+impl Storage {
+    /// Create a new Storage instance using the configured data directory
+    /// (defaults to ~/.shelltape/)
+    pub fn new() -> Result<Self> {
+        let config = Config::load()?;
+        Self::open(config.data_dir()?, config.storage.backend)
+    }
+
+    /// Create a new Storage instance with a custom data directory, keeping
+    /// commands in JSONL
+    pub fn with_dir(data_dir: PathBuf) -> Result<Self> {
+        Self::open(data_dir, Backend::Jsonl)
+    }
+
+    /// Create a new Storage instance with a custom data directory and backend
+    pub fn open(data_dir: PathBuf, kind: Backend) -> Result<Self> {
+        tracing::trace!(data_dir = %data_dir.display(), ?kind, "Opening storage");
+        std::fs::create_dir_all(&data_dir)
+            .with_context(|| format!("Failed to create data directory: {}", data_dir.display()))?;
+
+        let commands_file = data_dir.join("commands.jsonl");
+        let sessions_file = data_dir.join("sessions.jsonl");
+        let backend: Arc<dyn StorageBackend> = match kind {
+            Backend::Jsonl => Arc::new(JsonlBackend::new(commands_file.clone())),
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => Arc::new(crate::sqlite::SqliteBackend::open(
+                &data_dir.join("commands.db"),
+            )?),
+            #[cfg(not(feature = "sqlite"))]
+            Backend::Sqlite => {
+                anyhow::bail!("This build of shelltape has no SQLite support")
+            }
+        };
+
+        Ok(Self {
+            data_dir,
+            commands_file,
+            sessions_file,
+            kind,
+            backend,
+        })
+    }
+
+    /// Get the data directory path
+    pub fn data_dir(&self) -> &PathBuf {
+        &self.data_dir
+    }
+
+    /// Get the commands file path
+    ///
+    /// This is the JSONL file whichever backend is in use; see
+    /// [`Storage::history_file`] for the file that holds the commands.
+    pub fn commands_file(&self) -> &PathBuf {
+        &self.commands_file
+    }
+
+    /// Which backend keeps the commands
+    pub fn backend(&self) -> Backend {
+        self.kind
+    }
+
+    /// The file the backend keeps the commands in
+    pub fn history_file(&self) -> PathBuf {
+        match self.kind {
+            Backend::Jsonl => self.commands_file.clone(),
+            Backend::Sqlite => self.data_dir.join("commands.db"),
+        }
+    }
+
+    /// Get the sessions file path
+    pub fn sessions_file(&self) -> &PathBuf {
+        &self.sessions_file
+    }
+
+    /// Get the directory holding markers for commands still executing
+    pub fn running_dir(&self) -> PathBuf {
+        self.data_dir.join("running")
+    }
+
+    /// Write a marker for a command that has started executing
+    pub fn mark_running(&self, running: &RunningCommand) -> Result<()> {
+        let dir = self.running_dir();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+        let json = serde_json::to_string(running)
+            .with_context(|| "Failed to serialize running command to JSON")?;
+        let path = dir.join(format!("{}.json", running.id));
+
+        std::fs::write(&path, json)
+            .with_context(|| format!("Failed to write running marker: {}", path.display()))
+    }
+
+    /// Remove the marker for a command that has finished executing
+    pub fn clear_running(&self, id: &str) -> Result<()> {
+        let path = self.running_dir().join(format!("{}.json", id));
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove running marker: {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Read markers for all commands currently executing
+    ///
+    /// Markers whose wrapper process no longer exists (killed before it could
+    /// clear its marker) are deleted.
+    pub fn read_running(&self) -> Result<Vec<RunningCommand>> {
+        let dir = self.running_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut running = Vec::new();
+        for entry in std::fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read directory: {}", dir.display()))?
+        {
+            let path = entry?.path();
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let Ok(cmd) = serde_json::from_str::<RunningCommand>(&content) else {
+                continue;
+            };
+            if process_alive(cmd.pid) {
+                running.push(cmd);
+            } else if let Err(e) = std::fs::remove_file(&path) {
+                tracing::debug!("Failed to remove stale marker {}: {}", path.display(), e);
+            }
+        }
+
+        running.sort_by_key(|c| c.started_at);
+        Ok(running)
+    }
+
+    /// Append a command to the commands file
+    pub fn append_command(&self, cmd: &Command) -> Result<()> {
+        self.append_commands(std::slice::from_ref(cmd))
+    }
+
+    /// Append commands to the commands file in a single write
+    pub fn append_commands(&self, cmds: &[Command]) -> Result<()> {
+        self.backend.append_commands(cmds)
+    }
+
+    /// A cursor at the end of the history, to follow it with
+    /// [`Storage::commands_since`]
+    pub fn end_cursor(&self) -> Result<u64> {
+        self.backend.end_cursor()
+    }
+
+    /// Commands added after `cursor` with their positions, oldest first, and
+    /// the cursor to continue from
+    pub fn commands_since(&self, cursor: u64) -> Result<(Vec<(Command, RecordPos)>, u64)> {
+        self.backend.commands_since(cursor)
+    }
+
+    /// Flush appended commands to disk
+    ///
+    /// Appends are not synced one by one; writers that batch them (like the
+    /// daemon) call this once per batch.
+    pub fn sync_commands(&self) -> Result<()> {
+        self.backend.sync()
+    }
+
+    /// Read all commands from the commands file
+    pub fn read_all_commands(&self) -> Result<Vec<Command>> {
+        self.scan_commands(None, &|_| true)
+    }
+
+    /// Read the commands `keep` accepts, parsing the file on all cores
+    ///
+    /// With `text`, lines that cannot contain it (case-insensitively) are
+    /// skipped before parsing; `keep` still has to check for it. `keep` sees
+    /// records borrowed from the file, and only the ones it accepts are
+    /// copied out.
+    pub fn scan_commands(
+        &self,
+        text: Option<&str>,
+        keep: &(dyn Fn(&CommandView) -> bool + Sync),
+    ) -> Result<Vec<Command>> {
+        self.backend.scan_commands(text, keep)
+    }
+
+    /// Read every command without its output and timing, with where each
+    /// record sits so [`Storage::read_command_at`] can load it in full
+    ///
+    /// Keeps memory low for long histories with large outputs.
+    pub fn read_command_metadata(&self) -> Result<Vec<(Command, RecordPos)>> {
+        self.backend.read_command_metadata()
+    }
+
+    /// IDs of the commands whose line, directory or output contains `query`
+    /// (case-insensitive)
+    pub fn search_command_ids(&self, query: &str) -> Result<HashSet<String>> {
+        self.backend.search_command_ids(query)
+    }
+
+    /// Read the full record at `pos`, as given by [`Storage::read_command_metadata`]
+    ///
+    /// Fails if the file was rewritten since and the position is stale.
+    pub fn read_command_at(&self, pos: RecordPos) -> Result<Command> {
+        self.backend.read_command_at(pos)
+    }
+
+    /// Tallies over every command, from a cache that only reads the
+    /// records added since it was last used
+    pub fn aggregate(&self) -> Result<Aggregate> {
+        self.backend.aggregate()
+    }
+
+    /// Find a command by its ID or a unique ID prefix
+    pub fn find_command(&self, id: &str) -> Result<Command> {
+        unique_match(id, self.backend.commands_with_id_prefix(id)?)
+    }
+
+    /// Find a command by ID prefix, or the most recent command when `id` is `None`
+    pub fn find_command_or_last(&self, id: Option<&str>) -> Result<Command> {
+        match id {
+            Some(id) => self.find_command(id),
+            None => self
+                .get_recent_commands(1)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("No commands recorded yet")),
+        }
+    }
+
+    /// Apply a change to one command (by ID or unique prefix) and persist it
+    ///
+    /// Returns the updated command.
+    pub fn update_command(&self, id: &str, update: impl FnOnce(&mut Command)) -> Result<Command> {
+        self.backend.update_command(id, Box::new(update))
+    }
+
+    /// Search for commands matching a query string, most recent first
+    pub fn search_commands(&self, query: &str, limit: usize) -> Result<Vec<Command>> {
+        let mut results = self.scan_commands(Some(query), &|cmd| cmd.matches_query(query))?;
+
+        // Sort by most recent first
+        results.sort_by_key(|c| std::cmp::Reverse(c.started_at));
+        results.truncate(limit);
+
+        Ok(results)
+    }
+
+    /// Build an index of commands keyed by working directory
+    pub fn commands_by_dir(&self) -> Result<BTreeMap<String, Vec<Command>>> {
+        let mut index: BTreeMap<String, Vec<Command>> = BTreeMap::new();
+        for cmd in self.read_all_commands()? {
+            index.entry(cmd.cwd.clone()).or_default().push(cmd);
+        }
+        Ok(index)
+    }
+
+    /// Get commands run in a directory (and optionally its subdirectories), most recent first
+    pub fn commands_in_dir(&self, dir: &str, recursive: bool) -> Result<Vec<Command>> {
+        // "/" trims to "", which as a prefix matches every absolute path
+        let dir = dir.trim_end_matches('/');
+        let mut index = self.commands_by_dir()?;
+
+        let mut results = if !recursive {
+            let key = if dir.is_empty() { "/" } else { dir };
+            index.remove(key).unwrap_or_default()
+        } else {
+            // Subdirectories sort right after their parent, so a range scan finds them all
+            let prefix = format!("{}/", dir);
+            index
+                .range(dir.to_string()..)
+                .take_while(|(cwd, _)| cwd.starts_with(dir))
+                .filter(|(cwd, _)| cwd.as_str() == dir || cwd.starts_with(&prefix))
+                .flat_map(|(_, cmds)| cmds.iter().cloned())
+                .collect()
+        };
+
+        results.sort_by_key(|c| std::cmp::Reverse(c.started_at));
+        Ok(results)
+    }
+
+    /// Get the most recent commands
+    pub fn get_recent_commands(&self, limit: usize) -> Result<Vec<Command>> {
+        self.recent_commands_matching(limit, |_| true)
+    }
+
+    /// Get up to `limit` of the most recent commands that `keep` accepts
+    ///
+    /// Stops once enough are found, so the cost follows the number of
+    /// records read rather than the size of the history: the JSONL backend
+    /// reads the file backwards, SQLite walks its start-time index. Order
+    /// matches sorting `read_all_commands` by start time, newest first.
+    pub fn recent_commands_matching(
+        &self,
+        limit: usize,
+        mut keep: impl FnMut(&Command) -> bool,
+    ) -> Result<Vec<Command>> {
+        self.backend.recent_commands(limit, &mut keep)
+    }
+
+    /// Page through the commands, newest first
+    pub fn command_pages(&self) -> Result<CommandPages> {
+        self.backend.command_pages()
+    }
+
+    /// Append a session to the sessions file
+    pub fn append_session(&self, session: &Session) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.sessions_file)
+            .with_context(|| {
+                format!(
+                    "Failed to open sessions file: {}",
+                    self.sessions_file.display()
+                )
+            })?;
+
+        let json = serde_json::to_string(session)
+            .with_context(|| "Failed to serialize session to JSON")?;
+
+        writeln!(file, "{}", json).with_context(|| "Failed to write session to file")?;
+
+        Ok(())
+    }
+
+    /// Read all sessions from the sessions file
+    pub fn read_all_sessions(&self) -> Result<Vec<Session>> {
+        let Some(data) = mapped::open(&self.sessions_file).with_context(|| {
+            format!(
+                "Failed to open sessions file: {}",
+                self.sessions_file.display()
+            )
+        })?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut sessions = Vec::new();
+
+        for (line_num, line) in data.split(|&b| b == b'\n').enumerate() {
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+
+            let session: Session = serde_json::from_slice(line).with_context(|| {
+                format!(
+                    "Failed to parse session from line {} in sessions file",
+                    line_num + 1
+                )
+            })?;
+
+            sessions.push(session);
+        }
+
+        Ok(sessions)
+    }
+
+    /// Update a session's end time
+    pub fn update_session(&self, session_id: &str, ended_at: DateTime<Utc>) -> Result<()> {
+        let mut sessions = self.read_all_sessions()?;
+
+        // Find and update the matching session
+        let updated = sessions.iter_mut().any(|session| {
+            if session.id == session_id {
+                session.ended_at = Some(ended_at);
+                true
+            } else {
+                false
+            }
+        });
+
+        if !updated {
+            return Err(anyhow!("Session not found: {}", session_id));
+        }
+
+        // Rewrite the entire sessions file
+        self.rewrite_sessions(&sessions)?;
+
+        Ok(())
+    }
+
+    /// Rewrite the sessions file with the provided sessions
+    fn rewrite_sessions(&self, sessions: &[Session]) -> Result<()> {
+        jsonl::replace_lines(&self.sessions_file, sessions)
+    }
+
+    /// Drop lines (1-based) from the commands or sessions file, keeping a
+    /// copy of it next to it as `.jsonl.bak`
+    ///
+    /// Lines appended meanwhile are kept, and the commands file's caches are
+    /// rebuilt on the next read. Returns the path of the copy.
+    pub fn drop_lines(&self, file: &Path, lines: &[usize]) -> Result<PathBuf> {
+        let backup = file.with_extension("jsonl.bak");
+        if file == self.commands_file {
+            JsonlBackend::new(self.commands_file.clone()).drop_lines(lines, &backup)?;
+        } else if file == self.sessions_file {
+            jsonl::drop_lines(file, lines, &backup)?;
+        } else {
+            anyhow::bail!("Not a history file: {}", file.display());
+        }
+        Ok(backup)
+    }
+
+    /// Merge commands from another history, skipping IDs already present
+    ///
+    /// Returns the number of commands added.
+    pub fn merge_commands(&self, incoming: Vec<Command>) -> Result<usize> {
+        self.backend.merge_commands(incoming)
+    }
+
+    /// Merge sessions from another history, skipping IDs already present
+    ///
+    /// Returns the number of sessions added.
+    pub fn merge_sessions(&self, incoming: Vec<Session>) -> Result<usize> {
+        let (mut merged, added) = merge_records(self.read_all_sessions()?, incoming, |s| &s.id);
+        if added > 0 {
+            merged.sort_by_key(|s| s.started_at);
+            self.rewrite_sessions(&merged)?;
+        }
+        Ok(added)
+    }
+
+    /// Clean up old commands older than the specified number of days
+    pub fn cleanup_old_commands(&self, days: u64) -> Result<usize> {
+        let cutoff = Utc::now() - chrono::Duration::days(days as i64);
+        self.retain_commands(|cmd| cmd.started_at > cutoff)
+    }
+
+    /// Keep only the commands matching a predicate, returning how many were removed
+    pub fn retain_commands(&self, mut keep: impl FnMut(&Command) -> bool) -> Result<usize> {
+        self.backend.retain_commands(&mut keep)
+    }
+
+    /// Remove the commands with these (full) IDs, returning how many were removed
+    ///
+    /// Commands recorded while the history is being rewritten are kept.
+    pub fn delete_commands(&self, ids: &HashSet<String>) -> Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        self.backend.delete_commands(ids)
+    }
+}
+
+/// The only one of `matches`, the commands whose IDs start with `id`
+pub(crate) fn unique_match<T>(id: &str, mut matches: Vec<T>) -> Result<T> {
+    match matches.len() {
+        0 => Err(anyhow!("No command found with ID: {}", id)),
+        1 => Ok(matches.remove(0)),
+        n => Err(anyhow!(
+            "ID prefix '{}' is ambiguous ({} matches); use more characters",
+            id,
+            n
+        )),
+    }
+}
+
+/// Union two record lists by ID, keeping existing records on conflict
+///
+/// Returns the merged list and how many incoming records were new.
+pub fn merge_records<T>(
+    existing: Vec<T>,
+    incoming: Vec<T>,
+    id: impl Fn(&T) -> &String,
+) -> (Vec<T>, usize) {
+    let mut seen: std::collections::HashSet<String> =
+        existing.iter().map(|r| id(r).clone()).collect();
+    let mut merged = existing;
+    let mut added = 0;
+
+    for record in incoming {
+        if seen.insert(id(&record).clone()) {
+            merged.push(record);
+            added += 1;
+        }
+    }
+
+    (merged, added)
+}
+
+/// Check whether a process with the given PID is still running
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks that the process exists; EPERM means it does but
+    // belongs to another user
+    // SAFETY: kill with signal 0 sends nothing
+    let found = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0;
+    found || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_storage_append_and_read() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+
+        let cmd = Command {
+            id: "test-1".to_string(),
+            command: "echo hello".to_string(),
+            output: "hello\n".to_string(),
+            exit_code: 0,
+            cwd: "/tmp".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 10,
+            session_id: "session-1".to_string(),
+            shell: "bash".to_string(),
+            hostname: "localhost".to_string(),
+            username: "testuser".to_string(),
+            ..Default::default()
+        };
+
+        let cursor = storage.end_cursor().unwrap();
+        storage.append_command(&cmd).unwrap();
+        let commands = storage.read_all_commands().unwrap();
+
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].command, "echo hello");
+
+        // Following picks up what was appended, but not a partial line
+        let (added, cursor) = storage.commands_since(cursor).unwrap();
+        assert_eq!(added.len(), 1);
+        assert_eq!(storage.read_command_at(added[0].1).unwrap().id, cmd.id);
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(storage.commands_file())
+            .unwrap();
+        write!(file, "{{\"id\":").unwrap();
+        let (added, next) = storage.commands_since(cursor).unwrap();
+        assert!(added.is_empty());
+        assert_eq!(next, cursor);
+    }
+
+    #[test]
+    fn test_running_markers() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+
+        let running = RunningCommand {
+            id: "run-1".to_string(),
+            command: "sleep 10".to_string(),
+            cwd: "/tmp".to_string(),
+            started_at: Utc::now(),
+            session_id: "session-1".to_string(),
+            pid: std::process::id(),
+        };
+
+        storage.mark_running(&running).unwrap();
+        let found = storage.read_running().unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].command, "sleep 10");
+
+        storage.clear_running("run-1").unwrap();
+        assert!(storage.read_running().unwrap().is_empty());
+
+        // The marker of a wrapper that died is cleaned up when read
+        #[cfg(unix)]
+        {
+            let mut child = std::process::Command::new("true").spawn().unwrap();
+            child.wait().unwrap();
+            let stale = RunningCommand {
+                id: "run-2".to_string(),
+                pid: child.id(),
+                ..running
+            };
+            storage.mark_running(&stale).unwrap();
+            assert!(storage.read_running().unwrap().is_empty());
+            assert!(!storage.running_dir().join("run-2.json").exists());
+        }
+    }
+
+    #[test]
+    fn test_merge_commands_dedupes_by_id() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+
+        let make = |id: &str, command: &str| Command {
+            id: id.to_string(),
+            command: command.to_string(),
+            exit_code: 0,
+            cwd: "/tmp".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 1,
+            session_id: "session-1".to_string(),
+            shell: "bash".to_string(),
+            hostname: "localhost".to_string(),
+            username: "testuser".to_string(),
+            ..Default::default()
+        };
+
+        storage.append_command(&make("a", "ls")).unwrap();
+        storage.append_command(&make("b", "pwd")).unwrap();
+
+        let added = storage
+            .merge_commands(vec![make("b", "pwd"), make("c", "make")])
+            .unwrap();
+        assert_eq!(added, 1);
+
+        let ids: Vec<String> = storage
+            .read_all_commands()
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.contains(&"c".to_string()));
+
+        assert_eq!(storage.merge_commands(vec![make("a", "ls")]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_find_command_by_prefix() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+
+        for id in ["abc123", "abd456"] {
+            storage
+                .append_command(&Command {
+                    id: id.to_string(),
+                    command: format!("echo {}", id),
+                    exit_code: 0,
+                    cwd: "/tmp".to_string(),
+                    started_at: Utc::now(),
+                    duration_ms: 1,
+                    session_id: "session-1".to_string(),
+                    shell: "bash".to_string(),
+                    hostname: "localhost".to_string(),
+                    username: "testuser".to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        assert_eq!(storage.find_command("abc").unwrap().id, "abc123");
+        assert!(storage.find_command("ab").is_err());
+        assert!(storage.find_command("zzz").is_err());
+    }
+
+    #[test]
+    fn test_update_command() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+
+        for id in ["abc123", "def456"] {
+            storage
+                .append_command(&Command {
+                    id: id.to_string(),
+                    command: format!("echo {}", id),
+                    exit_code: 0,
+                    cwd: "/tmp".to_string(),
+                    started_at: Utc::now(),
+                    duration_ms: 1,
+                    session_id: "session-1".to_string(),
+                    shell: "bash".to_string(),
+                    hostname: "localhost".to_string(),
+                    username: "testuser".to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let updated = storage
+            .update_command("def", |cmd| cmd.note = Some("fixed prod".to_string()))
+            .unwrap();
+        assert_eq!(updated.id, "def456");
+
+        let commands = storage.read_all_commands().unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].note, None);
+        assert_eq!(commands[1].note.as_deref(), Some("fixed prod"));
+        assert!(storage.update_command("zzz", |_| {}).is_err());
+
+        let delete = HashSet::from(["abc123".to_string(), "zzz".to_string()]);
+        assert_eq!(storage.delete_commands(&delete).unwrap(), 1);
+        assert_eq!(storage.delete_commands(&delete).unwrap(), 0);
+        let commands = storage.read_all_commands().unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].id, "def456");
+
+        // A command another shell records during the update is kept
+        let shell = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        storage
+            .update_command("def", |cmd| {
+                let recorded = Command {
+                    id: "ghi789".to_string(),
+                    ..cmd.clone()
+                };
+                shell.append_command(&recorded).unwrap();
+                cmd.bookmarked = true;
+            })
+            .unwrap();
+        let commands = storage.read_all_commands().unwrap();
+        let ids: Vec<&str> = commands.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["def456", "ghi789"]);
+        assert!(commands[0].bookmarked);
+
+        // And so is one recorded while other commands are removed
+        let mut recorded = commands[1].clone();
+        recorded.id = "jkl012".to_string();
+        let removed = storage
+            .retain_commands(|cmd| {
+                if cmd.id == "ghi789" {
+                    shell.append_command(&recorded).unwrap();
+                }
+                cmd.id != "def456"
+            })
+            .unwrap();
+        assert_eq!(removed, 1);
+        let commands = storage.read_all_commands().unwrap();
+        let ids: Vec<&str> = commands.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["ghi789", "jkl012"]);
+    }
+
+    #[test]
+    fn test_commands_in_dir() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+
+        for (i, cwd) in ["/repo", "/repo/src", "/repo-other", "/tmp"]
+            .iter()
+            .enumerate()
+        {
+            let cmd = Command {
+                id: format!("test-{}", i),
+                command: "make".to_string(),
+                exit_code: 0,
+                cwd: cwd.to_string(),
+                started_at: Utc::now(),
+                duration_ms: 1,
+                session_id: "session-1".to_string(),
+                shell: "bash".to_string(),
+                hostname: "localhost".to_string(),
+                username: "testuser".to_string(),
+                ..Default::default()
+            };
+            storage.append_command(&cmd).unwrap();
+        }
+
+        let exact = storage.commands_in_dir("/repo/", false).unwrap();
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].cwd, "/repo");
+
+        let mut tree: Vec<String> = storage
+            .commands_in_dir("/repo", true)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.cwd)
+            .collect();
+        tree.sort();
+        assert_eq!(tree, vec!["/repo", "/repo/src"]);
+    }
+
+    #[test]
+    fn test_search() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+
+        let cmd1 = Command {
+            id: "test-1".to_string(),
+            command: "echo hello".to_string(),
+            output: "hello\n".to_string(),
+            exit_code: 0,
+            cwd: "/tmp".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 10,
+            session_id: "session-1".to_string(),
+            shell: "bash".to_string(),
+            hostname: "localhost".to_string(),
+            username: "testuser".to_string(),
+            ..Default::default()
+        };
+
+        let cmd2 = Command {
+            id: "test-2".to_string(),
+            command: "ls -la".to_string(),
+            output: "total 0\n".to_string(),
+            exit_code: 0,
+            cwd: "/tmp".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 5,
+            session_id: "session-1".to_string(),
+            shell: "bash".to_string(),
+            hostname: "localhost".to_string(),
+            username: "testuser".to_string(),
+            ..Default::default()
+        };
+
+        storage.append_command(&cmd1).unwrap();
+        storage.append_command(&cmd2).unwrap();
+
+        let results = storage.search_commands("echo", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].command, "echo hello");
+    }
+
+    #[test]
+    fn test_recent_commands_use_index() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        let start = Utc::now();
+
+        // Appended out of order, as merged histories can be
+        for (i, minutes) in [3, 1, 4, 2].iter().enumerate() {
+            let cmd = Command {
+                id: format!("test-{}", i),
+                command: format!("make {}", minutes),
+                exit_code: 0,
+                cwd: "/tmp".to_string(),
+                started_at: start + chrono::Duration::minutes(*minutes),
+                duration_ms: 1,
+                session_id: "session-1".to_string(),
+                shell: "bash".to_string(),
+                hostname: "localhost".to_string(),
+                username: "testuser".to_string(),
+                ..Default::default()
+            };
+            storage.append_command(&cmd).unwrap();
+        }
+        let index_file = dir.path().join("commands.idx");
+        assert_eq!(std::fs::metadata(&index_file).unwrap().len(), 8 + 4 * 32);
+
+        let recent = |limit| -> Vec<String> {
+            storage
+                .get_recent_commands(limit)
+                .unwrap()
+                .into_iter()
+                .map(|c| c.command)
+                .collect()
+        };
+        assert_eq!(recent(2), vec!["make 4", "make 3"]);
+
+        // Rewrites drop the index; a garbled one is rebuilt
+        storage.retain_commands(|c| c.command != "make 4").unwrap();
+        assert!(!index_file.exists());
+        assert_eq!(recent(1), vec!["make 3"]);
+        std::fs::write(&index_file, [7; 48]).unwrap();
+        assert_eq!(recent(5), vec!["make 3", "make 2", "make 1"]);
+    }
+
+    #[test]
+    fn test_metadata_and_read_at() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        for (id, output) in [("a", "first output"), ("b", "second output")] {
+            let cmd = Command {
+                id: id.to_string(),
+                command: "make".to_string(),
+                output: output.to_string(),
+                exit_code: 0,
+                cwd: "/tmp".to_string(),
+                started_at: Utc::now(),
+                duration_ms: 1,
+                session_id: "session-1".to_string(),
+                shell: "bash".to_string(),
+                hostname: "localhost".to_string(),
+                username: "testuser".to_string(),
+                timing: vec![(0, 5)],
+                ..Default::default()
+            };
+            storage.append_command(&cmd).unwrap();
+        }
+
+        let records = storage.read_command_metadata().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records[1].0.output.is_empty() && records[1].0.timing.is_empty());
+
+        let full = storage.read_command_at(records[1].1).unwrap();
+        assert_eq!(
+            (full.id.as_str(), full.output.as_str()),
+            ("b", "second output")
+        );
+        assert_eq!(
+            storage.search_command_ids("SECOND").unwrap(),
+            HashSet::from(["b".to_string()])
+        );
+
+        // Pages come newest first and point at the same records
+        let mut pages = storage.command_pages().unwrap();
+        let page = pages.next_page(1).unwrap();
+        assert_eq!((page[0].0.id.as_str(), page[0].1), ("b", records[1].1));
+        assert!(page[0].0.output.is_empty());
+        assert!(!pages.is_done());
+        assert_eq!(pages.next_page(5).unwrap()[0].0.id, "a");
+        assert!(pages.is_done());
+    }
+}
@@ -0,0 +1,371 @@
+//! Trigram filters that let substring searches skip most of the history
+//!
+//! `commands.tri` splits the commands file into blocks of whole lines, about
+//! [`BLOCK_BYTES`] each, and keeps a bloom filter of the lowercased trigrams
+//! in every block. A search only reads the blocks whose filter holds all the
+//! trigrams of the text, plus the newest lines not yet sealed into a block.
+//!
+//! Like the offset index, this is only a cache: blocks are sealed as the
+//! file grows and the whole file is rebuilt when it no longer fits.
+
+use crate::index::starts_line;
+use crate::mapped;
+use crate::scan;
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Bytes of the commands file a block covers before it is sealed
+const BLOCK_BYTES: u64 = 64 * 1024;
+
+/// Bits in a block's filter; a power of two
+const FILTER_BITS: usize = 64 * 1024;
+
+/// Bits set per trigram
+const HASHES: u32 = 3;
+
+/// Block header: offset, length, first line number and line count
+const HEADER_SIZE: usize = 32;
+
+const BLOCK_SIZE: usize = HEADER_SIZE + FILTER_BITS / 8;
+
+/// A sealed run of whole lines and the trigrams in it
+struct Block {
+    offset: u64,
+    len: u64,
+    /// Line number of the first line, counting from 1
+    first_line: u64,
+    lines: u64,
+    filter: Vec<u8>,
+}
+
+impl Block {
+    fn build(offset: u64, first_line: u64, data: &[u8]) -> Self {
+        let mut filter = vec![0; FILTER_BITS / 8];
+        let mut lines = 0;
+        for line in data.split(|&b| b == b'\n') {
+            lines += 1;
+            for t in trigrams(&lowercase(line)) {
+                for bit in bits(t) {
+                    filter[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+        }
+        Self {
+            offset,
+            len: data.len() as u64,
+            first_line,
+            // Blocks end with a newline, which split counts as one more line
+            lines: lines - 1,
+            filter,
+        }
+    }
+
+    fn end(&self) -> u64 {
+        self.offset.saturating_add(self.len)
+    }
+
+    fn may_contain(&self, trigrams: &[u32]) -> bool {
+        trigrams
+            .iter()
+            .all(|&t| bits(t).all(|bit| self.filter[bit / 8] & (1 << (bit % 8)) != 0))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(BLOCK_SIZE);
+        for field in [self.offset, self.len, self.first_line, self.lines] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.filter);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let field = |i: usize| u64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap());
+        Self {
+            offset: field(0),
+            len: field(1),
+            first_line: field(2),
+            lines: field(3),
+            filter: bytes[HEADER_SIZE..].to_vec(),
+        }
+    }
+}
+
+/// A part of the commands file to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub offset: u64,
+    /// `None` reads to the end of the file
+    pub len: Option<u64>,
+    /// Line number of the first line, counting from 1
+    pub first_line: usize,
+}
+
+/// The trigram file next to a commands file
+#[derive(Debug, Clone)]
+pub(crate) struct TrigramIndex {
+    path: PathBuf,
+}
+
+impl TrigramIndex {
+    pub fn for_file(commands_file: &Path) -> Self {
+        Self {
+            path: commands_file.with_extension("tri"),
+        }
+    }
+
+    /// The parts of `commands_file` that may hold `text`, in file order
+    ///
+    /// `None` when the filters cannot rule anything out for this text, so
+    /// the whole file has to be read.
+    pub fn candidates(&self, commands_file: &Path, text: &str) -> Result<Option<Vec<Span>>> {
+        let wanted = trigrams(text.to_ascii_lowercase().as_bytes());
+        if !scan::byte_searchable(text) || wanted.is_empty() {
+            return Ok(None);
+        }
+        let Some(blocks) = self.load(commands_file)? else {
+            return Ok(None);
+        };
+
+        let mut spans: Vec<Span> = Vec::new();
+        for block in blocks.iter().filter(|b| b.may_contain(&wanted)) {
+            match spans.last_mut() {
+                Some(last)
+                    if last
+                        .len
+                        .is_some_and(|len| last.offset + len == block.offset) =>
+                {
+                    last.len = Some(last.len.unwrap_or_default() + block.len);
+                }
+                _ => spans.push(Span {
+                    offset: block.offset,
+                    len: Some(block.len),
+                    first_line: block.first_line as usize,
+                }),
+            }
+        }
+        // Lines not sealed into a block yet are always read
+        let (offset, first_line) = blocks
+            .last()
+            .map_or((0, 1), |b| (b.end(), b.first_line + b.lines));
+        spans.push(Span {
+            offset,
+            len: None,
+            first_line: first_line as usize,
+        });
+        tracing::debug!(
+            blocks = blocks.len(),
+            spans = spans.len(),
+            "Narrowed search with trigrams"
+        );
+        Ok(Some(spans))
+    }
+
+    /// Seal the lines appended since the last block, once there are enough
+    pub fn update(&self, commands_file: &Path) -> Result<()> {
+        let file_len = std::fs::metadata(commands_file)?.len();
+        let (covered, next_line) = match self.last_block()? {
+            Some(block) => (block.end(), block.first_line.saturating_add(block.lines)),
+            None => (0, 1),
+        };
+        if covered > file_len {
+            // Rewritten shorter; rebuilt on the next search
+            return self.invalidate();
+        }
+        if file_len - covered < BLOCK_BYTES {
+            return Ok(());
+        }
+        let blocks = seal(commands_file, covered, next_line)?;
+        self.write_blocks(&blocks, true)
+    }
+
+    /// Drop the filters; the next search rebuilds them
+    pub fn invalidate(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Blocks covering `commands_file`, caught up or rebuilt as needed
+    ///
+    /// `None` when there is no commands file.
+    fn load(&self, commands_file: &Path) -> Result<Option<Vec<Block>>> {
+        let file_len = match std::fs::metadata(commands_file) {
+            Ok(meta) => meta.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut blocks = self.read_blocks()?;
+        let mut next = (0, 1);
+        let mut fits = true;
+        for block in &blocks {
+            fits &= block.offset == next.0 && block.first_line == next.1;
+            next = (block.end(), block.first_line.saturating_add(block.lines));
+        }
+        if !fits || next.0 > file_len || !starts_line(commands_file, next.0)? {
+            tracing::debug!(path = %self.path.display(), "Trigram filters are stale, rebuilding");
+            blocks = seal(commands_file, 0, 1)?;
+            self.write_blocks(&blocks, false)?;
+        } else if file_len - next.0 >= BLOCK_BYTES {
+            let new = seal(commands_file, next.0, next.1)?;
+            self.write_blocks(&new, true)?;
+            blocks.extend(new);
+        }
+        Ok(Some(blocks))
+    }
+
+    fn last_block(&self) -> Result<Option<Block>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let len = file.metadata()?.len() / BLOCK_SIZE as u64 * BLOCK_SIZE as u64;
+        if len == 0 {
+            return Ok(None);
+        }
+        let mut bytes = vec![0; BLOCK_SIZE];
+        file.seek(SeekFrom::Start(len - BLOCK_SIZE as u64))?;
+        file.read_exact(&mut bytes)?;
+        Ok(Some(Block::from_bytes(&bytes)))
+    }
+
+    fn read_blocks(&self) -> Result<Vec<Block>> {
+        let bytes = match std::fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        // A torn final block is dropped and sealed again from the file
+        Ok(bytes
+            .chunks_exact(BLOCK_SIZE)
+            .map(Block::from_bytes)
+            .collect())
+    }
+
+    fn write_blocks(&self, blocks: &[Block], append: bool) -> Result<()> {
+        let bytes: Vec<u8> = blocks.iter().flat_map(Block::to_bytes).collect();
+        if append {
+            if bytes.is_empty() {
+                return Ok(());
+            }
+            // Cut a torn final block first so the new ones stay aligned
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            let len = file.metadata()?.len();
+            file.set_len(len - len % BLOCK_SIZE as u64)?;
+            (&file).write_all(&bytes)?;
+        } else {
+            let tmp = self.path.with_extension("tri.tmp");
+            std::fs::write(&tmp, &bytes)
+                .with_context(|| format!("Failed to write trigram filters: {}", tmp.display()))?;
+            std::fs::rename(&tmp, &self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Split the complete lines of `path` from `offset` on into full blocks
+fn seal(path: &Path, offset: u64, first_line: u64) -> Result<Vec<Block>> {
+    let data = mapped::open(path)?.unwrap_or_else(mapped::empty);
+
+    let mut blocks = Vec::new();
+    let mut rest = data.get(offset as usize..).unwrap_or_default();
+    let (mut offset, mut first_line) = (offset, first_line);
+    while rest.len() as u64 >= BLOCK_BYTES {
+        let Some(i) = memchr::memchr(b'\n', &rest[BLOCK_BYTES as usize - 1..]) else {
+            break;
+        };
+        let (chunk, tail) = rest.split_at(BLOCK_BYTES as usize + i);
+        let block = Block::build(offset, first_line, chunk);
+        offset = block.end();
+        first_line += block.lines;
+        blocks.push(block);
+        rest = tail;
+    }
+    Ok(blocks)
+}
+
+/// A line as `Command::matches_query` compares it: lowercased, with the
+/// few non-ASCII characters that lowercase to ASCII ones folded too
+fn lowercase(line: &[u8]) -> Vec<u8> {
+    if line.is_ascii() {
+        line.to_ascii_lowercase()
+    } else {
+        String::from_utf8_lossy(line).to_lowercase().into_bytes()
+    }
+}
+
+fn trigrams(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .windows(3)
+        .map(|w| u32::from_le_bytes([w[0], w[1], w[2], 0]))
+        .collect()
+}
+
+/// Filter bits for a trigram, by double hashing
+fn bits(trigram: u32) -> impl Iterator<Item = usize> {
+    let hash = u64::from(trigram).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    let (h1, h2) = ((hash >> 32) as u32, hash as u32 | 1);
+    (0..HASHES).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) as usize & (FILTER_BITS - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_candidates() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("commands.jsonl");
+        let index = TrigramIndex::for_file(&path);
+
+        // Three blocks of filler, one rare command in the middle one
+        let mut data = String::new();
+        let mut i = 0;
+        while data.len() < 3 * BLOCK_BYTES as usize + 100 {
+            let command = if i == 1500 { "kubectl apply" } else { "ls -la" };
+            data.push_str(&format!(
+                "{{\"id\":\"{}\",\"command\":\"{}\"}}\n",
+                i, command
+            ));
+            i += 1;
+        }
+        std::fs::write(&path, &data).unwrap();
+        index.update(&path).unwrap();
+
+        let spans = index.candidates(&path, "KUBECTL").unwrap().unwrap();
+        assert_eq!(spans.len(), 2);
+        let found = &data[spans[0].offset as usize..][..spans[0].len.unwrap() as usize];
+        assert!(found.contains("kubectl"));
+        let line = data[..spans[0].offset as usize].matches('\n').count() + 1;
+        assert_eq!(spans[0].first_line, line);
+        assert_eq!(spans[1].len, None);
+        assert!(index.candidates(&path, "ls").unwrap().is_none());
+
+        // Rewritten shorter
+        std::fs::write(&path, "{\"command\":\"make\"}\n").unwrap();
+        let spans = index.candidates(&path, "kubectl").unwrap().unwrap();
+        assert_eq!(
+            spans,
+            vec![Span {
+                offset: 0,
+                len: None,
+                first_line: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lowercase_folds_kelvin_sign() {
+        let block = Block::build(0, 1, "{\"command\":\"\u{212a}ube\"}\n".as_bytes());
+        assert!(block.may_contain(&trigrams(b"kube")));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::config::{CommandFilter, WebhookConfig, expand_home};
+use crate::models::Command;
+use anyhow::{Context, Result, anyhow};
+use regex::Regex;
+use serde_json::Value;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Child, Stdio};
+
+/// Seconds a single delivery attempt may take
+const TIMEOUT_SECS: &str = "10";
+
+/// Fire every webhook that matches a freshly recorded command
+///
+/// Deliveries run in a detached `curl` so recording never waits on the
+/// network; failures to start them are logged, not returned.
+pub fn fire(webhooks: &[WebhookConfig], cmd: &Command) {
+    for hook in webhooks {
+        let result = matches(&hook.filter, cmd).and_then(|matched| {
+            if matched {
+                send(hook, &payload(hook, cmd))
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(e) = result {
+            tracing::warn!(url = %hook.url, "Webhook failed: {:#}", e);
+        }
+    }
+}
+
+/// Whether `cmd` passes the filter
+pub fn matches(filter: &CommandFilter, cmd: &Command) -> Result<bool> {
+    if filter.failed_only && cmd.exit_code == 0 {
+        return Ok(false);
+    }
+    if let Some(dir) = &filter.dir
+        && !Path::new(&cmd.cwd).starts_with(expand_home(dir))
+    {
+        return Ok(false);
+    }
+    if let Some(pattern) = &filter.command {
+        let re =
+            Regex::new(pattern).with_context(|| format!("Invalid command pattern: {}", pattern))?;
+        return Ok(re.is_match(&cmd.command));
+    }
+    Ok(true)
+}
+
+/// The request body: the rendered template, or the command as JSON
+fn payload(hook: &WebhookConfig, cmd: &Command) -> String {
+    if hook.template.is_empty() {
+        serde_json::to_string(cmd).unwrap_or_default()
+    } else {
+        render(&hook.template, cmd)
+    }
+}
+
+/// Replace `{{field}}` placeholders with the command's fields
+///
+/// Strings are escaped for use inside a JSON string; unknown placeholders
+/// are left as they are.
+pub fn render(template: &str, cmd: &Command) -> String {
+    let fields = serde_json::to_value(cmd).unwrap_or_default();
+    let placeholder = Regex::new(r"\{\{\s*(\w+)\s*\}\}").expect("valid regex");
+
+    placeholder
+        .replace_all(template, |caps: &regex::Captures| {
+            match fields.get(&caps[1]) {
+                Some(Value::String(s)) => {
+                    let quoted = Value::String(s.clone()).to_string();
+                    quoted[1..quoted.len() - 1].to_string()
+                }
+                Some(value) => value.to_string(),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// POST the payload with a background `curl`, retrying with backoff
+fn send(hook: &WebhookConfig, payload: &str) -> Result<()> {
+    let mut child = post(&hook.url, &hook.headers, hook.retries, payload)?;
+    tracing::debug!(url = %hook.url, pid = child.id(), "Webhook dispatched");
+
+    // Reap the child in long-running processes like `serve`; a short-lived
+    // recorder exits first and leaves curl to finish on its own
+    std::thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+/// Start a `curl` that POSTs a JSON payload, retrying failed attempts
+///
+/// The caller decides whether to wait for it or let it run in the background.
+pub fn post(url: &str, headers: &[String], retries: u32, payload: &str) -> Result<Child> {
+    let mut curl = std::process::Command::new("curl");
+    curl.args(["-fsS", "--max-time", TIMEOUT_SECS, "-X", "POST"])
+        .args(["--retry", &retries.to_string(), "--retry-all-errors"])
+        .args(["-H", "Content-Type: application/json"]);
+    for header in headers {
+        curl.args(["-H", header]);
+    }
+    curl.args(["--data-binary", "@-"])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let mut child = curl.spawn().context("Failed to run curl")?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("Failed to open stdin"))?
+        .write_all(payload.as_bytes())?;
+
+    Ok(child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn cmd(command: &str, exit_code: i32, cwd: &str) -> Command {
+        Command {
+            id: "abc".to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: cwd.to_string(),
+            started_at: Utc::now(),
+            duration_ms: 42,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let filter = CommandFilter {
+            failed_only: true,
+            dir: Some("/srv/prod-scripts".to_string()),
+            command: Some(r"^(rm|deploy)\b".to_string()),
+        };
+
+        assert!(matches(&filter, &cmd("deploy api", 1, "/srv/prod-scripts/app")).unwrap());
+        assert!(!matches(&filter, &cmd("deploy api", 0, "/srv/prod-scripts")).unwrap());
+        assert!(!matches(&filter, &cmd("deploy api", 1, "/srv/prod-scripts-old")).unwrap());
+        assert!(!matches(&filter, &cmd("ls", 1, "/srv/prod-scripts")).unwrap());
+    }
+
+    #[test]
+    fn test_render() {
+        let cmd = cmd(r#"echo "hi""#, 2, "/tmp");
+        assert_eq!(
+            render(
+                r#"{"text": "{{command}} exited {{ exit_code }} in {{duration_ms}}ms {{nope}}"}"#,
+                &cmd
+            ),
+            r#"{"text": "echo \"hi\" exited 2 in 42ms {{nope}}"}"#
+        );
+    }
+}
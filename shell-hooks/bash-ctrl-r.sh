@@ -0,0 +1,17 @@
+#!/bin/bash
+# Shelltape Ctrl-R widget for bash
+# Source this file in your ~/.bashrc to search history with `shelltape pick`
+
+__shelltape_pick_widget() {
+    local selected
+    # Seed the search with the current line; the picked command replaces it
+    selected=$(command shelltape pick -- "$READLINE_LINE") || return
+    READLINE_LINE=$selected
+    READLINE_POINT=${#READLINE_LINE}
+}
+
+# Only bind in interactive shells with line editing
+if [[ $- == *i* ]]; then
+    bind -m emacs-standard -x '"\C-r": __shelltape_pick_widget'
+    bind -m vi-insert -x '"\C-r": __shelltape_pick_widget'
+fi
@@ -0,0 +1,17 @@
+#!/usr/bin/env fish
+# Shelltape Ctrl-R widget for fish
+# Source this file in your ~/.config/fish/config.fish to search history with `shelltape pick`
+
+function __shelltape_pick_widget
+    # Seed the search with the current line; the picked command replaces it
+    set -l selected (command shelltape pick -- (commandline) | string collect)
+    if test -n "$selected"
+        commandline --replace -- $selected
+    end
+    commandline -f repaint
+end
+
+bind \cr __shelltape_pick_widget
+if bind -M insert >/dev/null 2>&1
+    bind -M insert \cr __shelltape_pick_widget
+end
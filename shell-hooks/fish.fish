@@ -37,21 +37,28 @@ function __shelltape_postcmd --on-event fish_postexec
             case 'shelltape*' '__shelltape*' 'cd' 'cd *' 'ls' 'ls *' 'pwd' 'clear'
                 # Skip recording
             case '*'
-                # Read captured output (if any)
-                set -l output ""
-                if test -f $SHELLTAPE_OUTPUT_FILE
-                    set output (cat $SHELLTAPE_OUTPUT_FILE 2>/dev/null)
-                end
+                # Hand this command's output over under its own name, so the
+                # next command clearing the capture file can't race the
+                # recorder reading it
+                set -l handoff "$SHELLTAPE_OUTPUT_FILE.$end"
+                command mv -f $SHELLTAPE_OUTPUT_FILE $handoff 2>/dev/null
+                or echo -n > $handoff
 
-                # Record the command in background to avoid blocking the shell
-                fish -c "shelltape record \
-                    --command '$SHELLTAPE_CMD' \
+                # Record the command in background to avoid blocking the shell.
+                # Output is read from the file on stdin so it keeps newlines
+                # and isn't limited by the argument size; the redirection is
+                # opened before the job starts, so the file can go right away.
+                shelltape record \
+                    --command "$SHELLTAPE_CMD" \
                     --exit-code $exit_code \
                     --start-time $SHELLTAPE_START \
                     --end-time $end \
-                    --cwd '$PWD' \
-                    --session-id '$SHELLTAPE_SESSION_ID' \
-                    --output '$output' &" &
+                    --cwd "$PWD" \
+                    --session-id "$SHELLTAPE_SESSION_ID" \
+                    --output - \
+                    --via-daemon <$handoff >/dev/null 2>&1 &
+                disown 2>/dev/null
+                command rm -f $handoff
         end
     end
 
@@ -0,0 +1,18 @@
+#!/bin/zsh
+# Shelltape Ctrl-R widget for zsh
+# Source this file in your ~/.zshrc to search history with `shelltape pick`
+
+__shelltape_pick_widget() {
+    local selected
+    # Seed the search with the current buffer; the picked command replaces it
+    selected=$(command shelltape pick -- "$BUFFER" </dev/tty)
+    if [[ -n "$selected" ]]; then
+        BUFFER=$selected
+        CURSOR=${#BUFFER}
+    fi
+    zle reset-prompt
+}
+
+zle -N __shelltape_pick_widget
+bindkey -M emacs '^R' __shelltape_pick_widget
+bindkey -M viins '^R' __shelltape_pick_widget
@@ -0,0 +1,191 @@
+use crate::timespec::parse_time;
+use anyhow::{Context, Result, bail};
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Magic bytes at the start of every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Move commands older than a cutoff out of the live store into an archive file
+///
+/// The archive is JSONL, zstd-compressed when the output ends in `.zst`.
+pub fn archive_commands(
+    storage: &Storage,
+    older_than: &str,
+    output: &Path,
+    yes: bool,
+) -> Result<()> {
+    let cutoff = parse_time(older_than)?;
+
+    if output.exists() {
+        bail!("Archive already exists: {}", output.display());
+    }
+
+    let mut old: Vec<Command> = storage
+        .read_all_commands()?
+        .into_iter()
+        .filter(|cmd| cmd.started_at < cutoff)
+        .collect();
+
+    if old.is_empty() {
+        println!("No commands older than {} found", older_than);
+        return Ok(());
+    }
+
+    println!(
+        "📦 This will move {} commands (older than {}) to {}",
+        old.len(),
+        cutoff
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M"),
+        output.display()
+    );
+
+    if !yes {
+        print!("Continue? [y/N] ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    old.sort_by_key(|c| c.started_at);
+    write_archive(output, &old)?;
+
+    // Only drop records from the live store once the archive is safely written
+    let removed = storage.retain_commands(|cmd| cmd.started_at >= cutoff)?;
+
+    println!("✓ Archived {} commands to {}", removed, output.display());
+    println!(
+        "  Restore with: shelltape archive --restore {}",
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Import the commands from an archive back into the live store
+pub fn restore_archive(storage: &Storage, input: &Path) -> Result<()> {
+    let commands = read_archive(input)?;
+    let total = commands.len();
+    let added = storage.merge_commands(commands)?;
+
+    println!(
+        "✓ Restored {} commands from {} ({} already present)",
+        added,
+        input.display(),
+        total - added
+    );
+
+    Ok(())
+}
+
+/// Write commands as JSONL, compressing when the path ends in `.zst`
+fn write_archive(path: &Path, commands: &[Command]) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create archive: {}", path.display()))?;
+
+    if path.extension().is_some_and(|ext| ext == "zst") {
+        let mut encoder = zstd::Encoder::new(file, 0)?;
+        write_lines(&mut encoder, commands)?;
+        encoder.finish()?.sync_all()?;
+    } else {
+        let mut writer = BufWriter::new(file);
+        write_lines(&mut writer, commands)?;
+        writer.into_inner()?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// Write one JSON record per line
+fn write_lines(writer: &mut impl Write, commands: &[Command]) -> Result<()> {
+    for cmd in commands {
+        serde_json::to_writer(&mut *writer, cmd)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read commands from a plain or zstd-compressed JSONL archive
+fn read_archive(path: &Path) -> Result<Vec<Command>> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open archive: {}", path.display()))?;
+
+    let mut magic = [0u8; 4];
+    let compressed = file.read_exact(&mut magic).is_ok() && magic == ZSTD_MAGIC;
+    let file = File::open(path)?;
+
+    let reader: Box<dyn BufRead> = if compressed {
+        Box::new(BufReader::new(zstd::Decoder::new(file)?))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+
+    let mut commands = Vec::new();
+    for (line_num, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cmd = serde_json::from_str(&line).with_context(|| {
+            format!(
+                "Failed to parse line {} of {}",
+                line_num + 1,
+                path.display()
+            )
+        })?;
+        commands.push(cmd);
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let dir = tempdir().unwrap();
+        let commands: Vec<Command> = (0..3)
+            .map(|i| Command {
+                id: format!("test-{}", i),
+                command: format!("echo {}", i),
+                output: format!("{}\n", i),
+                exit_code: 0,
+                started_at: Utc::now(),
+                duration_ms: 1,
+                cwd: "/tmp".to_string(),
+                session_id: "session-1".to_string(),
+                shell: "bash".to_string(),
+                hostname: "localhost".to_string(),
+                username: "testuser".to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        for name in ["archive.jsonl", "archive.jsonl.zst"] {
+            let path = dir.path().join(name);
+            write_archive(&path, &commands).unwrap();
+
+            let restored = read_archive(&path).unwrap();
+            let ids: Vec<_> = restored.iter().map(|c| c.id.as_str()).collect();
+            assert_eq!(ids, vec!["test-0", "test-1", "test-2"]);
+        }
+
+        let compressed = std::fs::read(dir.path().join("archive.jsonl.zst")).unwrap();
+        assert_eq!(compressed[..4], ZSTD_MAGIC);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::recording::{self, Chunk, Recording};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use shelltape_core::storage::Storage;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The header of a cast (the whole file for version 1)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Header {
+    version: u8,
+    /// Start time in seconds since the epoch
+    timestamp: Option<i64>,
+    command: Option<String>,
+    title: Option<String>,
+    env: HashMap<String, String>,
+    /// Version 1 keeps its `[delay, data]` events here
+    stdout: Vec<(f64, String)>,
+}
+
+/// Import an asciinema cast (versions 1 to 3), splitting it into commands
+/// at prompts
+///
+/// Output timing is kept, so imported commands can be replayed. A cast of a
+/// single program without shell prompts becomes one record.
+pub fn import_asciicast(storage: &Storage, file: &Path, prompt: Option<&str>) -> Result<()> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read: {}", file.display()))?;
+    let recording = parse_cast(&content)
+        .with_context(|| format!("Failed to parse asciicast: {}", file.display()))?;
+    recording::import(storage, file, recording, prompt)
+}
+
+fn parse_cast(content: &str) -> Result<Recording> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let first = lines.next().unwrap_or_default();
+
+    let (header, events): (Header, Vec<(f64, String, String)>) =
+        match serde_json::from_str::<Header>(first) {
+            Ok(header) if header.version >= 2 => {
+                let events = lines
+                    .enumerate()
+                    .map(|(i, line)| {
+                        parse_event(line)
+                            .with_context(|| format!("Invalid event on line {}", i + 2))
+                    })
+                    .collect::<Result<_>>()?;
+                (header, events)
+            }
+            // Version 1 is a single JSON document spread over many lines
+            _ => {
+                let mut header: Header = serde_json::from_str(content)?;
+                if header.version != 1 {
+                    bail!("Unsupported asciicast version {}", header.version);
+                }
+                let events = std::mem::take(&mut header.stdout)
+                    .into_iter()
+                    .map(|(delay, data)| (delay, "o".to_string(), data))
+                    .collect();
+                (header, events)
+            }
+        };
+
+    // Version 2 has times since the start; 1 and 3 delays since the last event
+    let relative = header.version != 2;
+    let mut at = 0.0;
+    let mut data = Vec::new();
+    let mut chunks = Vec::new();
+    let mut exit_code = None;
+    for (time, code, text) in events {
+        at = if relative { at + time } else { time };
+        match code.as_str() {
+            "o" => {
+                chunks.push(Chunk {
+                    at,
+                    offset: data.len(),
+                    len: text.len(),
+                });
+                data.extend_from_slice(text.as_bytes());
+            }
+            "x" => exit_code = text.trim().parse().ok(),
+            // Input, markers and resizes
+            _ => {}
+        }
+    }
+
+    let started = header
+        .timestamp
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .unwrap_or_else(Utc::now);
+    let shell = header
+        .env
+        .get("SHELL")
+        .map(|shell| shell.rsplit('/').next().unwrap_or(shell).to_string());
+
+    Ok(Recording {
+        started,
+        data,
+        chunks,
+        command: header.command.or(header.title).or_else(|| shell.clone()),
+        shell,
+        exit_code,
+    })
+}
+
+/// An event line: `[time, code, data]`
+fn parse_event(line: &str) -> Result<(f64, String, String)> {
+    let event: Vec<Value> = serde_json::from_str(line)?;
+    match event.as_slice() {
+        [time, code, data] => Ok((
+            time.as_f64().context("Event time is not a number")?,
+            code.as_str().unwrap_or_default().to_string(),
+            data.as_str().unwrap_or_default().to_string(),
+        )),
+        _ => bail!("Expected [time, code, data]"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_v2() {
+        let cast = r#"{"version": 2, "width": 80, "height": 24, "timestamp": 1792227600, "env": {"SHELL": "/bin/zsh"}}
+[0.5, "o", "% ls\r\n"]
+[0.6, "i", "x"]
+[0.7, "o", "src\r\n"]
+"#;
+        let recording = parse_cast(cast).unwrap();
+        assert_eq!(recording.data, b"% ls\r\nsrc\r\n");
+        assert_eq!(recording.chunks[1].at, 0.7);
+        assert_eq!(recording.chunks[1].offset, 6);
+        assert_eq!(recording.shell.as_deref(), Some("zsh"));
+        assert_eq!(recording.started.timestamp(), 1792227600);
+    }
+
+    #[test]
+    fn test_parse_v3() {
+        let cast = r#"{"version": 3, "term": {"cols": 80, "rows": 24}, "timestamp": 1792227600, "command": "htop"}
+[0.5, "o", "CPU"]
+[0.25, "o", " 12%"]
+[1.0, "x", "2"]
+"#;
+        let recording = parse_cast(cast).unwrap();
+        assert_eq!(recording.chunks[1].at, 0.75);
+        assert_eq!(recording.command.as_deref(), Some("htop"));
+        assert_eq!(recording.exit_code, Some(2));
+    }
+
+    #[test]
+    fn test_parse_v1() {
+        let cast = r#"{
+  "version": 1,
+  "command": "/bin/bash",
+  "stdout": [[0.1, "$ "], [0.2, "ls\r\n"]]
+}"#;
+        let recording = parse_cast(cast).unwrap();
+        assert_eq!(recording.data, b"$ ls\r\n");
+        assert!((recording.chunks[1].at - 0.3).abs() < 1e-9);
+    }
+}
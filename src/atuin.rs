@@ -0,0 +1,293 @@
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{Connection, OpenFlags, params};
+use shelltape_core::models::{Command, Session};
+use shelltape_core::storage::Storage;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Shell recorded for imported commands; atuin does not store it
+const UNKNOWN_SHELL: &str = "unknown";
+
+/// Schema of atuin's `history` table, created when exporting to a new database
+const HISTORY_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS history (
+    id TEXT PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    duration INTEGER NOT NULL,
+    exit INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    cwd TEXT NOT NULL,
+    session TEXT NOT NULL,
+    hostname TEXT NOT NULL,
+    deleted_at INTEGER,
+    UNIQUE(timestamp, cwd, command)
+)";
+
+/// One row of atuin's `history` table
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtuinEntry {
+    pub id: String,
+    /// Start time in nanoseconds since the epoch
+    pub timestamp: i64,
+    /// Duration in nanoseconds, negative if unknown
+    pub duration: i64,
+    pub exit: i64,
+    pub command: String,
+    pub cwd: String,
+    pub session: String,
+    /// `hostname:username`
+    pub hostname: String,
+}
+
+/// Where atuin keeps its history by default
+///
+/// Atuin uses the XDG data directory on every platform.
+pub fn default_db_path() -> Result<PathBuf> {
+    let data_dir = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".local/share"),
+    };
+    Ok(data_dir.join("atuin/history.db"))
+}
+
+/// Import atuin's history into shelltape
+///
+/// Commands already in the history (same ID, or same start time and command
+/// line) are skipped, so importing twice is harmless.
+pub fn import_atuin(storage: &Storage, db: Option<PathBuf>) -> Result<()> {
+    let db = match db {
+        Some(db) => db,
+        None => default_db_path()?,
+    };
+    let entries = read_entries(&db)?;
+    let total = entries.len();
+
+    let existing: HashSet<(DateTime<Utc>, String)> = storage
+        .read_all_commands()?
+        .into_iter()
+        .map(|c| (c.started_at, c.command))
+        .collect();
+    let commands: Vec<Command> = entries
+        .into_iter()
+        .map(to_command)
+        .filter(|c| !existing.contains(&(c.started_at, c.command.clone())))
+        .collect();
+    let sessions = sessions_for(&commands);
+
+    let added = storage.merge_commands(commands)?;
+    storage.merge_sessions(sessions)?;
+
+    println!(
+        "✓ Imported {} of {} atuin commands from {} ({} already present)",
+        added,
+        total,
+        db.display(),
+        total - added
+    );
+    Ok(())
+}
+
+/// Write commands into an atuin database, creating it if needed
+///
+/// Rows that atuin already has are left alone. Output, tags and notes have
+/// no place in atuin's schema and are not exported.
+pub fn export_atuin(commands: &[Command], db: &Path) -> Result<usize> {
+    if let Some(parent) = db.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let mut conn = Connection::open(db)
+        .with_context(|| format!("Failed to open atuin database: {}", db.display()))?;
+    conn.execute(HISTORY_SCHEMA, [])
+        .context("Failed to create atuin history table")?;
+
+    let tx = conn.transaction()?;
+    let mut added = 0;
+    {
+        let mut insert = tx.prepare(
+            "INSERT OR IGNORE INTO history
+                 (id, timestamp, duration, exit, command, cwd, session, hostname)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for entry in commands.iter().map(to_entry) {
+            added += insert.execute(params![
+                entry.id,
+                entry.timestamp,
+                entry.duration,
+                entry.exit,
+                entry.command,
+                entry.cwd,
+                entry.session,
+                entry.hostname,
+            ])?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(added)
+}
+
+/// Read the live (not deleted) entries of an atuin database, oldest first
+fn read_entries(db: &Path) -> Result<Vec<AtuinEntry>> {
+    if !db.exists() {
+        bail!("Atuin database not found: {}", db.display());
+    }
+    let conn = Connection::open_with_flags(db, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open atuin database: {}", db.display()))?;
+
+    let mut query = conn
+        .prepare(
+            "SELECT id, timestamp, duration, exit, command, cwd, session, hostname
+             FROM history WHERE deleted_at IS NULL ORDER BY timestamp",
+        )
+        .context("Not an atuin history database")?;
+    let entries = query
+        .query_map([], |row| {
+            Ok(AtuinEntry {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                duration: row.get(2)?,
+                exit: row.get(3)?,
+                command: row.get(4)?,
+                cwd: row.get(5)?,
+                session: row.get(6)?,
+                hostname: row.get(7)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(entries)
+}
+
+/// Convert an atuin entry into a command record
+pub fn to_command(entry: AtuinEntry) -> Command {
+    let (hostname, username) = match entry.hostname.rsplit_once(':') {
+        Some((host, user)) => (host.to_string(), user.to_string()),
+        None => (entry.hostname, String::new()),
+    };
+
+    Command {
+        id: hyphenate(&entry.id),
+        command: entry.command,
+        output: String::new(),
+        exit_code: entry.exit as i32,
+        cwd: entry.cwd,
+        started_at: DateTime::from_timestamp_nanos(entry.timestamp),
+        duration_ms: (entry.duration.max(0) / 1_000_000) as u64,
+        session_id: hyphenate(&entry.session),
+        shell: UNKNOWN_SHELL.to_string(),
+        hostname,
+        username,
+        timing: Vec::new(),
+        tags: Vec::new(),
+        note: None,
+        bookmarked: false,
+        rerun_of: None,
+    }
+}
+
+/// Convert a command record into an atuin entry
+pub fn to_entry(cmd: &Command) -> AtuinEntry {
+    AtuinEntry {
+        id: cmd.id.replace('-', ""),
+        timestamp: cmd.started_at.timestamp_nanos_opt().unwrap_or_default(),
+        duration: cmd.duration_ms as i64 * 1_000_000,
+        exit: cmd.exit_code.into(),
+        command: cmd.command.clone(),
+        cwd: cmd.cwd.clone(),
+        session: cmd.session_id.replace('-', ""),
+        hostname: format!("{}:{}", cmd.hostname, cmd.username),
+    }
+}
+
+/// Session records spanning the imported commands of each atuin session
+fn sessions_for(commands: &[Command]) -> Vec<Session> {
+    let mut by_session: BTreeMap<&str, Vec<&Command>> = BTreeMap::new();
+    for cmd in commands {
+        by_session.entry(&cmd.session_id).or_default().push(cmd);
+    }
+
+    by_session
+        .into_iter()
+        .map(|(id, cmds)| Session {
+            id: id.to_string(),
+            started_at: cmds.iter().map(|c| c.started_at).min().unwrap_or_default(),
+            ended_at: cmds
+                .iter()
+                .map(|c| c.started_at + Duration::milliseconds(c.duration_ms as i64))
+                .max(),
+            hostname: cmds[0].hostname.clone(),
+            shell: UNKNOWN_SHELL.to_string(),
+            command_count: cmds.len() as u32,
+            name: None,
+            tty: None,
+        })
+        .collect()
+}
+
+/// Atuin stores UUIDs without hyphens; shelltape IDs have them
+fn hyphenate(id: &str) -> String {
+    if id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit()) {
+        format!(
+            "{}-{}-{}-{}-{}",
+            &id[..8],
+            &id[8..12],
+            &id[12..16],
+            &id[16..20],
+            &id[20..]
+        )
+    } else {
+        id.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry() -> AtuinEntry {
+        AtuinEntry {
+            id: "0190f3a2b7c47d1e8a9b0c1d2e3f4a5b".to_string(),
+            timestamp: 1_760_000_000_123_000_000,
+            duration: 2_500_000_000,
+            exit: 1,
+            command: "cargo test".to_string(),
+            cwd: "/src/app".to_string(),
+            session: "0190f3a2000070008000000000000001".to_string(),
+            hostname: "laptop:alice".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_entry_round_trip() {
+        let cmd = to_command(entry());
+        assert_eq!(cmd.id, "0190f3a2-b7c4-7d1e-8a9b-0c1d2e3f4a5b");
+        assert_eq!(
+            (cmd.hostname.as_str(), cmd.username.as_str()),
+            ("laptop", "alice")
+        );
+        assert_eq!(cmd.duration_ms, 2500);
+        assert_eq!(cmd.exit_code, 1);
+
+        assert_eq!(to_entry(&cmd), entry());
+    }
+
+    #[test]
+    fn test_export_then_read() {
+        let dir = TempDir::new().unwrap();
+        let db = dir.path().join("atuin/history.db");
+        let cmd = to_command(entry());
+
+        assert_eq!(export_atuin(std::slice::from_ref(&cmd), &db).unwrap(), 1);
+        // Already there: nothing new is written
+        assert_eq!(export_atuin(&[cmd], &db).unwrap(), 0);
+
+        assert_eq!(read_entries(&db).unwrap(), vec![entry()]);
+    }
+}
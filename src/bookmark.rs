@@ -0,0 +1,41 @@
+use crate::cli::{self, OutputFormat};
+use crate::list;
+use anyhow::Result;
+use shelltape_core::storage::Storage;
+
+/// Bookmark (or un-bookmark) a command; `None` targets the most recent one
+pub fn set_bookmark(storage: &Storage, id: Option<String>, bookmarked: bool) -> Result<()> {
+    let target = storage.find_command_or_last(id.as_deref())?;
+    let cmd = storage.update_command(&target.id, |cmd| cmd.bookmarked = bookmarked)?;
+
+    if bookmarked {
+        println!("★ Bookmarked `{}`", cmd.command);
+    } else {
+        println!("✓ Removed bookmark from `{}`", cmd.command);
+    }
+
+    Ok(())
+}
+
+/// List bookmarked commands, most recent first
+pub fn list_bookmarks(storage: &Storage, format: OutputFormat) -> Result<()> {
+    let mut commands: Vec<_> = storage
+        .read_all_commands()?
+        .into_iter()
+        .filter(|cmd| cmd.bookmarked)
+        .collect();
+    commands.sort_by_key(|c| std::cmp::Reverse(c.started_at));
+
+    if format == OutputFormat::Json {
+        cli::print_json(&commands)?;
+        return Ok(());
+    }
+
+    if commands.is_empty() {
+        println!("No bookmarks yet. Add one with: shelltape bookmark --last");
+        return Ok(());
+    }
+
+    list::print_table(&commands)?;
+    Ok(())
+}
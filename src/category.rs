@@ -0,0 +1,252 @@
+use anyhow::{Result, bail};
+use shelltape_core::config::Config;
+use shelltape_core::normalize;
+use std::collections::HashMap;
+
+/// Category for commands that match no rule
+pub const OTHER: &str = "other";
+
+/// Built-in categories, checked in order after the user's own
+///
+/// A pattern is a program (`kubectl`) or a program and subcommand
+/// (`cargo test`). Subcommand patterns are tried first, so `cargo test` is a
+/// test even though `cargo` on its own is a package manager.
+const BUILTIN: &[(&str, &[&str])] = &[
+    (
+        "test",
+        &[
+            "cargo test",
+            "cargo nextest",
+            "go test",
+            "npm test",
+            "pnpm test",
+            "yarn test",
+            "bun test",
+            "pytest",
+            "jest",
+            "vitest",
+            "tox",
+            "ctest",
+        ],
+    ),
+    (
+        "build",
+        &[
+            "cargo build",
+            "cargo check",
+            "cargo run",
+            "cargo clippy",
+            "cargo fmt",
+            "go build",
+            "go run",
+            "npm run",
+            "pnpm run",
+            "yarn run",
+            "make",
+            "cmake",
+            "ninja",
+            "gcc",
+            "g++",
+            "clang",
+            "rustc",
+            "tsc",
+            "gradle",
+            "mvn",
+            "bazel",
+            "just",
+        ],
+    ),
+    ("git", &["git", "gh", "tig", "lazygit"]),
+    (
+        "docker",
+        &[
+            "docker",
+            "docker-compose",
+            "podman",
+            "kubectl",
+            "helm",
+            "k9s",
+            "minikube",
+            "kind",
+        ],
+    ),
+    (
+        "package-manager",
+        &[
+            "apt", "apt-get", "dnf", "yum", "pacman", "brew", "nix", "nix-env", "snap", "flatpak",
+            "npm", "pnpm", "yarn", "bun", "pip", "pip3", "pipx", "uv", "poetry", "conda", "cargo",
+            "rustup", "go", "gem", "composer",
+        ],
+    ),
+    (
+        "navigation",
+        &[
+            "cd", "ls", "ll", "la", "pwd", "tree", "z", "zoxide", "pushd", "popd", "exa", "eza",
+            "fd", "find",
+        ],
+    ),
+    (
+        "editor",
+        &[
+            "vim", "nvim", "vi", "nano", "emacs", "code", "hx", "helix", "micro",
+        ],
+    ),
+    (
+        "network",
+        &[
+            "curl", "wget", "ssh", "scp", "rsync", "ping", "dig", "nc", "http", "telnet",
+        ],
+    ),
+    (
+        "system",
+        &[
+            "ps",
+            "top",
+            "htop",
+            "btop",
+            "kill",
+            "pkill",
+            "systemctl",
+            "journalctl",
+            "df",
+            "du",
+            "free",
+            "mount",
+            "chmod",
+            "chown",
+        ],
+    ),
+];
+
+/// Assigns commands to categories from user rules and the built-in taxonomy
+pub struct Categorizer {
+    /// User rules from `[categories]` in the config, checked first
+    custom: Vec<(String, Vec<String>)>,
+}
+
+impl Categorizer {
+    /// Build a categorizer with the user's `[categories]` rules
+    pub fn new(config: &Config) -> Self {
+        Self {
+            custom: config
+                .categories
+                .iter()
+                .map(|(name, patterns)| (name.clone(), patterns.clone()))
+                .collect(),
+        }
+    }
+
+    /// Category of a command line, or `OTHER`
+    pub fn categorize(&self, command: &str) -> &str {
+        let words = normalize::program_words(command);
+        let Some(program) = words.first() else {
+            return OTHER;
+        };
+        let subcommand = normalize::subcommand(&words[1..]);
+
+        let matches = |pattern: &str| match pattern.split_once(' ') {
+            Some((prog, sub)) => prog == program && subcommand == Some(sub.trim()),
+            None => pattern == program,
+        };
+
+        // Exact subcommand rules win over program rules within each source
+        for specific in [true, false] {
+            for (name, patterns) in &self.custom {
+                if patterns
+                    .iter()
+                    .any(|p| p.contains(' ') == specific && matches(p))
+                {
+                    return name;
+                }
+            }
+        }
+        for specific in [true, false] {
+            for (name, patterns) in BUILTIN {
+                if patterns
+                    .iter()
+                    .any(|p| p.contains(' ') == specific && matches(p))
+                {
+                    return name;
+                }
+            }
+        }
+
+        OTHER
+    }
+
+    /// Number of commands in each category, largest first, from how often
+    /// each command line ran
+    pub fn counts<'a>(&self, runs: impl Iterator<Item = (&'a str, usize)>) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (command, n) in runs {
+            *counts.entry(self.categorize(command)).or_insert(0) += n;
+        }
+
+        let mut counts: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Check that `name` is a known category, case-insensitively
+    pub fn validate(&self, name: &str) -> Result<()> {
+        if self.names().iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            return Ok(());
+        }
+        bail!(
+            "Unknown category '{}'. Known categories: {}",
+            name,
+            self.names().join(", ")
+        )
+    }
+
+    /// All category names: the user's, then the built-in ones, then `OTHER`
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.custom.iter().map(|(name, _)| name.as_str()).collect();
+        for (name, _) in BUILTIN {
+            if !names.contains(name) {
+                names.push(name);
+            }
+        }
+        names.push(OTHER);
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_categories() {
+        let categorizer = Categorizer::new(&Config::default());
+        assert_eq!(categorizer.categorize("git status -sb"), "git");
+        assert_eq!(categorizer.categorize("cargo test --all"), "test");
+        assert_eq!(categorizer.categorize("cargo build --release"), "build");
+        assert_eq!(categorizer.categorize("cargo add serde"), "package-manager");
+        assert_eq!(
+            categorizer.categorize("sudo apt install jq"),
+            "package-manager"
+        );
+        assert_eq!(categorizer.categorize("docker compose up"), "docker");
+        assert_eq!(categorizer.categorize("cd ~/src"), "navigation");
+        assert_eq!(categorizer.categorize("frobnicate --hard"), OTHER);
+    }
+
+    #[test]
+    fn test_custom_categories() {
+        let mut config = Config::default();
+        config.categories.insert(
+            "deploy".to_string(),
+            vec!["kubectl".to_string(), "git push".to_string()],
+        );
+        let categorizer = Categorizer::new(&config);
+
+        assert_eq!(categorizer.categorize("kubectl apply -f x.yaml"), "deploy");
+        assert_eq!(categorizer.categorize("git push origin main"), "deploy");
+        assert_eq!(categorizer.categorize("git pull"), "git");
+        assert_eq!(categorizer.names()[0], "deploy");
+    }
+}
@@ -1,11 +1,9 @@
-use crate::storage::Storage;
 use anyhow::Result;
+use shelltape_core::storage::Storage;
 use std::io::{self, Write};
 
 /// Clean old commands from history
-pub fn clean_commands(older_than_days: u64, yes: bool) -> Result<()> {
-    let storage = Storage::new()?;
-
+pub fn clean_commands(storage: &Storage, older_than_days: u64, yes: bool) -> Result<()> {
     // Get count before cleaning
     let commands_before = storage.read_all_commands()?;
     let total_before = commands_before.len();
@@ -6,10 +6,60 @@ use std::path::PathBuf;
 #[command(about = "Record and browse your terminal command history", long_about = None)]
 #[command(version = env!("GDL_VERSION"))]
 pub struct Cli {
+    /// Output format for commands that print records or reports
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+
+    /// Shorthand for --format json
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Log more details to stderr (-v info, -vv debug, -vvv trace)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Use this data directory instead of the configured one
+    #[arg(long, global = true, value_name = "PATH")]
+    pub data_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+impl Cli {
+    /// Get the effective output format
+    pub fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.format
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// Structured JSON
+    Json,
+    /// Tab-separated values with a header row (`list` and `search`; text elsewhere)
+    Tsv,
+}
+
+/// Print a value as pretty JSON for `--format json`
+///
+/// Goes through a locked stdout so a closed pipe is an error, not a panic.
+pub fn print_json(value: &impl serde::Serialize) -> anyhow::Result<()> {
+    use std::io::Write;
+    writeln!(
+        std::io::stdout().lock(),
+        "{}",
+        serde_json::to_string_pretty(value)?
+    )?;
+    Ok(())
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Install shell hooks for automatic command recording
@@ -17,6 +67,10 @@ pub enum Commands {
         /// Shell to install hooks for (auto-detected if not specified)
         #[arg(short, long)]
         shell: Option<Shell>,
+
+        /// Also bind Ctrl-R to `shelltape pick` (bash, zsh and fish)
+        #[arg(long)]
+        ctrl_r: bool,
     },
 
     /// Uninstall shell hooks
@@ -32,9 +86,14 @@ pub enum Commands {
         #[arg(required = true)]
         command: Vec<String>,
 
-        /// Session ID for this shell session
+        /// Session ID for this shell session (defaults to $SHELLTAPE_SESSION_ID,
+        /// then a per-terminal session)
         #[arg(long)]
-        session_id: String,
+        session_id: Option<String>,
+
+        /// Label for the session; runs with the same name share a session
+        #[arg(short, long, conflicts_with = "session_id")]
+        name: Option<String>,
     },
 
     /// Record a command (called by shell hooks)
@@ -63,31 +122,336 @@ pub enum Commands {
         #[arg(long)]
         session_id: String,
 
-        /// Command output (optional)
+        /// Command output (optional; use `-` to read it from stdin)
         #[arg(long, default_value = "")]
         output: String,
+
+        /// Read the command output from a file
+        #[arg(long, conflicts_with = "output")]
+        output_file: Option<PathBuf>,
+
+        /// Let a running daemon filter and redact the command, skipping the
+        /// recorder setup here; records directly if no daemon is running
+        #[arg(long)]
+        via_daemon: bool,
     },
 
     /// Browse commands interactively (TUI)
-    Browse,
+    Browse {
+        /// Only show commands with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+    },
+
+    /// Fuzzy-pick a previous command and print it (for shell keybindings)
+    Pick {
+        /// Initial search query
+        #[arg(default_value = "")]
+        query: String,
+
+        /// Only offer commands run in the current directory
+        #[arg(long)]
+        here: bool,
+
+        /// Only offer commands that succeeded
+        #[arg(long)]
+        success: bool,
+
+        /// Print all matches, best first, instead of opening the selector
+        #[arg(short, long)]
+        filter: bool,
+    },
 
     /// List recent commands
     List {
-        /// Maximum number of commands to display
-        #[arg(short, long, default_value = "20")]
-        limit: usize,
+        /// Maximum number of commands to display (defaults to ui.default_limit)
+        #[arg(short, long)]
+        limit: Option<usize>,
 
         /// Filter commands by query string
         #[arg(short, long)]
         filter: Option<String>,
+
+        /// Only show commands with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Only show commands in this category (e.g. git, build, test, docker)
+        #[arg(short = 'C', long)]
+        category: Option<String>,
+
+        /// Columns to show, comma-separated (default: id,time,status,command,dir)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Option<Vec<ListColumn>>,
+
+        /// Show start times relative to now (e.g. "3h ago")
+        #[arg(short, long)]
+        relative_time: bool,
+
+        /// Don't shorten long commands and directories
+        #[arg(long)]
+        no_truncate: bool,
+
+        /// Show the first N lines of output under each command
+        #[arg(short = 'p', long, value_name = "N", default_value = "0")]
+        output_preview: usize,
+
+        /// Print tab-separated id, time, exit code and command, for fzf and scripts
+        #[arg(long)]
+        porcelain: bool,
+    },
+
+    /// Print a command and its output, for fzf's --preview
+    Preview {
+        /// Command ID (or unique prefix)
+        id: String,
+    },
+
+    /// Show what was run in a directory (defaults to the current one)
+    Dir {
+        /// Directory to query
+        path: Option<String>,
+
+        /// Include commands run in subdirectories
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Only commands since this time (YYYY-MM-DD, today, yesterday, or an age like 2h, 3d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only commands before this time (same formats as --since)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only show commands that failed
+        #[arg(long)]
+        failed: bool,
+
+        /// Maximum number of commands to display (defaults to ui.default_limit)
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+
+    /// Search command lines, directories and output
+    Search {
+        /// Text to look for (case-insensitive), or a regex with --regex
+        query: String,
+
+        /// Treat the query as a regular expression (case-sensitive unless it starts with (?i))
+        #[arg(short = 'E', long)]
+        regex: bool,
+
+        /// Only match the command line
+        #[arg(long, conflicts_with = "output_only")]
+        command_only: bool,
+
+        /// Only match the captured output
+        #[arg(long)]
+        output_only: bool,
+
+        /// Only commands run in this directory or below it
+        #[arg(long, value_name = "DIR")]
+        cwd: Option<String>,
+
+        /// Only commands that exited with this code
+        #[arg(long, value_name = "CODE", allow_negative_numbers = true)]
+        exit_code: Option<i32>,
+
+        /// Only commands with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Only commands since this time (YYYY-MM-DD, today, yesterday, or an age like 2h, 3d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only commands before this time (same formats as --since)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Maximum number of commands to display (defaults to ui.default_limit)
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+
+    /// Follow new commands as they are recorded, across all sessions
+    #[command(visible_alias = "tail")]
+    Watch {
+        /// Only show commands containing this string (command or directory)
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Only show failed commands
+        #[arg(long)]
+        failed: bool,
+
+        /// Only show commands from this session (ID prefix)
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Number of existing records to print before following
+        #[arg(short = 'n', long, default_value = "0")]
+        lines: usize,
     },
 
-    /// Export commands to markdown
+    /// Run a recorded command again, recording the new run
+    Rerun {
+        /// Command ID (or unique prefix), as shown by `list`
+        #[arg(
+            required_unless_present_any = ["last", "interactive"],
+            conflicts_with_all = ["last", "interactive"]
+        )]
+        id: Option<String>,
+
+        /// Rerun the most recent command
+        #[arg(long, conflicts_with = "interactive")]
+        last: bool,
+
+        /// Pick the command in a fuzzy selector
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Run in the current directory instead of the original one
+        #[arg(long)]
+        here: bool,
+    },
+
+    /// Copy a recorded command (or its output) to the clipboard
+    Copy {
+        /// Command ID (or unique prefix), as shown by `list`
+        #[arg(required_unless_present = "last", conflicts_with = "last")]
+        id: Option<String>,
+
+        /// Copy the most recent command
+        #[arg(long)]
+        last: bool,
+
+        /// Copy the command's output instead of the command line
+        #[arg(short, long)]
+        output: bool,
+    },
+
+    /// Add, remove and list tags on recorded commands
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+
+    /// Attach a free-form note to a recorded command (appends by default)
+    #[command(visible_alias = "annotate")]
+    Note {
+        /// Command ID (or unique prefix); omit with --last
+        #[arg(required_unless_present = "last")]
+        id: Option<String>,
+
+        /// Note text
+        #[arg(required_unless_present_any = ["last", "clear"])]
+        text: Option<String>,
+
+        /// Annotate the most recent command
+        #[arg(long)]
+        last: bool,
+
+        /// Replace the existing note instead of appending to it
+        #[arg(short, long, conflicts_with = "clear")]
+        replace: bool,
+
+        /// Remove the note
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// List shell sessions, or show the commands of one
+    #[command(args_conflicts_with_subcommands = true)]
+    Sessions {
+        #[command(subcommand)]
+        action: Option<SessionsAction>,
+
+        /// Maximum number of sessions to display (defaults to ui.default_limit)
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+
+    /// Bookmark a command for quick recall, or list bookmarks
+    #[command(args_conflicts_with_subcommands = true)]
+    Bookmark {
+        #[command(subcommand)]
+        action: Option<BookmarkAction>,
+
+        /// Command ID (or unique prefix) to bookmark
+        #[arg(required_unless_present = "last", conflicts_with = "last")]
+        id: Option<String>,
+
+        /// Bookmark the most recent command
+        #[arg(long)]
+        last: bool,
+
+        /// Remove the bookmark instead of adding it
+        #[arg(short, long)]
+        remove: bool,
+    },
+
+    /// Show a unified diff between the outputs of two recorded runs
+    Diff {
+        /// ID (or unique prefix) of the first run
+        id1: String,
+
+        /// ID (or unique prefix) of the second run
+        id2: String,
+
+        /// Also compare metadata (directory, exit code, duration, host, ...)
+        #[arg(short, long)]
+        metadata: bool,
+
+        /// Lines of context around each change
+        #[arg(short = 'U', long, default_value = "3")]
+        context: usize,
+    },
+
+    /// Open the data directory, or a record in $EDITOR for inspection
+    Open {
+        /// ID (or unique prefix) of a record to open; omit for the data directory
+        id: Option<String>,
+
+        /// Open the record in $PAGER instead of $EDITOR
+        #[arg(short, long)]
+        pager: bool,
+    },
+
+    /// Play back a recorded command's output, or a whole session, with its original timing
+    Replay {
+        /// ID (or unique prefix) of the command, or of a session
+        id: String,
+
+        /// Treat the ID as a session and replay all of its commands in order
+        #[arg(short = 'S', long)]
+        session: bool,
+
+        /// Playback speed multiplier (2.0 = twice as fast)
+        #[arg(short, long, default_value = "1.0")]
+        speed: f64,
+
+        /// Print the output at once, ignoring timing data
+        #[arg(long)]
+        instant: bool,
+
+        /// Longest pause between output chunks (or commands), in seconds
+        #[arg(long, default_value = "2.0")]
+        max_idle: f64,
+    },
+
+    /// Export commands to markdown or another tool's history
     Export {
-        /// Output file path
+        /// Output file path (collector URL for `--to otlp`, `-` for stdout
+        /// with `--to json`, `jsonl` or `script`)
         #[arg(short, long)]
         output: PathBuf,
 
+        /// Format to write (`--format` is the global output flag)
+        #[arg(long, value_enum, default_value = "markdown")]
+        to: ExportFormat,
+
         /// Filter by session ID
         #[arg(short, long)]
         session: Option<String>,
@@ -95,16 +459,240 @@ pub enum Commands {
         /// Filter by query string
         #[arg(short, long)]
         filter: Option<String>,
+
+        /// Only export commands with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Annotate commands with the git commit they led to
+        #[arg(long)]
+        commits: bool,
+    },
+
+    /// Show git commits with the recorded commands that produced them
+    GitLog {
+        /// Commit, branch or range to show (as for `git log`)
+        #[arg(default_value = "HEAD")]
+        rev: String,
+
+        /// Repository to read (defaults to the current directory)
+        #[arg(short = 'C', long, value_name = "PATH")]
+        repo: Option<PathBuf>,
+
+        /// Number of commits to show
+        #[arg(short = 'n', long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Import history from another tool
+    Import {
+        /// Tool to import from
+        #[arg(long, value_enum)]
+        from: ImportSource,
+
+        /// Database to read (defaults to the tool's usual location)
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+
+        /// File to import (for typescript, asciicast and shell-history)
+        file: Option<PathBuf>,
+
+        /// Timing file written by `script -t` or `--log-timing`
+        #[arg(long, value_name = "PATH")]
+        timing: Option<PathBuf>,
+
+        /// Regex matching prompt lines; its last capture group is the command
+        #[arg(long, value_name = "REGEX")]
+        prompt: Option<String>,
+    },
+
+    /// Serve the history over a local HTTP API (token-protected)
+    Serve {
+        /// Address to listen on (defaults to serve.listen, 127.0.0.1:7070)
+        #[arg(long, value_name = "ADDR")]
+        listen: Option<String>,
+
+        /// Listen on 127.0.0.1 at this port (shorthand for --listen)
+        #[arg(short, long, conflicts_with = "listen")]
+        port: Option<u16>,
+
+        /// Bearer token clients must send (defaults to $SHELLTAPE_API_TOKEN or serve.token)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Also serve a web UI at `/` for searching and browsing sessions and output
+        #[arg(long)]
+        ui: bool,
+
+        /// Accept new commands at `POST /api/commands` (the API is read-only otherwise)
+        #[arg(long)]
+        allow_record: bool,
+    },
+
+    /// Answer JSON-RPC requests on stdin/stdout, for editor integrations
+    Rpc,
+
+    /// Run a background daemon that records and answers queries over a unix socket (unix only)
+    ///
+    /// There is no named-pipe transport for Windows; there `record` always
+    /// writes to the history itself.
+    Daemon {
+        /// Stop the running daemon after it writes pending commands
+        #[arg(long, conflicts_with = "status")]
+        stop: bool,
+
+        /// Show whether the daemon is running
+        #[arg(long)]
+        status: bool,
+    },
+
+    /// Print Prometheus metrics, or write them for node_exporter's textfile collector
+    Metrics {
+        /// Write to this file (replaced atomically) instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 
     /// Show statistics about command history
-    Stats,
+    Stats {
+        /// Number of weeks shown in the daily activity heatmap
+        #[arg(short, long, default_value_t = 12, value_parser = clap::value_parser!(u16).range(1..))]
+        weeks: u16,
+
+        /// Summarize each session (duration, commands, failure rate, top directory)
+        #[arg(long)]
+        by_session: bool,
+
+        /// Only show the N most recent sessions
+        #[arg(long, value_name = "N", requires = "by_session")]
+        last: Option<usize>,
+
+        /// Summarize each host (volume, failure rate, top commands)
+        #[arg(long, conflicts_with = "by_session")]
+        by_host: bool,
+
+        /// Break down how a program is used by subcommand and flag (e.g. `git`)
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["by_session", "by_host"])]
+        program: Option<String>,
+
+        /// Compare the current period with the previous one
+        #[arg(long, value_name = "PERIOD", num_args = 0..=1, default_missing_value = "week",
+              conflicts_with_all = ["by_session", "by_host", "program"])]
+        compare: Option<StatsPeriod>,
+
+        /// Show which records, commands and directories use the most storage
+        #[arg(long, conflicts_with_all = ["by_session", "by_host", "program", "compare"])]
+        storage: bool,
+
+        /// Show commonly mistyped commands and commands retried after failing
+        #[arg(long, conflicts_with_all = ["by_session", "by_host", "program", "compare", "storage"])]
+        typos: bool,
+
+        /// Only count commands whose text, directory or output contains this
+        #[arg(short, long, value_name = "QUERY")]
+        filter: Option<String>,
+
+        /// Only count commands since this time (YYYY-MM-DD, today, yesterday, or an age like 2h, 3d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only count commands before this time (same formats as --since)
+        #[arg(long)]
+        until: Option<String>,
+    },
+
+    /// Digest of today's activity (or the past week's) for standups and reviews
+    Summary {
+        /// Summarize the past 7 days instead of today
+        #[arg(short, long)]
+        week: bool,
+    },
 
     /// Clean old commands from history
     Clean {
-        /// Remove commands older than this many days
-        #[arg(long, default_value = "90")]
-        older_than_days: u64,
+        /// Remove commands older than this many days (defaults to storage.retention_days)
+        #[arg(long)]
+        older_than_days: Option<u64>,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Remove selected commands from history
+    Prune {
+        /// Remove commands that exited with a non-zero code
+        #[arg(long)]
+        failed: bool,
+
+        /// Remove repeated commands, keeping the most recent run of each
+        #[arg(long)]
+        duplicates: bool,
+
+        /// Remove commands run in this directory (or below it)
+        #[arg(long, value_name = "PATH")]
+        dir: Option<String>,
+
+        /// Remove commands matching a glob pattern (e.g. 'ls*')
+        #[arg(long, value_name = "GLOB")]
+        command_glob: Option<String>,
+
+        /// Remove commands that produced no output
+        #[arg(long)]
+        empty_output: bool,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Move old commands into a (compressed) archive file, or restore one
+    Archive {
+        /// Archive commands older than this (YYYY-MM-DD or an age like 180d, 26w)
+        #[arg(long, required_unless_present = "restore")]
+        older_than: Option<String>,
+
+        /// Archive file to write (compressed with zstd when it ends in .zst)
+        #[arg(short, long, required_unless_present = "restore")]
+        output: Option<PathBuf>,
+
+        /// Import the commands from an archive back into history
+        #[arg(long, conflicts_with_all = ["older_than", "output"])]
+        restore: Option<PathBuf>,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Delete recorded commands by ID, or all that match a filter or session
+    Delete {
+        /// Command IDs (or unique prefixes), as shown by `list`
+        #[arg(required_unless_present_any = ["filter", "session"])]
+        ids: Vec<String>,
+
+        /// Delete commands whose line, directory or output contains this text
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Delete commands from this session (ID prefix); with --filter, only matching ones
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Delete all recorded data (commands, sessions and everything else in the data directory)
+    Purge {
+        /// Keep the config file
+        #[arg(long)]
+        keep_config: bool,
+
+        /// Overwrite files with zeros before deleting them
+        #[arg(long)]
+        shred: bool,
 
         /// Don't ask for confirmation
         #[arg(short, long)]
@@ -113,6 +701,368 @@ pub enum Commands {
 
     /// Show status and storage information
     Status,
+
+    /// Search stored command outputs for a pattern
+    Grep {
+        /// Regular expression to search for
+        pattern: String,
+
+        /// Treat the pattern as a literal string
+        #[arg(short = 'F', long)]
+        fixed_strings: bool,
+
+        /// Match case-insensitively
+        #[arg(short, long)]
+        ignore_case: bool,
+
+        /// Lines of context after each match
+        #[arg(short = 'A', long, value_name = "NUM")]
+        after_context: Option<usize>,
+
+        /// Lines of context before each match
+        #[arg(short = 'B', long, value_name = "NUM")]
+        before_context: Option<usize>,
+
+        /// Lines of context before and after each match
+        #[arg(short = 'C', long, value_name = "NUM")]
+        context: Option<usize>,
+
+        /// Maximum number of commands to report
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+
+    /// Sync history with another machine (directory, [user@]host:path, or URL)
+    #[command(args_conflicts_with_subcommands = true)]
+    Sync {
+        #[command(subcommand)]
+        action: Option<SyncAction>,
+
+        /// Sync target: a directory, an SSH `[user@]host:path`, or an HTTP(S) URL
+        /// (defaults to sync.target)
+        target: Option<String>,
+
+        /// Only pull remote records into local history
+        #[arg(long, conflicts_with = "push")]
+        pull: bool,
+
+        /// Only push local records to the target
+        #[arg(long)]
+        push: bool,
+    },
+
+    /// Query commands with a jq-style expression, printing JSON
+    ///
+    /// Stages are separated by `|`: `select(expr)`, `sort_by(expr)`,
+    /// `limit(n)`, `reverse`, `count`, or an expression mapping each record,
+    /// e.g. `select(exit_code != 0 and duration_ms > 5000) | {command, cwd}`
+    Query {
+        /// The query to run
+        query: String,
+
+        /// Print one compact JSON value per line instead of an array
+        #[arg(short, long)]
+        compact: bool,
+    },
+
+    /// Merge the history of remote hosts into local history over SSH
+    Fetch {
+        /// Hosts as ssh://[user@]host[:port]
+        #[arg(required = true)]
+        hosts: Vec<String>,
+
+        /// Only fetch commands since this time (YYYY-MM-DD, today, yesterday, or an age like 2h, 3d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Path of shelltape on the remote hosts
+        #[arg(long, default_value = "shelltape")]
+        remote_bin: String,
+    },
+
+    /// Write all records to stdout as JSONL (used by fetch)
+    Dump {
+        /// Only records since this time (YYYY-MM-DD, today, yesterday, or an age like 2h, 3d)
+        #[arg(long)]
+        since: Option<String>,
+    },
+
+    /// Show a day's commands grouped by session, with idle gaps
+    Timeline {
+        /// Day to show (YYYY-MM-DD, today, or yesterday)
+        #[arg(short, long)]
+        date: Option<String>,
+
+        /// Annotate gaps between commands of at least this many minutes
+        #[arg(long, default_value = "15")]
+        gap_minutes: i64,
+    },
+
+    /// Live view of running and recently finished commands
+    Top {
+        /// Number of recently finished commands to show
+        #[arg(short, long, default_value = "20")]
+        recent: usize,
+    },
+
+    /// Copy the commands into another storage backend and switch to it
+    Migrate {
+        /// Backend to move the commands to
+        #[arg(value_enum)]
+        to: Backend,
+    },
+
+    /// Diagnose installation and data problems
+    Doctor {
+        /// Apply safe automatic fixes
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// View or modify configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SyncAction {
+    /// Encrypt and upload local records the target doesn't have
+    Push {
+        /// Sync target (defaults to sync.target)
+        target: Option<String>,
+    },
+
+    /// Download, decrypt and merge records from the target
+    Pull {
+        /// Sync target (defaults to sync.target)
+        target: Option<String>,
+    },
+
+    /// Compare local history with the encrypted copy on the target
+    Status {
+        /// Sync target (defaults to sync.target)
+        target: Option<String>,
+    },
+
+    /// Print the encryption key, creating it if needed, or import one
+    Key {
+        /// Use this key (64 hex digits) from another machine
+        #[arg(long, value_name = "KEY")]
+        set: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the value of a config key (e.g. recording.max_output_size)
+    Get {
+        /// Dotted config key
+        key: String,
+    },
+
+    /// Set a config key to a value
+    Set {
+        /// Dotted config key
+        key: String,
+
+        /// New value (parsed as TOML, e.g. 500, true, ["ls", "cd"])
+        value: String,
+    },
+
+    /// List all config keys and their values
+    List,
+
+    /// Open the config file in $EDITOR
+    Edit,
+
+    /// Write a config file with every key at its default value
+    Init {
+        /// Replace an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print the config file path
+    Path,
+}
+
+#[derive(Subcommand)]
+pub enum TagAction {
+    /// Add tags to a command
+    Add {
+        /// Command ID (or unique prefix); omit with --last
+        #[arg(required_unless_present = "last")]
+        id: Option<String>,
+
+        /// Tag the most recent command
+        #[arg(long)]
+        last: bool,
+
+        /// Tags to add (space- or comma-separated)
+        tags: Vec<String>,
+    },
+
+    /// Remove tags from a command
+    Rm {
+        /// Command ID (or unique prefix); omit with --last
+        #[arg(required_unless_present = "last")]
+        id: Option<String>,
+
+        /// Untag the most recent command
+        #[arg(long)]
+        last: bool,
+
+        /// Tags to remove (space- or comma-separated)
+        tags: Vec<String>,
+    },
+
+    /// List all tags with how many commands carry them
+    List,
+}
+
+/// A column of the `list` table
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListColumn {
+    /// Short command ID
+    Id,
+    /// Start time
+    Time,
+    /// Exit status
+    Status,
+    /// Execution time
+    Duration,
+    /// Command line
+    Command,
+    /// Working directory
+    Dir,
+    /// Short session ID
+    Session,
+    /// Hostname
+    Host,
+    /// Tags
+    Tags,
+}
+
+impl ListColumn {
+    /// Column heading
+    pub fn header(&self) -> &'static str {
+        match self {
+            ListColumn::Id => "ID",
+            ListColumn::Time => "TIME",
+            ListColumn::Status => "STATUS",
+            ListColumn::Duration => "DURATION",
+            ListColumn::Command => "COMMAND",
+            ListColumn::Dir => "DIRECTORY",
+            ListColumn::Session => "SESSION",
+            ListColumn::Host => "HOST",
+            ListColumn::Tags => "TAGS",
+        }
+    }
+
+    /// Fixed width in the default (truncating) layout, or `None` to size the column to its content
+    pub fn max_width(&self) -> Option<usize> {
+        match self {
+            ListColumn::Time => Some(20),
+            ListColumn::Status => Some(8),
+            ListColumn::Command => Some(50),
+            ListColumn::Dir => Some(30),
+            ListColumn::Tags => Some(20),
+            _ => None,
+        }
+    }
+}
+
+/// Storage backends `migrate` can move to
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// One JSON record per line in commands.jsonl
+    Jsonl,
+    /// An SQLite database, commands.db
+    Sqlite,
+}
+
+/// File formats written by `export`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Markdown report with output
+    Markdown,
+    /// Self-contained HTML report with collapsible output and filtering
+    Html,
+    /// Atuin history database (SQLite); existing entries are kept
+    Atuin,
+    /// OpenTelemetry spans: OTLP/JSON file, or sent to a collector when the output is a URL
+    Otlp,
+    /// zsh extended history file
+    ZshHistory,
+    /// bash history file with timestamps
+    BashHistory,
+    /// JSON array of the full records
+    Json,
+    /// One JSON record per line, as in commands.jsonl
+    Jsonl,
+    /// Shell script rerunning the commands, changing directory as they did
+    Script,
+}
+
+/// Tools `import` can read history from
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportSource {
+    /// Atuin's SQLite history database
+    Atuin,
+    /// A `script(1)` typescript, optionally with its timing file
+    Typescript,
+    /// An asciinema cast (`.cast` file)
+    Asciicast,
+    /// bash, zsh or fish history file; all that exist when no file is given
+    ShellHistory,
+}
+
+/// Length of the periods compared by `stats --compare`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsPeriod {
+    /// The last 24 hours
+    Day,
+    /// The last 7 days
+    Week,
+    /// The last 30 days
+    Month,
+}
+
+impl StatsPeriod {
+    /// Length of the period
+    pub fn duration(&self) -> chrono::Duration {
+        match self {
+            StatsPeriod::Day => chrono::Duration::days(1),
+            StatsPeriod::Week => chrono::Duration::weeks(1),
+            StatsPeriod::Month => chrono::Duration::days(30),
+        }
+    }
+
+    /// Name used in headings, e.g. "week"
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsPeriod::Day => "day",
+            StatsPeriod::Week => "week",
+            StatsPeriod::Month => "month",
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum SessionsAction {
+    /// List every command recorded in a session, oldest first
+    Show {
+        /// Session ID (or unique prefix)
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BookmarkAction {
+    /// List bookmarked commands
+    List,
 }
 
 #[derive(ValueEnum, Clone, Copy, Debug)]
@@ -138,6 +1088,34 @@ impl Shell {
         }
     }
 
+    /// Get the line that sources the hook file from the RC file
+    pub fn hook_line(&self) -> String {
+        self.source_line(self.hook_file())
+    }
+
+    /// Get the line that sources the Ctrl-R widget, if the shell has one
+    pub fn widget_line(&self) -> Option<String> {
+        self.widget_file().map(|file| self.source_line(file))
+    }
+
+    /// Get the Ctrl-R widget file name for this shell, if it has one
+    pub fn widget_file(&self) -> Option<&'static str> {
+        match self {
+            Shell::Bash => Some("bash-ctrl-r.sh"),
+            Shell::Zsh => Some("zsh-ctrl-r.sh"),
+            Shell::Fish => Some("fish-ctrl-r.fish"),
+            Shell::Powershell => None,
+        }
+    }
+
+    /// Line that sources a file from ~/.shelltape/
+    fn source_line(&self, file: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh | Shell::Fish => format!("source ~/.shelltape/{}", file),
+            Shell::Powershell => format!(". ~\\.shelltape\\{}", file),
+        }
+    }
+
     /// Get the hook file name for this shell
     pub fn hook_file(&self) -> &'static str {
         match self {
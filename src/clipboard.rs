@@ -0,0 +1,90 @@
+use anyhow::{Result, bail};
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Clipboard tools tried in order: (program, arguments)
+const CLIPBOARD_TOOLS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+    ("clip", &[]),
+];
+
+/// Copy text to the system clipboard, returning the method that was used
+///
+/// Tries the platform clipboard tools first and falls back to the OSC 52
+/// terminal escape sequence, which also works over SSH in most terminals.
+pub fn copy_to_clipboard(text: &str) -> Result<&'static str> {
+    for (program, args) in CLIPBOARD_TOOLS {
+        if which::which(program).is_err() {
+            continue;
+        }
+
+        let Ok(mut child) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(text.as_bytes());
+        }
+
+        if child.wait().is_ok_and(|status| status.success()) {
+            return Ok(program);
+        }
+    }
+
+    let mut stdout = std::io::stdout();
+    if stdout.is_terminal() {
+        write!(stdout, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))?;
+        stdout.flush()?;
+        return Ok("OSC 52");
+    }
+
+    bail!("No clipboard tool found (install wl-copy, xclip or xsel)")
+}
+
+/// Encode bytes as standard base64
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -0,0 +1,75 @@
+use crate::cli::ConfigAction;
+use anyhow::{Context, Result, bail};
+use shelltape_core::config::Config;
+
+/// Format a value for display (strings are shown without quotes)
+fn display_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Handle the `config` subcommand
+pub fn config_command(action: ConfigAction) -> Result<()> {
+    let path = Config::path()?;
+
+    match action {
+        ConfigAction::Get { key } => {
+            let config = Config::load_from(&path)?;
+            println!("{}", display_value(&config.get(&key)?));
+        }
+        ConfigAction::Set { key, value } => {
+            let config = Config::set_in_file(&path, &key, &value)?;
+            println!("✓ Set {} = {}", key, display_value(&config.get(&key)?));
+        }
+        ConfigAction::List => {
+            let config = Config::load_from(&path)?;
+            for (key, value) in config.entries()? {
+                println!("{} = {}", key, value);
+            }
+        }
+        ConfigAction::Init { force } => {
+            if path.exists() && !force {
+                bail!(
+                    "Config file already exists: {} (use --force to replace it)",
+                    path.display()
+                );
+            }
+            Config::default().save_to(&path)?;
+            println!("✓ Wrote default config to {}", path.display());
+        }
+        ConfigAction::Path => {
+            println!("{}", path.display());
+        }
+        ConfigAction::Edit => {
+            if !path.exists() {
+                Config::default().save_to(&path)?;
+            }
+
+            let editor = std::env::var("VISUAL")
+                .or_else(|_| std::env::var("EDITOR"))
+                .unwrap_or_else(|_| {
+                    if cfg!(target_os = "windows") {
+                        "notepad".to_string()
+                    } else {
+                        "vi".to_string()
+                    }
+                });
+
+            let status = std::process::Command::new(&editor)
+                .arg(&path)
+                .status()
+                .with_context(|| format!("Failed to launch editor: {}", editor))?;
+
+            if !status.success() {
+                bail!("Editor exited with status: {}", status);
+            }
+
+            // Validate the edited file
+            Config::load_from(&path)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use crate::clipboard;
+use anyhow::{Result, bail};
+use shelltape_core::storage::Storage;
+
+/// Copy a stored command (or its output) to the clipboard
+pub fn copy_command(storage: &Storage, id: Option<String>, last: bool, output: bool) -> Result<()> {
+    let cmd = match (id, last) {
+        (Some(id), false) => storage.find_command(&id)?,
+        (None, true) => storage.find_command_or_last(None)?,
+        _ => bail!("Specify a command ID or --last"),
+    };
+
+    let text = if output { &cmd.output } else { &cmd.command };
+    let method = clipboard::copy_to_clipboard(text)?;
+
+    println!(
+        "✓ Copied {} of `{}` to clipboard (via {})",
+        if output { "output" } else { "command" },
+        cmd.command,
+        method
+    );
+
+    Ok(())
+}
@@ -0,0 +1,413 @@
+use crate::rpc::{self, RpcError};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use shelltape_core::models::Command;
+use shelltape_core::recorder::Origin;
+use shelltape_core::storage::Storage;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Socket file in the data directory
+const SOCKET_FILE: &str = "daemon.sock";
+
+/// How long a record waits for others to be written with it
+const BATCH_WINDOW: Duration = Duration::from_millis(200);
+
+/// How long a client waits for the daemon to answer
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Path of the daemon's socket
+pub fn socket_path(storage: &Storage) -> PathBuf {
+    storage.data_dir().join(SOCKET_FILE)
+}
+
+/// Hand a prepared command to the daemon, if one is running
+///
+/// Returns whether the daemon accepted it; otherwise the caller stores the
+/// command itself.
+pub fn send_record(storage: &Storage, cmd: &Command) -> bool {
+    match request(storage, "record", json!(cmd)) {
+        Ok(Some(_)) => true,
+        Ok(None) => false,
+        Err(e) => {
+            tracing::warn!("Daemon did not take the command: {:#}", e);
+            false
+        }
+    }
+}
+
+/// A finished command as a shell hook reports it
+///
+/// The daemon applies exclusion, redaction and truncation, so `record
+/// --via-daemon` does not have to load the recorder's configuration.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RawRecord {
+    pub command: String,
+    pub output: String,
+    pub exit_code: i32,
+    /// Nanoseconds since the epoch
+    pub start_time: i64,
+    /// Nanoseconds since the epoch
+    pub end_time: i64,
+    pub cwd: String,
+    pub session_id: String,
+    /// The shell, host and user of the hook, not of the daemon
+    #[serde(flatten)]
+    pub origin: Origin,
+}
+
+/// Hand an unprocessed command to the daemon, if one is running
+///
+/// Returns whether the daemon accepted it, like [`send_record`].
+pub fn send_raw(storage: &Storage, raw: &RawRecord) -> bool {
+    match request(storage, "record_raw", json!(raw)) {
+        Ok(Some(_)) => true,
+        Ok(None) => false,
+        Err(e) => {
+            tracing::warn!("Daemon did not take the command: {:#}", e);
+            false
+        }
+    }
+}
+
+/// Stop a running daemon after it has written pending commands
+pub fn stop(storage: &Storage) -> Result<()> {
+    match request(storage, "shutdown", Value::Null)? {
+        Some(_) => println!("✓ Daemon stopped"),
+        None => println!("Daemon is not running"),
+    }
+    Ok(())
+}
+
+/// Print whether the daemon runs and what it has done
+pub fn status(storage: &Storage) -> Result<()> {
+    match request(storage, "status", Value::Null)? {
+        Some(status) => {
+            println!("Daemon running (pid {})", status["pid"]);
+            println!("  Socket:   {}", socket_path(storage).display());
+            println!("  Uptime:   {}s", status["uptime_secs"]);
+            println!("  Recorded: {}", status["recorded"]);
+            println!("  Pending:  {}", status["pending"]);
+        }
+        None => println!("Daemon is not running"),
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub use unix::{request, run};
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use anyhow::{Context, anyhow, bail};
+    use shelltape_core::recorder::Recorder;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::time::Instant;
+
+    /// Shared state of a running daemon
+    struct Daemon {
+        storage: Storage,
+        recorder: Recorder,
+        socket: PathBuf,
+        pending: Mutex<Vec<Command>>,
+        wake: Condvar,
+        stopping: AtomicBool,
+        recorded: AtomicUsize,
+        started: Instant,
+    }
+
+    /// Run the daemon in the foreground until `daemon --stop`
+    ///
+    /// Shell hooks hand it finished commands over the socket, which it
+    /// writes in batches, syncing each to disk once, together with their
+    /// index entries, webhooks and other hooks. The socket also answers the history methods of
+    /// `shelltape rpc`.
+    pub fn run(storage: Storage) -> Result<()> {
+        let recorder = Recorder::new(storage.clone())?;
+        listen(storage, recorder)
+    }
+
+    pub(super) fn listen(storage: Storage, recorder: Recorder) -> Result<()> {
+        let socket = socket_path(&storage);
+        if UnixStream::connect(&socket).is_ok() {
+            bail!("The daemon is already running on {}", socket.display());
+        }
+        // Left over from a daemon that did not shut down cleanly
+        let _ = std::fs::remove_file(&socket);
+
+        // Created without group and other access, so no one else can connect
+        // before the permissions are narrowed below
+        // SAFETY: umask only swaps the process's file mode mask
+        let umask = unsafe { libc::umask(0o077) };
+        let listener = UnixListener::bind(&socket);
+        // SAFETY: as above, restoring the previous mask
+        unsafe { libc::umask(umask) };
+        let listener =
+            listener.with_context(|| format!("Failed to listen on {}", socket.display()))?;
+        std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600))?;
+        eprintln!("Listening on {}", socket.display());
+
+        let daemon = Arc::new(Daemon {
+            recorder,
+            storage,
+            socket,
+            pending: Mutex::new(Vec::new()),
+            wake: Condvar::new(),
+            stopping: AtomicBool::new(false),
+            recorded: AtomicUsize::new(0),
+            started: Instant::now(),
+        });
+
+        let writer = {
+            let daemon = Arc::clone(&daemon);
+            std::thread::spawn(move || daemon.write_batches())
+        };
+
+        for stream in listener.incoming() {
+            if daemon.stopping.load(Ordering::SeqCst) {
+                break;
+            }
+            match stream {
+                Ok(stream) => {
+                    let daemon = Arc::clone(&daemon);
+                    std::thread::spawn(move || {
+                        if let Err(e) = daemon.serve(stream) {
+                            tracing::debug!("Daemon connection failed: {:#}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept connection: {}", e),
+            }
+        }
+
+        writer
+            .join()
+            .map_err(|_| anyhow!("Daemon writer panicked"))?;
+        let _ = std::fs::remove_file(&daemon.socket);
+        eprintln!(
+            "Stopped after recording {} commands",
+            daemon.recorded.load(Ordering::SeqCst)
+        );
+        Ok(())
+    }
+
+    impl Daemon {
+        /// Answer the requests on one connection, one JSON message per line
+        fn serve(&self, stream: UnixStream) -> Result<()> {
+            let mut out = stream.try_clone()?;
+            for line in BufReader::new(stream).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if let Some(reply) =
+                    rpc::handle_message(&line, &|method, params| self.call(method, params))
+                {
+                    writeln!(out, "{}", reply)?;
+                }
+                if self.stopping.load(Ordering::SeqCst) {
+                    // Wake the accept loop so it sees the flag
+                    let _ = UnixStream::connect(&self.socket);
+                    break;
+                }
+            }
+            Ok(())
+        }
+
+        fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+            match method {
+                "record" => {
+                    let cmd: Command = rpc::params_of(params)?;
+                    let id = cmd.id.clone();
+                    self.pending.lock().unwrap().push(cmd);
+                    self.wake.notify_one();
+                    Ok(json!({ "id": id }))
+                }
+                "record_raw" => {
+                    let raw: RawRecord = rpc::params_of(params)?;
+                    let Some(cmd) = self.recorder.prepare(
+                        raw.command,
+                        raw.output,
+                        raw.exit_code,
+                        raw.start_time,
+                        raw.end_time,
+                        raw.cwd,
+                        raw.session_id,
+                        Vec::new(),
+                        raw.origin,
+                    ) else {
+                        // Excluded by the configuration
+                        return Ok(json!({ "id": null }));
+                    };
+                    let id = cmd.id.clone();
+                    self.pending.lock().unwrap().push(cmd);
+                    self.wake.notify_one();
+                    Ok(json!({ "id": id }))
+                }
+                "status" => Ok(json!({
+                    "pid": std::process::id(),
+                    "uptime_secs": self.started.elapsed().as_secs(),
+                    "recorded": self.recorded.load(Ordering::SeqCst),
+                    "pending": self.pending.lock().unwrap().len(),
+                })),
+                "shutdown" => {
+                    // Under the lock, so the writer cannot miss the wake-up
+                    let _pending = self.pending.lock().unwrap();
+                    self.stopping.store(true, Ordering::SeqCst);
+                    self.wake.notify_one();
+                    Ok(Value::Null)
+                }
+                _ => rpc::call(&self.storage, method, params),
+            }
+        }
+
+        /// Write pending commands in batches until shutdown
+        fn write_batches(&self) {
+            loop {
+                let mut pending = self.pending.lock().unwrap();
+                while pending.is_empty() && !self.stopping.load(Ordering::SeqCst) {
+                    pending = self.wake.wait(pending).unwrap();
+                }
+                if pending.is_empty() {
+                    return;
+                }
+                if !self.stopping.load(Ordering::SeqCst) {
+                    // Give commands finishing together a chance to share the write
+                    drop(pending);
+                    std::thread::sleep(BATCH_WINDOW);
+                    pending = self.pending.lock().unwrap();
+                }
+                let batch = std::mem::take(&mut *pending);
+                drop(pending);
+
+                match self.recorder.save_all(&batch) {
+                    Ok(()) => {
+                        self.recorded.fetch_add(batch.len(), Ordering::SeqCst);
+                        tracing::debug!(count = batch.len(), "Wrote batch");
+                        // One fsync per batch instead of one per command
+                        if let Err(e) = self.storage.sync_commands() {
+                            tracing::warn!("Failed to sync the history: {:#}", e);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!(count = batch.len(), "Failed to write batch: {:#}", e)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Call a method on the running daemon
+    ///
+    /// Returns `None` if no daemon is listening.
+    pub fn request(storage: &Storage, method: &str, params: Value) -> Result<Option<Value>> {
+        let Ok(mut stream) = UnixStream::connect(socket_path(storage)) else {
+            return Ok(None);
+        };
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        writeln!(stream, "{}", message)?;
+        let mut reply = String::new();
+        BufReader::new(stream)
+            .read_line(&mut reply)
+            .context("No answer from the daemon")?;
+
+        let mut reply: Value =
+            serde_json::from_str(&reply).context("Invalid answer from the daemon")?;
+        if let Some(error) = reply.get("error") {
+            bail!("Daemon error: {}", error["message"]);
+        }
+        Ok(Some(reply["result"].take()))
+    }
+}
+
+// No named-pipe transport yet: without the daemon, `record` writes directly
+#[cfg(not(unix))]
+pub fn run(_storage: Storage) -> Result<()> {
+    anyhow::bail!("The daemon needs unix domain sockets, which this platform lacks")
+}
+
+#[cfg(not(unix))]
+pub fn request(_storage: &Storage, _method: &str, _params: Value) -> Result<Option<Value>> {
+    Ok(None)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_query() {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        let recorder = shelltape_core::recorder::Recorder::with_storage(storage.clone());
+        let cmd = recorder
+            .prepare(
+                "make".to_string(),
+                String::new(),
+                0,
+                0,
+                1_000_000,
+                "/src".to_string(),
+                "s".to_string(),
+                Vec::new(),
+                Origin::current(),
+            )
+            .unwrap();
+
+        assert!(!send_record(&storage, &cmd));
+
+        let daemon = {
+            let storage = storage.clone();
+            let recorder = shelltape_core::recorder::Recorder::with_storage(storage.clone());
+            std::thread::spawn(move || unix::listen(storage, recorder))
+        };
+        while !socket_path(&storage).exists() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(send_record(&storage, &cmd));
+        // Raw records are prepared (and redacted) by the daemon
+        let raw = RawRecord {
+            command: "login --token hunter2".to_string(),
+            output: String::new(),
+            exit_code: 0,
+            start_time: 2_000_000,
+            end_time: 3_000_000,
+            cwd: "/src".to_string(),
+            session_id: "s".to_string(),
+            origin: Origin {
+                shell: "/bin/fish".to_string(),
+                hostname: "laptop".to_string(),
+                username: "ada".to_string(),
+            },
+        };
+        assert!(send_raw(&storage, &raw));
+        let status = request(&storage, "status", Value::Null).unwrap().unwrap();
+        assert_eq!(
+            status["pending"].as_u64().unwrap() + status["recorded"].as_u64().unwrap(),
+            2
+        );
+
+        // Pending commands are written before the daemon exits
+        request(&storage, "shutdown", Value::Null).unwrap();
+        daemon.join().unwrap().unwrap();
+        assert!(!socket_path(&storage).exists());
+        let commands = storage.read_all_commands().unwrap();
+        assert_eq!(commands[0].id, cmd.id);
+        assert_eq!(commands[1].command, "login --token *******");
+        assert_eq!(commands[1].shell, "/bin/fish");
+        assert_eq!(commands[1].hostname, "laptop");
+        assert_eq!(commands[1].username, "ada");
+        assert!(request(&storage, "status", Value::Null).unwrap().is_none());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::list;
+use anyhow::Result;
+use shelltape_core::query::Query;
+use shelltape_core::storage::Storage;
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+/// Most selected commands listed before asking for confirmation
+const SHOWN: usize = 20;
+
+/// Delete commands by ID, or every command matching `filter` and `session`
+pub fn delete_commands(
+    storage: &Storage,
+    ids: Vec<String>,
+    filter: Option<String>,
+    session: Option<String>,
+    yes: bool,
+) -> Result<()> {
+    let mut selected = Vec::new();
+    for id in &ids {
+        selected.push(storage.find_command(id)?);
+    }
+    if filter.is_some() || session.is_some() {
+        let query = Query {
+            text: filter,
+            session,
+            ..Query::default()
+        };
+        selected.extend(query.run(storage)?);
+    }
+
+    let mut seen = HashSet::new();
+    selected.retain(|cmd| seen.insert(cmd.id.clone()));
+    if selected.is_empty() {
+        println!("No commands matched");
+        return Ok(());
+    }
+
+    list::print_table(&selected[..selected.len().min(SHOWN)])?;
+    if selected.len() > SHOWN {
+        println!("… and {} more", selected.len() - SHOWN);
+    }
+
+    // Ask for confirmation unless --yes flag is set
+    if !yes {
+        print!("Delete {} commands? [y/N] ", selected.len());
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    let removed = storage.delete_commands(&seen)?;
+    println!("✓ Deleted {} commands", removed);
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use crate::ansi;
+use anyhow::Result;
+use crossterm::style::Stylize;
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use similar::{ChangeTag, TextDiff};
+use std::io::IsTerminal;
+
+/// Compare the outputs (and optionally metadata) of two recorded runs
+pub fn diff_commands(
+    storage: &Storage,
+    id1: &str,
+    id2: &str,
+    metadata: bool,
+    context: usize,
+) -> Result<()> {
+    let old = storage.find_command(id1)?;
+    let new = storage.find_command(id2)?;
+    let color = std::io::stdout().is_terminal();
+
+    print_line(
+        &format!("--- a/{}", describe(&old)),
+        Some(ChangeTag::Delete),
+        color,
+        true,
+    );
+    print_line(
+        &format!("+++ b/{}", describe(&new)),
+        Some(ChangeTag::Insert),
+        color,
+        true,
+    );
+
+    if metadata {
+        print_metadata_diff(&old, &new, color);
+    }
+
+    let old_output = clean_output(&old.output);
+    let new_output = clean_output(&new.output);
+
+    if old_output == new_output {
+        println!("Outputs are identical");
+        return Ok(());
+    }
+
+    let diff = TextDiff::from_lines(&old_output, &new_output);
+    for hunk in diff.unified_diff().context_radius(context).iter_hunks() {
+        print_line(&hunk.header().to_string(), None, color, true);
+        for change in hunk.iter_changes() {
+            let sign = match change.tag() {
+                ChangeTag::Delete => '-',
+                ChangeTag::Insert => '+',
+                ChangeTag::Equal => ' ',
+            };
+            let line = format!("{}{}", sign, change.value().trim_end_matches('\n'));
+            print_line(&line, Some(change.tag()), color, false);
+        }
+    }
+
+    Ok(())
+}
+
+/// One-line description of a run used in the diff header
+fn describe(cmd: &Command) -> String {
+    format!(
+        "{}  {}  ({}, exit {})",
+        cmd.id.get(..8).unwrap_or(&cmd.id),
+        cmd.command,
+        cmd.started_at.format("%Y-%m-%d %H:%M:%S"),
+        cmd.exit_code
+    )
+}
+
+/// Print differing metadata fields
+fn print_metadata_diff(old: &Command, new: &Command, color: bool) {
+    let fields = [
+        ("command", old.command.clone(), new.command.clone()),
+        ("cwd", old.cwd.clone(), new.cwd.clone()),
+        (
+            "exit_code",
+            old.exit_code.to_string(),
+            new.exit_code.to_string(),
+        ),
+        (
+            "duration_ms",
+            old.duration_ms.to_string(),
+            new.duration_ms.to_string(),
+        ),
+        ("session_id", old.session_id.clone(), new.session_id.clone()),
+        ("shell", old.shell.clone(), new.shell.clone()),
+        ("hostname", old.hostname.clone(), new.hostname.clone()),
+        ("username", old.username.clone(), new.username.clone()),
+    ];
+
+    print_line("@@ metadata @@", None, color, true);
+    for (name, a, b) in fields {
+        if a == b {
+            print_line(
+                &format!(" {}: {}", name, a),
+                Some(ChangeTag::Equal),
+                color,
+                false,
+            );
+        } else {
+            print_line(
+                &format!("-{}: {}", name, a),
+                Some(ChangeTag::Delete),
+                color,
+                false,
+            );
+            print_line(
+                &format!("+{}: {}", name, b),
+                Some(ChangeTag::Insert),
+                color,
+                false,
+            );
+        }
+    }
+}
+
+/// Normalize captured output so escape codes and CRs don't show up as changes
+fn clean_output(output: &str) -> String {
+    output
+        .lines()
+        .map(|line| ansi::strip_ansi(line) + "\n")
+        .collect()
+}
+
+/// Print a diff line, colored by change kind when writing to a terminal
+fn print_line(line: &str, tag: Option<ChangeTag>, color: bool, bold: bool) {
+    if !color {
+        println!("{}", line);
+        return;
+    }
+
+    let styled = match tag {
+        Some(ChangeTag::Delete) => line.red(),
+        Some(ChangeTag::Insert) => line.green(),
+        Some(ChangeTag::Equal) => line.reset(),
+        None => line.cyan(),
+    };
+
+    if bold {
+        println!("{}", styled.bold());
+    } else {
+        println!("{}", styled);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::cli::{self, OutputFormat};
+use crate::list;
+use crate::timespec::parse_time;
+use anyhow::Result;
+use shelltape_core::storage::Storage;
+use std::path::PathBuf;
+
+/// Filters for the directory history query
+pub struct DirFilter {
+    /// Include commands from subdirectories
+    pub recursive: bool,
+    /// Only commands started at or after this time
+    pub since: Option<String>,
+    /// Only commands started before this time
+    pub until: Option<String>,
+    /// Only show commands that failed
+    pub failed: bool,
+    /// Maximum number of commands to show
+    pub limit: usize,
+}
+
+/// Show the commands run in a directory (defaults to the current one)
+pub fn dir_history(
+    storage: &Storage,
+    path: Option<String>,
+    filter: DirFilter,
+    format: OutputFormat,
+) -> Result<()> {
+    let dir = resolve_dir(path)?;
+    let since = filter.since.as_deref().map(parse_time).transpose()?;
+    let until = filter.until.as_deref().map(parse_time).transpose()?;
+
+    let mut commands: Vec<_> = storage
+        .commands_in_dir(&dir, filter.recursive)?
+        .into_iter()
+        .filter(|cmd| !filter.failed || cmd.exit_code != 0)
+        .filter(|cmd| since.is_none_or(|t| cmd.started_at >= t))
+        .filter(|cmd| until.is_none_or(|t| cmd.started_at < t))
+        .collect();
+    commands.truncate(filter.limit);
+
+    if format == OutputFormat::Json {
+        cli::print_json(&commands)?;
+        return Ok(());
+    }
+
+    println!(
+        "📁 {}{}\n",
+        dir,
+        if filter.recursive { " (recursive)" } else { "" }
+    );
+    list::print_table(&commands)?;
+
+    Ok(())
+}
+
+/// Resolve the directory to query, matching how the shell hooks record `$PWD`
+pub fn resolve_dir(path: Option<String>) -> Result<String> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => std::env::current_dir()?,
+    };
+
+    // Prefer the canonical path, but allow directories that no longer exist
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    Ok(path.to_string_lossy().into_owned())
+}
@@ -0,0 +1,576 @@
+use crate::cli::Shell;
+use crate::install;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::de::DeserializeOwned;
+use shelltape_core::config::{Backend, Config};
+use shelltape_core::models::{Command, Session};
+use shelltape_core::storage::Storage;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Size above which the commands file is reported as large
+const LARGE_FILE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Age after which leftover temporary files are considered stale
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Severity of a diagnostic finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// A safe, automatic fix that `--fix` can apply
+#[derive(Debug)]
+enum Fix {
+    /// Rewrite the hook file with the bundled version
+    RewriteHook(Shell),
+    /// Rewrite the Ctrl-R widget file with the bundled version
+    RewriteWidget(Shell),
+    /// Restrict permissions on a file or directory to the owner
+    #[cfg_attr(not(unix), allow(dead_code))]
+    RestrictPermissions(PathBuf, u32),
+    /// Remove leftover temporary files
+    RemoveFiles(Vec<PathBuf>),
+    /// Drop unparsable lines from a JSONL file (after writing a backup)
+    DropCorruptLines(PathBuf, Vec<usize>),
+}
+
+/// A single diagnostic finding
+struct Finding {
+    level: Level,
+    message: String,
+    suggestion: Option<String>,
+    fix: Option<Fix>,
+}
+
+impl Finding {
+    fn ok(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Ok,
+            message: message.into(),
+            suggestion: None,
+            fix: None,
+        }
+    }
+
+    fn warn(message: impl Into<String>, suggestion: impl Into<String>) -> Self {
+        Self {
+            level: Level::Warn,
+            message: message.into(),
+            suggestion: Some(suggestion.into()),
+            fix: None,
+        }
+    }
+
+    fn fail(message: impl Into<String>, suggestion: impl Into<String>) -> Self {
+        Self {
+            level: Level::Fail,
+            ..Self::warn(message, suggestion)
+        }
+    }
+
+    fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+/// Run diagnostics and optionally apply safe fixes
+pub fn run_doctor(storage: &Storage, fix: bool) -> Result<()> {
+    println!("╔════════════════════════════════════════════════╗");
+    println!("║          Shelltape Doctor                      ║");
+    println!("╚════════════════════════════════════════════════╝");
+    println!();
+
+    let sections = vec![
+        ("📦 Binary", check_binary()),
+        ("⚙️  Configuration", check_config()),
+        ("🔧 Shell Integration", check_shell_hooks()),
+        ("💾 Data Files", check_data_files(storage)?),
+        ("🧹 Temporary Files", check_temp_files()),
+    ];
+
+    let mut problems = 0;
+    let mut fixes = Vec::new();
+
+    for (title, findings) in sections {
+        println!("{}:", title);
+        for finding in findings {
+            let icon = match finding.level {
+                Level::Ok => "✓",
+                Level::Warn => "⚠",
+                Level::Fail => "✗",
+            };
+            println!("  {} {}", icon, finding.message);
+
+            if finding.level != Level::Ok {
+                problems += 1;
+                if let Some(suggestion) = &finding.suggestion {
+                    println!("    → {}", suggestion);
+                }
+            }
+
+            if let Some(f) = finding.fix {
+                fixes.push(f);
+            }
+        }
+        println!();
+    }
+
+    if problems == 0 {
+        println!("✓ No problems found");
+        return Ok(());
+    }
+
+    println!(
+        "Found {} problem(s), {} can be fixed automatically",
+        problems,
+        fixes.len()
+    );
+
+    if fixes.is_empty() {
+        return Ok(());
+    }
+
+    if !fix {
+        println!("Run `shelltape doctor --fix` to apply safe fixes");
+        return Ok(());
+    }
+
+    println!();
+    for f in fixes {
+        match apply_fix(storage, &f) {
+            Ok(message) => println!("  [OK] {}", message),
+            Err(e) => println!("  [FAILED] {:?}: {:#}", f, e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the `shelltape` binary used by the hooks is this one
+fn check_binary() -> Vec<Finding> {
+    let mut findings = vec![Finding::ok(format!("Version {}", env!("GDL_VERSION")))];
+
+    let current = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.canonicalize().ok());
+
+    match which::which("shelltape") {
+        Ok(on_path) => {
+            let on_path = on_path.canonicalize().unwrap_or(on_path);
+            if current.as_ref().is_some_and(|c| c != &on_path) {
+                findings.push(Finding::warn(
+                    format!("Hooks will run a different binary: {}", on_path.display()),
+                    "Make sure the shelltape on your PATH is the version you expect",
+                ));
+            } else {
+                findings.push(Finding::ok(format!("Found on PATH: {}", on_path.display())));
+            }
+        }
+        Err(_) => findings.push(Finding::fail(
+            "shelltape is not on your PATH",
+            "Shell hooks call `shelltape` by name; add its directory to PATH",
+        )),
+    }
+
+    findings
+}
+
+/// Check that the config file parses
+fn check_config() -> Vec<Finding> {
+    let path = match Config::path() {
+        Ok(path) => path,
+        Err(e) => return vec![Finding::fail(format!("{:#}", e), "Set the HOME variable")],
+    };
+
+    if !path.exists() {
+        return vec![Finding::ok(format!(
+            "No config file, using defaults ({})",
+            path.display()
+        ))];
+    }
+
+    match Config::load_from(&path) {
+        Ok(_) => vec![Finding::ok(format!(
+            "Config file is valid: {}",
+            path.display()
+        ))],
+        Err(e) => vec![Finding::fail(
+            format!("{:#}", e),
+            "Run `shelltape config edit` to correct it",
+        )],
+    }
+}
+
+/// Check hook files and RC file wiring for every shell
+fn check_shell_hooks() -> Vec<Finding> {
+    let Some(home) = dirs::home_dir() else {
+        return vec![Finding::fail(
+            "Could not determine home directory",
+            "Set the HOME variable",
+        )];
+    };
+
+    let shelltape_dir = home.join(".shelltape");
+    let mut findings = Vec::new();
+
+    for &shell in Shell::value_variants() {
+        let rc_path = home.join(shell.rc_file());
+        let Ok(rc_content) = fs::read_to_string(&rc_path) else {
+            continue;
+        };
+
+        let hook_line = shell.hook_line();
+        let sourced = rc_content
+            .lines()
+            .filter(|line| line.trim() == hook_line)
+            .count();
+
+        // Hooks for other shells sourced from this RC file
+        for &other in Shell::value_variants() {
+            if other.hook_file() != shell.hook_file() && rc_content.contains(&other.hook_line()) {
+                findings.push(Finding::fail(
+                    format!("{} sources the {:?} hook", rc_path.display(), other),
+                    format!("Remove `{}` from {}", other.hook_line(), rc_path.display()),
+                ));
+            }
+        }
+
+        if sourced == 0 {
+            continue;
+        }
+
+        if sourced > 1 {
+            findings.push(Finding::fail(
+                format!(
+                    "{:?} hook is sourced {} times in {} (commands may be recorded twice)",
+                    shell,
+                    sourced,
+                    rc_path.display()
+                ),
+                format!("Keep a single `{}` line", hook_line),
+            ));
+        } else {
+            findings.push(Finding::ok(format!(
+                "{:?} hook wired in {}",
+                shell,
+                rc_path.display()
+            )));
+        }
+
+        let hook_path = shelltape_dir.join(shell.hook_file());
+        match fs::read_to_string(&hook_path) {
+            Ok(content) if content == install::hook_content(shell) => {
+                findings.push(Finding::ok(format!("{:?} hook file is up to date", shell)));
+            }
+            Ok(_) => findings.push(
+                Finding::warn(
+                    format!(
+                        "{:?} hook file differs from this version: {}",
+                        shell,
+                        hook_path.display()
+                    ),
+                    "Reinstall the hook to pick up the latest version",
+                )
+                .with_fix(Fix::RewriteHook(shell)),
+            ),
+            Err(_) => findings.push(
+                Finding::fail(
+                    format!("{:?} hook file is missing: {}", shell, hook_path.display()),
+                    format!(
+                        "Run `shelltape install --shell {}`",
+                        format!("{:?}", shell).to_lowercase()
+                    ),
+                )
+                .with_fix(Fix::RewriteHook(shell)),
+            ),
+        }
+
+        // The Ctrl-R widget, if `install --ctrl-r` wired it up
+        if let (Some(file), Some(line)) = (shell.widget_file(), shell.widget_line())
+            && rc_content.lines().any(|l| l.trim() == line)
+        {
+            let widget_path = shelltape_dir.join(file);
+            match fs::read_to_string(&widget_path) {
+                Ok(content) if Some(content.as_str()) == install::widget_content(shell) => {
+                    findings.push(Finding::ok(format!(
+                        "{:?} Ctrl-R widget is up to date",
+                        shell
+                    )));
+                }
+                Ok(_) => findings.push(
+                    Finding::warn(
+                        format!(
+                            "{:?} Ctrl-R widget differs from this version: {}",
+                            shell,
+                            widget_path.display()
+                        ),
+                        "Reinstall the widget to pick up the latest version",
+                    )
+                    .with_fix(Fix::RewriteWidget(shell)),
+                ),
+                Err(_) => findings.push(
+                    Finding::fail(
+                        format!(
+                            "{:?} Ctrl-R widget is missing: {}",
+                            shell,
+                            widget_path.display()
+                        ),
+                        format!("Remove `{}` from {}", line, rc_path.display()),
+                    )
+                    .with_fix(Fix::RewriteWidget(shell)),
+                ),
+            }
+        }
+    }
+
+    if findings.is_empty() {
+        findings.push(Finding::warn(
+            "No shell hooks installed",
+            "Run `shelltape install` to start recording",
+        ));
+    }
+
+    findings
+}
+
+/// Check data directory permissions and data file integrity
+fn check_data_files(storage: &Storage) -> Result<Vec<Finding>> {
+    let data_dir = storage.data_dir();
+    let mut findings = Vec::new();
+
+    let probe = data_dir.join(".doctor-write-test");
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            findings.push(Finding::ok(format!(
+                "Data directory is writable: {}",
+                data_dir.display()
+            )));
+        }
+        Err(e) => findings.push(Finding::fail(
+            format!("Cannot write to {}: {}", data_dir.display(), e),
+            "Check ownership and permissions of the data directory",
+        )),
+    }
+
+    findings.extend(check_private(data_dir, 0o700));
+
+    let commands_file = storage.history_file();
+    if commands_file.exists() {
+        findings.extend(check_private(&commands_file, 0o600));
+        if storage.backend() == Backend::Jsonl {
+            findings.push(check_jsonl::<Command>(&commands_file, "commands")?);
+        }
+
+        let size = fs::metadata(&commands_file)?.len();
+        if size > LARGE_FILE_BYTES {
+            findings.push(Finding::warn(
+                format!("Commands file is large: {} MB", size / (1024 * 1024)),
+                "Run `shelltape clean` to remove old commands",
+            ));
+        }
+    } else {
+        findings.push(Finding::ok("No commands recorded yet"));
+    }
+
+    let sessions_file = storage.sessions_file();
+    if sessions_file.exists() {
+        findings.push(check_jsonl::<Session>(sessions_file, "sessions")?);
+    }
+
+    Ok(findings)
+}
+
+/// Check that a path is not readable by other users
+#[cfg(unix)]
+fn check_private(path: &Path, mode: u32) -> Option<Finding> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let current = fs::metadata(path).ok()?.permissions().mode() & 0o777;
+    if current & 0o077 == 0 {
+        return None;
+    }
+
+    Some(
+        Finding::warn(
+            format!(
+                "{} is accessible by other users (mode {:o})",
+                path.display(),
+                current
+            ),
+            format!("Restrict it with `chmod {:o} {}`", mode, path.display()),
+        )
+        .with_fix(Fix::RestrictPermissions(path.to_path_buf(), mode)),
+    )
+}
+
+#[cfg(not(unix))]
+fn check_private(_path: &Path, _mode: u32) -> Option<Finding> {
+    None
+}
+
+/// Find line numbers (1-based) that fail to parse as `T`
+fn corrupt_lines<T: DeserializeOwned>(path: &Path) -> Result<Vec<usize>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+
+    Ok(content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter(|(_, line)| serde_json::from_str::<T>(line).is_err())
+        .map(|(i, _)| i + 1)
+        .collect())
+}
+
+/// Check that every line of a JSONL file parses
+fn check_jsonl<T: DeserializeOwned>(path: &Path, name: &str) -> Result<Finding> {
+    let bad = corrupt_lines::<T>(path)?;
+
+    if bad.is_empty() {
+        return Ok(Finding::ok(format!("All {} parse correctly", name)));
+    }
+
+    let preview: Vec<String> = bad.iter().take(5).map(|n| n.to_string()).collect();
+    Ok(Finding::fail(
+        format!(
+            "{} corrupt line(s) in {} (line {}{})",
+            bad.len(),
+            path.display(),
+            preview.join(", "),
+            if bad.len() > 5 { ", ..." } else { "" }
+        ),
+        "Corrupt lines prevent history from loading; --fix drops them after writing a backup",
+    )
+    .with_fix(Fix::DropCorruptLines(path.to_path_buf(), bad)))
+}
+
+/// Check for output capture files left behind by crashed shells
+fn check_temp_files() -> Vec<Finding> {
+    let now = SystemTime::now();
+    let stale: Vec<PathBuf> = fs::read_dir(std::env::temp_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("shelltape_")
+        })
+        .filter(|entry| {
+            entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age > STALE_AFTER)
+        })
+        .map(|entry| entry.path())
+        .collect();
+
+    if stale.is_empty() {
+        return vec![Finding::ok("No stale temporary files")];
+    }
+
+    vec![
+        Finding::warn(
+            format!(
+                "{} stale output capture file(s) in {}",
+                stale.len(),
+                std::env::temp_dir().display()
+            ),
+            "These are left behind by shells that exited uncleanly and can be removed",
+        )
+        .with_fix(Fix::RemoveFiles(stale)),
+    ]
+}
+
+/// Apply a single fix, returning a description of what was done
+fn apply_fix(storage: &Storage, fix: &Fix) -> Result<String> {
+    match fix {
+        Fix::RewriteHook(shell) => {
+            let shelltape_dir = dirs::home_dir()
+                .context("Could not determine home directory")?
+                .join(".shelltape");
+            fs::create_dir_all(&shelltape_dir)?;
+            install::copy_hook_file(&shelltape_dir, *shell)?;
+            Ok(format!("Reinstalled {:?} hook file", shell))
+        }
+        Fix::RewriteWidget(shell) => {
+            let shelltape_dir = dirs::home_dir()
+                .context("Could not determine home directory")?
+                .join(".shelltape");
+            fs::create_dir_all(&shelltape_dir)?;
+            install::copy_widget_file(&shelltape_dir, *shell)?;
+            Ok(format!("Reinstalled {:?} Ctrl-R widget", shell))
+        }
+        #[cfg(unix)]
+        Fix::RestrictPermissions(path, mode) => {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(path, fs::Permissions::from_mode(*mode))?;
+            Ok(format!("Set mode {:o} on {}", mode, path.display()))
+        }
+        #[cfg(not(unix))]
+        Fix::RestrictPermissions(path, _) => Ok(format!("Skipped {}", path.display())),
+        Fix::RemoveFiles(paths) => {
+            for path in paths {
+                fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove: {}", path.display()))?;
+            }
+            Ok(format!("Removed {} stale file(s)", paths.len()))
+        }
+        Fix::DropCorruptLines(path, bad) => {
+            let backup = storage.drop_lines(path, bad)?;
+            Ok(format!(
+                "Dropped {} corrupt line(s) from {} (backup: {})",
+                bad.len(),
+                path.display(),
+                backup.display()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_drop_corrupt_lines() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        let path = storage.commands_file().clone();
+
+        let good = r#"{"id":"1","command":"ls","output":"","exit_code":0,"cwd":"/","started_at":"2024-01-01T00:00:00Z","duration_ms":1,"session_id":"s","shell":"bash","hostname":"h","username":"u"}"#;
+        // Caches built before the line went bad must not outlive the fix
+        fs::write(&path, format!("{}\n", good)).unwrap();
+        storage.get_recent_commands(1).unwrap();
+        storage.aggregate().unwrap();
+        assert!(path.with_extension("idx").exists());
+        assert!(path.with_extension("stats").exists());
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "not json\n\n{}\n", good).unwrap();
+        let bad = corrupt_lines::<Command>(&path).unwrap();
+        assert_eq!(bad, vec![2]);
+
+        apply_fix(&storage, &Fix::DropCorruptLines(path.clone(), bad)).unwrap();
+        assert_eq!(storage.read_all_commands().unwrap().len(), 2);
+        assert!(path.with_extension("jsonl.bak").exists());
+        assert!(!path.with_extension("idx").exists());
+        assert!(!path.with_extension("stats").exists());
+        assert_eq!(storage.get_recent_commands(5).unwrap().len(), 2);
+        assert_eq!(storage.aggregate().unwrap().total, 2);
+    }
+}
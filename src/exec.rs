@@ -0,0 +1,115 @@
+use crate::pty_capture::ExecutionResult;
+use anyhow::Result;
+use shelltape_core::models::{self, Command};
+use shelltape_core::recorder::{Origin, Recorder};
+use shelltape_core::storage::Storage;
+
+/// Run a command with `run` and record it, as `exec` and `rerun` do
+///
+/// The command is marked as running meanwhile so `shelltape top` can show
+/// it. Returns the recorded command, or `None` if it matched an exclude
+/// pattern, along with its exit code.
+pub fn run_and_record(
+    storage: Storage,
+    command: String,
+    cwd: String,
+    session_id: String,
+    rerun_of: Option<String>,
+    run: impl FnOnce() -> Result<ExecutionResult>,
+) -> Result<(Option<Command>, i32)> {
+    // Failing to write the marker must not prevent execution
+    let running = models::RunningCommand {
+        id: uuid::Uuid::new_v4().to_string(),
+        command: command.clone(),
+        cwd: cwd.clone(),
+        started_at: chrono::Utc::now(),
+        session_id: session_id.clone(),
+        pid: std::process::id(),
+    };
+    if let Err(e) = storage.mark_running(&running) {
+        tracing::warn!("Failed to write running marker: {:#}", e);
+    }
+
+    // Execute with PTY capture (output is displayed in real-time by PTY)
+    tracing::info!(command = %command, cwd = %cwd, session = %session_id, "exec");
+    let result = run();
+    if let Err(e) = storage.clear_running(&running.id) {
+        tracing::warn!("Failed to clear running marker: {:#}", e);
+    }
+    let result = result?;
+    tracing::info!(
+        exit_code = result.exit_code,
+        output_bytes = result.output.len(),
+        "exec finished"
+    );
+
+    record(
+        &Recorder::new(storage)?,
+        command,
+        cwd,
+        session_id,
+        rerun_of,
+        result,
+    )
+}
+
+/// Record the result of a run, linked to the command it reran if any
+fn record(
+    recorder: &Recorder,
+    command: String,
+    cwd: String,
+    session_id: String,
+    rerun_of: Option<String>,
+    result: ExecutionResult,
+) -> Result<(Option<Command>, i32)> {
+    let Some(mut cmd) = recorder.prepare(
+        command,
+        result.output,
+        result.exit_code,
+        result.start_time,
+        result.end_time,
+        cwd,
+        session_id,
+        result.timing,
+        Origin::current(),
+    ) else {
+        return Ok((None, result.exit_code));
+    };
+    cmd.rerun_of = rerun_of;
+    recorder.save(&cmd)?;
+    Ok((Some(cmd), result.exit_code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_rerun() {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        let recorder = Recorder::with_storage(storage.clone());
+        let result = ExecutionResult {
+            output: "ok\n".to_string(),
+            exit_code: 3,
+            start_time: 0,
+            end_time: 2_000_000,
+            timing: Vec::new(),
+        };
+
+        let (cmd, exit_code) = record(
+            &recorder,
+            "make test".to_string(),
+            "/src".to_string(),
+            "s1".to_string(),
+            Some("original-id".to_string()),
+            result,
+        )
+        .unwrap();
+        assert_eq!(exit_code, 3);
+        let stored = storage.find_command(&cmd.unwrap().id).unwrap();
+        assert_eq!(stored.rerun_of.as_deref(), Some("original-id"));
+        assert_eq!((stored.duration_ms, stored.output.as_str()), (2, "ok\n"));
+    }
+}
@@ -1,16 +1,31 @@
-use crate::storage::Storage;
+use crate::atuin;
+use crate::cli::ExportFormat;
+use crate::git::{self, Commit};
+use crate::html;
+use crate::shell_history;
+use crate::sync::shell_quote;
 use anyhow::{Context, Result};
 use chrono::Utc;
+use shelltape_core::config::Config;
+use shelltape_core::models::Command;
+use shelltape_core::normalize;
+use shelltape_core::otlp;
+use shelltape_core::storage::Storage;
+use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
 
-/// Export commands to markdown format
+/// Export commands to a file in the given format
 pub fn export_commands(
+    storage: &Storage,
     output: PathBuf,
+    format: ExportFormat,
     session: Option<String>,
     filter: Option<String>,
+    tag: Option<String>,
+    commits: bool,
 ) -> Result<()> {
-    let storage = Storage::new()?;
     let mut commands = storage.read_all_commands()?;
 
     // Filter by session
@@ -24,10 +39,166 @@ pub fn export_commands(
         commands.retain(|cmd| cmd.command.to_lowercase().contains(&query_lower));
     }
 
+    // Filter by tag
+    if let Some(tag) = &tag {
+        commands.retain(|cmd| cmd.has_tag(tag));
+    }
+
     // Sort chronologically (oldest first for export)
-    commands.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    commands.sort_by_key(|a| a.started_at);
+
+    match format {
+        ExportFormat::Markdown | ExportFormat::Html => {
+            let links = if commits {
+                git::commits_for_commands(&commands)
+            } else {
+                HashMap::new()
+            };
+            let report = if format == ExportFormat::Markdown {
+                to_markdown(&commands, &links, session.as_deref(), filter.as_deref())
+            } else {
+                html::to_html(&commands, &links, session.as_deref(), filter.as_deref())
+            };
+            fs::write(&output, report)
+                .with_context(|| format!("Failed to write to: {}", output.display()))?;
+            println!(
+                "✓ Exported {} commands to {}",
+                commands.len(),
+                output.display()
+            );
+        }
+        ExportFormat::Atuin => {
+            let added = atuin::export_atuin(&commands, &output)?;
+            println!(
+                "✓ Exported {} commands to {} ({} new)",
+                commands.len(),
+                output.display(),
+                added
+            );
+        }
+        ExportFormat::Otlp => {
+            let config = Config::load()?.otlp;
+            let target = output.to_string_lossy();
+            if target.starts_with("http://") || target.starts_with("https://") {
+                otlp::export_batch(&config, &target, &commands)?;
+                println!(
+                    "✓ Sent {} spans to {}",
+                    commands.len(),
+                    otlp::traces_url(&target)
+                );
+            } else {
+                let traces = serde_json::to_string_pretty(&otlp::traces(&config, &commands))?;
+                fs::write(&output, traces)
+                    .with_context(|| format!("Failed to write to: {}", output.display()))?;
+                println!(
+                    "✓ Exported {} spans to {}",
+                    commands.len(),
+                    output.display()
+                );
+            }
+        }
+        ExportFormat::ZshHistory | ExportFormat::BashHistory => {
+            let history = if format == ExportFormat::ZshHistory {
+                shell_history::to_zsh_history(&commands)
+            } else {
+                shell_history::to_bash_history(&commands).into_bytes()
+            };
+            fs::write(&output, history)
+                .with_context(|| format!("Failed to write to: {}", output.display()))?;
+            println!(
+                "✓ Exported {} commands to {}",
+                commands.len(),
+                output.display()
+            );
+        }
+        ExportFormat::Json | ExportFormat::Jsonl | ExportFormat::Script => {
+            let data = match format {
+                ExportFormat::Json => serde_json::to_string_pretty(&commands)? + "\n",
+                ExportFormat::Jsonl => to_jsonl(&commands)?,
+                _ => to_script(&commands),
+            };
+            if output.as_os_str() == "-" {
+                // Only the records, so the output can be piped into other tools
+                std::io::stdout().lock().write_all(data.as_bytes())?;
+                return Ok(());
+            }
+            fs::write(&output, data)
+                .with_context(|| format!("Failed to write to: {}", output.display()))?;
+            #[cfg(unix)]
+            if format == ExportFormat::Script {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&output, fs::Permissions::from_mode(0o755))?;
+            }
+            println!(
+                "✓ Exported {} commands to {}",
+                commands.len(),
+                output.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Render commands as JSON lines, one record per line
+fn to_jsonl(commands: &[Command]) -> Result<String> {
+    let mut out = String::new();
+    for cmd in commands {
+        out.push_str(&serde_json::to_string(cmd)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Render commands as a shell script that runs them again in order
+///
+/// A `cd` is inserted whenever the directory changes, so recorded `cd`
+/// commands on their own are left out. The interpreter is the shell the
+/// first command ran in. Commands that failed are marked with a comment;
+/// the script does not stop at them, as the session did not either.
+fn to_script(commands: &[Command]) -> String {
+    let shell = commands
+        .first()
+        .and_then(|c| c.shell.rsplit('/').next())
+        .filter(|s| ["bash", "zsh", "fish", "ksh", "dash"].contains(s))
+        .unwrap_or("sh");
+    let mut script = format!("#!/usr/bin/env {}\n", shell);
+    script.push_str(&format!(
+        "# Generated by shelltape on {}\n",
+        Utc::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+
+    let mut cwd = "";
+    for cmd in commands {
+        let command = cmd.command.trim();
+        if command.is_empty() || is_plain_cd(command) {
+            continue;
+        }
+        if !cmd.cwd.is_empty() && cmd.cwd != cwd {
+            cwd = &cmd.cwd;
+            script.push_str(&format!("\ncd {}\n", shell_quote(cwd)));
+        }
+        if cmd.exit_code != 0 {
+            script.push_str(&format!("# exited with {}\n", cmd.exit_code));
+        }
+        script.push_str(command);
+        script.push('\n');
+    }
+    script
+}
 
-    // Build markdown content
+/// Whether a command only changes directory
+fn is_plain_cd(command: &str) -> bool {
+    normalize::program(command).as_deref() == Some("cd") && !command.contains([';', '&', '|', '\n'])
+}
+
+/// Render commands as a Markdown report
+fn to_markdown(
+    commands: &[Command],
+    commits: &HashMap<String, Commit>,
+    session: Option<&str>,
+    filter: Option<&str>,
+) -> String {
     let mut markdown = String::new();
 
     // Header
@@ -38,23 +209,39 @@ pub fn export_commands(
     ));
     markdown.push_str(&format!("Total commands: {}\n\n", commands.len()));
 
-    if let Some(sid) = &session {
+    if let Some(sid) = session {
         markdown.push_str(&format!("Session: `{}`\n\n", sid));
     }
 
-    if let Some(query) = &filter {
+    if let Some(query) = filter {
         markdown.push_str(&format!("Filter: `{}`\n\n", query));
     }
 
     markdown.push_str("---\n\n");
 
     // Commands
-    for cmd in &commands {
+    for cmd in commands {
         markdown.push_str(&format!(
             "## {}\n\n",
             cmd.started_at.format("%Y-%m-%d %H:%M:%S")
         ));
         markdown.push_str(&format!("**Directory:** `{}`\n\n", cmd.cwd));
+        if let Some(commit) = commits.get(&cmd.id) {
+            markdown.push_str(&format!(
+                "**Commit:** `{}` {}\n\n",
+                commit.short_hash(),
+                commit.subject
+            ));
+        }
+        if !cmd.tags.is_empty() {
+            markdown.push_str(&format!("**Tags:** {}\n\n", cmd.tags.join(", ")));
+        }
+        if let Some(note) = &cmd.note {
+            for line in note.lines() {
+                markdown.push_str(&format!("> {}\n", line));
+            }
+            markdown.push('\n');
+        }
         markdown.push_str(&format!("**Duration:** {}ms\n\n", cmd.duration_ms));
 
         let status = if cmd.exit_code == 0 {
@@ -82,15 +269,42 @@ pub fn export_commands(
         markdown.push_str("---\n\n");
     }
 
-    // Write to file
-    fs::write(&output, markdown)
-        .with_context(|| format!("Failed to write to: {}", output.display()))?;
+    markdown
+}
 
-    println!(
-        "✓ Exported {} commands to {}",
-        commands.len(),
-        output.display()
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
 
-    Ok(())
+    fn cmd(command: &str, cwd: &str, exit_code: i32) -> Command {
+        Command {
+            id: "abc".to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: cwd.to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap(),
+            duration_ms: 0,
+            session_id: "s".to_string(),
+            shell: "/bin/bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_to_script() {
+        let script = to_script(&[
+            cmd("git clone repo", "/src", 0),
+            cmd("cd repo", "/src", 0),
+            cmd("make", "/src/it's", 2),
+            cmd("cd .. && ls", "/src/it's", 0),
+        ]);
+        let body = script.split_once('\n').unwrap();
+        assert_eq!(body.0, "#!/usr/bin/env bash");
+        assert!(body.1.ends_with(
+            "\ncd '/src'\ngit clone repo\n\ncd '/src/it'\\''s'\n# exited with 2\nmake\ncd .. && ls\n"
+        ));
+    }
 }
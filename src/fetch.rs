@@ -0,0 +1,231 @@
+use crate::timespec;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use shelltape_core::models::{Command, Session};
+use shelltape_core::storage::Storage;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::process::Stdio;
+
+/// One line of `shelltape dump` output
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Record {
+    Command(Command),
+    Session(Session),
+}
+
+/// A host to fetch history from
+#[derive(Debug, PartialEq, Eq)]
+struct Remote {
+    /// `[user@]host`, as passed to `ssh`
+    destination: String,
+    /// Host name alone, used to tag fetched commands
+    host: String,
+    port: Option<u16>,
+}
+
+impl Remote {
+    /// Parse `ssh://[user@]host[:port]` or a bare `[user@]host`
+    fn parse(url: &str) -> Result<Self> {
+        let rest = url
+            .strip_prefix("ssh://")
+            .unwrap_or(url)
+            .trim_end_matches('/');
+        let (destination, port) = match rest.rsplit_once(':') {
+            Some((destination, port)) => {
+                let port = port
+                    .parse()
+                    .with_context(|| format!("Invalid port in {}", url))?;
+                (destination, Some(port))
+            }
+            None => (rest, None),
+        };
+        let host = destination
+            .rsplit_once('@')
+            .map_or(destination, |(_, host)| host);
+        if host.is_empty() || host.contains('/') {
+            bail!("Expected ssh://[user@]host[:port], got {}", url);
+        }
+
+        Ok(Remote {
+            destination: destination.to_string(),
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+/// Write every record as JSONL to stdout, for `fetch` and other tools
+pub fn dump(storage: &Storage, since: Option<String>) -> Result<()> {
+    let since = since.as_deref().map(timespec::parse_time).transpose()?;
+    let mut out = BufWriter::new(std::io::stdout().lock());
+
+    for session in storage.read_all_sessions()? {
+        if since.is_none_or(|since| session.started_at >= since) {
+            serde_json::to_writer(&mut out, &Record::Session(session))?;
+            out.write_all(b"\n")?;
+        }
+    }
+    for cmd in storage.read_all_commands()? {
+        if since.is_none_or(|since| cmd.started_at >= since) {
+            serde_json::to_writer(&mut out, &Record::Command(cmd))?;
+            out.write_all(b"\n")?;
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+/// Pull the history of remote hosts into local history over SSH
+///
+/// Each host runs its own `shelltape dump`; the records are merged by ID,
+/// so fetching again only adds what is new. Fetched commands are tagged with
+/// the host they came from. A host that fails is reported and skipped.
+pub fn fetch(
+    storage: &Storage,
+    urls: &[String],
+    since: Option<String>,
+    remote_bin: &str,
+) -> Result<()> {
+    let mut failed = 0;
+    for url in urls {
+        let result = Remote::parse(url).and_then(|remote| {
+            println!("Fetching from {}...", remote.host);
+            fetch_host(storage, &remote, since.as_deref(), remote_bin)
+        });
+        if let Err(e) = result {
+            eprintln!("✗ {}: {:#}", url, e);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        bail!("Failed to fetch from {} of {} hosts", failed, urls.len());
+    }
+    println!("✓ Fetch complete");
+    Ok(())
+}
+
+fn fetch_host(
+    storage: &Storage,
+    remote: &Remote,
+    since: Option<&str>,
+    remote_bin: &str,
+) -> Result<()> {
+    let mut ssh = std::process::Command::new("ssh");
+    // Never stop to ask for a password when fetching from a fleet
+    ssh.args(["-o", "BatchMode=yes"]);
+    if let Some(port) = remote.port {
+        ssh.arg("-p").arg(port.to_string());
+    }
+    let mut script = format!("{} dump", remote_bin);
+    if let Some(since) = since {
+        script.push_str(&format!(" --since '{}'", since.replace('\'', r"'\''")));
+    }
+    ssh.arg(&remote.destination).arg(script);
+
+    let mut child = ssh
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context("Failed to run ssh")?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("Failed to read ssh output"))?;
+
+    let (commands, sessions) = read_records(BufReader::new(stdout), &remote.host)?;
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("ssh exited with status {}", status);
+    }
+
+    let fetched = commands.len();
+    let added = storage.merge_commands(commands)?;
+    println!("  • Commands: {} new of {}", added, fetched);
+    let fetched = sessions.len();
+    let added = storage.merge_sessions(sessions)?;
+    println!("  • Sessions: {} new of {}", added, fetched);
+
+    Ok(())
+}
+
+/// Parse streamed records, tagging commands with the host they came from
+fn read_records(reader: impl BufRead, host: &str) -> Result<(Vec<Command>, Vec<Session>)> {
+    let mut commands = Vec::new();
+    let mut sessions = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .with_context(|| format!("Failed to parse line {} from {}", i + 1, host))?;
+        match record {
+            Record::Command(mut cmd) => {
+                if cmd.hostname.is_empty() {
+                    cmd.hostname = host.to_string();
+                }
+                if !cmd.has_tag(host) {
+                    cmd.tags.push(host.to_string());
+                }
+                commands.push(cmd);
+            }
+            Record::Session(session) => sessions.push(session),
+        }
+    }
+
+    Ok((commands, sessions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_parse_remote() {
+        assert_eq!(
+            Remote::parse("ssh://deploy@web1.example.com:2222").unwrap(),
+            Remote {
+                destination: "deploy@web1.example.com".to_string(),
+                host: "web1.example.com".to_string(),
+                port: Some(2222),
+            }
+        );
+        assert_eq!(Remote::parse("db1").unwrap().destination, "db1");
+        assert!(Remote::parse("ssh://").is_err());
+        assert!(Remote::parse("ssh://host:http").is_err());
+    }
+
+    #[test]
+    fn test_read_records() {
+        let cmd = Command {
+            id: "abc".to_string(),
+            command: "systemctl restart nginx".to_string(),
+            exit_code: 0,
+            cwd: "/".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 10,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: String::new(),
+            username: "root".to_string(),
+            ..Default::default()
+        };
+        let stream = format!(
+            "{}\n\n",
+            serde_json::to_string(&Record::Command(cmd)).unwrap()
+        );
+
+        let (commands, sessions) = read_records(stream.as_bytes(), "web1").unwrap();
+        assert!(sessions.is_empty());
+        assert_eq!(commands[0].hostname, "web1");
+        assert_eq!(commands[0].tags, vec!["web1"]);
+
+        assert!(read_records("not json\n".as_bytes(), "web1").is_err());
+    }
+}
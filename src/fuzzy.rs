@@ -0,0 +1,113 @@
+/// Score how well `query` fuzzy-matches `text`, or `None` if it doesn't
+///
+/// Each whitespace-separated term must appear in `text` as an ordered,
+/// case-insensitive subsequence. Consecutive characters and matches at the
+/// start of a word score higher; gaps between matched characters cost a bit.
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+
+    query
+        .split_whitespace()
+        .map(|term| score_term(term, &text))
+        .sum()
+}
+
+/// Score a single search term against already-lowercased text
+fn score_term(term: &str, text: &[char]) -> Option<i64> {
+    let term: Vec<char> = term.chars().flat_map(char::to_lowercase).collect();
+
+    // Prefer an exact substring match when there is one
+    if let Some(pos) = find_substring(text, &term) {
+        let boundary = if is_word_start(text, pos) { 8 } else { 0 };
+        return Some(16 * term.len() as i64 + boundary);
+    }
+
+    let mut score = 0;
+    let mut last: Option<usize> = None;
+    let mut pos = 0;
+    for &c in &term {
+        let idx = pos + text[pos..].iter().position(|&t| t == c)?;
+        score += match last {
+            Some(prev) if idx == prev + 1 => 8,
+            Some(prev) => 4 - (idx - prev - 1).min(4) as i64,
+            None => 4,
+        };
+        if is_word_start(text, idx) {
+            score += 4;
+        }
+        last = Some(idx);
+        pos = idx + 1;
+    }
+
+    Some(score)
+}
+
+/// Edit distance between two strings, counted in characters
+///
+/// Insertions, deletions, substitutions and swaps of adjacent characters
+/// each cost one, so `gti` is a single edit away from `git`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // d[i][j] is the distance between a[..i] and b[..j]
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+fn find_substring(text: &[char], term: &[char]) -> Option<usize> {
+    if term.is_empty() {
+        return Some(0);
+    }
+    text.windows(term.len()).position(|w| w == term)
+}
+
+fn is_word_start(text: &[char], idx: usize) -> bool {
+    idx == 0 || !text[idx - 1].is_alphanumeric()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        assert!(score("gco", "git checkout main").is_some());
+        assert!(score("GIT main", "git checkout main").is_some());
+        assert!(score("ocg", "git checkout main").is_none());
+        assert!(score("docker", "git checkout main").is_none());
+        assert_eq!(score("", "anything"), Some(0));
+
+        // Substrings beat scattered matches, word starts beat the middle of words
+        assert!(score("push", "git push") > score("push", "p-u-s-h"));
+        assert!(score("make", "make test") > score("make", "cmake ."));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("git", "git"), 0);
+        assert_eq!(edit_distance("gti", "git"), 1);
+        assert_eq!(edit_distance("gi", "git"), 1);
+        assert_eq!(edit_distance("", "ls"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
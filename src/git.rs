@@ -0,0 +1,255 @@
+use crate::cli::{self, OutputFormat};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::Serialize;
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Commands run longer than this before a commit are not linked to it
+const MAX_WINDOW_HOURS: i64 = 24;
+
+/// A commit read from `git log`
+#[derive(Debug, Clone, Serialize)]
+pub struct Commit {
+    pub hash: String,
+    pub committed_at: DateTime<Utc>,
+    pub author: String,
+    pub subject: String,
+}
+
+impl Commit {
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+}
+
+/// A commit with the recorded commands that led up to it
+#[derive(Debug, Serialize)]
+struct LinkedCommit<'a> {
+    #[serde(flatten)]
+    commit: &'a Commit,
+    commands: Vec<&'a Command>,
+}
+
+/// Show recent commits of a repository with the commands that produced them
+///
+/// A command belongs to the first commit made after it started, in the
+/// repository containing its directory, as long as it is no more than
+/// `MAX_WINDOW_HOURS` older than that commit.
+pub fn git_log(
+    storage: &Storage,
+    repo: Option<PathBuf>,
+    rev: &str,
+    limit: usize,
+    format: OutputFormat,
+) -> Result<()> {
+    let dir = match repo {
+        Some(dir) => dir,
+        None => std::env::current_dir()?,
+    };
+    let Some(root) = repo_root(&dir) else {
+        bail!("Not inside a git repository: {}", dir.display());
+    };
+
+    // One commit more than shown, so the oldest shown commit only claims
+    // commands made after its predecessor
+    let mut commits = read_commits(&root, &[rev, "-n", &(limit + 1).to_string()])?;
+    commits.reverse();
+
+    let commands: Vec<Command> = storage
+        .read_all_commands()?
+        .into_iter()
+        .filter(|cmd| Path::new(&cmd.cwd).starts_with(&root))
+        .collect();
+
+    let mut linked: Vec<LinkedCommit> = commits
+        .iter()
+        .map(|commit| LinkedCommit {
+            commit,
+            commands: Vec::new(),
+        })
+        .collect();
+    for cmd in &commands {
+        if let Some(i) = commit_for(&commits, cmd) {
+            linked[i].commands.push(cmd);
+        }
+    }
+    if commits.len() > limit {
+        linked.remove(0);
+    }
+    linked.reverse();
+    for entry in &mut linked {
+        entry.commands.sort_by_key(|c| c.started_at);
+    }
+
+    if format == OutputFormat::Json {
+        cli::print_json(&linked)?;
+        return Ok(());
+    }
+
+    if linked.is_empty() {
+        println!("No commits found.");
+        return Ok(());
+    }
+    for entry in &linked {
+        let commit = entry.commit;
+        println!(
+            "{}  {}  {} ({})",
+            commit.short_hash(),
+            commit
+                .committed_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            commit.subject,
+            commit.author
+        );
+        if entry.commands.is_empty() {
+            println!("    (no recorded commands)");
+        }
+        for cmd in &entry.commands {
+            println!(
+                "    {}  {} {}",
+                cmd.started_at.with_timezone(&Local).format("%H:%M:%S"),
+                if cmd.exit_code == 0 { "✓" } else { "✗" },
+                cmd.command
+            );
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// The commit each command led to, keyed by command ID
+///
+/// Commands outside any repository, or in one whose directory no longer
+/// exists, are left out.
+pub fn commits_for_commands(commands: &[Command]) -> HashMap<String, Commit> {
+    let Some(oldest) = commands.iter().map(|c| c.started_at).min() else {
+        return HashMap::new();
+    };
+    let since = format!("--since=@{}", oldest.timestamp());
+
+    let mut roots: HashMap<&str, Option<PathBuf>> = HashMap::new();
+    let mut history: HashMap<PathBuf, Vec<Commit>> = HashMap::new();
+    let mut links = HashMap::new();
+
+    for cmd in commands {
+        let root = roots
+            .entry(&cmd.cwd)
+            .or_insert_with(|| repo_root(Path::new(&cmd.cwd)));
+        let Some(root) = root else {
+            continue;
+        };
+        let commits = history.entry(root.clone()).or_insert_with(|| {
+            let mut commits = read_commits(root, &["--all", &since]).unwrap_or_else(|e| {
+                tracing::warn!(repo = %root.display(), "Failed to read commits: {:#}", e);
+                Vec::new()
+            });
+            commits.sort_by_key(|c| c.committed_at);
+            commits
+        });
+        if let Some(i) = commit_for(commits, cmd) {
+            links.insert(cmd.id.clone(), commits[i].clone());
+        }
+    }
+
+    links
+}
+
+/// Index of the first commit (oldest first) made after `cmd` started
+fn commit_for(commits: &[Commit], cmd: &Command) -> Option<usize> {
+    // Commit times have second precision, so compare whole seconds
+    let started = cmd.started_at.timestamp();
+    let i = commits.partition_point(|c| c.committed_at.timestamp() < started);
+    let commit = commits.get(i)?;
+    (commit.committed_at - cmd.started_at <= Duration::hours(MAX_WINDOW_HOURS)).then_some(i)
+}
+
+/// The working tree containing `dir`, if any
+fn repo_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|d| d.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Run `git log` in `repo`, newest commit first
+fn read_commits(repo: &Path, args: &[&str]) -> Result<Vec<Commit>> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["log", "--format=%H%x1f%ct%x1f%an%x1f%s"])
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git log failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(parse_log(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_log(log: &str) -> Vec<Commit> {
+    log.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\x1f');
+            let hash = fields.next()?.to_string();
+            let committed_at = DateTime::from_timestamp(fields.next()?.parse().ok()?, 0)?;
+            Some(Commit {
+                hash,
+                committed_at,
+                author: fields.next()?.to_string(),
+                subject: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn cmd(command: &str, hour: u32, minute: u32) -> Command {
+        Command {
+            id: command.to_string(),
+            command: command.to_string(),
+            exit_code: 0,
+            cwd: "/src/app".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, hour, minute, 0).unwrap(),
+            duration_ms: 100,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_commit_for() {
+        let log = "\
+aaaa\x1f1792227600\x1fAlice\x1fFirst
+bbbb\x1f1792231200\x1fBob\x1fSecond";
+        // 09:00 and 10:00 UTC
+        let commits = parse_log(log);
+        assert_eq!(commits[1].subject, "Second");
+        assert_eq!(commits[0].short_hash(), "aaaa");
+
+        let at = |h, m| commit_for(&commits, &cmd("c", h, m));
+        assert_eq!(at(8, 30), Some(0));
+        // The `git commit` itself starts in the second of the commit
+        assert_eq!(at(9, 0), Some(0));
+        assert_eq!(at(9, 30), Some(1));
+        assert_eq!(at(10, 30), None);
+
+        let mut early = cmd("early", 0, 0);
+        early.started_at -= Duration::days(1);
+        assert_eq!(commit_for(&commits, &early), None);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::ansi;
+use anyhow::{Context, Result};
+use crossterm::style::Stylize;
+use regex::{Regex, RegexBuilder};
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::io::IsTerminal;
+
+/// Options controlling how output is searched and printed
+pub struct GrepOptions {
+    /// Treat the pattern as a literal string instead of a regex
+    pub fixed_strings: bool,
+    /// Match case-insensitively
+    pub ignore_case: bool,
+    /// Lines of context to print after each match
+    pub after: usize,
+    /// Lines of context to print before each match
+    pub before: usize,
+    /// Maximum number of commands to report
+    pub limit: Option<usize>,
+}
+
+/// Search stored command outputs and print matching lines with context
+pub fn grep_outputs(storage: &Storage, pattern: &str, opts: GrepOptions) -> Result<()> {
+    let regex = build_regex(pattern, opts.fixed_strings, opts.ignore_case)?;
+    let color = std::io::stdout().is_terminal();
+
+    let mut commands = storage.read_all_commands()?;
+    commands.sort_by_key(|c| c.started_at);
+
+    let mut matched_commands = 0;
+    let mut matched_lines = 0;
+
+    for cmd in &commands {
+        if opts.limit.is_some_and(|limit| matched_commands >= limit) {
+            break;
+        }
+
+        let lines: Vec<String> = cmd.output.lines().map(ansi::strip_ansi).collect();
+        let hits: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| regex.is_match(line))
+            .map(|(i, _)| i)
+            .collect();
+
+        if hits.is_empty() {
+            continue;
+        }
+
+        matched_commands += 1;
+        matched_lines += hits.len();
+
+        print_header(cmd, color);
+        print_hits(&lines, &hits, &regex, &opts, color);
+        println!();
+    }
+
+    if matched_commands == 0 {
+        println!("No matches found");
+    } else {
+        println!(
+            "{} matching lines in {} commands",
+            matched_lines, matched_commands
+        );
+    }
+
+    Ok(())
+}
+
+/// Compile the search pattern
+fn build_regex(pattern: &str, fixed_strings: bool, ignore_case: bool) -> Result<Regex> {
+    let pattern = if fixed_strings {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .with_context(|| format!("Invalid pattern: {}", pattern))
+}
+
+/// Print the command that owns a group of matches
+fn print_header(cmd: &Command, color: bool) {
+    let status = if cmd.exit_code == 0 {
+        "✓".to_string()
+    } else {
+        format!("✗ {}", cmd.exit_code)
+    };
+
+    let header = format!(
+        "── {}  {}  {}  ({})",
+        cmd.started_at.format("%Y-%m-%d %H:%M:%S"),
+        status,
+        cmd.command,
+        cmd.cwd
+    );
+
+    if color {
+        println!("{}", header.bold());
+    } else {
+        println!("{}", header);
+    }
+}
+
+/// Print matching lines with grep-style context (`:` for matches, `-` for context)
+fn print_hits(lines: &[String], hits: &[usize], regex: &Regex, opts: &GrepOptions, color: bool) {
+    let mut visible = vec![false; lines.len()];
+    for &hit in hits {
+        let end = (hit + opts.after).min(lines.len() - 1);
+        for flag in &mut visible[hit.saturating_sub(opts.before)..=end] {
+            *flag = true;
+        }
+    }
+
+    let mut last_printed: Option<usize> = None;
+    for (i, line) in lines.iter().enumerate().filter(|(i, _)| visible[*i]) {
+        // Separate non-adjacent groups like grep does
+        if last_printed.is_some_and(|last| i > last + 1) {
+            println!("  --");
+        }
+
+        if hits.binary_search(&i).is_ok() {
+            println!("  {:>5}: {}", i + 1, highlight(line, regex, color));
+        } else {
+            println!("  {:>5}- {}", i + 1, line);
+        }
+        last_printed = Some(i);
+    }
+}
+
+/// Highlight regex matches within a line
+fn highlight(line: &str, regex: &Regex, color: bool) -> String {
+    if !color {
+        return line.to_string();
+    }
+
+    regex
+        .replace_all(line, |caps: &regex::Captures| {
+            caps[0].to_string().red().bold().to_string()
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_strings() {
+        let regex = build_regex("a.b", true, false).unwrap();
+        assert!(regex.is_match("a.b"));
+        assert!(!regex.is_match("axb"));
+
+        let regex = build_regex("ERROR", false, true).unwrap();
+        assert!(regex.is_match("error: x"));
+    }
+}
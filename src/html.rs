@@ -0,0 +1,218 @@
+use crate::ansi::strip_ansi;
+use crate::git::Commit;
+use chrono::Utc;
+use shelltape_core::models::{Command, format_duration};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Styles of the report; kept inline so the file stands alone
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 72rem; padding: 0 1rem; color: #1f2328; }
+header p { color: #59636e; margin: 0.2rem 0; }
+.controls { position: sticky; top: 0; background: #fff; padding: 0.8rem 0; display: flex; gap: 1rem; align-items: center; border-bottom: 1px solid #d1d9e0; }
+.controls input[type=search] { flex: 1; padding: 0.4rem 0.6rem; font-size: 1rem; }
+article { border: 1px solid #d1d9e0; border-left: 4px solid #1a7f37; border-radius: 6px; margin: 1rem 0; padding: 0.6rem 1rem; }
+article.failed { border-left-color: #cf222e; }
+.meta { color: #59636e; font-size: 0.85rem; display: flex; flex-wrap: wrap; gap: 0.4rem 1.2rem; }
+.status { font-weight: 600; color: #1a7f37; }
+.failed .status { color: #cf222e; }
+.tag { background: #ddf4ff; border-radius: 1rem; padding: 0 0.5rem; }
+blockquote { margin: 0.5rem 0; padding-left: 0.8rem; border-left: 3px solid #d1d9e0; color: #59636e; white-space: pre-wrap; }
+pre { background: #f6f8fa; border-radius: 6px; padding: 0.6rem; overflow-x: auto; margin: 0.5rem 0; }
+pre.command { font-weight: 600; }
+summary { cursor: pointer; color: #59636e; }
+"#;
+
+/// Filters the commands as the search text or checkbox change
+const SCRIPT: &str = r#"
+const search = document.getElementById('search');
+const failed = document.getElementById('failed');
+const count = document.getElementById('count');
+function update() {
+  const text = search.value.toLowerCase();
+  let shown = 0;
+  for (const article of document.querySelectorAll('article')) {
+    const show = article.textContent.toLowerCase().includes(text)
+      && (!failed.checked || article.classList.contains('failed'));
+    article.hidden = !show;
+    if (show) shown++;
+  }
+  count.textContent = shown + ' shown';
+}
+search.addEventListener('input', update);
+failed.addEventListener('change', update);
+update();
+"#;
+
+/// Render commands as a single self-contained HTML report
+///
+/// Output is stripped of escape sequences and folded into a `<details>`
+/// block; a search box and a "failed only" switch filter the commands in
+/// the browser.
+pub fn to_html(
+    commands: &[Command],
+    commits: &HashMap<String, Commit>,
+    session: Option<&str>,
+    filter: Option<&str>,
+) -> String {
+    let mut html = String::new();
+    let failed = commands.iter().filter(|c| c.exit_code != 0).count();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    html.push_str("<title>Shelltape Command History</title>\n");
+    let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+
+    html.push_str("<header>\n<h1>Shelltape Command History</h1>\n");
+    let _ = writeln!(
+        html,
+        "<p>Generated: {}</p>",
+        Utc::now().format("%Y-%m-%d %H:%M:%S")
+    );
+    let _ = writeln!(
+        html,
+        "<p>Total commands: {} ({} failed)</p>",
+        commands.len(),
+        failed
+    );
+    if let Some(sid) = session {
+        let _ = writeln!(html, "<p>Session: <code>{}</code></p>", escape(sid));
+    }
+    if let Some(query) = filter {
+        let _ = writeln!(html, "<p>Filter: <code>{}</code></p>", escape(query));
+    }
+    html.push_str("</header>\n");
+
+    html.push_str("<div class=\"controls\">\n");
+    html.push_str("<input type=\"search\" id=\"search\" placeholder=\"Filter commands\">\n");
+    html.push_str("<label><input type=\"checkbox\" id=\"failed\"> Failed only</label>\n");
+    html.push_str("<span id=\"count\"></span>\n</div>\n<main>\n");
+
+    for cmd in commands {
+        let class = if cmd.exit_code == 0 {
+            ""
+        } else {
+            " class=\"failed\""
+        };
+        let status = if cmd.exit_code == 0 {
+            "✓ 0".to_string()
+        } else {
+            format!("✗ {}", cmd.exit_code)
+        };
+        let _ = writeln!(html, "<article{}>", class);
+        let _ = writeln!(
+            html,
+            "<pre class=\"command\">{}</pre>",
+            escape(&cmd.command)
+        );
+
+        html.push_str("<div class=\"meta\">");
+        let _ = write!(html, "<span class=\"status\">{}</span>", status);
+        let _ = write!(
+            html,
+            "<span>{}</span>",
+            cmd.started_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        let _ = write!(html, "<span>{}</span>", format_duration(cmd.duration_ms));
+        let _ = write!(html, "<span><code>{}</code></span>", escape(&cmd.cwd));
+        let _ = write!(
+            html,
+            "<span>{}@{}</span>",
+            escape(&cmd.username),
+            escape(&cmd.hostname)
+        );
+        if let Some(commit) = commits.get(&cmd.id) {
+            let _ = write!(
+                html,
+                "<span>commit <code>{}</code> {}</span>",
+                commit.short_hash(),
+                escape(&commit.subject)
+            );
+        }
+        for tag in &cmd.tags {
+            let _ = write!(html, "<span class=\"tag\">{}</span>", escape(tag));
+        }
+        html.push_str("</div>\n");
+
+        if let Some(note) = &cmd.note {
+            let _ = writeln!(html, "<blockquote>{}</blockquote>", escape(note));
+        }
+        if !cmd.output.is_empty() {
+            let output = strip_ansi(&cmd.output);
+            // Failures are usually what the reader is after, so show them open
+            let open = if cmd.exit_code == 0 { "" } else { " open" };
+            let _ = writeln!(
+                html,
+                "<details{}><summary>Output ({} lines)</summary><pre>{}</pre></details>",
+                open,
+                output.lines().count(),
+                escape(&output)
+            );
+        }
+        html.push_str("</article>\n");
+    }
+
+    let _ = writeln!(
+        html,
+        "</main>\n<script>{}</script>\n</body>\n</html>",
+        SCRIPT
+    );
+    html
+}
+
+/// Escape text for use in HTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_to_html() {
+        let cmd = Command {
+            id: "abc".to_string(),
+            command: "echo '<b>' && false".to_string(),
+            output: "\x1b[31m<b>\x1b[0m\r\n".to_string(),
+            exit_code: 1,
+            cwd: "/src".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap(),
+            duration_ms: 2500,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            tags: vec!["incident".to_string()],
+            note: Some("root cause".to_string()),
+            ..Default::default()
+        };
+        let html = to_html(&[cmd], &HashMap::new(), None, Some("echo"));
+
+        assert!(
+            html.contains("<pre class=\"command\">echo &#39;&lt;b&gt;&#39; &amp;&amp; false</pre>")
+        );
+        assert!(html.contains("<article class=\"failed\">"));
+        assert!(html.contains("<span class=\"status\">✗ 1</span>"));
+        assert!(
+            html.contains(
+                "<details open><summary>Output (1 lines)</summary><pre>&lt;b&gt;\n</pre>"
+            )
+        );
+        assert!(html.contains("<span class=\"tag\">incident</span>"));
+        assert!(html.contains("Total commands: 1 (1 failed)"));
+        assert!(!html.contains("<b>"));
+    }
+}
@@ -5,7 +5,7 @@ use std::io::Write;
 use std::path::Path;
 
 /// Install shell hooks for automatic command recording
-pub fn install(shell: Option<Shell>) -> Result<()> {
+pub fn install(shell: Option<Shell>, ctrl_r: bool) -> Result<()> {
     let shell = shell.or_else(Shell::detect).ok_or_else(|| {
         anyhow!(
             "Could not detect shell. Please specify explicitly with --shell (bash, zsh, fish, or powershell)"
@@ -26,7 +26,15 @@ pub fn install(shell: Option<Shell>) -> Result<()> {
     copy_hook_file(&shelltape_dir, shell)?;
 
     // Add source line to RC file
-    add_to_rc_file(shell)?;
+    add_to_rc_file(
+        shell,
+        &shell.hook_line(),
+        "Shelltape - Terminal command history recorder",
+    )?;
+
+    if ctrl_r {
+        install_widget(&shelltape_dir, shell)?;
+    }
 
     println!("\nShelltape installed successfully!");
     println!("\nTo start recording commands, either:");
@@ -37,18 +45,26 @@ pub fn install(shell: Option<Shell>) -> Result<()> {
     println!("  - shelltape browse        - Interactive browser (TUI)");
     println!("  - shelltape stats         - Show statistics");
     println!("  - shelltape export -o file.md - Export to markdown");
+    if ctrl_r && shell.widget_file().is_some() {
+        println!("  - Ctrl-R                  - Search history with shelltape pick");
+    }
 
     Ok(())
 }
 
-/// Copy the appropriate hook file to ~/.shelltape/
-fn copy_hook_file(shelltape_dir: &Path, shell: Shell) -> Result<()> {
-    let hook_content = match shell {
+/// Get the hook script bundled with this version of shelltape
+pub fn hook_content(shell: Shell) -> &'static str {
+    match shell {
         Shell::Bash => include_str!("../shell-hooks/bash.sh"),
         Shell::Zsh => include_str!("../shell-hooks/zsh.sh"),
         Shell::Fish => include_str!("../shell-hooks/fish.fish"),
         Shell::Powershell => include_str!("../shell-hooks/powershell.ps1"),
-    };
+    }
+}
+
+/// Copy the appropriate hook file to ~/.shelltape/
+pub fn copy_hook_file(shelltape_dir: &Path, shell: Shell) -> Result<()> {
+    let hook_content = hook_content(shell);
 
     let hook_file_path = shelltape_dir.join(shell.hook_file());
 
@@ -60,8 +76,47 @@ fn copy_hook_file(shelltape_dir: &Path, shell: Shell) -> Result<()> {
     Ok(())
 }
 
-/// Add source line to the shell's RC file
-fn add_to_rc_file(shell: Shell) -> Result<()> {
+/// Get the Ctrl-R widget script bundled with this version of shelltape
+pub fn widget_content(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(include_str!("../shell-hooks/bash-ctrl-r.sh")),
+        Shell::Zsh => Some(include_str!("../shell-hooks/zsh-ctrl-r.sh")),
+        Shell::Fish => Some(include_str!("../shell-hooks/fish-ctrl-r.fish")),
+        Shell::Powershell => None,
+    }
+}
+
+/// Copy the Ctrl-R widget to ~/.shelltape/ and source it from the RC file
+///
+/// The widget is sourced after the hook so its binding wins over the
+/// shell's own reverse search.
+fn install_widget(shelltape_dir: &Path, shell: Shell) -> Result<()> {
+    let Some(line) = shell.widget_line() else {
+        println!("  [WARN] Ctrl-R binding is not supported for {:?}", shell);
+        return Ok(());
+    };
+
+    copy_widget_file(shelltape_dir, shell)?;
+    add_to_rc_file(shell, &line, "Shelltape - Ctrl-R history picker")
+}
+
+/// Copy the Ctrl-R widget to ~/.shelltape/ (no-op for shells without one)
+pub fn copy_widget_file(shelltape_dir: &Path, shell: Shell) -> Result<()> {
+    let (Some(file), Some(content)) = (shell.widget_file(), widget_content(shell)) else {
+        return Ok(());
+    };
+
+    let widget_path = shelltape_dir.join(file);
+    fs::write(&widget_path, content)
+        .with_context(|| format!("Failed to write widget file to: {}", widget_path.display()))?;
+
+    println!("  [OK] Copied Ctrl-R widget to {}", widget_path.display());
+
+    Ok(())
+}
+
+/// Add a source line to the shell's RC file
+fn add_to_rc_file(shell: Shell, hook_line: &str, comment: &str) -> Result<()> {
     let home_dir = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
 
     let rc_path = home_dir.join(shell.rc_file());
@@ -76,16 +131,11 @@ fn add_to_rc_file(shell: Shell) -> Result<()> {
     let content = fs::read_to_string(&rc_path)
         .with_context(|| format!("Failed to read: {}", rc_path.display()))?;
 
-    let hook_line = match shell {
-        Shell::Bash | Shell::Zsh => format!("source ~/.shelltape/{}", shell.hook_file()),
-        Shell::Fish => format!("source ~/.shelltape/{}", shell.hook_file()),
-        Shell::Powershell => format!(". ~\\.shelltape\\{}", shell.hook_file()),
-    };
-
     // Check if already installed
-    if content.contains(&hook_line) {
+    if content.contains(hook_line) {
         println!(
-            "  [INFO] Shelltape hooks already present in {}",
+            "  [INFO] `{}` already present in {}",
+            hook_line,
             rc_path.display()
         );
         return Ok(());
@@ -97,10 +147,10 @@ fn add_to_rc_file(shell: Shell) -> Result<()> {
         .open(&rc_path)
         .with_context(|| format!("Failed to open {} for appending", rc_path.display()))?;
 
-    writeln!(file, "\n# Shelltape - Terminal command history recorder")?;
+    writeln!(file, "\n# {}", comment)?;
     writeln!(file, "{}", hook_line)?;
 
-    println!("  [OK] Added hooks to {}", rc_path.display());
+    println!("  [OK] Added `{}` to {}", hook_line, rc_path.display());
 
     Ok(())
 }
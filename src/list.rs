@@ -1,54 +1,374 @@
-use crate::storage::Storage;
+use crate::ansi;
+use crate::category::Categorizer;
+use crate::cli::{self, ListColumn, OutputFormat};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use shelltape_core::config::Config;
+use shelltape_core::models::Command;
+use shelltape_core::query::Query;
+use shelltape_core::storage::Storage;
+use std::io::Write;
 
 /// List recent commands
-pub fn list_commands(limit: usize, filter: Option<String>) -> Result<()> {
-    let storage = Storage::new()?;
+pub fn list_commands(
+    storage: &Storage,
+    limit: usize,
+    filter: Option<String>,
+    tag: Option<String>,
+    category: Option<String>,
+    table: TableOptions,
+    format: OutputFormat,
+) -> Result<()> {
+    let categorizer = match &category {
+        Some(category) => {
+            let categorizer = Categorizer::new(&Config::load()?);
+            categorizer.validate(category)?;
+            Some(categorizer)
+        }
+        None => None,
+    };
 
-    let commands = if let Some(query) = filter {
-        storage.search_commands(&query, limit)?
-    } else {
-        storage.get_recent_commands(limit)?
+    let mut query = Query {
+        text: filter,
+        tag,
+        ..Query::default()
     };
+    // The category filter runs after the query, so only truncate once it is applied
+    if category.is_none() {
+        query = query.limit(limit);
+    }
+    let mut commands = query.run(storage)?;
+
+    if let (Some(category), Some(categorizer)) = (&category, &categorizer) {
+        commands.retain(|cmd| {
+            categorizer
+                .categorize(&cmd.command)
+                .eq_ignore_ascii_case(category)
+        });
+        commands.truncate(limit);
+    }
+
+    if table.porcelain {
+        let mut out = std::io::stdout().lock();
+        for cmd in &commands {
+            writeln!(out, "{}", porcelain_line(cmd))?;
+        }
+        return Ok(());
+    }
+
+    match format {
+        OutputFormat::Json => cli::print_json(&commands)?,
+        OutputFormat::Tsv => print_tsv(&commands, &table.columns)?,
+        OutputFormat::Text => print_table_with(&commands, &table)?,
+    }
+
+    Ok(())
+}
+
+/// Layout options for the command table
+pub struct TableOptions {
+    /// Columns to show, in order
+    pub columns: Vec<ListColumn>,
+    /// Show start times as "3h ago" instead of timestamps
+    pub relative_time: bool,
+    /// Shorten long commands and directories to fit fixed-width columns
+    pub truncate: bool,
+    /// Number of output lines to show under each command
+    pub output_preview: usize,
+    /// Print tab-separated lines without headers, for fzf and scripts
+    pub porcelain: bool,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            columns: DEFAULT_COLUMNS.to_vec(),
+            relative_time: false,
+            truncate: true,
+            output_preview: 0,
+            porcelain: false,
+        }
+    }
+}
+
+/// Columns shown when `--columns` is not given
+pub const DEFAULT_COLUMNS: &[ListColumn] = &[
+    ListColumn::Id,
+    ListColumn::Time,
+    ListColumn::Status,
+    ListColumn::Command,
+    ListColumn::Dir,
+];
+
+/// Print commands as a table with a total line
+pub fn print_table(commands: &[Command]) -> Result<()> {
+    print_table_with(commands, &TableOptions::default())
+}
 
+/// Print commands as a table using custom layout options
+pub fn print_table_with(commands: &[Command], opts: &TableOptions) -> Result<()> {
+    let mut out = std::io::stdout().lock();
     if commands.is_empty() {
-        println!("No commands found");
+        writeln!(out, "No commands found")?;
         return Ok(());
     }
 
-    // Print header
-    println!("{:<20} {:<8} {:<50} DIRECTORY", "TIME", "STATUS", "COMMAND");
-    println!("{}", "─".repeat(100));
+    let rows: Vec<Vec<String>> = commands
+        .iter()
+        .map(|cmd| {
+            opts.columns
+                .iter()
+                .map(|col| cell(cmd, *col, opts))
+                .collect()
+        })
+        .collect();
 
-    // Print commands
-    for cmd in &commands {
-        let time = cmd.started_at.format("%Y-%m-%d %H:%M:%S");
+    // Fixed widths keep the layout stable; without truncation columns grow to fit
+    let widths: Vec<usize> = opts
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| {
+            let content = rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0);
+            let header = col.header().len();
+            let relative = *col == ListColumn::Time && opts.relative_time;
+            match col.max_width() {
+                Some(max) if opts.truncate && !relative => max.max(header),
+                _ => content.max(header),
+            }
+        })
+        .collect();
 
-        let status_display = if cmd.exit_code == 0 {
-            "✓".to_string()
-        } else {
-            format!("✗ {}", cmd.exit_code)
-        };
+    let headers: Vec<String> = opts
+        .columns
+        .iter()
+        .map(|c| c.header().to_string())
+        .collect();
+    writeln!(out, "{}", format_row(&headers, &widths))?;
+    let total_width = widths.iter().sum::<usize>() + widths.len().saturating_sub(1);
+    writeln!(out, "{}", "─".repeat(total_width))?;
 
-        let command_display = if cmd.command.len() > 50 {
-            format!("{}...", &cmd.command[..47])
-        } else {
-            cmd.command.clone()
-        };
+    for (cmd, row) in commands.iter().zip(&rows) {
+        writeln!(out, "{}", format_row(row, &widths))?;
+
+        if opts.output_preview > 0 {
+            for line in cmd
+                .output
+                .lines()
+                .map(|l| ansi::strip_ansi(l).trim_end().to_string())
+                .filter(|l| !l.is_empty())
+                .take(opts.output_preview)
+            {
+                let line = if opts.truncate {
+                    truncate_end(&line, PREVIEW_WIDTH)
+                } else {
+                    line
+                };
+                writeln!(out, "    │ {}", line)?;
+            }
+        }
+    }
+
+    writeln!(out, "\nTotal: {} commands", commands.len())?;
+    Ok(())
+}
+
+/// Widest preview line shown when truncating
+const PREVIEW_WIDTH: usize = 100;
+
+/// Render one column of a command
+fn cell(cmd: &Command, col: ListColumn, opts: &TableOptions) -> String {
+    let max = col.max_width().filter(|_| opts.truncate);
+    match col {
+        ListColumn::Id => cmd.id.get(..8).unwrap_or(&cmd.id).to_string(),
+        ListColumn::Time if opts.relative_time => relative_time(cmd.started_at, Utc::now()),
+        ListColumn::Time => cmd.started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ListColumn::Status => {
+            if cmd.exit_code == 0 {
+                "✓".to_string()
+            } else {
+                format!("✗ {}", cmd.exit_code)
+            }
+        }
+        ListColumn::Duration => format!("{}ms", cmd.duration_ms),
+        ListColumn::Command => match max {
+            Some(max) => truncate_end(&cmd.command, max),
+            None => cmd.command.clone(),
+        },
+        ListColumn::Dir => match max {
+            Some(max) => truncate_start(&cmd.cwd, max),
+            None => cmd.cwd.clone(),
+        },
+        ListColumn::Session => cmd
+            .session_id
+            .get(..8)
+            .unwrap_or(&cmd.session_id)
+            .to_string(),
+        ListColumn::Host => cmd.hostname.clone(),
+        ListColumn::Tags => {
+            let tags = cmd.tags.join(",");
+            match max {
+                Some(max) => truncate_end(&tags, max),
+                None => tags,
+            }
+        }
+    }
+}
+
+/// Print commands as tab-separated values under a header row
+///
+/// Values are complete and unformatted: full IDs, RFC 3339 times, exit codes
+/// and durations in milliseconds. Tabs and newlines are escaped as in
+/// `--porcelain`.
+pub fn print_tsv(commands: &[Command], columns: &[ListColumn]) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    let headers: Vec<String> = columns.iter().map(|c| c.header().to_lowercase()).collect();
+    writeln!(out, "{}", headers.join("\t"))?;
+    for cmd in commands {
+        let row: Vec<String> = columns.iter().map(|col| tsv_cell(cmd, *col)).collect();
+        writeln!(out, "{}", row.join("\t"))?;
+    }
+    Ok(())
+}
+
+fn tsv_cell(cmd: &Command, col: ListColumn) -> String {
+    match col {
+        ListColumn::Id => cmd.id.clone(),
+        ListColumn::Time => cmd.started_at.to_rfc3339(),
+        ListColumn::Status => cmd.exit_code.to_string(),
+        ListColumn::Duration => cmd.duration_ms.to_string(),
+        ListColumn::Command => escape_field(cmd.command.trim_end()),
+        ListColumn::Dir => escape_field(&cmd.cwd),
+        ListColumn::Session => escape_field(&cmd.session_id),
+        ListColumn::Host => escape_field(&cmd.hostname),
+        ListColumn::Tags => escape_field(&cmd.tags.join(",")),
+    }
+}
+
+/// Escape backslashes, tabs and newlines so a value stays in one field
+fn escape_field(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+/// One `id<TAB>time<TAB>exit<TAB>command` line
+///
+/// Tabs and newlines in the command are escaped so every command stays on
+/// one line with four fields.
+fn porcelain_line(cmd: &Command) -> String {
+    let command = escape_field(cmd.command.trim_end());
+    format!(
+        "{}\t{}\t{}\t{}",
+        cmd.id,
+        cmd.started_at.format("%Y-%m-%d %H:%M:%S"),
+        cmd.exit_code,
+        command
+    )
+}
+
+/// Join cells padded to their column widths (the last column is not padded)
+fn format_row(cells: &[String], widths: &[usize]) -> String {
+    let last = cells.len().saturating_sub(1);
+    cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(i, (cell, width))| {
+            if i == last {
+                cell.clone()
+            } else {
+                format!("{:<width$}", cell, width = width)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Keep the start of a string, marking the cut with "..."
+pub fn truncate_end(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    let kept: String = s.chars().take(max.saturating_sub(3)).collect();
+    format!("{}...", kept)
+}
+
+/// Keep the end of a string (e.g. the deepest part of a path), marking the cut with "..."
+pub fn truncate_start(s: &str, max: usize) -> String {
+    let len = s.chars().count();
+    if len <= max {
+        return s.to_string();
+    }
+    let kept: String = s.chars().skip(len - max.saturating_sub(3)).collect();
+    format!("...{}", kept)
+}
+
+/// Describe how long ago a time was, e.g. "3h ago"
+fn relative_time(then: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let secs = (now - then).num_seconds().max(0);
+    match secs {
+        0..10 => "just now".to_string(),
+        10..60 => format!("{}s ago", secs),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86_400 => format!("{}h ago", secs / 3600),
+        86_400..2_592_000 => format!("{}d ago", secs / 86_400),
+        2_592_000..31_536_000 => format!("{}mo ago", secs / 2_592_000),
+        _ => format!("{}y ago", secs / 31_536_000),
+    }
+}
 
-        let cwd_display = if cmd.cwd.len() > 30 {
-            format!("...{}", &cmd.cwd[cmd.cwd.len() - 27..])
-        } else {
-            cmd.cwd.clone()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_relative_time() {
+        let now = Utc::now();
+        assert_eq!(relative_time(now, now), "just now");
+        assert_eq!(relative_time(now - Duration::seconds(42), now), "42s ago");
+        assert_eq!(relative_time(now - Duration::minutes(5), now), "5m ago");
+        assert_eq!(relative_time(now - Duration::hours(3), now), "3h ago");
+        assert_eq!(relative_time(now - Duration::days(2), now), "2d ago");
+    }
+
+    #[test]
+    fn test_porcelain_line() {
+        let cmd = Command {
+            id: "0190f3a2-b7c4-7d1e-8a9b-0c1d2e3f4a5b".to_string(),
+            command: "printf 'a\tb'\nls\n".to_string(),
+            exit_code: 2,
+            cwd: "/src".to_string(),
+            started_at: DateTime::from_timestamp(1792227600, 0).unwrap(),
+            duration_ms: 1,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
         };
+        assert_eq!(
+            porcelain_line(&cmd),
+            "0190f3a2-b7c4-7d1e-8a9b-0c1d2e3f4a5b\t2026-10-17 09:00:00\t2\tprintf 'a\\tb'\\nls"
+        );
 
-        println!(
-            "{:<20} {:<8} {:<50} {}",
-            time, status_display, command_display, cwd_display
+        let tsv: Vec<String> = [ListColumn::Time, ListColumn::Status, ListColumn::Command]
+            .iter()
+            .map(|col| tsv_cell(&cmd, *col))
+            .collect();
+        assert_eq!(
+            tsv,
+            ["2026-10-17T09:00:00+00:00", "2", "printf 'a\\tb'\\nls"]
         );
     }
 
-    println!("\nTotal: {} commands", commands.len());
-
-    Ok(())
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate_end("cargo build --release", 10), "cargo b...");
+        assert_eq!(truncate_start("/home/user/src/project", 11), ".../project");
+        assert_eq!(truncate_end("héllo wörld", 8), "héllo...");
+        assert_eq!(truncate_end("short", 10), "short");
+    }
 }
@@ -0,0 +1,64 @@
+use anyhow::{Context, Result, anyhow};
+use shelltape_core::config::LoggingConfig;
+use std::path::Path;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::EnvFilter;
+use tracing_subscriber::prelude::*;
+
+/// Environment variable holding a filter directive (e.g. `debug`, `shelltape::storage=trace`)
+pub const LOG_ENV: &str = "SHELLTAPE_LOG";
+
+/// Set up diagnostic logging to stderr and, if enabled, a rotating log file
+///
+/// `SHELLTAPE_LOG` overrides the level for both outputs. Otherwise stderr
+/// follows `-v` (warn, info, debug, trace) and the log file records debug
+/// and above, since shell hooks never pass `-v`.
+pub fn init(verbose: u8, config: &LoggingConfig, log_dir: Option<&Path>) -> Result<()> {
+    let env_filter = std::env::var(LOG_ENV).ok();
+    let filter = |default: &str| match &env_filter {
+        Some(directive) => EnvFilter::try_new(directive)
+            .map_err(|e| anyhow!("Invalid {} value '{}': {}", LOG_ENV, directive, e)),
+        None => Ok(EnvFilter::new(default)),
+    };
+
+    let stderr_level = match verbose {
+        0 => "warn",
+        1 => "shelltape=info",
+        2 => "shelltape=debug",
+        _ => "shelltape=trace",
+    };
+    let stderr_layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(verbose >= 2)
+        .with_filter(filter(stderr_level)?);
+
+    let file_layer = match log_dir {
+        Some(dir) if config.file => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create log directory: {}", dir.display()))?;
+            let appender = RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix("shelltape")
+                .filename_suffix("log")
+                .max_log_files(config.max_files.max(1))
+                .build(dir)
+                .with_context(|| format!("Failed to open log directory: {}", dir.display()))?;
+
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(appender)
+                    .with_ansi(false)
+                    .with_filter(filter("shelltape=debug")?),
+            )
+        }
+        _ => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr_layer)
+        .with(file_layer)
+        .try_init()
+        .context("Failed to initialize logging")?;
+
+    Ok(())
+}
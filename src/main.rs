@@ -1,27 +1,110 @@
+mod ansi;
+mod archive;
+mod asciicast;
+mod atuin;
+mod bookmark;
+mod category;
 mod clean;
 mod cli;
+mod clipboard;
+mod config;
+mod copy;
+mod daemon;
+mod delete;
+mod diff;
+mod dir;
+mod doctor;
+mod exec;
 mod export;
+mod fetch;
+mod fuzzy;
+mod git;
+mod grep;
+mod html;
 mod install;
 mod list;
-mod models;
+mod logging;
+mod metrics;
+mod migrate;
+mod note;
+mod open;
+mod pick;
+mod preview;
+mod prune;
 mod pty_capture;
-mod recorder;
+mod purge;
+mod query;
+mod recording;
+mod replay;
+mod rerun;
+mod rpc;
+mod search;
+mod serve;
+mod session;
+mod sessions;
+mod shell_history;
 mod stats;
 mod status;
-mod storage;
+mod summary;
+mod sync;
+mod tag;
+mod timeline;
+mod timespec;
+mod top;
 mod tui;
+mod typescript;
 mod uninstall;
+mod watch;
+mod webui;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, OutputFormat, SyncAction};
+use shelltape_core::config::{Backend, Config};
+use shelltape_core::{recorder, storage};
+use std::io::Read;
 
 fn main() -> Result<()> {
+    match run() {
+        // The reader went away (`shelltape list | head`); nothing is lost
+        Err(e) if is_broken_pipe(&e) => Ok(()),
+        result => result,
+    }
+}
+
+/// Whether writing output failed because its reader closed the pipe
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe)
+    })
+}
+
+fn run() -> Result<()> {
     let cli = Cli::parse();
+    let format = cli.output_format();
+    let data_dir = cli.data_dir.clone();
+
+    // Logging must never stop a command from running, so a broken config
+    // or log directory only produces a warning
+    let log_config = Config::load().unwrap_or_default();
+    let log_dir = match &data_dir {
+        Some(dir) => Some(dir.clone()),
+        None => log_config.data_dir().ok(),
+    }
+    .map(|dir| dir.join("logs"));
+    if let Err(e) = logging::init(cli.verbose, &log_config.logging, log_dir.as_deref()) {
+        eprintln!("shelltape: {:#}", e);
+    }
+    let open_storage = || match &data_dir {
+        Some(dir) => storage::Storage::open(dir.clone(), Config::load()?.storage.backend),
+        None => storage::Storage::new(),
+    };
 
     match cli.command {
-        Commands::Install { shell } => {
-            install::install(shell)?;
+        Commands::Install { shell, ctrl_r } => {
+            install::install(shell, ctrl_r)?;
         }
         Commands::Uninstall { shell } => {
             uninstall::uninstall(shell)?;
@@ -29,6 +112,7 @@ fn main() -> Result<()> {
         Commands::Exec {
             command,
             session_id,
+            name,
         } => {
             // Join command parts
             let command_str = command.join(" ");
@@ -37,23 +121,19 @@ fn main() -> Result<()> {
                 .to_string_lossy()
                 .to_string();
 
-            // Execute with PTY capture (output is displayed in real-time by PTY)
-            let result = pty_capture::execute_with_capture(&command_str, &cwd)?;
-
-            // Record the command
-            let recorder = recorder::Recorder::new()?;
-            recorder.record(
-                command_str,
-                result.output,
-                result.exit_code,
-                result.start_time,
-                result.end_time,
-                cwd,
+            let storage = open_storage()?;
+            let session_id = session::resolve_exec_session(&storage, session_id, name)?;
+            let (_, exit_code) = exec::run_and_record(
+                storage,
+                command_str.clone(),
+                cwd.clone(),
                 session_id,
+                None,
+                || pty_capture::execute_with_capture(&command_str, &cwd),
             )?;
 
             // Exit with same code as command
-            std::process::exit(result.exit_code);
+            std::process::exit(exit_code);
         }
         Commands::Record {
             command,
@@ -63,36 +143,486 @@ fn main() -> Result<()> {
             cwd,
             session_id,
             output,
+            output_file,
+            via_daemon,
+        } => {
+            // Large or binary output doesn't survive argv; read it as bytes instead
+            let output = match (output_file, output.as_str()) {
+                (Some(path), _) => {
+                    let bytes = std::fs::read(&path).with_context(|| {
+                        format!("Failed to read output file: {}", path.display())
+                    })?;
+                    String::from_utf8_lossy(&bytes).into_owned()
+                }
+                (None, "-") => {
+                    let mut bytes = Vec::new();
+                    std::io::stdin()
+                        .read_to_end(&mut bytes)
+                        .context("Failed to read output from stdin")?;
+                    String::from_utf8_lossy(&bytes).into_owned()
+                }
+                (None, _) => output,
+            };
+            let storage = open_storage()?;
+            let raw = daemon::RawRecord {
+                command,
+                output,
+                exit_code,
+                start_time,
+                end_time,
+                cwd,
+                session_id,
+                origin: recorder::Origin::current(),
+            };
+            if via_daemon && daemon::send_raw(&storage, &raw) {
+                return Ok(());
+            }
+            let recorder = recorder::Recorder::new(storage.clone())?;
+            // A running daemon writes it, sparing this process the file and hooks
+            if let Some(cmd) = recorder.prepare(
+                raw.command,
+                raw.output,
+                raw.exit_code,
+                raw.start_time,
+                raw.end_time,
+                raw.cwd,
+                raw.session_id,
+                Vec::new(),
+                raw.origin,
+            ) && !daemon::send_record(&storage, &cmd)
+            {
+                recorder.save(&cmd)?;
+            }
+        }
+        Commands::Browse { tag } => {
+            tui::run(open_storage()?, tag)?;
+        }
+        Commands::Pick {
+            query,
+            here,
+            success,
+            filter,
+        } => {
+            let opts = pick::PickOptions {
+                query,
+                here,
+                success,
+                filter,
+            };
+            if !pick::pick_command(&open_storage()?, opts)? {
+                std::process::exit(1);
+            }
+        }
+        Commands::List {
+            limit,
+            filter,
+            tag,
+            category,
+            columns,
+            relative_time,
+            no_truncate,
+            output_preview,
+            porcelain,
+        } => {
+            let limit = match limit {
+                Some(limit) => limit,
+                None => Config::load()?.ui.default_limit,
+            };
+            let table = list::TableOptions {
+                columns: columns.unwrap_or_else(|| list::DEFAULT_COLUMNS.to_vec()),
+                relative_time,
+                truncate: !no_truncate,
+                output_preview,
+                porcelain,
+            };
+            list::list_commands(
+                &open_storage()?,
+                limit,
+                filter,
+                tag,
+                category,
+                table,
+                format,
+            )?;
+        }
+        Commands::Preview { id } => {
+            preview::preview(&open_storage()?, &id)?;
+        }
+        Commands::Dir {
+            path,
+            recursive,
+            since,
+            until,
+            failed,
+            limit,
+        } => {
+            let limit = match limit {
+                Some(limit) => limit,
+                None => Config::load()?.ui.default_limit,
+            };
+            let filter = dir::DirFilter {
+                recursive,
+                since,
+                until,
+                failed,
+                limit,
+            };
+            dir::dir_history(&open_storage()?, path, filter, format)?;
+        }
+        Commands::Search {
+            query,
+            regex,
+            command_only,
+            output_only,
+            cwd,
+            exit_code,
+            tag,
+            since,
+            until,
+            limit,
+        } => {
+            let limit = match limit {
+                Some(limit) => limit,
+                None => Config::load()?.ui.default_limit,
+            };
+            let fields = if command_only {
+                search::Fields::Command
+            } else if output_only {
+                search::Fields::Output
+            } else {
+                search::Fields::All
+            };
+            let filter = search::SearchFilter {
+                regex,
+                fields,
+                cwd,
+                exit_code,
+                tag,
+                since,
+                until,
+                limit,
+            };
+            search::search(&open_storage()?, &query, filter, format)?;
+        }
+        Commands::Watch {
+            filter,
+            failed,
+            session,
+            lines,
         } => {
-            let recorder = recorder::Recorder::new()?;
-            recorder.record(
-                command, output, exit_code, start_time, end_time, cwd, session_id,
+            watch::watch_commands(
+                &open_storage()?,
+                watch::WatchFilter {
+                    query: filter,
+                    failed,
+                    session,
+                },
+                format == OutputFormat::Json,
+                lines,
             )?;
         }
-        Commands::Browse => {
-            tui::run()?;
+        Commands::Rerun {
+            id,
+            last,
+            interactive,
+            here,
+        } => {
+            let exit_code = rerun::rerun(open_storage()?, id, last, interactive, here)?;
+            std::process::exit(exit_code);
+        }
+        Commands::Copy { id, last, output } => {
+            copy::copy_command(&open_storage()?, id, last, output)?;
+        }
+        Commands::Tag { action } => {
+            tag::tag_command(&open_storage()?, action, format)?;
+        }
+        Commands::Note {
+            id,
+            text,
+            last,
+            replace,
+            clear,
+        } => {
+            note::note_command(&open_storage()?, id, last, text, replace, clear)?;
+        }
+        Commands::Sessions { action, limit } => match action {
+            Some(cli::SessionsAction::Show { id }) => {
+                sessions::show_session(&open_storage()?, &id, format)?;
+            }
+            None => {
+                let limit = match limit {
+                    Some(limit) => limit,
+                    None => Config::load()?.ui.default_limit,
+                };
+                sessions::list_sessions(&open_storage()?, limit, format)?;
+            }
+        },
+        // `--last` only satisfies clap; a missing ID already means the latest command
+        Commands::Bookmark {
+            action,
+            id,
+            last: _,
+            remove,
+        } => match action {
+            Some(cli::BookmarkAction::List) => {
+                bookmark::list_bookmarks(&open_storage()?, format)?;
+            }
+            None => bookmark::set_bookmark(&open_storage()?, id, !remove)?,
+        },
+        Commands::Diff {
+            id1,
+            id2,
+            metadata,
+            context,
+        } => {
+            diff::diff_commands(&open_storage()?, &id1, &id2, metadata, context)?;
+        }
+        Commands::Open { id, pager } => {
+            open::open(&open_storage()?, id, pager)?;
         }
-        Commands::List { limit, filter } => {
-            list::list_commands(limit, filter)?;
+        Commands::Replay {
+            id,
+            session,
+            speed,
+            instant,
+            max_idle,
+        } => {
+            let playback = replay::Playback {
+                speed,
+                instant,
+                max_idle,
+            };
+            replay::replay(&open_storage()?, &id, session, playback)?;
         }
         Commands::Export {
             output,
+            to,
             session,
             filter,
+            tag,
+            commits,
+        } => {
+            export::export_commands(&open_storage()?, output, to, session, filter, tag, commits)?;
+        }
+        Commands::GitLog { rev, repo, limit } => {
+            git::git_log(&open_storage()?, repo, &rev, limit, cli.format)?;
+        }
+        Commands::Import {
+            from,
+            db,
+            file,
+            timing,
+            prompt,
+        } => {
+            let required_file = || file.clone().context("Pass the file to import");
+            match from {
+                cli::ImportSource::Atuin => atuin::import_atuin(&open_storage()?, db)?,
+                cli::ImportSource::Typescript => typescript::import_typescript(
+                    &open_storage()?,
+                    &required_file()?,
+                    timing.as_deref(),
+                    prompt.as_deref(),
+                )?,
+                cli::ImportSource::Asciicast => asciicast::import_asciicast(
+                    &open_storage()?,
+                    &required_file()?,
+                    prompt.as_deref(),
+                )?,
+                cli::ImportSource::ShellHistory => {
+                    shell_history::import_shell_history(&open_storage()?, file)?
+                }
+            }
+        }
+        Commands::Metrics { output } => {
+            metrics::write_metrics(&open_storage()?, output)?;
+        }
+        Commands::Serve {
+            listen,
+            port,
+            token,
+            ui,
+            allow_record,
+        } => {
+            let listen = listen.or_else(|| port.map(|port| format!("127.0.0.1:{}", port)));
+            serve::serve(open_storage()?, listen, token, ui, allow_record)?;
+        }
+        Commands::Rpc => {
+            rpc::rpc(open_storage()?)?;
+        }
+        Commands::Daemon { stop, status } => {
+            let storage = open_storage()?;
+            if stop {
+                daemon::stop(&storage)?;
+            } else if status {
+                daemon::status(&storage)?;
+            } else {
+                daemon::run(storage)?;
+            }
+        }
+        Commands::Stats {
+            weeks,
+            by_session,
+            last,
+            by_host,
+            program,
+            compare,
+            storage,
+            typos,
+            filter,
+            since,
+            until,
         } => {
-            export::export_commands(output, session, filter)?;
+            stats::show_stats(
+                &open_storage()?,
+                stats::StatsOptions {
+                    weeks: weeks.into(),
+                    by_session,
+                    last,
+                    by_host,
+                    program,
+                    compare,
+                    storage,
+                    typos,
+                    filter,
+                    since,
+                    until,
+                },
+                format,
+            )?;
         }
-        Commands::Stats => {
-            stats::show_stats()?;
+        Commands::Summary { week } => {
+            summary::show_summary(&open_storage()?, week, format)?;
         }
         Commands::Clean {
             older_than_days,
             yes,
         } => {
-            clean::clean_commands(older_than_days, yes)?;
+            let older_than_days = match older_than_days {
+                Some(days) => days,
+                None => Config::load()?.storage.retention_days,
+            };
+            clean::clean_commands(&open_storage()?, older_than_days, yes)?;
+        }
+        Commands::Prune {
+            failed,
+            duplicates,
+            dir,
+            command_glob,
+            empty_output,
+            yes,
+        } => {
+            prune::prune_commands(
+                &open_storage()?,
+                prune::PruneOptions {
+                    failed,
+                    duplicates,
+                    dir,
+                    command_glob,
+                    empty_output,
+                },
+                yes,
+            )?;
+        }
+        Commands::Archive {
+            older_than,
+            output,
+            restore,
+            yes,
+        } => match (restore, older_than, output) {
+            (Some(input), _, _) => archive::restore_archive(&open_storage()?, &input)?,
+            (None, Some(older_than), Some(output)) => {
+                archive::archive_commands(&open_storage()?, &older_than, &output, yes)?
+            }
+            _ => unreachable!("clap requires --older-than and --output without --restore"),
+        },
+        Commands::Delete {
+            ids,
+            filter,
+            session,
+            yes,
+        } => {
+            delete::delete_commands(&open_storage()?, ids, filter, session, yes)?;
+        }
+        Commands::Purge {
+            keep_config,
+            shred,
+            yes,
+        } => {
+            purge::purge(&open_storage()?, keep_config, shred, yes)?;
         }
         Commands::Status => {
-            status::show_status()?;
+            status::show_status(&open_storage()?, format)?;
+        }
+        Commands::Grep {
+            pattern,
+            fixed_strings,
+            ignore_case,
+            after_context,
+            before_context,
+            context,
+            limit,
+        } => {
+            grep::grep_outputs(
+                &open_storage()?,
+                &pattern,
+                grep::GrepOptions {
+                    fixed_strings,
+                    ignore_case,
+                    after: after_context.or(context).unwrap_or(0),
+                    before: before_context.or(context).unwrap_or(0),
+                    limit,
+                },
+            )?;
+        }
+        Commands::Sync {
+            action,
+            target,
+            pull,
+            push,
+        } => match action {
+            None => sync::sync(&open_storage()?, sync::resolve_target(target)?, pull, push)?,
+            Some(SyncAction::Push { target }) => {
+                sync::encrypted::push(&open_storage()?, &sync::resolve_target(target)?)?
+            }
+            Some(SyncAction::Pull { target }) => {
+                sync::encrypted::pull(&open_storage()?, &sync::resolve_target(target)?)?
+            }
+            Some(SyncAction::Status { target }) => {
+                sync::encrypted::status(&open_storage()?, &sync::resolve_target(target)?)?
+            }
+            Some(SyncAction::Key { set }) => sync::encrypted::key(&open_storage()?, set)?,
+        },
+        Commands::Query { query, compact } => {
+            query::run_query(&open_storage()?, &query, compact)?;
+        }
+        Commands::Fetch {
+            hosts,
+            since,
+            remote_bin,
+        } => {
+            fetch::fetch(&open_storage()?, &hosts, since, &remote_bin)?;
+        }
+        Commands::Dump { since } => {
+            fetch::dump(&open_storage()?, since)?;
+        }
+        Commands::Timeline { date, gap_minutes } => {
+            timeline::show_timeline(&open_storage()?, date, gap_minutes)?;
+        }
+        Commands::Top { recent } => {
+            top::run_top(&open_storage()?, recent)?;
+        }
+        Commands::Migrate { to } => {
+            let to = match to {
+                cli::Backend::Jsonl => Backend::Jsonl,
+                cli::Backend::Sqlite => Backend::Sqlite,
+            };
+            migrate::migrate(&open_storage()?, to, data_dir.is_none())?;
+        }
+        Commands::Doctor { fix } => {
+            doctor::run_doctor(&open_storage()?, fix)?;
+        }
+        Commands::Config { action } => {
+            config::config_command(action)?;
         }
     }
 
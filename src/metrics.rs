@@ -0,0 +1,219 @@
+use anyhow::{Context, Result};
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+/// Upper bounds in seconds of the command duration histogram buckets
+const DURATION_BUCKETS: [f64; 8] = [0.1, 0.5, 1.0, 5.0, 10.0, 60.0, 300.0, 600.0];
+
+/// Print the metrics, or write them to a file for node_exporter's textfile collector
+///
+/// The file is replaced atomically so the collector never reads half of it.
+pub fn write_metrics(storage: &Storage, output: Option<PathBuf>) -> Result<()> {
+    let metrics = metrics(storage)?;
+
+    match output {
+        Some(path) => {
+            let tmp = path.with_extension("prom.tmp");
+            fs::write(&tmp, metrics)
+                .with_context(|| format!("Failed to write to: {}", tmp.display()))?;
+            fs::rename(&tmp, &path)
+                .with_context(|| format!("Failed to write to: {}", path.display()))?;
+        }
+        None => print!("{}", metrics),
+    }
+
+    Ok(())
+}
+
+/// Metrics about the whole history in the Prometheus text format
+pub fn metrics(storage: &Storage) -> Result<String> {
+    let commands = storage.read_all_commands()?;
+    let sessions = storage.read_all_sessions()?.len();
+
+    let mut files = Vec::new();
+    for path in [storage.history_file(), storage.sessions_file().clone()] {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let bytes = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        files.push((name.into_owned(), bytes));
+    }
+
+    Ok(render(&commands, sessions, &files))
+}
+
+/// Render the metrics for a set of commands
+fn render(commands: &[Command], sessions: usize, files: &[(String, u64)]) -> String {
+    let mut per_host: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for cmd in commands {
+        let (total, failed) = per_host.entry(&cmd.hostname).or_default();
+        *total += 1;
+        if cmd.exit_code != 0 {
+            *failed += 1;
+        }
+    }
+
+    let mut out = String::new();
+
+    header(
+        &mut out,
+        "shelltape_commands_total",
+        "counter",
+        "Commands recorded.",
+    );
+    for (host, (total, _)) in &per_host {
+        let _ = writeln!(
+            out,
+            "shelltape_commands_total{{host=\"{}\"}} {}",
+            escape(host),
+            total
+        );
+    }
+
+    header(
+        &mut out,
+        "shelltape_command_failures_total",
+        "counter",
+        "Commands that exited with a non-zero code.",
+    );
+    for (host, (_, failed)) in &per_host {
+        let _ = writeln!(
+            out,
+            "shelltape_command_failures_total{{host=\"{}\"}} {}",
+            escape(host),
+            failed
+        );
+    }
+
+    header(
+        &mut out,
+        "shelltape_sessions_total",
+        "counter",
+        "Shell sessions recorded.",
+    );
+    let _ = writeln!(out, "shelltape_sessions_total {}", sessions);
+
+    header(
+        &mut out,
+        "shelltape_command_duration_seconds",
+        "histogram",
+        "How long commands took.",
+    );
+    let durations: Vec<f64> = commands
+        .iter()
+        .map(|c| c.duration_ms as f64 / 1000.0)
+        .collect();
+    for bound in DURATION_BUCKETS {
+        let count = durations.iter().filter(|&&d| d <= bound).count();
+        let _ = writeln!(
+            out,
+            "shelltape_command_duration_seconds_bucket{{le=\"{}\"}} {}",
+            bound, count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "shelltape_command_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        durations.len()
+    );
+    let _ = writeln!(
+        out,
+        "shelltape_command_duration_seconds_sum {}",
+        durations.iter().sum::<f64>()
+    );
+    let _ = writeln!(
+        out,
+        "shelltape_command_duration_seconds_count {}",
+        durations.len()
+    );
+
+    header(
+        &mut out,
+        "shelltape_storage_bytes",
+        "gauge",
+        "Size of the data files.",
+    );
+    for (file, bytes) in files {
+        let _ = writeln!(
+            out,
+            "shelltape_storage_bytes{{file=\"{}\"}} {}",
+            escape(file),
+            bytes
+        );
+    }
+
+    if let Some(last) = commands.iter().map(|c| c.started_at).max() {
+        header(
+            &mut out,
+            "shelltape_last_command_timestamp_seconds",
+            "gauge",
+            "When the most recent command started.",
+        );
+        let _ = writeln!(
+            out,
+            "shelltape_last_command_timestamp_seconds {}",
+            last.timestamp()
+        );
+    }
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn cmd(hostname: &str, exit_code: i32, duration_ms: u64) -> Command {
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: "make".to_string(),
+            exit_code,
+            cwd: "/tmp".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap(),
+            duration_ms,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: hostname.to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let commands = vec![
+            cmd("laptop", 0, 50),
+            cmd("laptop", 1, 2_000),
+            cmd("ci \"1\"", 0, 700_000),
+        ];
+        let metrics = render(&commands, 2, &[("commands.jsonl".to_string(), 512)]);
+        let lines: Vec<&str> = metrics.lines().collect();
+
+        assert!(lines.contains(&"shelltape_commands_total{host=\"laptop\"} 2"));
+        assert!(lines.contains(&"shelltape_commands_total{host=\"ci \\\"1\\\"\"} 1"));
+        assert!(lines.contains(&"shelltape_command_failures_total{host=\"laptop\"} 1"));
+        assert!(lines.contains(&"shelltape_sessions_total 2"));
+        assert!(lines.contains(&"shelltape_command_duration_seconds_bucket{le=\"0.1\"} 1"));
+        assert!(lines.contains(&"shelltape_command_duration_seconds_bucket{le=\"600\"} 2"));
+        assert!(lines.contains(&"shelltape_command_duration_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(lines.contains(&"shelltape_command_duration_seconds_sum 702.05"));
+        assert!(lines.contains(&"shelltape_storage_bytes{file=\"commands.jsonl\"} 512"));
+        assert!(lines.contains(&"shelltape_last_command_timestamp_seconds 1792227600"));
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use shelltape_core::config::{Backend, Config};
+use shelltape_core::storage::Storage;
+
+/// Copy every command into the `to` backend, then make it the configured one
+///
+/// The old file is left in place. With a `--data-dir` override the config is
+/// not touched, since it may describe another data directory.
+pub fn migrate(storage: &Storage, to: Backend, update_config: bool) -> Result<()> {
+    if storage.backend() == to {
+        println!("Already using the {} backend", name(to));
+        return Ok(());
+    }
+
+    let target = Storage::open(storage.data_dir().clone(), to)?;
+    let commands = storage.read_all_commands()?;
+    let total = commands.len();
+    let added = target.merge_commands(commands)?;
+    println!(
+        "✓ Copied {} commands into {} ({} were already there)",
+        added,
+        target.history_file().display(),
+        total - added
+    );
+
+    if update_config {
+        let path = Config::path()?;
+        let mut config = Config::load_from(&path)?;
+        config.storage.backend = to;
+        config.save_to(&path)?;
+        println!("✓ Set storage.backend = {}", name(to));
+    } else {
+        println!(
+            "Run `shelltape config set storage.backend {}` to use it",
+            name(to)
+        );
+    }
+    println!(
+        "{} was left in place; remove it once you are happy with the result",
+        storage.history_file().display()
+    );
+    Ok(())
+}
+
+fn name(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Jsonl => "jsonl",
+        Backend::Sqlite => "sqlite",
+    }
+}
@@ -0,0 +1,44 @@
+use anyhow::{Result, bail};
+use shelltape_core::storage::Storage;
+
+/// Attach, append to, or clear the note on a recorded command
+pub fn note_command(
+    storage: &Storage,
+    id: Option<String>,
+    last: bool,
+    text: Option<String>,
+    replace: bool,
+    clear: bool,
+) -> Result<()> {
+    // With --last, the only positional argument is the note text
+    let (id, text) = if last {
+        (None, id.or(text))
+    } else {
+        (id, text)
+    };
+    let target = storage.find_command_or_last(id.as_deref())?;
+
+    if clear {
+        storage.update_command(&target.id, |cmd| cmd.note = None)?;
+        println!("✓ Cleared note on `{}`", target.command);
+        return Ok(());
+    }
+
+    let Some(text) = text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty()) else {
+        bail!("No note text given");
+    };
+
+    let cmd = storage.update_command(&target.id, |cmd| {
+        cmd.note = match cmd.note.take() {
+            Some(existing) if !replace => Some(format!("{}\n{}", existing, text)),
+            _ => Some(text),
+        };
+    })?;
+
+    println!("✓ Note on `{}`:", cmd.command);
+    for line in cmd.note.unwrap_or_default().lines() {
+        println!("  {}", line);
+    }
+
+    Ok(())
+}
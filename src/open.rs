@@ -0,0 +1,129 @@
+use crate::ansi;
+use anyhow::{Context, Result, bail};
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::path::Path;
+
+/// Open the data directory in the file manager, or a record in an editor/pager
+pub fn open(storage: &Storage, id: Option<String>, pager: bool) -> Result<()> {
+    let Some(id) = id else {
+        let dir = storage.data_dir();
+        open_in_file_manager(dir)?;
+        println!("Opened {}", dir.display());
+        return Ok(());
+    };
+
+    let cmd = storage.find_command(&id)?;
+    let path = std::env::temp_dir().join(format!(
+        "shelltape-{}.txt",
+        cmd.id.get(..8).unwrap_or(&cmd.id)
+    ));
+    std::fs::write(&path, format_record(&cmd))
+        .with_context(|| format!("Failed to write temp file: {}", path.display()))?;
+
+    let program = if pager {
+        std::env::var("PAGER").unwrap_or_else(|_| "less".to_string())
+    } else {
+        std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| {
+                if cfg!(target_os = "windows") {
+                    "notepad".to_string()
+                } else {
+                    "vi".to_string()
+                }
+            })
+    };
+
+    let result = run_program(&program, &path);
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Render a record as plain text for manual inspection
+fn format_record(cmd: &Command) -> String {
+    let mut text = format!(
+        "Command:   {}\n\
+         ID:        {}\n\
+         Started:   {}\n\
+         Duration:  {}ms\n\
+         Exit code: {}\n\
+         Directory: {}\n\
+         Session:   {}\n\
+         Host:      {}@{}\n\
+         Shell:     {}\n",
+        cmd.command,
+        cmd.id,
+        cmd.started_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        cmd.duration_ms,
+        cmd.exit_code,
+        cmd.cwd,
+        cmd.session_id,
+        cmd.username,
+        cmd.hostname,
+        cmd.shell,
+    );
+
+    if !cmd.tags.is_empty() {
+        text.push_str(&format!("Tags:      {}\n", cmd.tags.join(", ")));
+    }
+    if let Some(note) = &cmd.note {
+        text.push_str("\n─── Note ───\n\n");
+        text.push_str(note);
+        text.push('\n');
+    }
+    text.push_str("\n─── Output ───\n\n");
+
+    for line in cmd.output.lines() {
+        text.push_str(&ansi::strip_ansi(line));
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Launch an editor or pager command (which may include arguments) on a file
+fn run_program(program: &str, path: &Path) -> Result<()> {
+    let mut parts = program.split_whitespace();
+    let Some(name) = parts.next() else {
+        bail!("No editor or pager configured");
+    };
+
+    let status = std::process::Command::new(name)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to launch: {}", program))?;
+
+    if !status.success() {
+        bail!("{} exited with status: {}", name, status);
+    }
+
+    Ok(())
+}
+
+/// Open a directory with the platform's file manager
+fn open_in_file_manager(dir: &Path) -> Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+
+    std::process::Command::new(opener)
+        .arg(dir)
+        .spawn()
+        .with_context(|| {
+            format!(
+                "Failed to launch {} (data directory: {})",
+                opener,
+                dir.display()
+            )
+        })?;
+
+    Ok(())
+}
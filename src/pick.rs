@@ -0,0 +1,311 @@
+use crate::fuzzy;
+use anyhow::{Context, Result};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::io;
+
+/// Options for the `pick` command
+pub struct PickOptions {
+    /// Initial search query
+    pub query: String,
+    /// Only offer commands run in the current directory
+    pub here: bool,
+    /// Only offer commands that exited successfully
+    pub success: bool,
+    /// Print the ranked matches instead of opening the selector
+    pub filter: bool,
+}
+
+/// Let the user pick a historic command and print its text to stdout
+///
+/// The selector draws on stderr so the result can be captured with `$(...)`.
+/// Returns `false` if nothing was picked.
+pub fn pick_command(storage: &Storage, opts: PickOptions) -> Result<bool> {
+    let candidates = candidates(storage, &opts)?;
+
+    if opts.filter {
+        let matches = rank(&candidates, &opts.query);
+        for &idx in &matches {
+            println!("{}", candidates[idx].command);
+        }
+        return Ok(!matches.is_empty());
+    }
+
+    match run_selector(candidates, opts.query)? {
+        Some(cmd) => {
+            println!("{}", cmd.command);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Let the user pick a historic command in the selector
+///
+/// Each command line is offered once, as its latest run. Returns `None` if
+/// the user cancelled.
+pub fn select_command(storage: &Storage) -> Result<Option<Command>> {
+    let opts = PickOptions {
+        query: String::new(),
+        here: false,
+        success: false,
+        filter: false,
+    };
+    run_selector(candidates(storage, &opts)?, opts.query)
+}
+
+/// Unique command lines, most recently run first
+fn candidates(storage: &Storage, opts: &PickOptions) -> Result<Vec<Command>> {
+    let cwd = std::env::current_dir()?.to_string_lossy().to_string();
+
+    let mut commands = storage.read_all_commands()?;
+    commands.sort_by_key(|c| std::cmp::Reverse(c.started_at));
+
+    let mut seen = std::collections::HashSet::new();
+    Ok(commands
+        .into_iter()
+        .filter(|cmd| !opts.here || cmd.cwd == cwd)
+        .filter(|cmd| !opts.success || cmd.exit_code == 0)
+        .filter(|cmd| !cmd.command.trim().is_empty())
+        .filter(|cmd| seen.insert(cmd.command.clone()))
+        .collect())
+}
+
+/// Indices of the commands matching `query`, best match first
+///
+/// Equal scores keep their original (most recent first) order.
+fn rank(commands: &[Command], query: &str) -> Vec<usize> {
+    let mut scored: Vec<(usize, i64)> = commands
+        .iter()
+        .enumerate()
+        .filter_map(|(i, cmd)| fuzzy::score(query, &cmd.command).map(|s| (i, s)))
+        .collect();
+    scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    scored.into_iter().map(|(i, _)| i).collect()
+}
+
+/// State of the interactive selector
+struct Picker {
+    commands: Vec<Command>,
+    query: String,
+    matches: Vec<usize>,
+    list: ListState,
+}
+
+impl Picker {
+    fn new(commands: Vec<Command>, query: String) -> Self {
+        let mut picker = Self {
+            commands,
+            query,
+            matches: Vec::new(),
+            list: ListState::default(),
+        };
+        picker.refresh();
+        picker
+    }
+
+    fn refresh(&mut self) {
+        self.matches = rank(&self.commands, &self.query);
+        self.list.select(if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if let Some(selected) = self.list.selected() {
+            let last = self.matches.len().saturating_sub(1);
+            self.list
+                .select(Some(selected.saturating_add_signed(delta).min(last)));
+        }
+    }
+
+    fn selected(&self) -> Option<&Command> {
+        self.list
+            .selected()
+            .and_then(|i| self.matches.get(i))
+            .map(|&idx| &self.commands[idx])
+    }
+}
+
+/// What a key press asks the selector to do
+enum Action {
+    Continue,
+    Accept,
+    Cancel,
+}
+
+/// Run the full-screen selector; `None` if the user cancelled
+fn run_selector(commands: Vec<Command>, query: String) -> Result<Option<Command>> {
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    let mut stderr = io::stderr();
+    execute!(stderr, EnterAlternateScreen).context("Failed to enter alternate screen")?;
+    let mut terminal =
+        Terminal::new(CrosstermBackend::new(stderr)).context("Failed to create terminal")?;
+
+    let mut picker = Picker::new(commands, query);
+    let result = selector_loop(&mut terminal, &mut picker);
+
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)
+        .context("Failed to leave alternate screen")?;
+    terminal.show_cursor().context("Failed to show cursor")?;
+
+    Ok(match result? {
+        Action::Accept => picker.selected().cloned(),
+        _ => None,
+    })
+}
+
+fn selector_loop<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    picker: &mut Picker,
+) -> Result<Action> {
+    loop {
+        terminal.draw(|f| draw(f, picker))?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match handle_key(picker, key) {
+                Action::Continue => {}
+                action => return Ok(action),
+            }
+        }
+    }
+}
+
+fn handle_key(picker: &mut Picker, key: KeyEvent) -> Action {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+    match key.code {
+        KeyCode::Enter => return Action::Accept,
+        KeyCode::Esc => return Action::Cancel,
+        KeyCode::Char('c' | 'g' | 'd') if ctrl => return Action::Cancel,
+        KeyCode::Up => picker.move_by(-1),
+        KeyCode::Char('p' | 'k') if ctrl => picker.move_by(-1),
+        KeyCode::Down | KeyCode::Tab => picker.move_by(1),
+        KeyCode::Char('n' | 'j') if ctrl => picker.move_by(1),
+        KeyCode::PageUp => picker.move_by(-10),
+        KeyCode::PageDown => picker.move_by(10),
+        KeyCode::Char('u') if ctrl => {
+            picker.query.clear();
+            picker.refresh();
+        }
+        KeyCode::Char('w') if ctrl => {
+            let trimmed = picker.query.trim_end();
+            let keep = trimmed.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+            picker.query.truncate(keep);
+            picker.refresh();
+        }
+        KeyCode::Backspace => {
+            picker.query.pop();
+            picker.refresh();
+        }
+        KeyCode::Char(c) if !ctrl => {
+            picker.query.push(c);
+            picker.refresh();
+        }
+        _ => {}
+    }
+
+    Action::Continue
+}
+
+fn draw(f: &mut Frame, picker: &mut Picker) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Prompt
+            Constraint::Min(0),    // Matches
+        ])
+        .split(f.area());
+
+    let prompt = Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan)),
+        Span::raw(&picker.query),
+        Span::styled(
+            format!("  {}/{}", picker.matches.len(), picker.commands.len()),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]);
+    f.render_widget(Paragraph::new(prompt), chunks[0]);
+    f.set_cursor_position((
+        chunks[0].x + 2 + picker.query.chars().count() as u16,
+        chunks[0].y,
+    ));
+
+    let items: Vec<ListItem> = picker
+        .matches
+        .iter()
+        .map(|&idx| {
+            let cmd = &picker.commands[idx];
+            let status = if cmd.exit_code == 0 {
+                Span::styled("  ", Style::default())
+            } else {
+                Span::styled("✗ ", Style::default().fg(Color::Red))
+            };
+            ListItem::new(Line::from(vec![
+                status,
+                Span::raw(cmd.command.replace('\n', " ⏎ ")),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).highlight_style(
+        Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+    );
+    f.render_stateful_widget(list, chunks[1], &mut picker.list);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn command(text: &str) -> Command {
+        Command {
+            id: text.to_string(),
+            command: text.to_string(),
+            exit_code: 0,
+            cwd: "/tmp".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 0,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rank() {
+        let commands = vec![
+            command("cargo test"),
+            command("git status"),
+            command("cargo build --release"),
+        ];
+
+        assert_eq!(rank(&commands, ""), vec![0, 1, 2]);
+        assert_eq!(rank(&commands, "cargo"), vec![0, 2]);
+        assert_eq!(rank(&commands, "build"), vec![2]);
+        assert!(rank(&commands, "docker").is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use chrono::Local;
+use shelltape_core::models::format_duration;
+use shelltape_core::storage::Storage;
+
+/// Print a command and its captured output, for fzf's preview window
+///
+/// The output is printed as captured, colors included; use `fzf --ansi`
+/// (or `--preview-window` with `--ansi`) to see them.
+pub fn preview(storage: &Storage, id: &str) -> Result<()> {
+    let cmd = storage.find_command(id)?;
+
+    println!("$ {}", cmd.command.trim_end());
+    println!(
+        "{}  {}  {}  {}",
+        if cmd.exit_code == 0 {
+            "✓".to_string()
+        } else {
+            format!("✗ {}", cmd.exit_code)
+        },
+        format_duration(cmd.duration_ms),
+        cmd.started_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        cmd.cwd
+    );
+    if let Some(note) = &cmd.note {
+        println!("note: {}", note);
+    }
+    if let Some(original) = &cmd.rerun_of {
+        println!("rerun of: {}", original);
+    }
+    println!();
+
+    if cmd.output.is_empty() {
+        println!("(no output captured)");
+    } else {
+        print!("{}", cmd.output);
+        if !cmd.output.ends_with('\n') {
+            println!();
+        }
+    }
+
+    Ok(())
+}
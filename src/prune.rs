@@ -0,0 +1,208 @@
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Which commands `prune` should remove
+pub struct PruneOptions {
+    /// Remove commands with a non-zero exit code
+    pub failed: bool,
+    /// Remove repeated commands, keeping the most recent run of each
+    pub duplicates: bool,
+    /// Remove commands run in this directory or below it
+    pub dir: Option<String>,
+    /// Remove commands matching this glob (`*` and `?` wildcards)
+    pub command_glob: Option<String>,
+    /// Remove commands that produced no output
+    pub empty_output: bool,
+}
+
+/// Remove commands selected by targeted criteria
+pub fn prune_commands(storage: &Storage, opts: PruneOptions, yes: bool) -> Result<()> {
+    if !opts.failed
+        && !opts.duplicates
+        && opts.dir.is_none()
+        && opts.command_glob.is_none()
+        && !opts.empty_output
+    {
+        bail!(
+            "Nothing to prune. Use one or more of --failed, --duplicates, --dir, --command-glob, --empty-output"
+        );
+    }
+
+    let commands = storage.read_all_commands()?;
+
+    let glob = opts
+        .command_glob
+        .as_deref()
+        .map(glob_to_regex)
+        .transpose()?;
+
+    // Collect IDs per mode so we can report counts for each
+    let mut modes: Vec<(String, HashSet<String>)> = Vec::new();
+
+    if opts.failed {
+        modes.push((
+            "failed".to_string(),
+            select(&commands, |cmd| cmd.exit_code != 0),
+        ));
+    }
+    if opts.duplicates {
+        modes.push(("duplicates".to_string(), duplicate_ids(&commands)));
+    }
+    if let Some(dir) = &opts.dir {
+        let dir = Path::new(dir);
+        modes.push((
+            format!("in {}", dir.display()),
+            select(&commands, |cmd| Path::new(&cmd.cwd).starts_with(dir)),
+        ));
+    }
+    if let (Some(pattern), Some(glob)) = (&opts.command_glob, &glob) {
+        modes.push((
+            format!("matching '{}'", pattern),
+            select(&commands, |cmd| glob.is_match(&cmd.command)),
+        ));
+    }
+    if opts.empty_output {
+        modes.push((
+            "empty output".to_string(),
+            select(&commands, |cmd| cmd.output.trim().is_empty()),
+        ));
+    }
+
+    let remove: HashSet<String> = modes
+        .iter()
+        .flat_map(|(_, ids)| ids.iter().cloned())
+        .collect();
+
+    if remove.is_empty() {
+        println!("No commands matched");
+        return Ok(());
+    }
+
+    println!("Commands selected for removal:");
+    for (label, ids) in &modes {
+        println!("  • {:<20} {}", label, ids.len());
+    }
+    println!(
+        "⚠️  This will remove {} out of {} commands",
+        remove.len(),
+        commands.len()
+    );
+
+    // Ask for confirmation unless --yes flag is set
+    if !yes {
+        print!("Continue? [y/N] ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    let removed = storage.retain_commands(|cmd| !remove.contains(&cmd.id))?;
+
+    println!("✓ Removed {} commands", removed);
+    println!("  Remaining: {} commands", commands.len() - removed);
+
+    Ok(())
+}
+
+/// Collect the IDs of commands matching a predicate
+fn select(commands: &[Command], pred: impl Fn(&Command) -> bool) -> HashSet<String> {
+    commands
+        .iter()
+        .filter(|cmd| pred(cmd))
+        .map(|cmd| cmd.id.clone())
+        .collect()
+}
+
+/// Find IDs of repeated commands, keeping the most recent run of each
+fn duplicate_ids(commands: &[Command]) -> HashSet<String> {
+    let mut latest: HashMap<&str, &Command> = HashMap::new();
+    for cmd in commands {
+        latest
+            .entry(cmd.command.as_str())
+            .and_modify(|kept| {
+                if cmd.started_at > kept.started_at {
+                    *kept = cmd;
+                }
+            })
+            .or_insert(cmd);
+    }
+
+    commands
+        .iter()
+        .filter(|cmd| latest[cmd.command.as_str()].id != cmd.id)
+        .map(|cmd| cmd.id.clone())
+        .collect()
+}
+
+/// Convert a shell-style glob into an anchored regex
+fn glob_to_regex(glob: &str) -> Result<Regex> {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+
+    Regex::new(&pattern).with_context(|| format!("Invalid glob: {}", glob))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn make(id: &str, command: &str, age_secs: i64) -> Command {
+        Command {
+            id: id.to_string(),
+            command: command.to_string(),
+            exit_code: 0,
+            cwd: "/tmp".to_string(),
+            started_at: Utc::now() - Duration::seconds(age_secs),
+            duration_ms: 1,
+            session_id: "session-1".to_string(),
+            shell: "bash".to_string(),
+            hostname: "localhost".to_string(),
+            username: "testuser".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_glob_to_regex() {
+        let glob = glob_to_regex("ls*").unwrap();
+        assert!(glob.is_match("ls"));
+        assert!(glob.is_match("ls -la"));
+        assert!(!glob.is_match("cat ls"));
+
+        let glob = glob_to_regex("git ?ull").unwrap();
+        assert!(glob.is_match("git pull"));
+        assert!(!glob.is_match("git pull --rebase"));
+    }
+
+    #[test]
+    fn test_duplicates_keep_latest() {
+        let commands = vec![
+            make("1", "ls", 30),
+            make("2", "pwd", 20),
+            make("3", "ls", 10),
+            make("4", "ls", 20),
+        ];
+
+        let ids = duplicate_ids(&commands);
+        assert_eq!(ids, HashSet::from(["1".to_string(), "4".to_string()]));
+    }
+}
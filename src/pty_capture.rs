@@ -1,9 +1,16 @@
 use anyhow::{Context, Result};
 use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
 use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Output chunks arriving closer together than this are merged in the timing data
+const TIMING_RESOLUTION_MS: u64 = 10;
+
+/// How often stdin forwarding checks whether the command has exited
+const STDIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Result of command execution with captured output
 pub struct ExecutionResult {
@@ -11,10 +18,25 @@ pub struct ExecutionResult {
     pub exit_code: i32,
     pub start_time: i64,
     pub end_time: i64,
+    /// Output timing as `(milliseconds since start, bytes)` chunks
+    pub timing: Vec<(u64, usize)>,
 }
 
 /// Execute a command in a PTY and capture its output
 pub fn execute_with_capture(command: &str, cwd: &str) -> Result<ExecutionResult> {
+    let (program, args) = parse_command(command);
+    execute_program(&program, &args, cwd)
+}
+
+/// Execute a command line with `shell -c` in a PTY and capture its output
+///
+/// Unlike [`execute_with_capture`], quoting, pipes and other shell syntax in
+/// the line work as they did when it was typed.
+pub fn execute_in_shell(shell: &str, command: &str, cwd: &str) -> Result<ExecutionResult> {
+    execute_program(shell, &["-c".to_string(), command.to_string()], cwd)
+}
+
+fn execute_program(program: &str, args: &[String], cwd: &str) -> Result<ExecutionResult> {
     let start_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .context("Failed to get start time")?
@@ -39,12 +61,11 @@ pub fn execute_with_capture(command: &str, cwd: &str) -> Result<ExecutionResult>
         })
         .context("Failed to open PTY")?;
 
-    // Parse the command into program and args
-    let (program, args) = parse_command(command);
+    tracing::debug!(program = %program, ?args, rows, cols, "Spawning in PTY");
 
     // Build the command
-    let mut cmd = CommandBuilder::new(&program);
-    cmd.args(&args);
+    let mut cmd = CommandBuilder::new(program);
+    cmd.args(args);
     cmd.cwd(cwd);
 
     // Spawn the command in the PTY
@@ -63,6 +84,9 @@ pub fn execute_with_capture(command: &str, cwd: &str) -> Result<ExecutionResult>
         .context("Failed to clone reader")?;
     let output = Arc::new(Mutex::new(Vec::new()));
     let output_clone = Arc::clone(&output);
+    let timing = Arc::new(Mutex::new(Vec::<(u64, usize)>::new()));
+    let timing_clone = Arc::clone(&timing);
+    let started = Instant::now();
 
     // Spawn thread to read output and display it in real-time
     let read_thread = thread::spawn(move || {
@@ -80,6 +104,17 @@ pub fn execute_with_capture(command: &str, cwd: &str) -> Result<ExecutionResult>
                     if let Ok(mut out) = output_clone.lock() {
                         out.extend_from_slice(&buffer[..n]);
                     }
+
+                    // Record when this chunk arrived, merging bursts
+                    if let Ok(mut chunks) = timing_clone.lock() {
+                        let at = started.elapsed().as_millis() as u64;
+                        match chunks.last_mut() {
+                            Some((last_at, len)) if at - *last_at < TIMING_RESOLUTION_MS => {
+                                *len += n;
+                            }
+                            _ => chunks.push((at, n)),
+                        }
+                    }
                 }
                 Err(_) => break,
             }
@@ -90,31 +125,47 @@ pub fn execute_with_capture(command: &str, cwd: &str) -> Result<ExecutionResult>
     let mut writer = pair.master.take_writer().context("Failed to get writer")?;
 
     // Spawn thread to forward stdin to PTY (for interactive commands)
-    // This thread will be orphaned when the child exits - that's OK since
-    // stdin.read() is blocking and we can't easily interrupt it.
-    // The thread will exit when the process ends.
-    thread::spawn(move || {
-        let mut stdin = std::io::stdin();
-        let mut buffer = [0u8; 8192];
-        loop {
-            match stdin.read(&mut buffer) {
-                Ok(0) => break,
-                Ok(n) => {
-                    // If write fails, PTY is closed, so exit
-                    if writer.write_all(&buffer[..n]).is_err() {
-                        break;
-                    }
-                    if writer.flush().is_err() {
-                        break;
+    // It only reads once input is waiting, so it can stop when the child
+    // exits instead of swallowing the next keypress meant for us (the TUI
+    // keeps running after a rerun).
+    let exited = Arc::new(AtomicBool::new(false));
+    let stdin_thread = thread::spawn({
+        let exited = Arc::clone(&exited);
+        move || {
+            let mut stdin = std::io::stdin();
+            let mut buffer = [0u8; 8192];
+            loop {
+                let ready = stdin_ready(STDIN_POLL_INTERVAL);
+                if exited.load(Ordering::Relaxed) {
+                    break;
+                }
+                if !ready {
+                    continue;
+                }
+                match stdin.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        // If write fails, PTY is closed, so exit
+                        if writer.write_all(&buffer[..n]).is_err() {
+                            break;
+                        }
+                        if writer.flush().is_err() {
+                            break;
+                        }
                     }
+                    Err(_) => break,
                 }
-                Err(_) => break,
             }
         }
     });
 
     // Wait for child to exit
     let exit_status = child.wait().context("Failed to wait for child")?;
+    exited.store(true, Ordering::Relaxed);
+    // Blocked in a read on Windows, where input cannot be polled
+    if cfg!(unix) {
+        let _ = stdin_thread.join();
+    }
 
     // Close the master PTY to signal EOF to the read thread
     drop(pair.master);
@@ -133,11 +184,6 @@ pub fn execute_with_capture(command: &str, cwd: &str) -> Result<ExecutionResult>
     // If thread is still running, that's OK - we have the output we need
     // The thread will be terminated when the process exits
 
-    // Note: We don't wait for the stdin thread because stdin.read() is blocking.
-    // When the child exits and we drop the writer, the stdin thread will detect
-    // the error on its next write attempt and exit. If it's blocked on read,
-    // it will be cleaned up when the process exits.
-
     let end_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .context("Failed to get end time")?
@@ -150,11 +196,14 @@ pub fn execute_with_capture(command: &str, cwd: &str) -> Result<ExecutionResult>
     // Get exit code
     let exit_code = exit_status.exit_code() as i32;
 
+    let timing = timing.lock().unwrap().clone();
+
     Ok(ExecutionResult {
         output: output_string,
         exit_code,
         start_time,
         end_time,
+        timing,
     })
 }
 
@@ -199,6 +248,26 @@ fn parse_command(command: &str) -> (String, Vec<String>) {
     (program, args)
 }
 
+/// Wait up to `timeout` for input on stdin; errors count as ready so the
+/// read reports them
+#[cfg(unix)]
+fn stdin_ready(timeout: Duration) -> bool {
+    let mut fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: `fd` is a single valid pollfd for the duration of the call
+    let ready = unsafe { libc::poll(&mut fd, 1, timeout.as_millis() as libc::c_int) };
+    ready != 0
+}
+
+/// Windows consoles cannot be polled like this; reads simply block
+#[cfg(not(unix))]
+fn stdin_ready(_timeout: Duration) -> bool {
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
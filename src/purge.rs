@@ -0,0 +1,191 @@
+use crate::cli::Shell;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use shelltape_core::config::Config;
+use shelltape_core::storage::Storage;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Word the user has to type to confirm a purge
+const CONFIRM_WORD: &str = "purge";
+
+/// Delete all recorded data, optionally keeping the config file
+pub fn purge(storage: &Storage, keep_config: bool, shred: bool, yes: bool) -> Result<()> {
+    let config_path = Config::path()?;
+
+    let mut targets = data_files(storage.data_dir(), &config_path)?;
+    if !keep_config && config_path.exists() {
+        targets.push(config_path);
+    }
+
+    if targets.is_empty() {
+        println!("Nothing to purge");
+        return Ok(());
+    }
+
+    println!("⚠️  This will permanently delete:");
+    for path in &targets {
+        println!("  • {}", path.display());
+    }
+    if shred {
+        println!("  (files are overwritten with zeros before removal)");
+    }
+
+    if !yes {
+        print!("Type '{}' to continue: ", CONFIRM_WORD);
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        if input.trim() != CONFIRM_WORD {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    for path in &targets {
+        remove_path(path, shred)?;
+    }
+
+    println!("✓ Purged {} items", targets.len());
+    if keep_config {
+        println!("  Config kept: {}", Config::path()?.display());
+    }
+    println!("  Shell hooks are still installed; run `shelltape uninstall` to remove them");
+
+    Ok(())
+}
+
+/// Entries of the data directory that hold recorded data
+///
+/// Hook scripts and the config file are left out so a purge doesn't break
+/// the running shell integration.
+fn data_files(data_dir: &Path, config_path: &Path) -> Result<Vec<PathBuf>> {
+    if !data_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let hook_files: Vec<&str> = Shell::value_variants()
+        .iter()
+        .flat_map(|shell| std::iter::once(shell.hook_file()).chain(shell.widget_file()))
+        .collect();
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(data_dir)
+        .with_context(|| format!("Failed to read data directory: {}", data_dir.display()))?
+    {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+        if hook_files.contains(&name) || path == config_path {
+            continue;
+        }
+        files.push(path);
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Remove a file or directory tree, shredding files first if requested
+fn remove_path(path: &Path, shred: bool) -> Result<()> {
+    if path.is_dir() {
+        if shred {
+            for entry in fs::read_dir(path)? {
+                remove_path(&entry?.path(), shred)?;
+            }
+        }
+        fs::remove_dir_all(path)
+            .with_context(|| format!("Failed to remove: {}", path.display()))?;
+        return Ok(());
+    }
+
+    let path = if shred {
+        // Moved out of the way first, so nothing opens the history while it
+        // is being zeroed; readers that mapped it earlier keep a file of the
+        // same length
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let doomed = path.with_file_name(format!(".{}.shred", name));
+        fs::rename(path, &doomed)
+            .with_context(|| format!("Failed to move aside for shredding: {}", path.display()))?;
+        shred_file(&doomed)?;
+        doomed
+    } else {
+        path.to_path_buf()
+    };
+    fs::remove_file(&path).with_context(|| format!("Failed to remove: {}", path.display()))
+}
+
+/// Overwrite a file's contents with zeros and sync it to disk
+fn shred_file(path: &Path) -> Result<()> {
+    let len = fs::metadata(path)?.len();
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open for shredding: {}", path.display()))?;
+
+    let zeros = [0u8; 8192];
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..n])?;
+        remaining -= n as u64;
+    }
+    file.sync_all()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_remove_path_shreds_a_moved_copy() {
+        let dir = tempdir().unwrap();
+        let history = dir.path().join("commands.jsonl");
+        fs::write(&history, "secret\n").unwrap();
+        let reader = fs::File::open(&history).unwrap();
+
+        remove_path(&history, true).unwrap();
+        assert!(fs::read_dir(dir.path()).unwrap().next().is_none());
+        // An open handle sees zeros of the same length, never a shorter file
+        assert_eq!(reader.metadata().unwrap().len(), 7);
+        assert_eq!(std::io::read_to_string(&reader).unwrap(), "\0".repeat(7));
+    }
+
+    #[test]
+    fn test_data_files_skips_hooks_and_config() {
+        let dir = tempdir().unwrap();
+        for name in [
+            "commands.jsonl",
+            "sessions.jsonl",
+            "bash.sh",
+            "bash-ctrl-r.sh",
+            "config.toml",
+        ] {
+            fs::write(dir.path().join(name), "x").unwrap();
+        }
+        fs::create_dir(dir.path().join("running")).unwrap();
+
+        let files = data_files(dir.path(), &dir.path().join("config.toml")).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["commands.jsonl", "running", "sessions.jsonl"]);
+    }
+
+    #[test]
+    fn test_shred_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("secret");
+        fs::write(&path, "password123").unwrap();
+
+        shred_file(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), vec![0u8; 11]);
+    }
+}
@@ -0,0 +1,742 @@
+//! A small jq-style expression language over recorded commands
+//!
+//! A query is a pipeline of stages separated by `|`, each applied to the
+//! stream of records (as they appear in `--format json`):
+//!
+//! - `select(expr)` keeps records for which `expr` is true
+//! - `sort_by(expr)`, `reverse`, `limit(n)` and `count` work on the stream
+//! - any other expression replaces each record with its value, e.g.
+//!   `.command`, or `{command, secs: .duration_ms / 1000}`
+//!
+//! Expressions have fields (`.exit_code`, or just `exit_code`), string,
+//! number, boolean and null literals, `== != < <= > >=`, `=~` (regex match),
+//! `+ - * /`, `and or not`, and the functions `contains`, `startswith`,
+//! `endswith`, `matches`, `lower`, `upper`, `length`, `program` and `date`.
+
+use crate::cli;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local};
+use regex::Regex;
+use serde_json::{Map, Value, json};
+use shelltape_core::normalize;
+use shelltape_core::storage::Storage;
+use std::cmp::Ordering;
+use std::io::Write;
+
+/// Run a query over all recorded commands and print the results as JSON
+pub fn run_query(storage: &Storage, query: &str, compact: bool) -> Result<()> {
+    let pipeline = parse(query)?;
+    let records = storage
+        .read_all_commands()?
+        .into_iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match evaluate(&pipeline, records)? {
+        Output::Count(count) => println!("{}", count),
+        Output::Values(values) if compact => {
+            let mut out = std::io::stdout().lock();
+            for value in values {
+                writeln!(out, "{}", serde_json::to_string(&value)?)?;
+            }
+        }
+        Output::Values(values) => cli::print_json(&values)?,
+    }
+    Ok(())
+}
+
+/// Apply a query to records, giving an array of results or the `count`
+pub fn apply(query: &str, records: Vec<Value>) -> Result<Value> {
+    Ok(match evaluate(&parse(query)?, records)? {
+        Output::Count(count) => json!(count),
+        Output::Values(values) => Value::Array(values),
+    })
+}
+
+#[derive(Debug)]
+enum Stage {
+    Select(Expr),
+    Map(Expr),
+    SortBy(Expr),
+    Limit(usize),
+    Reverse,
+    Count,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value),
+    /// `.a.b` on the current record
+    Field(Vec<String>),
+    /// `.name` on the result of another expression
+    Get(Box<Expr>, String),
+    Object(Vec<(String, Expr)>),
+    Array(Vec<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+    Match(Box<Expr>, Regex),
+    Call(Func, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    Contains,
+    StartsWith,
+    EndsWith,
+    Lower,
+    Upper,
+    Length,
+    Program,
+    Date,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<(Func, usize)> {
+        Some(match name {
+            "contains" => (Func::Contains, 2),
+            "startswith" => (Func::StartsWith, 2),
+            "endswith" => (Func::EndsWith, 2),
+            "lower" => (Func::Lower, 1),
+            "upper" => (Func::Upper, 1),
+            "length" => (Func::Length, 1),
+            "program" => (Func::Program, 1),
+            "date" => (Func::Date, 1),
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Sym(&'static str),
+}
+
+const SYMBOLS: [&str; 19] = [
+    "==", "!=", "<=", ">=", "=~", "<", ">", "+", "-", "*", "/", "(", ")", "{", "}", "[", "]", ",",
+    ":",
+];
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(pos, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => s.push('\n'),
+                        Some((_, 't')) => s.push('\t'),
+                        Some((_, c)) => s.push(c),
+                        None => bail!("Unterminated string at position {}", pos),
+                    },
+                    Some((_, c)) => s.push(c),
+                    None => bail!("Unterminated string at position {}", pos),
+                }
+            }
+            tokens.push((pos, Token::Str(s)));
+        } else if c.is_ascii_digit() {
+            let mut end = pos;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let num = input[pos..end]
+                .parse()
+                .with_context(|| format!("Invalid number at position {}", pos))?;
+            tokens.push((pos, Token::Num(num)));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = pos;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push((pos, Token::Ident(input[pos..end].to_string())));
+        } else if c == '.' || c == '|' {
+            chars.next();
+            tokens.push((pos, Token::Sym(if c == '.' { "." } else { "|" })));
+        } else {
+            let Some(sym) = SYMBOLS.iter().find(|s| input[pos..].starts_with(**s)) else {
+                bail!("Unexpected `{}` at position {}", c, pos);
+            };
+            for _ in 0..sym.len() {
+                chars.next();
+            }
+            tokens.push((pos, Token::Sym(sym)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive-descent parser over the token list
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    len: usize,
+}
+
+fn parse(query: &str) -> Result<Vec<Stage>> {
+    let mut parser = Parser {
+        tokens: tokenize(query)?,
+        pos: 0,
+        len: query.len(),
+    };
+    let mut stages = vec![parser.stage()?];
+    while parser.eat_sym("|") {
+        stages.push(parser.stage()?);
+    }
+    if let Some((at, token)) = parser.tokens.get(parser.pos) {
+        bail!("Unexpected {:?} at position {}", token, at);
+    }
+    Ok(stages)
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.len, |(at, _)| *at)
+    }
+
+    fn eat_sym(&mut self, sym: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Sym(s)) if *s == sym) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_ident(&mut self, name: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(id)) if id == name) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_sym(&mut self, sym: &str) -> Result<()> {
+        if self.eat_sym(sym) {
+            Ok(())
+        } else {
+            bail!("Expected `{}` at position {}", sym, self.position())
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.peek().cloned() {
+            Some(Token::Ident(id)) => {
+                self.pos += 1;
+                Ok(id)
+            }
+            _ => bail!("Expected a name at position {}", self.position()),
+        }
+    }
+
+    fn stage(&mut self) -> Result<Stage> {
+        let next = self.tokens.get(self.pos + 1).map(|(_, t)| t);
+        let call = next == Some(&Token::Sym("("));
+        let stage = match self.peek() {
+            Some(Token::Ident(id)) if id == "select" && call => {
+                self.pos += 2;
+                Stage::Select(self.expr()?)
+            }
+            Some(Token::Ident(id)) if id == "sort_by" && call => {
+                self.pos += 2;
+                Stage::SortBy(self.expr()?)
+            }
+            Some(Token::Ident(id)) if id == "limit" && call => {
+                self.pos += 2;
+                match self.peek().cloned() {
+                    Some(Token::Num(n)) if n >= 0.0 => {
+                        self.pos += 1;
+                        Stage::Limit(n as usize)
+                    }
+                    _ => bail!("limit needs a number at position {}", self.position()),
+                }
+            }
+            Some(Token::Ident(id)) if id == "reverse" && !call => {
+                self.pos += 1;
+                return Ok(Stage::Reverse);
+            }
+            Some(Token::Ident(id)) if id == "count" && !call => {
+                self.pos += 1;
+                return Ok(Stage::Count);
+            }
+            _ => return Ok(Stage::Map(self.expr()?)),
+        };
+        self.expect_sym(")")?;
+        Ok(stage)
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut left = self.and()?;
+        while self.eat_ident("or") {
+            left = Expr::Binary(Box::new(left), BinOp::Or, Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut left = self.not()?;
+        while self.eat_ident("and") {
+            left = Expr::Binary(Box::new(left), BinOp::And, Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.eat_ident("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let left = self.sum()?;
+        if self.eat_sym("=~") {
+            let at = self.position();
+            let Expr::Literal(Value::String(pattern)) = self.sum()? else {
+                bail!("`=~` needs a string pattern at position {}", at);
+            };
+            return Ok(Expr::Match(Box::new(left), regex(&pattern)?));
+        }
+        let op = match self.peek() {
+            Some(Token::Sym("==")) => BinOp::Eq,
+            Some(Token::Sym("!=")) => BinOp::Ne,
+            Some(Token::Sym("<")) => BinOp::Lt,
+            Some(Token::Sym("<=")) => BinOp::Le,
+            Some(Token::Sym(">")) => BinOp::Gt,
+            Some(Token::Sym(">=")) => BinOp::Ge,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        Ok(Expr::Binary(Box::new(left), op, Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        let mut left = self.product()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Sym("+")) => BinOp::Add,
+                Some(Token::Sym("-")) => BinOp::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            left = Expr::Binary(Box::new(left), op, Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Sym("*")) => BinOp::Mul,
+                Some(Token::Sym("/")) => BinOp::Div,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            left = Expr::Binary(Box::new(left), op, Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat_sym("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let mut expr = self.primary()?;
+        while self.eat_sym(".") {
+            expr = Expr::Get(Box::new(expr), self.ident()?);
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let at = self.position();
+        let Some(token) = self.peek().cloned() else {
+            bail!("Unexpected end of query");
+        };
+        self.pos += 1;
+
+        match token {
+            Token::Num(n) => Ok(Expr::Literal(json!(n))),
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::Sym(".") => {
+                let mut path = Vec::new();
+                if let Some(Token::Ident(_)) = self.peek() {
+                    path.push(self.ident()?);
+                    while self.eat_sym(".") {
+                        path.push(self.ident()?);
+                    }
+                }
+                Ok(Expr::Field(path))
+            }
+            Token::Sym("(") => {
+                let expr = self.expr()?;
+                self.expect_sym(")")?;
+                Ok(expr)
+            }
+            Token::Sym("{") => {
+                let mut fields = Vec::new();
+                while !self.eat_sym("}") {
+                    if !fields.is_empty() {
+                        self.expect_sym(",")?;
+                    }
+                    let name = match self.peek().cloned() {
+                        Some(Token::Str(s)) => {
+                            self.pos += 1;
+                            s
+                        }
+                        _ => self.ident()?,
+                    };
+                    let value = if self.eat_sym(":") {
+                        self.expr()?
+                    } else {
+                        Expr::Field(vec![name.clone()])
+                    };
+                    fields.push((name, value));
+                }
+                Ok(Expr::Object(fields))
+            }
+            Token::Sym("[") => {
+                let mut items = Vec::new();
+                while !self.eat_sym("]") {
+                    if !items.is_empty() {
+                        self.expect_sym(",")?;
+                    }
+                    items.push(self.expr()?);
+                }
+                Ok(Expr::Array(items))
+            }
+            Token::Ident(id) => match id.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ if self.eat_sym("(") => self.call(&id, at),
+                // A bare name is a field of the record
+                _ => Ok(Expr::Field(vec![id])),
+            },
+            token => bail!("Unexpected {:?} at position {}", token, at),
+        }
+    }
+
+    fn call(&mut self, name: &str, at: usize) -> Result<Expr> {
+        let mut args = Vec::new();
+        while !self.eat_sym(")") {
+            if !args.is_empty() {
+                self.expect_sym(",")?;
+            }
+            args.push(self.expr()?);
+        }
+
+        if name == "matches" {
+            let [subject, Expr::Literal(Value::String(pattern))] = <[Expr; 2]>::try_from(args)
+                .map_err(|_| anyhow!("matches takes a value and a string pattern"))?
+            else {
+                bail!("matches needs a string pattern at position {}", at);
+            };
+            return Ok(Expr::Match(Box::new(subject), regex(&pattern)?));
+        }
+
+        let (func, arity) = Func::from_name(name)
+            .ok_or_else(|| anyhow!("Unknown function `{}` at position {}", name, at))?;
+        if args.len() != arity {
+            bail!("{} takes {} argument(s), got {}", name, arity, args.len());
+        }
+        Ok(Expr::Call(func, args))
+    }
+}
+
+fn regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).with_context(|| format!("Invalid regex: {}", pattern))
+}
+
+enum Output {
+    Values(Vec<Value>),
+    Count(usize),
+}
+
+fn evaluate(pipeline: &[Stage], mut values: Vec<Value>) -> Result<Output> {
+    for stage in pipeline {
+        match stage {
+            Stage::Select(expr) => {
+                let mut kept = Vec::with_capacity(values.len());
+                for value in values {
+                    if truthy(&eval(expr, &value)?) {
+                        kept.push(value);
+                    }
+                }
+                values = kept;
+            }
+            Stage::Map(expr) => {
+                values = values
+                    .iter()
+                    .map(|value| eval(expr, value))
+                    .collect::<Result<_>>()?;
+            }
+            Stage::SortBy(expr) => {
+                let mut keyed = values
+                    .into_iter()
+                    .map(|value| Ok((eval(expr, &value)?, value)))
+                    .collect::<Result<Vec<_>>>()?;
+                keyed.sort_by(|a, b| compare(&a.0, &b.0));
+                values = keyed.into_iter().map(|(_, value)| value).collect();
+            }
+            Stage::Limit(n) => values.truncate(*n),
+            Stage::Reverse => values.reverse(),
+            Stage::Count => return Ok(Output::Count(values.len())),
+        }
+    }
+    Ok(Output::Values(values))
+}
+
+fn eval(expr: &Expr, record: &Value) -> Result<Value> {
+    Ok(match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Field(path) => path
+            .iter()
+            .try_fold(record, |value, name| value.get(name))
+            .cloned()
+            .unwrap_or(Value::Null),
+        Expr::Get(expr, name) => eval(expr, record)?
+            .get(name)
+            .cloned()
+            .unwrap_or(Value::Null),
+        Expr::Object(fields) => {
+            let mut object = Map::new();
+            for (name, expr) in fields {
+                object.insert(name.clone(), eval(expr, record)?);
+            }
+            Value::Object(object)
+        }
+        Expr::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| eval(item, record))
+                .collect::<Result<_>>()?,
+        ),
+        Expr::Not(expr) => Value::Bool(!truthy(&eval(expr, record)?)),
+        Expr::Neg(expr) => json!(-number(&eval(expr, record)?)?),
+        Expr::Match(expr, regex) => Value::Bool(regex.is_match(&text(&eval(expr, record)?))),
+        Expr::Binary(left, op, right) => {
+            let left = eval(left, record)?;
+            // `and` and `or` only evaluate the right side when needed
+            match op {
+                BinOp::And if !truthy(&left) => return Ok(Value::Bool(false)),
+                BinOp::Or if truthy(&left) => return Ok(Value::Bool(true)),
+                _ => {}
+            }
+            binary(&left, *op, &eval(right, record)?)?
+        }
+        Expr::Call(func, args) => {
+            let args = args
+                .iter()
+                .map(|arg| eval(arg, record))
+                .collect::<Result<Vec<_>>>()?;
+            call(*func, &args)?
+        }
+    })
+}
+
+fn binary(left: &Value, op: BinOp, right: &Value) -> Result<Value> {
+    let order = || compare(left, right);
+    Ok(match op {
+        BinOp::And | BinOp::Or => Value::Bool(truthy(right)),
+        BinOp::Eq => Value::Bool(order() == Ordering::Equal),
+        BinOp::Ne => Value::Bool(order() != Ordering::Equal),
+        BinOp::Lt => Value::Bool(order() == Ordering::Less),
+        BinOp::Le => Value::Bool(order() != Ordering::Greater),
+        BinOp::Gt => Value::Bool(order() == Ordering::Greater),
+        BinOp::Ge => Value::Bool(order() != Ordering::Less),
+        BinOp::Add => match (left, right) {
+            (Value::String(a), Value::String(b)) => Value::String(format!("{}{}", a, b)),
+            _ => json!(number(left)? + number(right)?),
+        },
+        BinOp::Sub => json!(number(left)? - number(right)?),
+        BinOp::Mul => json!(number(left)? * number(right)?),
+        BinOp::Div => {
+            let divisor = number(right)?;
+            if divisor == 0.0 {
+                bail!("Division by zero");
+            }
+            json!(number(left)? / divisor)
+        }
+    })
+}
+
+fn call(func: Func, args: &[Value]) -> Result<Value> {
+    let string = |i: usize| text(&args[i]);
+    Ok(match func {
+        Func::Contains => match &args[0] {
+            Value::Array(items) => Value::Bool(
+                items
+                    .iter()
+                    .any(|item| compare(item, &args[1]) == Ordering::Equal),
+            ),
+            _ => Value::Bool(string(0).contains(&string(1))),
+        },
+        Func::StartsWith => Value::Bool(string(0).starts_with(&string(1))),
+        Func::EndsWith => Value::Bool(string(0).ends_with(&string(1))),
+        Func::Lower => Value::String(string(0).to_lowercase()),
+        Func::Upper => Value::String(string(0).to_uppercase()),
+        Func::Length => json!(match &args[0] {
+            Value::Array(items) => items.len(),
+            Value::Object(fields) => fields.len(),
+            Value::Null => 0,
+            value => text(value).chars().count(),
+        }),
+        Func::Program => normalize::program(&string(0)).map_or(Value::Null, Value::String),
+        Func::Date => {
+            let time: DateTime<Local> = string(0)
+                .parse::<DateTime<chrono::Utc>>()
+                .with_context(|| format!("Not a timestamp: {}", string(0)))?
+                .into();
+            Value::String(time.format("%Y-%m-%d").to_string())
+        }
+    })
+}
+
+/// `false` and `null` are false; everything else is true
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn number(value: &Value) -> Result<f64> {
+    value
+        .as_f64()
+        .ok_or_else(|| anyhow!("Expected a number, got {}", value))
+}
+
+/// A value as text: strings as they are, anything else as JSON
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Order values as jq does: null < false < true < numbers < strings <
+/// arrays < objects
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x
+            .as_f64()
+            .unwrap_or_default()
+            .total_cmp(&y.as_f64().unwrap_or_default()),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| compare(x, y))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => x.len().cmp(&y.len()).then_with(|| {
+            x.iter()
+                .zip(y)
+                .map(|((kx, vx), (ky, vy))| kx.cmp(ky).then_with(|| compare(vx, vy)))
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+        }),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<Value> {
+        vec![
+            json!({"command": "cargo build", "exit_code": 0, "duration_ms": 40000, "tags": ["ci"]}),
+            json!({"command": "git push", "exit_code": 1, "duration_ms": 1200, "tags": []}),
+            json!({"command": "cargo test", "exit_code": 101, "duration_ms": 9000, "tags": ["ci"]}),
+        ]
+    }
+
+    fn run(query: &str) -> Vec<Value> {
+        match evaluate(&parse(query).unwrap(), records()).unwrap() {
+            Output::Values(values) => values,
+            Output::Count(count) => vec![json!(count)],
+        }
+    }
+
+    #[test]
+    fn test_select_and_project() {
+        assert_eq!(
+            run(
+                r#"select(exit_code != 0 and startswith(.command, "cargo")) | {command, secs: .duration_ms / 1000}"#
+            ),
+            vec![json!({"command": "cargo test", "secs": 9.0})]
+        );
+        assert_eq!(
+            run(r#"select(contains(.tags, "ci")) | sort_by(-.duration_ms) | .command"#),
+            vec![json!("cargo build"), json!("cargo test")]
+        );
+        assert_eq!(
+            run(r#"select(.command =~ "^git " or .duration_ms > 30000) | count"#),
+            vec![json!(2)]
+        );
+        assert_eq!(
+            run("reverse | limit(1) | program(command)"),
+            vec![json!("cargo")]
+        );
+        assert_eq!(run("select(not exit_code == 0) | count"), vec![json!(2)]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("select(exit_code ==").is_err());
+        assert!(parse("frobnicate(.command)").is_err());
+        assert!(parse(r#"matches(.command, .cwd)"#).is_err());
+        assert!(parse(r#".command =~ "[""#).is_err());
+        assert!(parse("limit(1) extra").is_err());
+        assert!(evaluate(&parse(".command * 2").unwrap(), records()).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::ansi::strip_ansi;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use shelltape_core::models::{Command, Session};
+use shelltape_core::storage::Storage;
+use std::path::Path;
+
+/// Common prompt endings: `user@host:~/src$ `, `[user@host src]# `,
+/// `bash-5.2$ `, `% `, `❯ `; group 1 is the prompt, group 2 the command
+const DEFAULT_PROMPT: &str = r"^((?:\([^)]*\) )?(?:\[[^\]]*\]|[^\s$#%❯]*)[$#%❯]) (.*)$";
+
+/// Shell recorded when the recording does not name one
+const UNKNOWN_SHELL: &str = "unknown";
+
+/// A terminal recording read from a typescript or asciicast
+pub struct Recording {
+    /// When the recording started
+    pub started: DateTime<Utc>,
+    /// Everything written to the terminal
+    pub data: Vec<u8>,
+    /// When each part of `data` was written; empty if unknown
+    pub chunks: Vec<Chunk>,
+    pub shell: Option<String>,
+    /// Program the whole recording ran, used when no prompts are found
+    pub command: Option<String>,
+    /// Exit code of that program, if recorded
+    pub exit_code: Option<i32>,
+}
+
+/// A chunk of terminal output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chunk {
+    /// Seconds since the recording started
+    pub at: f64,
+    /// Byte offset into the recorded data
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// A command found in the recording, with the byte range of its output
+#[derive(Debug, PartialEq)]
+struct Span {
+    command: String,
+    cwd: Option<String>,
+    /// Offset just after the prompt line
+    start: usize,
+    /// Offset of the next prompt line, or the end of the data
+    end: usize,
+}
+
+/// Import a terminal recording, splitting it into commands at prompts
+///
+/// With timing data, commands get real start times, durations and
+/// replayable output timing; without it they are spaced a millisecond apart
+/// from the start of the recording. Command boundaries are guessed from
+/// prompt lines, so exit codes are unknown and recorded as 0. A recording
+/// without prompts becomes a single record of the program it ran. IDs are
+/// derived from the content, so importing the same file again adds nothing.
+pub fn import(
+    storage: &Storage,
+    file: &Path,
+    recording: Recording,
+    prompt: Option<&str>,
+) -> Result<()> {
+    let prompt = Regex::new(prompt.unwrap_or(DEFAULT_PROMPT)).context("Invalid prompt regex")?;
+    let (commands, session) = to_records(recording, &prompt, file)?;
+
+    let total = commands.len();
+    let added = storage.merge_commands(commands)?;
+    storage.merge_sessions(vec![session])?;
+
+    println!(
+        "✓ Imported {} of {} commands from {} ({} already present)",
+        added,
+        total,
+        file.display(),
+        total - added
+    );
+    Ok(())
+}
+
+fn to_records(
+    recording: Recording,
+    prompt: &Regex,
+    file: &Path,
+) -> Result<(Vec<Command>, Session)> {
+    let Recording {
+        started,
+        data,
+        chunks,
+        shell,
+        command,
+        exit_code,
+    } = recording;
+
+    let mut spans = find_commands(&data, prompt);
+    let mut whole_exit_code = 0;
+    if spans.is_empty() {
+        let Some(command) = command else {
+            bail!(
+                "No commands found in {}; pass --prompt to match your prompt",
+                file.display()
+            );
+        };
+        spans.push(Span {
+            command,
+            cwd: None,
+            start: 0,
+            end: data.len(),
+        });
+        whole_exit_code = exit_code.unwrap_or_default();
+    }
+
+    let digest = hex_digest(&data);
+    let session_id = uuid_from(&digest, &started.to_rfc3339());
+    let shell = shell.unwrap_or_else(|| UNKNOWN_SHELL.to_string());
+    let hostname = hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    let username = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    let commands: Vec<Command> = spans
+        .into_iter()
+        .enumerate()
+        .map(|(i, span)| {
+            let (started_at, duration_ms, timing) = if chunks.is_empty() {
+                (started + Duration::milliseconds(i as i64), 0, Vec::new())
+            } else {
+                // The command starts when Enter is echoed at the end of the
+                // prompt line, and ends when the next prompt is printed
+                let start = if span.start == 0 {
+                    0.0
+                } else {
+                    time_at(&chunks, span.start - 1)
+                };
+                let end = time_at(&chunks, span.end).max(start);
+                (
+                    started + Duration::microseconds((start * 1e6) as i64),
+                    ((end - start) * 1000.0) as u64,
+                    output_timing(&chunks, span.start, span.end, start),
+                )
+            };
+            Command {
+                id: uuid_from(&digest, &i.to_string()),
+                command: span.command,
+                output: String::from_utf8_lossy(&data[span.start..span.end]).into_owned(),
+                exit_code: whole_exit_code,
+                cwd: span.cwd.unwrap_or_default(),
+                started_at,
+                duration_ms,
+                session_id: session_id.clone(),
+                shell: shell.clone(),
+                hostname: hostname.clone(),
+                username: username.clone(),
+                timing,
+                tags: Vec::new(),
+                note: None,
+                bookmarked: false,
+                rerun_of: None,
+            }
+        })
+        .collect();
+
+    let session = Session {
+        id: session_id,
+        started_at: started,
+        ended_at: commands
+            .iter()
+            .map(|c| c.started_at + Duration::milliseconds(c.duration_ms as i64))
+            .max(),
+        hostname,
+        shell,
+        command_count: commands.len() as u32,
+        name: file.file_name().map(|n| n.to_string_lossy().into_owned()),
+        tty: None,
+    };
+
+    Ok((commands, session))
+}
+
+/// Seconds since the start at which the byte at `offset` was written
+fn time_at(chunks: &[Chunk], offset: usize) -> f64 {
+    let i = chunks.partition_point(|c| c.offset + c.len <= offset);
+    chunks
+        .get(i)
+        .or(chunks.last())
+        .map(|c| c.at)
+        .unwrap_or_default()
+}
+
+/// Output timing of `start..end` as `(milliseconds since start, bytes)`
+fn output_timing(chunks: &[Chunk], start: usize, end: usize, started: f64) -> Vec<(u64, usize)> {
+    chunks
+        .iter()
+        .filter_map(|c| {
+            let from = c.offset.max(start);
+            let to = (c.offset + c.len).min(end);
+            (from < to).then(|| ((((c.at - started) * 1000.0).max(0.0)) as u64, to - from))
+        })
+        .collect()
+}
+
+/// Find prompt lines and the output following each
+fn find_commands(data: &[u8], prompt: &Regex) -> Vec<Span> {
+    let mut prompts: Vec<(usize, usize, String, Option<String>)> = Vec::new();
+    let mut offset = 0;
+
+    for line in data.split_inclusive(|b| *b == b'\n') {
+        let start = offset;
+        offset += line.len();
+
+        let text = apply_backspaces(&strip_ansi(&String::from_utf8_lossy(line)));
+        let text = text.trim_end_matches('\n');
+        let Some(caps) = prompt.captures(text) else {
+            continue;
+        };
+        // The last group is the command; the first, if there are two, the prompt
+        let group = |i| caps.get(i).map_or("", |m| m.as_str());
+        let (prefix, command) = match caps.len() {
+            1 => ("", ""),
+            2 => ("", group(1)),
+            n => (group(1), group(n - 1)),
+        };
+        // `50% done` is output, not a prompt
+        let head = prefix.trim_end_matches(['$', '#', '%', '❯']);
+        if !head.is_empty() && head.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            continue;
+        }
+        let command = command.trim().to_string();
+        prompts.push((start, offset, command, cwd_from_prompt(prefix)));
+    }
+
+    let ends: Vec<usize> = prompts
+        .iter()
+        .skip(1)
+        .map(|p| p.0)
+        .chain(std::iter::once(data.len()))
+        .collect();
+    prompts
+        .into_iter()
+        .zip(ends)
+        .filter(|((_, _, command, _), _)| !command.is_empty())
+        .map(|((_, start, command, cwd), end)| Span {
+            command,
+            cwd,
+            start,
+            end: end.max(start),
+        })
+        .collect()
+}
+
+/// The directory in a `user@host:dir$` style prompt
+fn cwd_from_prompt(prompt: &str) -> Option<String> {
+    let dir = prompt
+        .trim_end_matches(['$', '#', '%', '❯'])
+        .rsplit_once(':')?
+        .1;
+    if !dir.starts_with('/') && !dir.starts_with('~') {
+        return None;
+    }
+    match (dir.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => Some(format!("{}{}", home.display(), rest)),
+        _ => Some(dir.to_string()),
+    }
+}
+
+/// Apply the backspaces of line editing, as the terminal showed them
+fn apply_backspaces(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\x08' {
+            out.pop();
+        } else if c != '\x07' {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub fn hex_digest(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// A stable UUID-shaped ID, so importing a file twice yields the same records
+pub fn uuid_from(digest: &str, salt: &str) -> String {
+    let hex = hex_digest(format!("{}:{}", digest, salt).as_bytes());
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION: &str = "\
+alice@box:/srv/app$ ls
+Cargo.toml  src
+alice@box:/srv/app$ make tets\x08\x08st
+50% done
+ok
+alice@box:/srv/app$ exit
+";
+
+    #[test]
+    fn test_find_commands() {
+        let data = SESSION.as_bytes();
+        let spans = find_commands(data, &Regex::new(DEFAULT_PROMPT).unwrap());
+        let commands: Vec<&str> = spans.iter().map(|s| s.command.as_str()).collect();
+        assert_eq!(commands, vec!["ls", "make test", "exit"]);
+        assert_eq!(spans[0].cwd.as_deref(), Some("/srv/app"));
+        assert_eq!(&data[spans[1].start..spans[1].end], b"50% done\nok\n");
+        assert_eq!(spans[2].start, spans[2].end);
+    }
+
+    #[test]
+    fn test_timing() {
+        let chunks = [
+            Chunk {
+                at: 0.5,
+                offset: 0,
+                len: 10,
+            },
+            Chunk {
+                at: 1.0,
+                offset: 10,
+                len: 5,
+            },
+        ];
+        assert_eq!(time_at(&chunks, 12), 1.0);
+        assert_eq!(output_timing(&chunks, 8, 15, 0.5), vec![(0, 2), (500, 5)]);
+    }
+
+    #[test]
+    fn test_whole_recording() {
+        let recording = |data: &str| Recording {
+            started: Utc::now(),
+            data: data.as_bytes().to_vec(),
+            chunks: Vec::new(),
+            shell: None,
+            command: Some("htop".to_string()),
+            exit_code: Some(1),
+        };
+        let prompt = Regex::new(DEFAULT_PROMPT).unwrap();
+        let file = Path::new("demo.cast");
+
+        let (commands, session) = to_records(recording("\x1b[H CPU 12%\n"), &prompt, file).unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(
+            (commands[0].command.as_str(), commands[0].exit_code),
+            ("htop", 1)
+        );
+        assert_eq!(session.name.as_deref(), Some("demo.cast"));
+
+        // Same content, same IDs
+        let (again, _) = to_records(recording("\x1b[H CPU 12%\n"), &prompt, file).unwrap();
+        assert_eq!(again[0].id, commands[0].id);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::sessions;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+/// How a replay is paced
+pub struct Playback {
+    /// Speed multiplier (2.0 = twice as fast)
+    pub speed: f64,
+    /// Print output at once, ignoring timing data
+    pub instant: bool,
+    /// Longest pause, in seconds
+    pub max_idle: f64,
+}
+
+/// Play a stored command's output back to the terminal
+///
+/// Output is written in the chunks it was captured in, sleeping between them
+/// according to the recorded timing. Records without timing data (or
+/// `--instant`) are printed in one go. With `session`, or when no command
+/// has the ID but a session does, the whole session is replayed.
+pub fn replay(storage: &Storage, id: &str, session: bool, playback: Playback) -> Result<()> {
+    if playback.speed <= 0.0 {
+        bail!("--speed must be greater than zero");
+    }
+
+    if session {
+        return replay_session(storage, id, &playback);
+    }
+    match storage.find_command(id) {
+        Ok(cmd) => play(&mut std::io::stdout(), &cmd, &playback),
+        Err(e) => match sessions::session_commands(storage, id) {
+            Ok(_) => replay_session(storage, id, &playback),
+            Err(_) => Err(e),
+        },
+    }
+}
+
+/// Play a session's commands in order, waiting between them as the user did
+fn replay_session(storage: &Storage, id: &str, playback: &Playback) -> Result<()> {
+    let (_, commands) = sessions::session_commands(storage, id)?;
+    let mut stdout = std::io::stdout();
+    let mut prev_end: Option<DateTime<Utc>> = None;
+
+    for cmd in &commands {
+        if let Some(prev_end) = prev_end
+            && !playback.instant
+        {
+            let gap = (cmd.started_at - prev_end).num_milliseconds().max(0) as u64;
+            thread::sleep(scaled_delay(gap, playback.speed, playback.max_idle));
+        }
+        play(&mut stdout, cmd, playback)?;
+        prev_end = Some(cmd.started_at + chrono::Duration::milliseconds(cmd.duration_ms as i64));
+    }
+
+    Ok(())
+}
+
+/// Print the prompt line and the output of one command
+fn play(stdout: &mut impl Write, cmd: &Command, playback: &Playback) -> Result<()> {
+    writeln!(stdout, "$ {}", cmd.command)?;
+    stdout.flush()?;
+
+    if playback.instant || cmd.timing.is_empty() {
+        stdout.write_all(cmd.output.as_bytes())?;
+    } else {
+        for (delay, chunk) in playback_chunks(cmd, playback.speed, playback.max_idle) {
+            thread::sleep(delay);
+            stdout.write_all(chunk.as_bytes())?;
+            stdout.flush()?;
+        }
+    }
+
+    if !cmd.output.is_empty() && !cmd.output.ends_with('\n') {
+        writeln!(stdout)?;
+    }
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// A recorded pause of `gap_ms`, sped up and capped at `max_idle` seconds
+fn scaled_delay(gap_ms: u64, speed: f64, max_idle: f64) -> Duration {
+    let gap = gap_ms as f64 / 1000.0 / speed;
+    Duration::from_secs_f64(gap.min(max_idle.max(0.0)))
+}
+
+/// Split the output into timed chunks, scaling delays by `speed` and capping idle gaps
+fn playback_chunks(cmd: &Command, speed: f64, max_idle: f64) -> Vec<(Duration, &str)> {
+    let output = cmd.output.as_str();
+    let mut chunks = Vec::with_capacity(cmd.timing.len() + 1);
+    let mut pos = 0;
+    let mut prev_ms = 0;
+
+    for &(at_ms, len) in &cmd.timing {
+        if pos >= output.len() {
+            break;
+        }
+
+        // Captured lengths are raw bytes; lossy UTF-8 conversion may have shifted them
+        let mut end = (pos + len).min(output.len());
+        while !output.is_char_boundary(end) {
+            end += 1;
+        }
+
+        let delay = scaled_delay(at_ms.saturating_sub(prev_ms), speed, max_idle);
+        chunks.push((delay, &output[pos..end]));
+
+        pos = end;
+        prev_ms = at_ms;
+    }
+
+    // Anything not covered by the timing data is flushed at the end
+    if pos < output.len() {
+        chunks.push((Duration::ZERO, &output[pos..]));
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn command_with_timing(output: &str, timing: Vec<(u64, usize)>) -> Command {
+        Command {
+            id: "test".to_string(),
+            command: "echo".to_string(),
+            output: output.to_string(),
+            exit_code: 0,
+            started_at: Utc::now(),
+            duration_ms: 0,
+            cwd: "/tmp".to_string(),
+            session_id: "session".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            timing,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_playback_chunks() {
+        let cmd = command_with_timing("hello world\n", vec![(0, 6), (1000, 3), (5000, 2)]);
+        let chunks = playback_chunks(&cmd, 2.0, 1.0);
+
+        let text: Vec<&str> = chunks.iter().map(|(_, s)| *s).collect();
+        assert_eq!(text, vec!["hello ", "wor", "ld", "\n"]);
+
+        assert_eq!(chunks[0].0, Duration::ZERO);
+        assert_eq!(chunks[1].0, Duration::from_millis(500));
+        // 4s gap at 2x speed is capped at max_idle
+        assert_eq!(chunks[2].0, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_playback_chunks_char_boundary() {
+        let cmd = command_with_timing("héllo", vec![(0, 2), (10, 4)]);
+        let chunks = playback_chunks(&cmd, 1.0, 2.0);
+
+        let text: Vec<&str> = chunks.iter().map(|(_, s)| *s).collect();
+        assert_eq!(text.concat(), "héllo");
+        assert_eq!(text[0], "hé");
+    }
+}
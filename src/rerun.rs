@@ -0,0 +1,100 @@
+use crate::exec;
+use crate::pick;
+use crate::pty_capture;
+use crate::session;
+use anyhow::{Result, bail};
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::path::Path;
+
+/// Run a recorded command again and record the new run, linked to the original
+///
+/// The command line goes through the shell it was recorded in, so pipes
+/// and quoting work as they did, in its original directory unless `here`.
+/// Returns the exit code of the new run (1 if nothing was picked).
+pub fn rerun(
+    storage: Storage,
+    id: Option<String>,
+    last: bool,
+    interactive: bool,
+    here: bool,
+) -> Result<i32> {
+    let original = if interactive {
+        match pick::select_command(&storage)? {
+            Some(cmd) => cmd,
+            None => return Ok(1),
+        }
+    } else {
+        match (id, last) {
+            (Some(id), false) => storage.find_command(&id)?,
+            (None, true) => storage.find_command_or_last(None)?,
+            _ => bail!("Specify a command ID, --last or --interactive"),
+        }
+    };
+
+    let cwd = if here {
+        std::env::current_dir()?.to_string_lossy().into_owned()
+    } else if original.cwd.is_empty() {
+        bail!(
+            "No directory was recorded for `{}`; pass --here to run it in the current one",
+            original.command
+        );
+    } else if !Path::new(&original.cwd).is_dir() {
+        bail!(
+            "Directory {} no longer exists; pass --here to run it in the current one",
+            original.cwd
+        );
+    } else {
+        original.cwd.clone()
+    };
+
+    let command = original.command.clone();
+    run_again(storage, &original, command, cwd)
+}
+
+/// Run `command` (the original's line, possibly edited) in `cwd` with the
+/// original's shell and record it as a rerun of `original`
+///
+/// Returns the exit code of the new run.
+pub fn run_again(
+    storage: Storage,
+    original: &Command,
+    command: String,
+    cwd: String,
+) -> Result<i32> {
+    let shell = shell_for(original);
+    let session_id = session::resolve_exec_session(&storage, None, None)?;
+    eprintln!("↻ {}  ({})", command, cwd);
+
+    let (_, exit_code) = exec::run_and_record(
+        storage,
+        command.clone(),
+        cwd.clone(),
+        session_id,
+        Some(original.id.clone()),
+        || {
+            if cfg!(windows) {
+                pty_capture::execute_with_capture(&command, &cwd)
+            } else {
+                pty_capture::execute_in_shell(&shell, &command, &cwd)
+            }
+        },
+    )?;
+    Ok(exit_code)
+}
+
+/// The shell to run a command with: the one it was recorded in if it is
+/// still installed, else `$SHELL`
+fn shell_for(cmd: &Command) -> String {
+    let recorded = Path::new(&cmd.shell);
+    if recorded.is_absolute() && recorded.exists() {
+        return cmd.shell.clone();
+    }
+    // Recorded on another machine, or by name only
+    match recorded.file_name().and_then(|name| name.to_str()) {
+        Some(name) if !name.is_empty() && name != "unknown" && which::which(name).is_ok() => {
+            name.to_string()
+        }
+        _ => std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string()),
+    }
+}
@@ -0,0 +1,406 @@
+use crate::query;
+use crate::stats::{self, StatsOptions};
+use crate::timespec;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use shelltape_core::query::Query;
+use shelltape_core::storage::Storage;
+use std::io::{BufRead, Write};
+
+/// Default number of commands returned by `query`
+const DEFAULT_LIMIT: usize = 50;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// Params of `query`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct QueryParams {
+    text: Option<String>,
+    tag: Option<String>,
+    session: Option<String>,
+    failed: bool,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+    /// A `shelltape query` expression applied to the selected commands
+    expr: Option<String>,
+    /// Include captured output and its timing
+    output: bool,
+}
+
+/// Params of `show`
+#[derive(Debug, Deserialize)]
+struct ShowParams {
+    id: String,
+}
+
+/// Params of `stats`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct StatsParams {
+    filter: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+}
+
+/// Params of `sessions`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SessionsParams {
+    limit: Option<usize>,
+}
+
+/// A JSON-RPC error object
+#[derive(Debug)]
+pub struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl std::fmt::Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// How messages are delimited on the wire
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Framing {
+    /// One JSON message per line
+    Lines,
+    /// `Content-Length` headers, as in the Language Server Protocol
+    Headers,
+}
+
+/// Answer JSON-RPC 2.0 requests on stdin/stdout until stdin closes
+///
+/// Messages are either one per line or framed with `Content-Length` headers
+/// like LSP; each reply uses the framing of its request.
+pub fn rpc(storage: Storage) -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+
+    while let Some((framing, message)) = read_message(&mut input)? {
+        let Some(reply) =
+            handle_message(&message, &|method, params| call(&storage, method, params))
+        else {
+            continue;
+        };
+        match framing {
+            Framing::Lines => writeln!(out, "{}", reply)?,
+            Framing::Headers => write!(out, "Content-Length: {}\r\n\r\n{}", reply.len(), reply)?,
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Read the next message, skipping blank lines
+fn read_message(input: &mut impl BufRead) -> Result<Option<(Framing, String)>> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if !line.trim().is_empty() {
+            break;
+        }
+    }
+
+    let Some(length) = content_length(&line) else {
+        return Ok(Some((Framing::Lines, line.trim_end().to_string())));
+    };
+    let mut length = length?;
+    // Further headers (e.g. Content-Type) up to the empty line
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some(other) = content_length(&line) {
+            length = other?;
+        }
+    }
+
+    let mut body = vec![0; length];
+    input
+        .read_exact(&mut body)
+        .context("Message ended before its Content-Length")?;
+    Ok(Some((
+        Framing::Headers,
+        String::from_utf8_lossy(&body).into_owned(),
+    )))
+}
+
+/// The value of a `Content-Length` header line, if it is one
+fn content_length(line: &str) -> Option<Result<usize>> {
+    let (name, value) = line.split_once(':')?;
+    name.trim().eq_ignore_ascii_case("content-length").then(|| {
+        value
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid Content-Length: {}", value.trim()))
+    })
+}
+
+/// A method handler: method name and params to result
+pub type Handler<'a> = dyn Fn(&str, Value) -> Result<Value, RpcError> + 'a;
+
+/// Answer a request or batch with `handler`; notifications get no reply
+pub fn handle_message(message: &str, handler: &Handler) -> Option<String> {
+    let reply = match serde_json::from_str::<Value>(message) {
+        Ok(Value::Array(batch)) if !batch.is_empty() => {
+            let replies: Vec<Value> = batch
+                .iter()
+                .filter_map(|request| handle_request(request, handler))
+                .collect();
+            (!replies.is_empty()).then_some(Value::Array(replies))
+        }
+        Ok(request) => handle_request(&request, handler),
+        Err(e) => Some(error_reply(
+            Value::Null,
+            RpcError::new(PARSE_ERROR, format!("Parse error: {}", e)),
+        )),
+    };
+    reply.map(|reply| reply.to_string())
+}
+
+fn handle_request(request: &Value, handler: &Handler) -> Option<Value> {
+    let id = request.get("id").cloned();
+    let method = request.get("method").and_then(Value::as_str);
+    let (Some(method), Some("2.0")) = (method, request.get("jsonrpc").and_then(Value::as_str))
+    else {
+        return Some(error_reply(
+            id.unwrap_or(Value::Null),
+            RpcError::new(INVALID_REQUEST, "Invalid request"),
+        ));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = handler(method, params);
+    tracing::info!(method, ok = result.is_ok(), "RPC request");
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => error_reply(id, e),
+    })
+}
+
+fn error_reply(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// Answer one of the history methods: `query`, `show`, `stats` or `sessions`
+pub fn call(storage: &Storage, method: &str, params: Value) -> Result<Value, RpcError> {
+    let internal = |e: anyhow::Error| RpcError::new(INTERNAL_ERROR, format!("{:#}", e));
+    match method {
+        "query" => query_commands(storage, params_of(params)?),
+        "show" => {
+            let params: ShowParams = params_of(params)?;
+            let cmd = storage
+                .find_command(&params.id)
+                .map_err(|e| RpcError::new(INVALID_PARAMS, format!("{:#}", e)))?;
+            Ok(json!(cmd))
+        }
+        "stats" => {
+            let params: StatsParams = params_of(params)?;
+            let opts = StatsOptions {
+                filter: params.filter,
+                since: params.since,
+                until: params.until,
+                ..StatsOptions::default()
+            };
+            stats::stats_json(storage, &opts).map_err(internal)
+        }
+        "sessions" => {
+            let params: SessionsParams = params_of(params)?;
+            let commands = storage.read_all_commands().map_err(internal)?;
+            let sessions = storage.read_all_sessions().map_err(internal)?;
+            let mut summaries = stats::sessions::session_summaries(&commands, &sessions);
+            summaries.truncate(params.limit.unwrap_or(DEFAULT_LIMIT));
+            Ok(json!(summaries))
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+/// Deserialize params, treating absent params as an empty object
+pub fn params_of<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+/// `query`: matching commands, most recent first, optionally run through an
+/// expression
+fn query_commands(storage: &Storage, params: QueryParams) -> Result<Value, RpcError> {
+    let time = |spec: Option<String>| {
+        spec.map(|spec| timespec::parse_time(&spec))
+            .transpose()
+            .map_err(|e| RpcError::new(INVALID_PARAMS, format!("{:#}", e)))
+    };
+    let query = Query {
+        text: params.text,
+        tag: params.tag,
+        session: params.session,
+        failed: params.failed,
+        since: time(params.since)?,
+        until: time(params.until)?,
+        limit: Some(params.limit.unwrap_or(DEFAULT_LIMIT)),
+    };
+    let commands = query
+        .run(storage)
+        .map_err(|e| RpcError::new(INTERNAL_ERROR, format!("{:#}", e)))?;
+
+    let records = commands
+        .into_iter()
+        .map(|cmd| {
+            let mut record = json!(cmd);
+            if !params.output
+                && let Some(fields) = record.as_object_mut()
+            {
+                fields.remove("output");
+                fields.remove("timing");
+            }
+            record
+        })
+        .collect();
+    match params.expr {
+        Some(expr) => query::apply(&expr, records)
+            .map_err(|e| RpcError::new(INVALID_PARAMS, format!("{:#}", e))),
+        None => Ok(Value::Array(records)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shelltape_core::recorder::Recorder;
+    use tempfile::TempDir;
+
+    fn request(storage: &Storage, message: &str) -> Value {
+        serde_json::from_str(&answer(storage, message).unwrap()).unwrap()
+    }
+
+    fn answer(storage: &Storage, message: &str) -> Option<String> {
+        handle_message(message, &|method, params| call(storage, method, params))
+    }
+
+    #[test]
+    fn test_methods() {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        let start = 1_792_227_600_000_000_000;
+        let cmd = Recorder::with_storage(storage.clone())
+            .record(
+                "cargo test".to_string(),
+                "test result: FAILED".to_string(),
+                101,
+                start,
+                start + 2_000_000_000,
+                "/src".to_string(),
+                "s".to_string(),
+                Vec::new(),
+            )
+            .unwrap()
+            .unwrap();
+
+        let found = request(
+            &storage,
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "query", "params": {"text": "cargo", "failed": true}}"#,
+        );
+        assert_eq!(found["id"], 1);
+        assert_eq!(found["result"][0]["command"], "cargo test");
+        assert!(found["result"][0].get("output").is_none());
+
+        let counted = request(
+            &storage,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "query", "params": {"expr": "count"}}"#,
+        );
+        assert_eq!(counted["result"], 1);
+
+        let shown = request(
+            &storage,
+            &format!(
+                r#"{{"jsonrpc": "2.0", "id": 3, "method": "show", "params": {{"id": "{}"}}}}"#,
+                &cmd.id[..8]
+            ),
+        );
+        assert_eq!(shown["result"]["output"], "test result: FAILED");
+
+        let stats = request(
+            &storage,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "stats"}"#,
+        );
+        assert_eq!(stats["result"]["total_commands"], 1);
+    }
+
+    #[test]
+    fn test_errors() {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+
+        let code = |message: &str| request(&storage, message)["error"]["code"].clone();
+        assert_eq!(code("{"), PARSE_ERROR);
+        assert_eq!(code(r#"{"id": 1, "method": "query"}"#), INVALID_REQUEST);
+        assert_eq!(
+            code(r#"{"jsonrpc": "2.0", "id": 1, "method": "nope"}"#),
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc": "2.0", "id": 1, "method": "show", "params": {}}"#),
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc": "2.0", "id": 1, "method": "query", "params": {"since": "soon"}}"#),
+            INVALID_PARAMS
+        );
+
+        // Notifications are not answered, even in a batch
+        assert!(answer(&storage, r#"{"jsonrpc": "2.0", "method": "stats"}"#).is_none());
+        let batch = request(
+            &storage,
+            r#"[{"jsonrpc": "2.0", "method": "stats"}, {"jsonrpc": "2.0", "id": 7, "method": "sessions"}]"#,
+        );
+        assert_eq!(batch.as_array().unwrap().len(), 1);
+        assert_eq!(batch[0]["id"], 7);
+    }
+
+    #[test]
+    fn test_read_message() {
+        let mut input =
+            "\n{\"a\": 1}\nContent-Length: 8\r\nContent-Type: x\r\n\r\n{\"b\": 2}".as_bytes();
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some((Framing::Lines, "{\"a\": 1}".to_string()))
+        );
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some((Framing::Headers, "{\"b\": 2}".to_string()))
+        );
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::cli::{self, OutputFormat};
+use crate::dir;
+use crate::list;
+use crate::timespec::parse_time;
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use shelltape_core::models::CommandView;
+use shelltape_core::storage::Storage;
+
+/// Which parts of a record the query is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fields {
+    /// Command line, directory and output
+    All,
+    /// Only the command line
+    Command,
+    /// Only the captured output
+    Output,
+}
+
+/// Filters for `search`
+pub struct SearchFilter {
+    /// Treat the query as a regular expression instead of a substring
+    pub regex: bool,
+    pub fields: Fields,
+    /// Only commands run in this directory or below it
+    pub cwd: Option<String>,
+    /// Only commands that exited with this code
+    pub exit_code: Option<i32>,
+    /// Only commands with this tag
+    pub tag: Option<String>,
+    /// Only commands started at or after this time
+    pub since: Option<String>,
+    /// Only commands started before this time
+    pub until: Option<String>,
+    /// Maximum number of commands to show
+    pub limit: usize,
+}
+
+/// Search the history, newest matches first
+pub fn search(
+    storage: &Storage,
+    query: &str,
+    filter: SearchFilter,
+    format: OutputFormat,
+) -> Result<()> {
+    let pattern = build_pattern(query, filter.regex)?;
+    let cwd = filter
+        .cwd
+        .map(|path| dir::resolve_dir(Some(path)))
+        .transpose()?;
+    let since = filter.since.as_deref().map(parse_time).transpose()?;
+    let until = filter.until.as_deref().map(parse_time).transpose()?;
+
+    let keep = |cmd: &CommandView| {
+        filter.exit_code.is_none_or(|code| cmd.exit_code == code)
+            && filter.tag.as_deref().is_none_or(|tag| cmd.has_tag(tag))
+            && since.is_none_or(|t| cmd.started_at >= t)
+            && until.is_none_or(|t| cmd.started_at < t)
+            && cwd.as_deref().is_none_or(|dir| in_dir(&cmd.cwd, dir))
+            && matches(&pattern, filter.fields, cmd)
+    };
+    // A substring lets the backend skip records that cannot contain it
+    let text = (!filter.regex).then_some(query);
+    let mut commands = storage.scan_commands(text, &keep)?;
+    commands.sort_by_key(|c| std::cmp::Reverse(c.started_at));
+    commands.truncate(filter.limit);
+
+    match format {
+        OutputFormat::Json => cli::print_json(&commands)?,
+        OutputFormat::Tsv => list::print_tsv(&commands, list::DEFAULT_COLUMNS)?,
+        OutputFormat::Text => list::print_table(&commands)?,
+    }
+    Ok(())
+}
+
+/// The query as a regex; substrings match case-insensitively, like `list --filter`
+fn build_pattern(query: &str, regex: bool) -> Result<Regex> {
+    if regex {
+        Regex::new(query).with_context(|| format!("Invalid regex: {}", query))
+    } else {
+        Ok(RegexBuilder::new(&regex::escape(query))
+            .case_insensitive(true)
+            .build()?)
+    }
+}
+
+fn matches(pattern: &Regex, fields: Fields, cmd: &CommandView) -> bool {
+    match fields {
+        Fields::All => [&cmd.command, &cmd.cwd, &cmd.output]
+            .iter()
+            .any(|field| pattern.is_match(field)),
+        Fields::Command => pattern.is_match(&cmd.command),
+        Fields::Output => pattern.is_match(&cmd.output),
+    }
+}
+
+/// Whether `cwd` is `dir` or one of its subdirectories
+fn in_dir(cwd: &str, dir: &str) -> bool {
+    cwd.strip_prefix(dir.trim_end_matches('/'))
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shelltape_core::models::Command;
+
+    #[test]
+    fn test_matches() {
+        let cmd = Command {
+            id: "abc".to_string(),
+            command: "cargo test".to_string(),
+            output: "test result: FAILED".to_string(),
+            exit_code: 101,
+            cwd: "/src/app".to_string(),
+            started_at: chrono::Utc::now(),
+            duration_ms: 0,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        };
+        let view = cmd.view();
+
+        let substring = build_pattern("failed", false).unwrap();
+        assert!(matches(&substring, Fields::All, &view));
+        assert!(matches(&substring, Fields::Output, &view));
+        assert!(!matches(&substring, Fields::Command, &view));
+
+        let regex = build_pattern(r"^cargo (test|build)$", true).unwrap();
+        assert!(matches(&regex, Fields::Command, &view));
+        assert!(!matches(
+            &build_pattern("CARGO", true).unwrap(),
+            Fields::All,
+            &view
+        ));
+        assert!(build_pattern("(", true).is_err());
+        // Regex syntax is literal in substring mode
+        assert!(!matches(
+            &build_pattern("c.rgo", false).unwrap(),
+            Fields::All,
+            &view
+        ));
+
+        assert!(in_dir("/src/app", "/src"));
+        assert!(in_dir("/src", "/src/"));
+        assert!(!in_dir("/srcs", "/src"));
+    }
+}
@@ -0,0 +1,397 @@
+use crate::metrics;
+use crate::sessions;
+use crate::stats::{self, StatsOptions};
+use crate::webui;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use shelltape_core::config::Config;
+use shelltape_core::query::Query;
+use shelltape_core::recorder::Recorder;
+use shelltape_core::storage::Storage;
+use std::collections::HashMap;
+use tiny_http::{Header, Method, Request, Server};
+
+/// Environment variable that can supply the API token
+const TOKEN_ENV: &str = "SHELLTAPE_API_TOKEN";
+
+/// Default number of commands returned by list and search
+const DEFAULT_LIMIT: usize = 50;
+
+/// Body of `POST /api/commands`
+#[derive(Debug, Deserialize)]
+struct NewCommand {
+    command: String,
+    exit_code: i32,
+    cwd: String,
+    session_id: String,
+    started_at: DateTime<Utc>,
+    #[serde(default)]
+    duration_ms: u64,
+    #[serde(default)]
+    output: String,
+}
+
+/// Status code and body of an API response
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    fn ok(body: Value) -> Self {
+        Self::json(200, body)
+    }
+
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Self::json(status, json!({ "error": message.to_string() }))
+    }
+}
+
+/// Serve the history over a local HTTP API until interrupted
+///
+/// Every request must carry `Authorization: Bearer <token>`. The token comes
+/// from `--token`, `$SHELLTAPE_API_TOKEN` or `serve.token`, in that order;
+/// without one a random token is generated and printed. With `ui`, `/` serves
+/// the browser UI, which holds no data itself and is open without the token.
+/// Commands can only be recorded through the API with `allow_record`.
+pub fn serve(
+    storage: Storage,
+    listen: Option<String>,
+    token: Option<String>,
+    ui: bool,
+    allow_record: bool,
+) -> Result<()> {
+    let config = Config::load()?;
+    let listen = listen.unwrap_or(config.serve.listen);
+    let token = token
+        .or_else(|| std::env::var(TOKEN_ENV).ok())
+        .filter(|t| !t.is_empty())
+        .unwrap_or(config.serve.token);
+    let token = if token.is_empty() {
+        let generated = uuid::Uuid::new_v4().simple().to_string();
+        eprintln!("Generated API token: {}", generated);
+        generated
+    } else {
+        token
+    };
+
+    let server =
+        Server::http(&listen).map_err(|e| anyhow!("Failed to listen on {}: {}", listen, e))?;
+    eprintln!("Serving shelltape API on http://{}", listen);
+    if ui {
+        // The fragment stays in the browser and hands the page the token
+        eprintln!("Web UI: http://{}/#token={}", listen, token);
+    }
+
+    let api = Api::new(storage, allow_record)?;
+    for request in server.incoming_requests() {
+        if let Err(e) = handle(&api, &token, ui, request) {
+            tracing::warn!("Failed to answer request: {:#}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the token and dispatch one request
+fn handle(api: &Api, token: &str, ui: bool, mut request: Request) -> Result<()> {
+    if ui && *request.method() == Method::Get && matches!(request.url(), "/" | "/index.html") {
+        let content_type = Header::from_bytes("Content-Type", "text/html; charset=utf-8")
+            .map_err(|_| anyhow!("Invalid header"))?;
+        request
+            .respond(tiny_http::Response::from_string(webui::INDEX).with_header(content_type))?;
+        return Ok(());
+    }
+
+    let authorized = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|t| tokens_match(t, token));
+
+    let response = if authorized {
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body)?;
+        api.route(request.method(), request.url(), &body)
+    } else {
+        Response::error(401, "Missing or invalid bearer token")
+    };
+    tracing::info!(method = %request.method(), url = request.url(), status = response.status, "API request");
+
+    let content_type = Header::from_bytes("Content-Type", response.content_type)
+        .map_err(|_| anyhow!("Invalid header"))?;
+    request.respond(
+        tiny_http::Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(content_type),
+    )?;
+    Ok(())
+}
+
+/// Compare a presented token with the expected one
+///
+/// Every byte is compared, so the time taken does not reveal how much of a
+/// guess was right.
+fn tokens_match(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// The API endpoints, independent of the HTTP server
+struct Api {
+    storage: Storage,
+    recorder: Recorder,
+    /// Whether `POST /api/commands` may record commands
+    allow_record: bool,
+}
+
+impl Api {
+    fn new(storage: Storage, allow_record: bool) -> Result<Self> {
+        Ok(Self {
+            recorder: Recorder::new(storage.clone())?,
+            storage,
+            allow_record,
+        })
+    }
+
+    fn route(&self, method: &Method, url: &str, body: &str) -> Response {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let query = parse_query(query);
+        let limit = match query.get("limit").map(|l| l.parse::<usize>()) {
+            Some(Ok(limit)) => limit,
+            Some(Err(_)) => return Response::error(400, "Invalid limit"),
+            None => DEFAULT_LIMIT,
+        };
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        let result = match (method, segments.as_slice()) {
+            (Method::Get, ["api", "commands"]) => self.list(&query, limit),
+            (Method::Post, ["api", "commands"]) if !self.allow_record => Ok(Response::error(
+                403,
+                "Recording is disabled; start serve with --allow-record",
+            )),
+            (Method::Post, ["api", "commands"]) => self.append(body),
+            (Method::Get, ["api", "commands", id]) => match self.storage.find_command(id) {
+                Ok(cmd) => Ok(Response::ok(json!(cmd))),
+                Err(e) => Ok(Response::error(404, e)),
+            },
+            (Method::Get, ["api", "search"]) => self.search(&query, limit),
+            (Method::Get, ["api", "stats"]) => self.stats(&query),
+            (Method::Get, ["api", "sessions"]) => self.sessions(limit),
+            (Method::Get, ["api", "sessions", id]) => {
+                match sessions::session_detail(&self.storage, id) {
+                    Ok((session, commands)) => Ok(Response::ok(
+                        json!({ "session": session, "commands": commands }),
+                    )),
+                    Err(e) => Ok(Response::error(404, e)),
+                }
+            }
+            (Method::Get, ["metrics"]) => metrics::metrics(&self.storage).map(|text| Response {
+                status: 200,
+                content_type: "text/plain; version=0.0.4",
+                body: text,
+            }),
+            (_, ["api", ..]) => Ok(Response::error(404, format!("No such endpoint: {}", path))),
+            _ => Ok(Response::error(404, "Not found")),
+        };
+
+        result.unwrap_or_else(|e| Response::error(500, format!("{:#}", e)))
+    }
+
+    /// `GET /api/commands?limit=&tag=&session=`: most recent first
+    fn list(&self, query: &HashMap<String, String>, limit: usize) -> Result<Response> {
+        let commands = Query {
+            tag: query.get("tag").cloned(),
+            session: query.get("session").cloned(),
+            ..Query::default()
+        }
+        .limit(limit)
+        .run(&self.storage)?;
+        Ok(Response::ok(json!(commands)))
+    }
+
+    /// `GET /api/search?q=&limit=`
+    fn search(&self, query: &HashMap<String, String>, limit: usize) -> Result<Response> {
+        let Some(q) = query.get("q") else {
+            return Ok(Response::error(400, "Missing query parameter: q"));
+        };
+        let commands = self.storage.search_commands(q, limit)?;
+        Ok(Response::ok(json!(commands)))
+    }
+
+    /// `GET /api/stats?filter=&since=&until=`: same report as `stats --json`
+    fn stats(&self, query: &HashMap<String, String>) -> Result<Response> {
+        let opts = StatsOptions {
+            filter: query.get("filter").cloned(),
+            since: query.get("since").cloned(),
+            until: query.get("until").cloned(),
+            ..StatsOptions::default()
+        };
+        Ok(Response::ok(stats::stats_json(&self.storage, &opts)?))
+    }
+
+    /// `GET /api/sessions?limit=`: most recent first
+    fn sessions(&self, limit: usize) -> Result<Response> {
+        let commands = self.storage.read_all_commands()?;
+        let sessions = self.storage.read_all_sessions()?;
+        let mut summaries = stats::sessions::session_summaries(&commands, &sessions);
+        summaries.truncate(limit);
+        Ok(Response::ok(json!(summaries)))
+    }
+
+    /// `POST /api/commands`: record a command, honouring exclude patterns
+    fn append(&self, body: &str) -> Result<Response> {
+        let new: NewCommand = match serde_json::from_str(body) {
+            Ok(new) => new,
+            Err(e) => return Ok(Response::error(400, format!("Invalid command: {}", e))),
+        };
+        let start = new.started_at.timestamp_nanos_opt().unwrap_or_default();
+        let end = start + new.duration_ms as i64 * 1_000_000;
+
+        let recorded = self.recorder.record(
+            new.command,
+            new.output,
+            new.exit_code,
+            start,
+            end,
+            new.cwd,
+            new.session_id,
+            Vec::new(),
+        )?;
+        Ok(match recorded {
+            Some(cmd) => Response::json(201, json!(cmd)),
+            None => Response::ok(json!({ "excluded": true })),
+        })
+    }
+}
+
+/// Parse `a=1&b=two%20words` into a map, decoding `+` and `%XX`
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'+', _) => out.push(b' '),
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 2;
+            }
+            (b, _) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_query() {
+        let query = parse_query("q=git+push&cwd=%2Ftmp%2Fx&flag&bad=%zz");
+        assert_eq!(query["q"], "git push");
+        assert_eq!(query["cwd"], "/tmp/x");
+        assert_eq!(query["flag"], "");
+        assert_eq!(query["bad"], "%zz");
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("s3cret", "s3cret"));
+        assert!(!tokens_match("s3cres", "s3cret"));
+        assert!(!tokens_match("s3cre", "s3cret"));
+        assert!(!tokens_match("", "s3cret"));
+    }
+
+    fn json(response: Response) -> Value {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn test_routes() {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        let mut api = Api {
+            recorder: Recorder::with_storage(storage.clone()),
+            storage,
+            allow_record: false,
+        };
+        let new = r#"{"command": "cargo test", "exit_code": 1, "cwd": "/src",
+            "session_id": "s", "started_at": "2026-10-17T09:00:00Z", "duration_ms": 1500}"#;
+
+        // Read-only unless recording was allowed
+        assert_eq!(api.route(&Method::Post, "/api/commands", new).status, 403);
+        assert!(api.storage.read_all_commands().unwrap().is_empty());
+        api.allow_record = true;
+
+        let created = api.route(&Method::Post, "/api/commands", new);
+        assert_eq!(created.status, 201);
+        let id = json(created)["id"].as_str().unwrap().to_string();
+
+        let shown = json(api.route(&Method::Get, &format!("/api/commands/{}", &id[..8]), ""));
+        assert_eq!(shown["command"], "cargo test");
+        assert_eq!(shown["duration_ms"], 1500);
+
+        let found = json(api.route(&Method::Get, "/api/search?q=CARGO", ""));
+        assert_eq!(found.as_array().unwrap().len(), 1);
+
+        let stats = json(api.route(&Method::Get, "/api/stats", ""));
+        assert_eq!(stats["total_commands"], 1);
+
+        let session = json(api.route(&Method::Get, "/api/sessions/s", ""));
+        assert_eq!(session["session"]["commands"], 1);
+        assert_eq!(session["commands"][0]["id"], id.as_str());
+        assert_eq!(api.route(&Method::Get, "/api/sessions/x", "").status, 404);
+
+        let metrics = api.route(&Method::Get, "/metrics", "");
+        assert!(metrics.content_type.starts_with("text/plain"));
+        assert!(metrics.body.contains("shelltape_command_failures_total"));
+
+        assert_eq!(api.route(&Method::Post, "/api/commands", "{}").status, 400);
+        assert_eq!(
+            api.route(&Method::Get, "/api/commands/nope", "").status,
+            404
+        );
+        assert_eq!(api.route(&Method::Get, "/api/search", "").status, 400);
+        assert_eq!(
+            api.route(&Method::Get, "/api/commands?limit=x", "").status,
+            400
+        );
+        assert_eq!(api.route(&Method::Delete, "/api/stats", "").status, 404);
+    }
+}
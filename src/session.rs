@@ -0,0 +1,124 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use shelltape_core::models::Session;
+use shelltape_core::storage::Storage;
+
+/// Environment variable the shell hooks use to share a session ID
+pub const SESSION_ENV: &str = "SHELLTAPE_SESSION_ID";
+
+/// How long an ad-hoc session on the same terminal keeps being reused
+const TTY_SESSION_MAX_AGE_HOURS: i64 = 24;
+
+/// Pick the session for an `exec` run
+///
+/// An explicit `--session-id` wins, then a session with the given `--name`,
+/// then `$SHELLTAPE_SESSION_ID` (set by the hooks), then the latest session
+/// on this terminal. A new session is created if nothing matches.
+pub fn resolve_exec_session(
+    storage: &Storage,
+    session_id: Option<String>,
+    name: Option<String>,
+) -> Result<String> {
+    if let Some(id) = session_id {
+        return Ok(id);
+    }
+    if name.is_none()
+        && let Ok(id) = std::env::var(SESSION_ENV)
+    {
+        return Ok(id);
+    }
+
+    if let Some(session) = open_session(storage, name.as_deref())? {
+        tracing::debug!(session = %session.id, "Reusing session");
+        return Ok(session.id);
+    }
+
+    let session = Session {
+        id: uuid::Uuid::new_v4().to_string(),
+        started_at: Utc::now(),
+        ended_at: None,
+        hostname: hostname(),
+        shell: std::env::var("SHELL").unwrap_or_else(|_| "unknown".to_string()),
+        command_count: 0,
+        name,
+        tty: current_tty(),
+    };
+    storage.append_session(&session)?;
+    tracing::debug!(session = %session.id, tty = ?session.tty, "Created session");
+
+    Ok(session.id)
+}
+
+/// The session this shell is in, without creating one
+///
+/// `$SHELLTAPE_SESSION_ID` (set by the hooks) wins, then the latest session
+/// on this terminal, as for `exec`.
+pub fn current_session(storage: &Storage) -> Result<Option<String>> {
+    if let Ok(id) = std::env::var(SESSION_ENV) {
+        return Ok(Some(id));
+    }
+    Ok(open_session(storage, None)?.map(|s| s.id))
+}
+
+/// The latest open session on this host with `name`, or without a name on
+/// this terminal
+fn open_session(storage: &Storage, name: Option<&str>) -> Result<Option<Session>> {
+    let tty = current_tty();
+    let hostname = hostname();
+    let cutoff = Utc::now() - Duration::hours(TTY_SESSION_MAX_AGE_HOURS);
+
+    Ok(storage
+        .read_all_sessions()?
+        .into_iter()
+        .filter(|s| s.hostname == hostname && s.ended_at.is_none())
+        .filter(|s| match name {
+            Some(name) => s.name.as_deref() == Some(name),
+            None => tty.is_some() && s.tty == tty && s.started_at > cutoff,
+        })
+        .max_by_key(|s| s.started_at))
+}
+
+/// Path of the terminal attached to stdin (e.g. `/dev/pts/3`), if any
+fn current_tty() -> Option<String> {
+    use std::io::IsTerminal;
+
+    if !std::io::stdin().is_terminal() {
+        return None;
+    }
+
+    ["/proc/self/fd/0", "/dev/fd/0"]
+        .iter()
+        .find_map(|link| std::fs::read_link(link).ok())
+        .map(|path| path.to_string_lossy().into_owned())
+        .filter(|path| path.starts_with("/dev/"))
+}
+
+/// Hostname of this machine
+fn hostname() -> String {
+    hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_named_session_is_reused() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+
+        let first = resolve_exec_session(&storage, None, Some("deploy".to_string())).unwrap();
+        let second = resolve_exec_session(&storage, None, Some("deploy".to_string())).unwrap();
+        let other = resolve_exec_session(&storage, None, Some("backup".to_string())).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_eq!(storage.read_all_sessions().unwrap().len(), 2);
+
+        let explicit = resolve_exec_session(&storage, Some("given".to_string()), None).unwrap();
+        assert_eq!(explicit, "given");
+    }
+}
@@ -0,0 +1,289 @@
+use crate::cli::{self, OutputFormat};
+use crate::list::{self, truncate_end};
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use serde_json::json;
+use shelltape_core::models::{Command, Session};
+use shelltape_core::query::Query;
+use shelltape_core::storage::Storage;
+use std::collections::HashMap;
+
+/// A session with the commands recorded in it
+#[derive(Debug, Serialize)]
+pub struct SessionEntry {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub hostname: String,
+    pub shell: String,
+    pub started_at: DateTime<Utc>,
+    /// When the session was closed, if it was
+    pub ended_at: Option<DateTime<Utc>>,
+    pub commands: usize,
+    pub failed: usize,
+}
+
+/// List sessions, most recent first
+pub fn list_sessions(storage: &Storage, limit: usize, format: OutputFormat) -> Result<()> {
+    let commands: Vec<Command> = storage
+        .read_command_metadata()?
+        .into_iter()
+        .map(|(cmd, _)| cmd)
+        .collect();
+    let mut entries = session_entries(storage.read_all_sessions()?, &commands);
+    entries.truncate(limit);
+
+    if format == OutputFormat::Json {
+        cli::print_json(&entries)?;
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No sessions recorded yet.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<8}  {:<12}  {:<16}  {:<16}  {:<16}  {:<6}  {:>5}  {:>6}",
+        "Session", "Name", "Started", "Ended", "Host", "Shell", "Cmds", "Failed"
+    );
+    println!("{}", "─".repeat(100));
+    for s in &entries {
+        println!(
+            "{:<8}  {:<12}  {:<16}  {:<16}  {:<16}  {:<6}  {:>5}  {:>6}",
+            s.id.get(..8).unwrap_or(&s.id),
+            truncate_end(s.name.as_deref().unwrap_or("-"), 12),
+            format_time(Some(s.started_at)),
+            format_time(s.ended_at),
+            truncate_end(&s.hostname, 16),
+            truncate_end(shell_name(&s.shell), 6),
+            s.commands,
+            s.failed
+        );
+    }
+    println!("\nShow a session's commands with: shelltape sessions show <id>");
+
+    Ok(())
+}
+
+/// Show a session and every command recorded in it, oldest first
+pub fn show_session(storage: &Storage, id: &str, format: OutputFormat) -> Result<()> {
+    let (entry, commands) = session_detail(storage, id)?;
+
+    if format == OutputFormat::Json {
+        let json = json!({ "session": entry, "commands": commands });
+        cli::print_json(&json)?;
+        return Ok(());
+    }
+
+    println!("Session:  {}", entry.id);
+    if let Some(name) = &entry.name {
+        println!("Name:     {}", name);
+    }
+    println!("Host:     {}", entry.hostname);
+    println!("Shell:    {}", entry.shell);
+    println!("Started:  {}", format_time(Some(entry.started_at)));
+    println!("Ended:    {}", format_time(entry.ended_at));
+    println!("Commands: {} ({} failed)\n", entry.commands, entry.failed);
+    list::print_table(&commands)?;
+
+    Ok(())
+}
+
+/// The session with this ID (or unique prefix) and its commands, oldest first
+pub fn session_detail(storage: &Storage, id: &str) -> Result<(SessionEntry, Vec<Command>)> {
+    let (session_id, commands) = session_commands(storage, id)?;
+    let sessions = storage
+        .read_all_sessions()?
+        .into_iter()
+        .filter(|s| s.id == session_id)
+        .collect();
+    let entry = session_entries(sessions, &commands)
+        .pop()
+        .ok_or_else(|| anyhow!("No session found with ID: {}", id))?;
+    Ok((entry, commands))
+}
+
+/// Resolve a session ID or unique prefix to the full ID and the session's
+/// commands, oldest first
+///
+/// An exact ID wins over longer IDs it is a prefix of. Sessions known only
+/// from their commands (e.g. imported ones) count too.
+pub fn session_commands(storage: &Storage, id: &str) -> Result<(String, Vec<Command>)> {
+    let sessions = storage.read_all_sessions()?;
+    let mut commands = Query::new().session(id).run(storage)?;
+
+    let mut matches: Vec<&str> = sessions
+        .iter()
+        .map(|s| s.id.as_str())
+        .chain(commands.iter().map(|c| c.session_id.as_str()))
+        .filter(|sid| sid.starts_with(id))
+        .collect();
+    matches.sort_unstable();
+    matches.dedup();
+    let session_id = match matches.as_slice() {
+        [] => bail!("No session found with ID: {}", id),
+        _ if matches.contains(&id) => id.to_string(),
+        [one] => one.to_string(),
+        _ => bail!(
+            "Session ID prefix {} is ambiguous ({} matches); use more characters",
+            id,
+            matches.len()
+        ),
+    };
+    // The query matched every session sharing the prefix
+    commands.retain(|c| c.session_id == session_id);
+    commands.reverse();
+
+    Ok((session_id, commands))
+}
+
+/// Combine session records with counts from their commands, most recent first
+///
+/// Sessions that only appear in commands get their host and shell from the
+/// first command and start when it did.
+fn session_entries(sessions: Vec<Session>, commands: &[Command]) -> Vec<SessionEntry> {
+    let mut entries: HashMap<String, SessionEntry> = sessions
+        .into_iter()
+        .map(|s| {
+            let entry = SessionEntry {
+                id: s.id.clone(),
+                name: s.name,
+                hostname: s.hostname,
+                shell: s.shell,
+                started_at: s.started_at,
+                ended_at: s.ended_at,
+                commands: 0,
+                failed: 0,
+            };
+            (s.id, entry)
+        })
+        .collect();
+
+    for cmd in commands {
+        let entry = entries
+            .entry(cmd.session_id.clone())
+            .or_insert_with(|| SessionEntry {
+                id: cmd.session_id.clone(),
+                name: None,
+                hostname: cmd.hostname.clone(),
+                shell: cmd.shell.clone(),
+                started_at: cmd.started_at,
+                ended_at: None,
+                commands: 0,
+                failed: 0,
+            });
+        entry.started_at = entry.started_at.min(cmd.started_at);
+        entry.commands += 1;
+        if cmd.exit_code != 0 {
+            entry.failed += 1;
+        }
+    }
+
+    let mut entries: Vec<SessionEntry> = entries.into_values().collect();
+    entries.sort_by(|a, b| b.started_at.cmp(&a.started_at).then(a.id.cmp(&b.id)));
+    entries
+}
+
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    match time {
+        Some(time) => time
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// The shell's name without its directory (`/bin/zsh` → `zsh`)
+fn shell_name(shell: &str) -> &str {
+    shell.rsplit('/').next().unwrap_or(shell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn cmd(session: &str, exit_code: i32, minute: u32) -> Command {
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: "ls".to_string(),
+            exit_code,
+            cwd: "/src".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, minute, 0).unwrap(),
+            duration_ms: 0,
+            session_id: session.to_string(),
+            shell: "zsh".to_string(),
+            hostname: "laptop".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_session_entries() {
+        let sessions = vec![
+            Session {
+                id: "a".to_string(),
+                started_at: Utc.with_ymd_and_hms(2026, 10, 17, 8, 0, 0).unwrap(),
+                ended_at: Some(Utc.with_ymd_and_hms(2026, 10, 17, 10, 0, 0).unwrap()),
+                hostname: "host".to_string(),
+                shell: "/bin/bash".to_string(),
+                command_count: 0,
+                name: Some("work".to_string()),
+                tty: None,
+            },
+            Session {
+                id: "idle".to_string(),
+                started_at: Utc.with_ymd_and_hms(2026, 10, 17, 7, 0, 0).unwrap(),
+                ended_at: None,
+                hostname: "host".to_string(),
+                shell: "/bin/bash".to_string(),
+                command_count: 0,
+                name: None,
+                tty: None,
+            },
+        ];
+        let commands = vec![cmd("a", 0, 0), cmd("a", 1, 5), cmd("imported", 0, 30)];
+
+        let entries = session_entries(sessions, &commands);
+        let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["imported", "a", "idle"]);
+
+        assert_eq!(entries[0].hostname, "laptop");
+        assert_eq!(entries[0].shell, "zsh");
+        assert_eq!(entries[0].commands, 1);
+
+        assert_eq!(entries[1].name.as_deref(), Some("work"));
+        assert_eq!(entries[1].hostname, "host");
+        assert_eq!((entries[1].commands, entries[1].failed), (2, 1));
+        assert!(entries[1].ended_at.is_some());
+
+        assert_eq!(entries[2].commands, 0);
+        assert_eq!(shell_name(&entries[2].shell), "bash");
+    }
+
+    #[test]
+    fn test_session_commands() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        storage
+            .append_commands(&[cmd("s1", 0, 0), cmd("s10", 0, 1), cmd("s1", 1, 2)])
+            .unwrap();
+
+        let (id, commands) = session_commands(&storage, "s1").unwrap();
+        assert_eq!(id, "s1");
+        assert_eq!(commands.len(), 2);
+        assert!(commands[0].started_at < commands[1].started_at);
+
+        let (id, commands) = session_commands(&storage, "s10").unwrap();
+        assert_eq!((id.as_str(), commands.len()), ("s10", 1));
+        assert!(session_commands(&storage, "s").is_err());
+        assert!(session_commands(&storage, "x").is_err());
+
+        let (entry, _) = session_detail(&storage, "s1").unwrap();
+        assert_eq!((entry.commands, entry.failed), (2, 1));
+    }
+}
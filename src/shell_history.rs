@@ -0,0 +1,530 @@
+use crate::recording::{hex_digest, uuid_from};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Duration, Utc};
+use shelltape_core::models::{Command, Session};
+use shelltape_core::storage::Storage;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// zsh's `Meta` byte, which escapes bytes that have a special meaning to
+/// zsh in its history file
+const ZSH_META: u8 = 0x83;
+
+/// Render commands as a zsh extended history file (`setopt extended_history`)
+///
+/// Each entry is `: <start>:<elapsed>;<command>`. Newlines inside a command
+/// are written as backslash-newline, and bytes zsh reserves for its own
+/// tokens are metafied, as zsh itself does when saving history.
+pub fn to_zsh_history(commands: &[Command]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for cmd in commands.iter().filter(|c| !c.command.trim().is_empty()) {
+        let entry = format!(
+            ": {}:{};{}\n",
+            cmd.started_at.timestamp(),
+            cmd.duration_ms / 1000,
+            cmd.command.trim_end().replace('\n', "\\\n")
+        );
+        out.extend(metafy(entry.as_bytes()));
+    }
+    out
+}
+
+/// Render commands as a bash history file with timestamps
+///
+/// Each entry is preceded by a `#<start>` line, which bash reads when
+/// `HISTTIMEFORMAT` is set; with `shopt -s lithist`, multi-line commands are
+/// read back as one entry.
+pub fn to_bash_history(commands: &[Command]) -> String {
+    let mut out = String::new();
+    for cmd in commands.iter().filter(|c| !c.command.trim().is_empty()) {
+        out.push_str(&format!(
+            "#{}\n{}\n",
+            cmd.started_at.timestamp(),
+            cmd.command.trim_end()
+        ));
+    }
+    out
+}
+
+/// Shells whose history files can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+
+    /// Guess the format from the file name, then from its first entry
+    fn detect(path: &Path, content: &[u8]) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.contains("fish") || content.starts_with(b"- cmd: ") {
+            Shell::Fish
+        } else if name.contains("zsh") || name.contains("zhistory") || is_zsh_entry(content) {
+            Shell::Zsh
+        } else {
+            Shell::Bash
+        }
+    }
+}
+
+/// An entry of a history file
+#[derive(Debug, PartialEq)]
+struct Entry {
+    command: String,
+    /// Start time in seconds since the epoch, if the file records it
+    time: Option<i64>,
+    /// Elapsed seconds, if the file records it
+    elapsed: Option<u64>,
+}
+
+/// Import the shell's own history files
+///
+/// With no file, reads whichever of `~/.bash_history`, `~/.zsh_history`
+/// and fish's `fish_history` exist. Timestamps come from the file where it
+/// has them (bash with `HISTTIMEFORMAT`, zsh extended history, fish);
+/// entries without one are placed just after the previous timestamp, or a
+/// second apart before the first one or the file's modification time. Exit
+/// codes and directories are not kept by these files and are left empty.
+/// Commands already in the history (same ID, or same start time and command
+/// line) are skipped, so importing twice is harmless.
+pub fn import_shell_history(storage: &Storage, file: Option<PathBuf>) -> Result<()> {
+    let files = match file {
+        Some(file) => vec![file],
+        None => {
+            let files: Vec<PathBuf> = default_files()?
+                .into_iter()
+                .filter(|f| f.exists())
+                .collect();
+            if files.is_empty() {
+                bail!("No shell history files found; pass the file to import");
+            }
+            files
+        }
+    };
+
+    let mut existing: HashSet<(DateTime<Utc>, String)> = storage
+        .read_all_commands()?
+        .into_iter()
+        .map(|c| (c.started_at, c.command))
+        .collect();
+    for file in files {
+        let content =
+            std::fs::read(&file).with_context(|| format!("Failed to read: {}", file.display()))?;
+        let modified = std::fs::metadata(&file)
+            .and_then(|m| m.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+        let shell = Shell::detect(&file, &content);
+        let (commands, session) = to_records(parse(shell, &content), shell, &file, modified);
+
+        let total = commands.len();
+        let commands: Vec<Command> = commands
+            .into_iter()
+            .filter(|c| existing.insert((c.started_at, c.command.clone())))
+            .collect();
+        let added = storage.merge_commands(commands)?;
+        if added > 0 {
+            storage.merge_sessions(vec![session])?;
+        }
+
+        println!(
+            "✓ Imported {} of {} {} commands from {} ({} already present)",
+            added,
+            total,
+            shell.name(),
+            file.display(),
+            total - added
+        );
+    }
+    Ok(())
+}
+
+/// Where bash, zsh and fish keep their history by default
+fn default_files() -> Result<Vec<PathBuf>> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+    let data_dir = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home.join(".local/share"),
+    };
+    Ok(vec![
+        home.join(".bash_history"),
+        home.join(".zsh_history"),
+        home.join(".zhistory"),
+        data_dir.join("fish/fish_history"),
+    ])
+}
+
+fn parse(shell: Shell, content: &[u8]) -> Vec<Entry> {
+    let entries = match shell {
+        Shell::Bash => parse_bash(&String::from_utf8_lossy(content)),
+        Shell::Zsh => parse_zsh(&String::from_utf8_lossy(&unmetafy(content))),
+        Shell::Fish => parse_fish(&String::from_utf8_lossy(content)),
+    };
+    entries
+        .into_iter()
+        .filter(|e| !e.command.trim().is_empty())
+        .collect()
+}
+
+/// Bash history, where a `#<seconds>` line precedes each entry when
+/// `HISTTIMEFORMAT` is set
+///
+/// With timestamps, every line up to the next one belongs to the entry, as
+/// bash reads multi-line commands back; without them each line is an entry.
+fn parse_bash(content: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut in_entry = false;
+    for line in content.lines() {
+        if let Some(time) = line
+            .strip_prefix('#')
+            .filter(|t| !t.is_empty() && t.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|t| t.parse().ok())
+        {
+            entries.push(Entry {
+                command: String::new(),
+                time: Some(time),
+                elapsed: None,
+            });
+            in_entry = true;
+            continue;
+        }
+        match entries.last_mut() {
+            Some(entry) if in_entry && !entry.command.is_empty() => {
+                entry.command.push('\n');
+                entry.command.push_str(line);
+            }
+            Some(entry) if in_entry => entry.command.push_str(line),
+            _ => entries.push(Entry {
+                command: line.to_string(),
+                time: None,
+                elapsed: None,
+            }),
+        }
+    }
+    entries
+}
+
+/// Whether `line` starts like a zsh extended history entry, `: <start>:<elapsed>;`
+fn is_zsh_entry(line: &[u8]) -> bool {
+    zsh_entry(&String::from_utf8_lossy(&line[..line.len().min(40)])).is_some()
+}
+
+/// The start, elapsed time and command of a zsh extended history line
+fn zsh_entry(line: &str) -> Option<(i64, u64, &str)> {
+    let (header, command) = line.strip_prefix(": ")?.split_once(';')?;
+    let (start, elapsed) = header.split_once(':')?;
+    Some((start.trim().parse().ok()?, elapsed.parse().ok()?, command))
+}
+
+/// zsh history, plain or extended, with backslash-newline continuations
+fn parse_zsh(content: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let mut entry = match zsh_entry(line) {
+            Some((start, elapsed, command)) => Entry {
+                command: command.to_string(),
+                time: Some(start),
+                elapsed: Some(elapsed),
+            },
+            None => Entry {
+                command: line.to_string(),
+                time: None,
+                elapsed: None,
+            },
+        };
+        while entry.command.ends_with('\\')
+            && let Some(next) = lines.next()
+        {
+            entry.command.pop();
+            entry.command.push('\n');
+            entry.command.push_str(next);
+        }
+        entries.push(entry);
+    }
+    entries
+}
+
+/// fish's YAML-like history: `- cmd: <command>` with an indented `when:`
+fn parse_fish(content: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for line in content.lines() {
+        if let Some(command) = line.strip_prefix("- cmd: ") {
+            entries.push(Entry {
+                command: unescape_fish(command),
+                time: None,
+                elapsed: None,
+            });
+        } else if let Some(when) = line.trim_start().strip_prefix("when: ")
+            && let Some(entry) = entries.last_mut()
+        {
+            entry.time = when.trim().parse().ok();
+        }
+    }
+    entries
+}
+
+/// fish writes newlines as `\n` and backslashes as `\\`
+fn unescape_fish(command: &str) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Commands for the entries of one file, and a session spanning them
+fn to_records(
+    entries: Vec<Entry>,
+    shell: Shell,
+    file: &Path,
+    modified: DateTime<Utc>,
+) -> (Vec<Command>, Session) {
+    let hostname = hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    let username = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    let session_id = uuid_from(&hex_digest(file.to_string_lossy().as_bytes()), shell.name());
+
+    // Entries before the first timestamp count back from it a second apart
+    let first = entries.iter().position(|e| e.time.is_some());
+    let lead = first.unwrap_or(entries.len());
+    let anchor = first
+        .and_then(|i| entries[i].time)
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .unwrap_or(modified);
+
+    let mut last = anchor;
+    let mut since_last = 0;
+    let mut seen: HashMap<(DateTime<Utc>, String), usize> = HashMap::new();
+    let commands: Vec<Command> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let started_at = match entry.time.and_then(|t| DateTime::from_timestamp(t, 0)) {
+                Some(time) => {
+                    last = time;
+                    since_last = 0;
+                    time
+                }
+                None if i < lead => anchor - Duration::seconds((lead - i) as i64),
+                None => {
+                    since_last += 1;
+                    last + Duration::milliseconds(since_last)
+                }
+            };
+            // The same command can run twice within a second
+            let repeat = seen.entry((started_at, entry.command.clone())).or_default();
+            *repeat += 1;
+            let salt = format!("{}:{}", started_at.timestamp(), repeat);
+
+            Command {
+                id: uuid_from(&hex_digest(entry.command.as_bytes()), &salt),
+                command: entry.command,
+                output: String::new(),
+                exit_code: 0,
+                cwd: String::new(),
+                started_at,
+                duration_ms: entry.elapsed.unwrap_or_default() * 1000,
+                session_id: session_id.clone(),
+                shell: shell.name().to_string(),
+                hostname: hostname.clone(),
+                username: username.clone(),
+                timing: Vec::new(),
+                tags: Vec::new(),
+                note: None,
+                bookmarked: false,
+                rerun_of: None,
+            }
+        })
+        .collect();
+
+    let session = Session {
+        id: session_id,
+        started_at: commands
+            .iter()
+            .map(|c| c.started_at)
+            .min()
+            .unwrap_or(modified),
+        ended_at: commands
+            .iter()
+            .map(|c| c.started_at + Duration::milliseconds(c.duration_ms as i64))
+            .max(),
+        hostname,
+        shell: shell.name().to_string(),
+        command_count: commands.len() as u32,
+        name: file.file_name().map(|n| n.to_string_lossy().into_owned()),
+        tty: None,
+    };
+
+    (commands, session)
+}
+
+/// Undo [`metafy`]
+fn unmetafy(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut bytes = bytes.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            ZSH_META => out.extend(bytes.next().map(|b| b ^ 0x20)),
+            _ => out.push(byte),
+        }
+    }
+    out
+}
+
+/// Escape bytes zsh uses internally: NUL, `Meta` and its token range
+fn metafy(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    for &byte in bytes {
+        if byte == 0 || (ZSH_META..=0xa2).contains(&byte) {
+            out.push(ZSH_META);
+            out.push(byte ^ 0x20);
+        } else {
+            out.push(byte);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn cmd(command: &str) -> Command {
+        Command {
+            id: "abc".to_string(),
+            command: command.to_string(),
+            exit_code: 0,
+            cwd: "/src".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap(),
+            duration_ms: 2500,
+            session_id: "s".to_string(),
+            shell: "zsh".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_zsh_history() {
+        let history = to_zsh_history(&[cmd("for f in *; do\necho $f\ndone"), cmd("  ")]);
+        assert_eq!(
+            history,
+            b": 1792227600:2;for f in *; do\\\necho $f\\\ndone\n".to_vec()
+        );
+
+        // "é" is 0xc3 0xa9 and passes through; "ă" is 0xc4 0x83 and is escaped
+        let history = to_zsh_history(&[cmd("echo é ă")]);
+        assert!(history.ends_with(&[b' ', 0xc3, 0xa9, b' ', 0xc4, ZSH_META, 0xa3, b'\n']));
+    }
+
+    #[test]
+    fn test_bash_history() {
+        assert_eq!(
+            to_bash_history(&[cmd("ls -la"), cmd("make\n")]),
+            "#1792227600\nls -la\n#1792227600\nmake\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_bash() {
+        let entries = parse(
+            Shell::Bash,
+            b"#1792227600\nls -la\n#1792227601\nfor f in *\ndo echo\n",
+        );
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].time, Some(1792227600));
+        assert_eq!(entries[1].command, "for f in *\ndo echo");
+
+        let entries = parse(Shell::Bash, b"ls\n\n# comment\nmake\n");
+        let commands: Vec<_> = entries.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, ["ls", "# comment", "make"]);
+        assert!(entries.iter().all(|e| e.time.is_none()));
+    }
+
+    #[test]
+    fn test_parse_zsh() {
+        // Reads back what the exporter writes
+        let history = to_zsh_history(&[cmd("for f in *; do\necho $f\ndone"), cmd("echo é ă")]);
+        assert!(is_zsh_entry(&history));
+        let entries = parse(Shell::Zsh, &history);
+        assert_eq!(
+            entries[0],
+            Entry {
+                command: "for f in *; do\necho $f\ndone".to_string(),
+                time: Some(1792227600),
+                elapsed: Some(2),
+            }
+        );
+        assert_eq!(entries[1].command, "echo é ă");
+        assert_eq!(parse(Shell::Zsh, b"ls\n")[0].time, None);
+    }
+
+    #[test]
+    fn test_parse_fish() {
+        let history = b"- cmd: echo a\\\\b\\nc\n  when: 1792227600\n  paths:\n    - a\n- cmd: ls\n";
+        assert_eq!(Shell::detect(Path::new("history"), history), Shell::Fish);
+        let entries = parse(Shell::Fish, history);
+        assert_eq!(entries[0].command, "echo a\\b\nc");
+        assert_eq!(entries[0].time, Some(1792227600));
+        assert_eq!(entries[1].time, None);
+    }
+
+    #[test]
+    fn test_best_effort_times() {
+        let modified = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let entries = parse(
+            Shell::Bash,
+            b"ls\nls\n#1792227600\nmake\n#1792227600\nmake\n",
+        );
+        let (commands, session) = to_records(entries, Shell::Bash, Path::new("h"), modified);
+        let start = Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap();
+        let times: Vec<_> = commands.iter().map(|c| c.started_at).collect();
+        assert_eq!(
+            times,
+            [
+                start - Duration::seconds(2),
+                start - Duration::seconds(1),
+                start,
+                start,
+            ]
+        );
+        assert_ne!(commands[2].id, commands[3].id);
+        assert_eq!(session.command_count, 4);
+
+        // Without any timestamps, the last entry ends at the file's mtime
+        let (commands, _) = to_records(
+            parse(Shell::Bash, b"ls\n"),
+            Shell::Bash,
+            Path::new("h"),
+            modified,
+        );
+        assert_eq!(commands[0].started_at, modified - Duration::seconds(1));
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use shelltape_core::models::{Command, Stats};
+
+/// Headline numbers for one period
+#[derive(Debug, Serialize)]
+pub struct PeriodSummary {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub commands: usize,
+    /// Percentage of commands that failed
+    pub failure_rate: f64,
+    pub average_duration_ms: u64,
+    /// Most used programs and subcommands (`git status`, `cargo build`)
+    pub top_commands: Vec<(String, usize)>,
+}
+
+/// The current period next to the one before it
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub current: PeriodSummary,
+    pub previous: PeriodSummary,
+    /// Top commands this period that were not in the previous top list
+    pub new_top_commands: Vec<String>,
+}
+
+/// Compare the `length` up to `now` with the same length before it
+pub fn compare_periods(commands: &[Command], now: DateTime<Utc>, length: Duration) -> Comparison {
+    let current = summarize(commands, now - length, now);
+    let previous = summarize(commands, now - length - length, now - length);
+
+    let new_top_commands = current
+        .top_commands
+        .iter()
+        .filter(|(name, _)| !previous.top_commands.iter().any(|(prev, _)| prev == name))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    Comparison {
+        current,
+        previous,
+        new_top_commands,
+    }
+}
+
+/// Summarize the commands started in `[start, end)`
+fn summarize(commands: &[Command], start: DateTime<Utc>, end: DateTime<Utc>) -> PeriodSummary {
+    let period: Vec<Command> = commands
+        .iter()
+        .filter(|c| c.started_at >= start && c.started_at < end)
+        .cloned()
+        .collect();
+    let stats = Stats::from_commands(&period, 0);
+
+    let average_duration_ms = match period.len() {
+        0 => 0,
+        n => period.iter().map(|c| c.duration_ms).sum::<u64>() / n as u64,
+    };
+
+    PeriodSummary {
+        start,
+        end,
+        commands: stats.total_commands,
+        failure_rate: if stats.total_commands == 0 {
+            0.0
+        } else {
+            100.0 - stats.success_rate
+        },
+        average_duration_ms,
+        top_commands: stats.most_used_subcommands,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn cmd(command: &str, exit_code: i32, days_ago: i64, duration_ms: u64) -> Command {
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: "/tmp".to_string(),
+            started_at: now - Duration::days(days_ago),
+            duration_ms,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compare_periods() {
+        let commands = vec![
+            cmd("git status", 0, 1, 100),
+            cmd("docker compose up", 1, 2, 300),
+            cmd("git status -sb", 0, 8, 50),
+            cmd("cargo test", 0, 20, 10),
+        ];
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+
+        let cmp = compare_periods(&commands, now, Duration::weeks(1));
+        assert_eq!(cmp.current.commands, 2);
+        assert_eq!(cmp.current.failure_rate, 50.0);
+        assert_eq!(cmp.current.average_duration_ms, 200);
+        assert_eq!(cmp.previous.commands, 1);
+        assert_eq!(cmp.previous.failure_rate, 0.0);
+        assert_eq!(cmp.new_top_commands, vec!["docker compose"]);
+    }
+}
@@ -0,0 +1,219 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use shelltape_core::models::Command;
+use std::collections::{HashMap, HashSet};
+
+/// How many entries each ranking keeps
+const TOP: usize = 10;
+
+/// Records older than this are suggested for archiving
+const ARCHIVE_AGE_DAYS: i64 = 90;
+
+/// Where the bytes in the command history go
+#[derive(Debug, Serialize)]
+pub struct StorageReport {
+    /// Total size of all records as stored (one JSON line each)
+    pub total_bytes: u64,
+    /// Largest individual records
+    pub records: Vec<RecordUsage>,
+    /// Command lines whose runs take the most space together
+    pub commands: Vec<GroupUsage>,
+    /// Directories whose commands take the most space
+    pub directories: Vec<GroupUsage>,
+    /// Ways to reclaim space, largest first
+    pub candidates: Vec<VacuumCandidate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecordUsage {
+    pub id: String,
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+    pub bytes: u64,
+    pub output_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupUsage {
+    pub name: String,
+    pub records: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VacuumCandidate {
+    pub description: String,
+    pub records: usize,
+    pub bytes: u64,
+    /// Command that reclaims the space
+    pub action: String,
+}
+
+/// Size of a record as stored in commands.jsonl
+fn stored_size(cmd: &Command) -> u64 {
+    serde_json::to_string(cmd).map_or(0, |line| line.len() as u64 + 1)
+}
+
+/// Analyze which records, commands and directories use the most storage
+pub fn storage_report(commands: &[Command], now: DateTime<Utc>) -> StorageReport {
+    let sizes: Vec<u64> = commands.iter().map(stored_size).collect();
+    let total_bytes = sizes.iter().sum();
+
+    let mut records: Vec<RecordUsage> = commands
+        .iter()
+        .zip(&sizes)
+        .map(|(cmd, &bytes)| RecordUsage {
+            id: cmd.id.clone(),
+            command: cmd.command.clone(),
+            started_at: cmd.started_at,
+            bytes,
+            output_bytes: cmd.output.len() as u64,
+        })
+        .collect();
+    records.sort_by_key(|r| std::cmp::Reverse(r.bytes));
+    records.truncate(TOP);
+
+    let group = |key: &dyn Fn(&Command) -> &str| {
+        let mut groups: HashMap<&str, (usize, u64)> = HashMap::new();
+        for (cmd, &bytes) in commands.iter().zip(&sizes) {
+            let entry = groups.entry(key(cmd)).or_default();
+            entry.0 += 1;
+            entry.1 += bytes;
+        }
+        let mut groups: Vec<GroupUsage> = groups
+            .into_iter()
+            .map(|(name, (records, bytes))| GroupUsage {
+                name: name.to_string(),
+                records,
+                bytes,
+            })
+            .collect();
+        groups.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+        groups.truncate(TOP);
+        groups
+    };
+    let by_command = group(&|cmd| cmd.command.as_str());
+    let by_directory = group(&|cmd| cmd.cwd.as_str());
+
+    StorageReport {
+        total_bytes,
+        records,
+        commands: by_command,
+        directories: by_directory,
+        candidates: vacuum_candidates(commands, &sizes, now),
+    }
+}
+
+/// Space reclaimable with `prune` and `archive`
+fn vacuum_candidates(
+    commands: &[Command],
+    sizes: &[u64],
+    now: DateTime<Utc>,
+) -> Vec<VacuumCandidate> {
+    let mut candidates = Vec::new();
+    let mut add = |description: String, matching: Vec<u64>, action: String| {
+        if !matching.is_empty() {
+            candidates.push(VacuumCandidate {
+                description,
+                records: matching.len(),
+                bytes: matching.iter().sum(),
+                action,
+            });
+        }
+    };
+
+    // Every run of a repeated command except the most recent one
+    let mut order: Vec<usize> = (0..commands.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(commands[i].started_at));
+    let mut seen = HashSet::new();
+    let duplicates = order
+        .into_iter()
+        .filter(|&i| !seen.insert(commands[i].command.as_str()))
+        .map(|i| sizes[i])
+        .collect();
+    add(
+        "Older runs of repeated commands".to_string(),
+        duplicates,
+        "shelltape prune --duplicates".to_string(),
+    );
+
+    let failed = commands
+        .iter()
+        .zip(sizes)
+        .filter(|(cmd, _)| cmd.exit_code != 0)
+        .map(|(_, &bytes)| bytes)
+        .collect();
+    add(
+        "Failed commands".to_string(),
+        failed,
+        "shelltape prune --failed".to_string(),
+    );
+
+    let cutoff = now - Duration::days(ARCHIVE_AGE_DAYS);
+    let old = commands
+        .iter()
+        .zip(sizes)
+        .filter(|(cmd, _)| cmd.started_at < cutoff)
+        .map(|(_, &bytes)| bytes)
+        .collect();
+    add(
+        format!("Commands older than {} days", ARCHIVE_AGE_DAYS),
+        old,
+        format!(
+            "shelltape archive --older-than {}d -o old.jsonl.zst",
+            ARCHIVE_AGE_DAYS
+        ),
+    );
+
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.bytes));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn cmd(command: &str, cwd: &str, output_len: usize, exit_code: i32, days_ago: i64) -> Command {
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            output: "x".repeat(output_len),
+            exit_code,
+            cwd: cwd.to_string(),
+            started_at: now - Duration::days(days_ago),
+            duration_ms: 0,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_storage_report() {
+        let commands = vec![
+            cmd("cat big.log", "/var/log", 5000, 0, 1),
+            cmd("cat big.log", "/var/log", 4000, 0, 200),
+            cmd("ls", "/home", 10, 1, 2),
+        ];
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let report = storage_report(&commands, now);
+
+        assert_eq!(
+            report.total_bytes,
+            commands.iter().map(stored_size).sum::<u64>()
+        );
+        assert_eq!(report.records[0].output_bytes, 5000);
+        assert_eq!(report.commands[0].name, "cat big.log");
+        assert_eq!(report.commands[0].records, 2);
+        assert_eq!(report.directories[0].name, "/var/log");
+
+        // The old `cat` run is both a duplicate and archivable; `ls` failed
+        let actions: Vec<_> = report.candidates.iter().map(|c| c.records).collect();
+        assert_eq!(actions, vec![1, 1, 1]);
+        assert_eq!(report.candidates[2].action, "shelltape prune --failed");
+    }
+}
@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use shelltape_core::models::{Command, Stats};
+use std::collections::{HashMap, HashSet};
+
+/// Activity summary for one machine
+#[derive(Debug, Serialize)]
+pub struct HostSummary {
+    pub hostname: String,
+    pub commands: usize,
+    pub sessions: usize,
+    pub failed: usize,
+    /// Percentage of commands that failed
+    pub failure_rate: f64,
+    pub average_duration_ms: u64,
+    pub last_active_at: DateTime<Utc>,
+    /// Most used programs and subcommands (`git status`, `cargo build`)
+    pub top_commands: Vec<(String, usize)>,
+}
+
+/// Summarize the commands recorded on each host, busiest first
+pub fn host_summaries(commands: &[Command]) -> Vec<HostSummary> {
+    let mut by_host: HashMap<&str, Vec<Command>> = HashMap::new();
+    for cmd in commands {
+        by_host.entry(&cmd.hostname).or_default().push(cmd.clone());
+    }
+
+    let mut summaries: Vec<HostSummary> = by_host
+        .into_iter()
+        .map(|(hostname, cmds)| {
+            let sessions = cmds
+                .iter()
+                .map(|c| c.session_id.as_str())
+                .collect::<HashSet<_>>()
+                .len();
+            let mut stats = Stats::from_commands(&cmds, sessions);
+            stats.most_used_subcommands.truncate(5);
+            let failed = cmds.iter().filter(|c| c.exit_code != 0).count();
+
+            HostSummary {
+                hostname: hostname.to_string(),
+                commands: cmds.len(),
+                sessions,
+                failed,
+                failure_rate: failed as f64 / cmds.len() as f64 * 100.0,
+                average_duration_ms: cmds.iter().map(|c| c.duration_ms).sum::<u64>()
+                    / cmds.len() as u64,
+                last_active_at: cmds.iter().map(|c| c.started_at).max().unwrap_or_default(),
+                top_commands: stats.most_used_subcommands,
+            }
+        })
+        .collect();
+
+    summaries.sort_by(|a, b| {
+        b.commands
+            .cmp(&a.commands)
+            .then_with(|| a.hostname.cmp(&b.hostname))
+    });
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn cmd(hostname: &str, command: &str, exit_code: i32, session: &str) -> Command {
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: "/tmp".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap(),
+            duration_ms: 100,
+            session_id: session.to_string(),
+            shell: "bash".to_string(),
+            hostname: hostname.to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_host_summaries() {
+        let commands = vec![
+            cmd("laptop", "git status", 0, "a"),
+            cmd("server", "systemctl restart app", 1, "b"),
+            cmd("laptop", "git status -sb", 0, "a"),
+            cmd("laptop", "cargo build", 1, "c"),
+        ];
+
+        let summaries = host_summaries(&commands);
+        assert_eq!(summaries.len(), 2);
+
+        let laptop = &summaries[0];
+        assert_eq!(laptop.hostname, "laptop");
+        assert_eq!((laptop.commands, laptop.sessions, laptop.failed), (3, 2, 1));
+        assert_eq!(laptop.top_commands[0], ("git status".to_string(), 2));
+
+        let server = &summaries[1];
+        assert_eq!(server.failure_rate, 100.0);
+    }
+}
@@ -0,0 +1,958 @@
+mod compare;
+mod disk;
+mod hosts;
+mod program;
+pub mod sessions;
+mod typos;
+
+use crate::category::Categorizer;
+use crate::cli::{self, OutputFormat, StatsPeriod};
+use crate::list::{truncate_end, truncate_start};
+use crate::timespec::parse_time;
+use anyhow::Result;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use serde_json::json;
+use shelltape_core::aggregate::Aggregate;
+use shelltape_core::config::Config;
+use shelltape_core::models::format_duration;
+use shelltape_core::models::{Command, DURATION_BUCKETS, Stats};
+use shelltape_core::storage::Storage;
+
+/// Options for the `stats` command
+pub struct StatsOptions {
+    /// Number of weeks shown in the daily activity heatmap
+    pub weeks: usize,
+    /// Show a per-session breakdown instead of the overall report
+    pub by_session: bool,
+    /// Limit breakdowns to this many entries
+    pub last: Option<usize>,
+    /// Show a per-host breakdown instead of the overall report
+    pub by_host: bool,
+    /// Break down the usage of this program by subcommand and flag
+    pub program: Option<String>,
+    /// Compare the current period of this length with the previous one
+    pub compare: Option<StatsPeriod>,
+    /// Report which records and directories take the most storage
+    pub storage: bool,
+    /// Report common typos and retry loops
+    pub typos: bool,
+    /// Only count commands matching this search query
+    pub filter: Option<String>,
+    /// Only count commands started at or after this time
+    pub since: Option<String>,
+    /// Only count commands started before this time
+    pub until: Option<String>,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            weeks: 12,
+            by_session: false,
+            last: None,
+            by_host: false,
+            program: None,
+            compare: None,
+            storage: false,
+            typos: false,
+            filter: None,
+            since: None,
+            until: None,
+        }
+    }
+}
+
+impl StatsOptions {
+    fn is_scoped(&self) -> bool {
+        self.filter.is_some() || self.since.is_some() || self.until.is_some()
+    }
+
+    /// Whether a breakdown other than the main report was asked for
+    fn is_breakdown(&self) -> bool {
+        self.by_session
+            || self.by_host
+            || self.program.is_some()
+            || self.compare.is_some()
+            || self.storage
+            || self.typos
+    }
+}
+
+/// Weekday labels in the order of `Stats::by_weekday`
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Width of the longest bar in histograms
+const BAR_WIDTH: usize = 30;
+
+/// Show statistics about command history
+pub fn show_stats(storage: &Storage, opts: StatsOptions, format: OutputFormat) -> Result<()> {
+    if opts.is_breakdown() {
+        return show_breakdown(storage, &opts, format);
+    }
+
+    let overview = Overview::compute(storage, &opts)?;
+    if format == OutputFormat::Json {
+        let report = overview_json(storage, &opts, &overview);
+        cli::print_json(&report)?;
+        return Ok(());
+    }
+    print_overview(storage, &opts, overview);
+    Ok(())
+}
+
+/// Show one of the breakdowns selected by the options
+fn show_breakdown(storage: &Storage, opts: &StatsOptions, format: OutputFormat) -> Result<()> {
+    let commands = scoped_commands(storage, opts)?;
+
+    if opts.by_session {
+        return show_session_stats(storage, &commands, opts.last, format);
+    }
+    if opts.by_host {
+        return show_host_stats(&commands, format);
+    }
+    if let Some(program) = &opts.program {
+        return show_program_stats(&commands, program, format);
+    }
+    if let Some(period) = opts.compare {
+        return show_comparison(&commands, period, format);
+    }
+    if opts.storage {
+        return show_storage_stats(&commands, format);
+    }
+    show_typo_stats(&commands, format)
+}
+
+/// Print the main report
+fn print_overview(storage: &Storage, opts: &StatsOptions, overview: Overview) {
+    let Overview {
+        stats,
+        today,
+        current_streak,
+        longest_streak,
+        categories,
+        avg_duration,
+        longest,
+        size_bytes,
+    } = overview;
+    let data_dir = storage.data_dir();
+
+    println!("╔════════════════════════════════════════════════╗");
+    println!("║          Shelltape Statistics                  ║");
+    println!("╚════════════════════════════════════════════════╝");
+    println!();
+
+    if opts.is_scoped() {
+        let mut scope = Vec::new();
+        if let Some(filter) = &opts.filter {
+            scope.push(format!("matching '{}'", filter));
+        }
+        if let Some(since) = &opts.since {
+            scope.push(format!("since {}", since));
+        }
+        if let Some(until) = &opts.until {
+            scope.push(format!("until {}", until));
+        }
+        println!("🔎 Scope: commands {}", scope.join(", "));
+        println!();
+    }
+
+    println!("📊 Overview:");
+    println!("  • Total Commands:  {}", stats.total_commands);
+    println!("  • Total Sessions:  {}", stats.total_sessions);
+    println!("  • Success Rate:    {:.1}%", stats.success_rate);
+    println!();
+
+    if !stats.most_used_commands.is_empty() {
+        println!("🔥 Most Used Commands:");
+        for (i, (cmd, count)) in stats.most_used_commands.iter().enumerate().take(10) {
+            let cmd_display = if cmd.len() > 60 {
+                format!("{}...", &cmd[..57])
+            } else {
+                cmd.clone()
+            };
+            println!("  {:2}. [{:4}×] {}", i + 1, count, cmd_display);
+        }
+        println!();
+
+        // Grouped rankings, so `git status` and `git status -sb` count together
+        println!("🧰 By Program:                   By Subcommand:");
+        let rows = stats
+            .most_used_programs
+            .len()
+            .max(stats.most_used_subcommands.len());
+        for i in 0..rows {
+            let cell = |ranking: &[(String, usize)]| match ranking.get(i) {
+                Some((name, count)) => format!("[{:4}×] {}", count, truncate_end(name, 20)),
+                None => String::new(),
+            };
+            println!(
+                "  {:2}. {:<28} {}",
+                i + 1,
+                cell(&stats.most_used_programs),
+                cell(&stats.most_used_subcommands)
+            );
+        }
+        println!();
+    }
+
+    if !categories.is_empty() {
+        println!("🗂️  By Category:");
+        let max = categories[0].1;
+        for (category, count) in &categories {
+            println!(
+                "  {:<16} │{} {:>5} {:>5.1}%",
+                category,
+                bar(*count, max, BAR_WIDTH),
+                count,
+                *count as f64 / stats.total_commands as f64 * 100.0
+            );
+        }
+        println!();
+    }
+
+    if stats.total_commands > 0 {
+        println!("⏱️  Performance:");
+        println!("  • Average Duration: {}ms", avg_duration);
+
+        if let Some(longest_cmd) = longest {
+            let cmd_display = if longest_cmd.command.len() > 50 {
+                format!("{}...", &longest_cmd.command[..47])
+            } else {
+                longest_cmd.command.clone()
+            };
+            println!(
+                "  • Longest Command:  {}ms - {}",
+                longest_cmd.duration_ms, cmd_display
+            );
+        }
+        println!(
+            "  • Percentiles:      p50 {} · p90 {} · p99 {}",
+            format_duration(stats.durations.p50),
+            format_duration(stats.durations.p90),
+            format_duration(stats.durations.p99)
+        );
+        println!();
+
+        println!("⏳ Duration by Command:");
+        println!("  {:>8} {:>8} {:>8}  Command", "p50", "p90", "p99");
+        for (cmd, p) in &stats.command_durations {
+            println!(
+                "  {:>8} {:>8} {:>8}  {}",
+                format_duration(p.p50),
+                format_duration(p.p90),
+                format_duration(p.p99),
+                truncate_end(cmd, 50)
+            );
+        }
+        println!();
+
+        println!("📶 Duration Distribution:");
+        let max = stats.duration_histogram.iter().copied().max().unwrap_or(0);
+        for ((_, label), &count) in DURATION_BUCKETS.iter().zip(&stats.duration_histogram) {
+            println!("  {:>6} │{} {}", label, bar(count, max, BAR_WIDTH), count);
+        }
+        println!();
+    }
+
+    if stats.total_commands > 0 {
+        println!("🕐 Activity by Hour:");
+        let max = stats.by_hour.iter().copied().max().unwrap_or(0);
+        for (hour, &count) in stats.by_hour.iter().enumerate() {
+            println!("  {:02} │{} {}", hour, bar(count, max, BAR_WIDTH), count);
+        }
+        println!();
+
+        println!("📅 Activity by Weekday:");
+        let max = stats.by_weekday.iter().copied().max().unwrap_or(0);
+        for (day, &count) in WEEKDAYS.iter().zip(&stats.by_weekday) {
+            println!("  {} │{} {}", day, bar(count, max, BAR_WIDTH), count);
+        }
+        println!();
+
+        println!("🗓️  Daily Activity (last {} weeks):", opts.weeks);
+        for line in heatmap(&stats, today, opts.weeks) {
+            println!("  {}", line);
+        }
+        println!("      less {} more", HEAT.iter().collect::<String>());
+        println!("  • Current Streak: {} days", current_streak);
+        println!("  • Longest Streak: {} days", longest_streak);
+        if let Some((day, count)) = stats.busiest_day() {
+            println!(
+                "  • Busiest Day:    {} ({} commands)",
+                day.format("%a %Y-%m-%d"),
+                count
+            );
+        }
+        println!();
+
+        let series = stats.success_rate_series(today, opts.weeks * 7);
+        let rates: Vec<f64> = series.iter().flatten().copied().collect();
+        if !rates.is_empty() {
+            println!("✅ Success Rate by Day (last {} weeks):", opts.weeks);
+            println!("  {}", sparkline(&series));
+            println!(
+                "  min {:.0}% · max {:.0}% (blank days had no commands)",
+                rates.iter().copied().fold(f64::INFINITY, f64::min),
+                rates.iter().copied().fold(0.0, f64::max)
+            );
+            println!();
+        }
+    }
+
+    // Storage info
+    println!("💾 Storage:");
+    println!("  • Location: {}", data_dir.display());
+
+    if let Some(size) = size_bytes {
+        let size_kb = size / 1024;
+        println!("  • Size: {} KB", size_kb);
+    }
+}
+
+/// Everything the main report shows, computed once for text and JSON output
+struct Overview {
+    stats: Stats,
+    today: NaiveDate,
+    current_streak: usize,
+    longest_streak: usize,
+    categories: Vec<(String, usize)>,
+    avg_duration: u64,
+    longest: Option<Command>,
+    size_bytes: Option<u64>,
+}
+
+impl Overview {
+    fn compute(storage: &Storage, opts: &StatsOptions) -> Result<Self> {
+        // The whole history comes from the stats cache; a scoped report
+        // tallies its commands, and only counts the sessions they belong to
+        let (aggregate, total_sessions) = if opts.is_scoped() {
+            let commands = scoped_commands(storage, opts)?;
+            let sessions = commands
+                .iter()
+                .map(|c| c.session_id.as_str())
+                .collect::<std::collections::HashSet<_>>()
+                .len();
+            (Aggregate::from_commands(&commands), sessions)
+        } else {
+            (storage.aggregate()?, storage.read_all_sessions()?.len())
+        };
+        let stats = aggregate.stats(total_sessions);
+        let today = Local::now().date_naive();
+        let (current_streak, longest_streak) = stats.streaks(today);
+
+        Ok(Self {
+            categories: Categorizer::new(&Config::load()?).counts(aggregate.command_counts()),
+            avg_duration: match aggregate.total {
+                0 => 0,
+                n => aggregate.total_duration_ms / n as u64,
+            },
+            longest: aggregate.longest,
+            size_bytes: std::fs::metadata(storage.history_file())
+                .map(|m| m.len())
+                .ok(),
+            stats,
+            today,
+            current_streak,
+            longest_streak,
+        })
+    }
+}
+
+/// The main report as JSON, as printed by `stats --format json`
+pub fn stats_json(storage: &Storage, opts: &StatsOptions) -> Result<serde_json::Value> {
+    let overview = Overview::compute(storage, opts)?;
+    Ok(overview_json(storage, opts, &overview))
+}
+
+fn overview_json(storage: &Storage, opts: &StatsOptions, o: &Overview) -> serde_json::Value {
+    json!({
+        "scope": {
+            "filter": opts.filter,
+            "since": opts.since,
+            "until": opts.until,
+        },
+        "total_commands": o.stats.total_commands,
+        "total_sessions": o.stats.total_sessions,
+        "success_rate": o.stats.success_rate,
+        "most_used_commands": o.stats
+            .most_used_commands
+            .iter()
+            .map(|(command, count)| json!({ "command": command, "count": count }))
+            .collect::<Vec<_>>(),
+        "most_used_programs": o.stats
+            .most_used_programs
+            .iter()
+            .map(|(program, count)| json!({ "program": program, "count": count }))
+            .collect::<Vec<_>>(),
+        "most_used_subcommands": o.stats
+            .most_used_subcommands
+            .iter()
+            .map(|(command, count)| json!({ "command": command, "count": count }))
+            .collect::<Vec<_>>(),
+        "categories": o.categories
+            .iter()
+            .map(|(category, count)| json!({ "category": category, "count": count }))
+            .collect::<Vec<_>>(),
+        "by_hour": o.stats.by_hour,
+        "by_weekday": WEEKDAYS
+            .iter()
+            .zip(o.stats.by_weekday)
+            .map(|(day, count)| json!({ "day": day, "count": count }))
+            .collect::<Vec<_>>(),
+        "daily": heatmap_days(o.today, opts.weeks)
+            .filter(|day| *day <= o.today)
+            .map(|day| json!({
+                "date": day.to_string(),
+                "count": o.stats.daily.get(&day).copied().unwrap_or(0),
+                "success_rate": o.stats.success_rate_series(day, 1)[0],
+            }))
+            .collect::<Vec<_>>(),
+        "streaks": {
+            "current_days": o.current_streak,
+            "longest_days": o.longest_streak,
+        },
+        "busiest_day": o.stats.busiest_day().map(|(day, count)| json!({
+            "date": day.to_string(),
+            "count": count,
+        })),
+        "average_duration_ms": o.avg_duration,
+        "duration_percentiles_ms": o.stats.durations,
+        "command_durations_ms": o.stats
+            .command_durations
+            .iter()
+            .map(|(command, p)| json!({ "command": command, "percentiles": p }))
+            .collect::<Vec<_>>(),
+        "duration_histogram": DURATION_BUCKETS
+            .iter()
+            .zip(&o.stats.duration_histogram)
+            .map(|((_, label), count)| json!({ "bucket": label, "count": count }))
+            .collect::<Vec<_>>(),
+        "longest_command": o.longest.as_ref().map(|c| json!({
+            "command": c.command,
+            "duration_ms": c.duration_ms,
+        })),
+        "storage": {
+            "location": storage.data_dir(),
+            "size_bytes": o.size_bytes,
+        },
+    })
+}
+
+/// Commands matching the `--filter`, `--since` and `--until` options
+fn scoped_commands(storage: &Storage, opts: &StatsOptions) -> Result<Vec<Command>> {
+    let since = opts.since.as_deref().map(parse_time).transpose()?;
+    let until = opts.until.as_deref().map(parse_time).transpose()?;
+
+    let mut commands = storage.read_all_commands()?;
+    commands.retain(|cmd| {
+        opts.filter.as_deref().is_none_or(|q| cmd.matches_query(q))
+            && since.is_none_or(|t| cmd.started_at >= t)
+            && until.is_none_or(|t| cmd.started_at < t)
+    });
+    Ok(commands)
+}
+
+/// Show a summary of each session, most recent first
+fn show_session_stats(
+    storage: &Storage,
+    commands: &[Command],
+    last: Option<usize>,
+    format: OutputFormat,
+) -> Result<()> {
+    let sessions = storage.read_all_sessions()?;
+
+    let mut summaries = sessions::session_summaries(commands, &sessions);
+    if let Some(last) = last {
+        summaries.truncate(last);
+    }
+
+    if format == OutputFormat::Json {
+        cli::print_json(&summaries)?;
+        return Ok(());
+    }
+
+    if summaries.is_empty() {
+        println!("No sessions with recorded commands yet.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<8}  {:<12}  {:<16}  {:>8}  {:>5}  {:>6}  Top Directory",
+        "Session", "Name", "Started", "Duration", "Cmds", "Failed"
+    );
+    println!("{}", "─".repeat(100));
+    for s in &summaries {
+        println!(
+            "{:<8}  {:<12}  {:<16}  {:>8}  {:>5}  {:>5.1}%  {}",
+            s.id.get(..8).unwrap_or(&s.id),
+            truncate_end(s.name.as_deref().unwrap_or("-"), 12),
+            s.started_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            format_duration(s.duration_ms),
+            s.commands,
+            s.failure_rate,
+            truncate_start(&s.top_directory, 40)
+        );
+    }
+
+    Ok(())
+}
+
+/// Show volume, failure rate and top commands for each host
+fn show_host_stats(commands: &[Command], format: OutputFormat) -> Result<()> {
+    let summaries = hosts::host_summaries(commands);
+
+    if format == OutputFormat::Json {
+        cli::print_json(&summaries)?;
+        return Ok(());
+    }
+
+    if summaries.is_empty() {
+        println!("No commands recorded yet.");
+        return Ok(());
+    }
+
+    let max = summaries[0].commands;
+    for h in &summaries {
+        println!(
+            "🖥️  {} │{} {} commands, {} sessions",
+            h.hostname,
+            bar(h.commands, max, 20),
+            h.commands,
+            h.sessions
+        );
+        println!(
+            "    {} failed ({:.1}%), avg {}, last active {}",
+            h.failed,
+            h.failure_rate,
+            format_duration(h.average_duration_ms),
+            h.last_active_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+        );
+        let top = h
+            .top_commands
+            .iter()
+            .map(|(name, count)| format!("{} ({})", name, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("    Top: {}", top);
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Show how a program is used, by subcommand and flag
+fn show_program_stats(commands: &[Command], program: &str, format: OutputFormat) -> Result<()> {
+    let usage = program::program_usage(commands, program);
+
+    if format == OutputFormat::Json {
+        cli::print_json(&usage)?;
+        return Ok(());
+    }
+
+    if usage.total == 0 {
+        println!("No recorded commands run `{}`.", program);
+        return Ok(());
+    }
+
+    println!("🔧 {} ({} runs)", usage.program, usage.total);
+    println!();
+
+    println!("Subcommands:");
+    let max = usage.subcommands.first().map_or(0, |s| s.count);
+    for sub in &usage.subcommands {
+        let flags = sub
+            .flags
+            .iter()
+            .map(|(flag, count)| format!("{} ({})", flag, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "  {:<14} │{} {:>4} {:>5.1}%  {:>4} failed  {}",
+            truncate_end(sub.name.as_deref().unwrap_or("(none)"), 14),
+            bar(sub.count, max, 20),
+            sub.count,
+            sub.count as f64 / usage.total as f64 * 100.0,
+            sub.failed,
+            flags
+        );
+    }
+    println!();
+
+    if !usage.flags.is_empty() {
+        println!("Common Flags:");
+        let max = usage.flags[0].1;
+        for (flag, count) in &usage.flags {
+            println!(
+                "  {:<14} │{} {}",
+                truncate_end(flag, 14),
+                bar(*count, max, 20),
+                count
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Show the current period next to the previous one, with change indicators
+fn show_comparison(commands: &[Command], period: StatsPeriod, format: OutputFormat) -> Result<()> {
+    let cmp = compare::compare_periods(commands, chrono::Utc::now(), period.duration());
+
+    if format == OutputFormat::Json {
+        cli::print_json(&cmp)?;
+        return Ok(());
+    }
+
+    let (cur, prev) = (&cmp.current, &cmp.previous);
+    println!(
+        "📈 This {} vs the previous {}",
+        period.label(),
+        period.label()
+    );
+    println!();
+    println!("  {:<14} {:>10} {:>10}  Change", "", "Current", "Previous");
+    println!(
+        "  {:<14} {:>10} {:>10}  {}",
+        "Commands",
+        cur.commands,
+        prev.commands,
+        change_percent(cur.commands as f64, prev.commands as f64)
+    );
+    println!(
+        "  {:<14} {:>9.1}% {:>9.1}%  {}",
+        "Failure rate",
+        cur.failure_rate,
+        prev.failure_rate,
+        change_points(cur.failure_rate, prev.failure_rate)
+    );
+    println!(
+        "  {:<14} {:>10} {:>10}  {}",
+        "Avg duration",
+        format_duration(cur.average_duration_ms),
+        format_duration(prev.average_duration_ms),
+        change_percent(
+            cur.average_duration_ms as f64,
+            prev.average_duration_ms as f64
+        )
+    );
+    println!();
+
+    if !cur.top_commands.is_empty() {
+        println!("  Top this {}:", period.label());
+        for (name, count) in cur.top_commands.iter().take(5) {
+            let prev_count = prev
+                .top_commands
+                .iter()
+                .find(|(p, _)| p == name)
+                .map(|(_, c)| *c);
+            let note = match prev_count {
+                Some(c) => change_percent(*count as f64, c as f64),
+                None => "new".to_string(),
+            };
+            println!("    [{:4}×] {:<30} {}", count, truncate_end(name, 30), note);
+        }
+    }
+    if !cmp.new_top_commands.is_empty() {
+        println!();
+        println!("  New in the top 10: {}", cmp.new_top_commands.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Show the most common typos and retry loops
+fn show_typo_stats(commands: &[Command], format: OutputFormat) -> Result<()> {
+    let report = typos::typo_report(commands);
+
+    if format == OutputFormat::Json {
+        cli::print_json(&report)?;
+        return Ok(());
+    }
+
+    if report.typos.is_empty() && report.retries.is_empty() {
+        println!("No typos or retry loops found.");
+        return Ok(());
+    }
+
+    if !report.typos.is_empty() {
+        println!("⌨️  Common Typos:");
+        for t in report.typos.iter().take(10) {
+            println!(
+                "  [{:4}×] {:<24} → {}",
+                t.count,
+                truncate_end(&t.typed, 24),
+                truncate_end(&t.corrected, 40)
+            );
+        }
+
+        // A mistyped program name can be fixed once and for all
+        let aliases: Vec<String> = report
+            .typos
+            .iter()
+            .filter(|t| t.count > 1 && !t.typed.contains(' ') && !t.corrected.contains(' '))
+            .take(5)
+            .map(|t| format!("alias {}={}", t.typed, t.corrected))
+            .collect();
+        if !aliases.is_empty() {
+            println!();
+            println!("  Suggested aliases:");
+            for alias in aliases {
+                println!("    {}", alias);
+            }
+        }
+        println!();
+    }
+
+    if !report.retries.is_empty() {
+        println!("🔁 Retry Loops:");
+        for r in report.retries.iter().take(10) {
+            println!(
+                "  [{:4}×] {:<40} up to {} attempts, {} eventually succeeded",
+                r.loops,
+                truncate_end(&r.command, 40),
+                r.max_attempts,
+                r.succeeded
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Show where the stored bytes go and how to reclaim them
+fn show_storage_stats(commands: &[Command], format: OutputFormat) -> Result<()> {
+    let report = disk::storage_report(commands, chrono::Utc::now());
+
+    if format == OutputFormat::Json {
+        cli::print_json(&report)?;
+        return Ok(());
+    }
+
+    if commands.is_empty() {
+        println!("No commands recorded yet.");
+        return Ok(());
+    }
+
+    let share = |bytes: u64| bytes as f64 / report.total_bytes.max(1) as f64 * 100.0;
+
+    println!(
+        "💾 {} commands, {} stored",
+        commands.len(),
+        format_bytes(report.total_bytes)
+    );
+    println!();
+
+    println!("Largest Records:");
+    for r in &report.records {
+        println!(
+            "  {:>9} {:>5.1}%  {}  {}  {}",
+            format_bytes(r.bytes),
+            share(r.bytes),
+            r.id.get(..8).unwrap_or(&r.id),
+            r.started_at.with_timezone(&Local).format("%Y-%m-%d"),
+            truncate_end(&r.command, 50)
+        );
+    }
+    println!();
+
+    println!("Largest Commands (all runs):");
+    for g in &report.commands {
+        println!(
+            "  {:>9} {:>5.1}%  [{:4}×] {}",
+            format_bytes(g.bytes),
+            share(g.bytes),
+            g.records,
+            truncate_end(&g.name, 50)
+        );
+    }
+    println!();
+
+    println!("By Directory:");
+    for g in &report.directories {
+        println!(
+            "  {:>9} {:>5.1}%  [{:4}×] {}",
+            format_bytes(g.bytes),
+            share(g.bytes),
+            g.records,
+            truncate_start(&g.name, 50)
+        );
+    }
+    println!();
+
+    if !report.candidates.is_empty() {
+        println!("Vacuum Candidates (these may overlap):");
+        for c in &report.candidates {
+            println!(
+                "  {:>9} {:>5.1}%  {} ({} records)",
+                format_bytes(c.bytes),
+                share(c.bytes),
+                c.description,
+                c.records
+            );
+            println!("  {:>16}  → {}", "", c.action);
+        }
+    }
+
+    Ok(())
+}
+
+/// Format a byte count with a binary unit, e.g. "1.5 MiB"
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Relative change with an up/down indicator, e.g. "▲ 12.5%"
+fn change_percent(current: f64, previous: f64) -> String {
+    if previous == 0.0 {
+        return if current == 0.0 {
+            "=".into()
+        } else {
+            "▲ new".into()
+        };
+    }
+    let change = (current - previous) / previous * 100.0;
+    indicator(change, format!("{:.1}%", change.abs()))
+}
+
+/// Absolute change of a percentage, in points, e.g. "▼ 1.2pt"
+fn change_points(current: f64, previous: f64) -> String {
+    let change = current - previous;
+    indicator(change, format!("{:.1}pt", change.abs()))
+}
+
+fn indicator(change: f64, amount: String) -> String {
+    if change.abs() < 0.05 {
+        "=".to_string()
+    } else if change > 0.0 {
+        format!("▲ {}", amount)
+    } else {
+        format!("▼ {}", amount)
+    }
+}
+
+/// Heatmap shades from no activity to the busiest day
+const HEAT: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// Days covered by the heatmap: `weeks` whole weeks (Monday first) ending with this one
+fn heatmap_days(today: NaiveDate, weeks: usize) -> impl Iterator<Item = NaiveDate> {
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let start = monday - Duration::weeks(weeks.saturating_sub(1) as i64);
+    (0..weeks as i64 * 7).map(move |offset| start + Duration::days(offset))
+}
+
+/// Render daily counts as one row per weekday and one column per week
+///
+/// Shades are relative to the busiest day in the window; future days are blank.
+fn heatmap(stats: &Stats, today: NaiveDate, weeks: usize) -> Vec<String> {
+    let days: Vec<NaiveDate> = heatmap_days(today, weeks).collect();
+    let count = |day: &NaiveDate| stats.daily.get(day).copied().unwrap_or(0);
+    let max = days.iter().map(count).max().unwrap_or(0);
+
+    WEEKDAYS
+        .iter()
+        .enumerate()
+        .map(|(weekday, label)| {
+            let cells: String = days
+                .iter()
+                .skip(weekday)
+                .step_by(7)
+                .map(|day| {
+                    if *day > today {
+                        ' '
+                    } else {
+                        match count(day) {
+                            0 => HEAT[0],
+                            n => HEAT[(n * (HEAT.len() - 1)).div_ceil(max)],
+                        }
+                    }
+                })
+                .flat_map(|cell| [cell, ' '])
+                .collect();
+            format!("{} {}", label, cells.trim_end())
+        })
+        .collect()
+}
+
+/// Render percentages (0-100) as a one-line sparkline; `None` leaves a gap
+fn sparkline(values: &[Option<f64>]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    values
+        .iter()
+        .map(|value| match value {
+            Some(v) => LEVELS[((v.clamp(0.0, 100.0) / 100.0) * 7.0).round() as usize],
+            None => ' ',
+        })
+        .collect()
+}
+
+/// Render `value` as a bar scaled so that `max` fills `width` cells
+///
+/// Uses eighth-block characters for sub-cell precision, padded to `width`.
+fn bar(value: usize, max: usize, width: usize) -> String {
+    const PARTIAL: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+    let eighths = if max == 0 {
+        0
+    } else {
+        (value * width * 8).div_ceil(max)
+    };
+    let mut bar = "█".repeat(eighths / 8);
+    if eighths % 8 != 0 {
+        bar.push(PARTIAL[eighths % 8]);
+    }
+
+    let len = bar.chars().count();
+    bar.extend(std::iter::repeat_n(' ', width.saturating_sub(len)));
+    bar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heatmap() {
+        let mut stats = Stats::from_commands(&[], 0);
+        let date = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        stats.daily.insert(date(5), 4);
+        stats.daily.insert(date(13), 1);
+
+        // 2026-10-14 is a Wednesday: two full weeks, the second cut after Wednesday
+        let rows = heatmap(&stats, date(14), 2);
+        assert_eq!(rows.len(), 7);
+        assert_eq!(rows[0], "Mon █ ·");
+        assert_eq!(rows[1], "Tue · ░");
+        assert_eq!(rows[2], "Wed · ·");
+        assert_eq!(rows[3], "Thu ·");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(
+            sparkline(&[Some(0.0), Some(50.0), None, Some(100.0)]),
+            "▁▅ █"
+        );
+    }
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(10, 10, 4), "████");
+        assert_eq!(bar(5, 10, 4), "██  ");
+        assert_eq!(bar(1, 16, 4), "▎   ");
+        assert_eq!(bar(0, 0, 3), "   ");
+    }
+}
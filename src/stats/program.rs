@@ -0,0 +1,144 @@
+use serde::Serialize;
+use shelltape_core::models::Command;
+use shelltape_core::normalize;
+use std::collections::{HashMap, HashSet};
+
+/// How often each subcommand and flag of one program is used
+#[derive(Debug, Serialize)]
+pub struct ProgramUsage {
+    pub program: String,
+    pub total: usize,
+    pub subcommands: Vec<SubcommandUsage>,
+    /// Most common flags across all invocations
+    pub flags: Vec<(String, usize)>,
+}
+
+/// Usage of one subcommand
+#[derive(Debug, Serialize)]
+pub struct SubcommandUsage {
+    /// Subcommand name, or `None` for invocations without one
+    pub name: Option<String>,
+    pub count: usize,
+    pub failed: usize,
+    /// Most common flags used with this subcommand
+    pub flags: Vec<(String, usize)>,
+}
+
+/// How many flags to keep per subcommand
+const FLAGS_PER_SUBCOMMAND: usize = 3;
+
+/// Break down the invocations of `program` by subcommand and flag
+pub fn program_usage(commands: &[Command], program: &str) -> ProgramUsage {
+    let mut total = 0;
+    let mut all_flags: HashMap<String, usize> = HashMap::new();
+    let mut subcommands: HashMap<Option<String>, (usize, usize, HashMap<String, usize>)> =
+        HashMap::new();
+
+    for cmd in commands {
+        let words = normalize::program_words(&cmd.command);
+        if words.first().map(String::as_str) != Some(program) {
+            continue;
+        }
+        total += 1;
+
+        let args = &words[1..];
+        let sub = normalize::subcommand(args).map(str::to_string);
+        let entry = subcommands.entry(sub).or_default();
+        entry.0 += 1;
+        if cmd.exit_code != 0 {
+            entry.1 += 1;
+        }
+
+        for flag in flags(args) {
+            *entry.2.entry(flag.clone()).or_insert(0) += 1;
+            *all_flags.entry(flag).or_insert(0) += 1;
+        }
+    }
+
+    let mut subcommands: Vec<SubcommandUsage> = subcommands
+        .into_iter()
+        .map(|(name, (count, failed, flags))| SubcommandUsage {
+            name,
+            count,
+            failed,
+            flags: ranked(flags, FLAGS_PER_SUBCOMMAND),
+        })
+        .collect();
+    subcommands.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.name.is_none().cmp(&b.name.is_none()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    ProgramUsage {
+        program: program.to_string(),
+        total,
+        subcommands,
+        flags: ranked(all_flags, 15),
+    }
+}
+
+/// Distinct flags in an argument list, with `--flag=value` reduced to `--flag`
+fn flags(args: &[String]) -> HashSet<String> {
+    args.iter()
+        .take_while(|arg| *arg != "--")
+        .filter(|arg| arg.starts_with('-') && arg.len() > 1)
+        .filter(|arg| !arg[1..].starts_with(|c: char| c.is_ascii_digit()))
+        .map(|arg| arg.split('=').next().unwrap_or(arg).to_string())
+        .collect()
+}
+
+/// Entries sorted by count (then name), keeping the first `limit`
+fn ranked(counts: HashMap<String, usize>, limit: usize) -> Vec<(String, usize)> {
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(limit);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn cmd(command: &str, exit_code: i32) -> Command {
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: "/tmp".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 0,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_program_usage() {
+        let commands = vec![
+            cmd("git rebase -i HEAD~3", 0),
+            cmd("git rebase --continue", 1),
+            cmd("git merge --no-ff feature", 0),
+            cmd("sudo git rebase -i main", 0),
+            cmd("git", 1),
+            cmd("cargo build", 0),
+        ];
+
+        let usage = program_usage(&commands, "git");
+        assert_eq!(usage.total, 5);
+
+        let rebase = &usage.subcommands[0];
+        assert_eq!(rebase.name.as_deref(), Some("rebase"));
+        assert_eq!((rebase.count, rebase.failed), (3, 1));
+        assert_eq!(rebase.flags[0], ("-i".to_string(), 2));
+
+        assert_eq!(usage.subcommands[1].name.as_deref(), Some("merge"));
+        assert_eq!(usage.subcommands[2].name, None);
+        assert_eq!(usage.flags[0], ("-i".to_string(), 2));
+    }
+}
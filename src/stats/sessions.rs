@@ -0,0 +1,132 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use shelltape_core::models::{Command, Session};
+use std::collections::HashMap;
+
+/// Activity summary for one session
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub hostname: String,
+    pub started_at: DateTime<Utc>,
+    /// When the last command in the session finished
+    pub last_active_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub commands: usize,
+    pub failed: usize,
+    /// Percentage of commands that failed
+    pub failure_rate: f64,
+    /// Directory the most commands ran in
+    pub top_directory: String,
+}
+
+/// Summarize each session that has recorded commands, most recent first
+///
+/// Times come from the commands themselves; the session records only add
+/// the name and an earlier start time if the shell was idle at first.
+pub fn session_summaries(commands: &[Command], sessions: &[Session]) -> Vec<SessionSummary> {
+    let mut by_session: HashMap<&str, Vec<&Command>> = HashMap::new();
+    for cmd in commands {
+        by_session.entry(&cmd.session_id).or_default().push(cmd);
+    }
+    let sessions: HashMap<&str, &Session> = sessions.iter().map(|s| (s.id.as_str(), s)).collect();
+
+    let mut summaries: Vec<SessionSummary> = by_session
+        .into_iter()
+        .map(|(id, cmds)| {
+            let session = sessions.get(id);
+
+            let first = cmds.iter().map(|c| c.started_at).min().unwrap_or_default();
+            let started_at = session.map_or(first, |s| s.started_at.min(first));
+            let last_active_at = cmds
+                .iter()
+                .map(|c| c.started_at + Duration::milliseconds(c.duration_ms as i64))
+                .max()
+                .unwrap_or(started_at);
+
+            let failed = cmds.iter().filter(|c| c.exit_code != 0).count();
+
+            let mut dirs: HashMap<&str, usize> = HashMap::new();
+            for cmd in &cmds {
+                *dirs.entry(&cmd.cwd).or_insert(0) += 1;
+            }
+            let top_directory = dirs
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(dir, _)| dir.to_string())
+                .unwrap_or_default();
+
+            SessionSummary {
+                id: id.to_string(),
+                name: session.and_then(|s| s.name.clone()),
+                hostname: cmds[0].hostname.clone(),
+                started_at,
+                last_active_at,
+                duration_ms: (last_active_at - started_at).num_milliseconds().max(0) as u64,
+                commands: cmds.len(),
+                failed,
+                failure_rate: failed as f64 / cmds.len() as f64 * 100.0,
+                top_directory,
+            }
+        })
+        .collect();
+
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.started_at));
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn cmd(session: &str, cwd: &str, exit_code: i32, minute: u32) -> Command {
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: "ls".to_string(),
+            exit_code,
+            cwd: cwd.to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, minute, 0).unwrap(),
+            duration_ms: 30_000,
+            session_id: session.to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_session_summaries() {
+        let commands = vec![
+            cmd("a", "/src", 0, 0),
+            cmd("a", "/src", 1, 10),
+            cmd("a", "/tmp", 0, 20),
+            cmd("b", "/home", 0, 30),
+        ];
+        let sessions = vec![Session {
+            id: "a".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 8, 55, 0).unwrap(),
+            ended_at: None,
+            hostname: "host".to_string(),
+            shell: "bash".to_string(),
+            command_count: 3,
+            name: Some("work".to_string()),
+            tty: None,
+        }];
+
+        let summaries = session_summaries(&commands, &sessions);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].id, "b");
+
+        let a = &summaries[1];
+        assert_eq!(a.name.as_deref(), Some("work"));
+        assert_eq!(a.commands, 3);
+        assert_eq!(a.failed, 1);
+        assert_eq!(a.top_directory, "/src");
+        // 08:55 until the last command finished at 09:20:30
+        assert_eq!(a.duration_ms, (25 * 60 + 30) * 1000);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::fuzzy::edit_distance;
+use chrono::Duration;
+use serde::Serialize;
+use shelltape_core::models::Command;
+use std::collections::HashMap;
+
+/// Longest pause after a failure for the next command to count as a fix
+const MAX_GAP_SECS: i64 = 60;
+
+/// Most characters a correction may change and still count as a typo fix
+const MAX_EDITS: usize = 3;
+
+/// A mistyped command and what it was corrected to
+#[derive(Debug, Serialize)]
+pub struct Typo {
+    /// What was typed: the program name if that was the mistake, else the whole line
+    pub typed: String,
+    pub corrected: String,
+    pub count: usize,
+}
+
+/// A command that was run again, unchanged, right after failing
+#[derive(Debug, Serialize)]
+pub struct RetryLoop {
+    pub command: String,
+    /// Number of separate retry loops
+    pub loops: usize,
+    /// Most runs in a single loop
+    pub max_attempts: usize,
+    /// Loops that ended with a successful run
+    pub succeeded: usize,
+}
+
+/// Typos and retry loops found in the history
+#[derive(Debug, Serialize)]
+pub struct TypoReport {
+    pub typos: Vec<Typo>,
+    pub retries: Vec<RetryLoop>,
+}
+
+/// Find failed commands that were immediately corrected or retried
+///
+/// Only consecutive commands in the same session are compared, and the
+/// second must start within `MAX_GAP_SECS` of the failure finishing.
+pub fn typo_report(commands: &[Command]) -> TypoReport {
+    let mut by_session: HashMap<&str, Vec<&Command>> = HashMap::new();
+    for cmd in commands {
+        by_session.entry(&cmd.session_id).or_default().push(cmd);
+    }
+
+    let mut typos: HashMap<(String, String), usize> = HashMap::new();
+    let mut retries: HashMap<&str, RetryLoop> = HashMap::new();
+
+    for cmds in by_session.values_mut() {
+        cmds.sort_by_key(|c| c.started_at);
+
+        let mut i = 0;
+        while i + 1 < cmds.len() {
+            let (failed, next) = (cmds[i], cmds[i + 1]);
+            if !is_quick_follow_up(failed, next) {
+                i += 1;
+                continue;
+            }
+
+            let command = failed.command.trim();
+            if command == next.command.trim() {
+                // Follow the loop until the command changes, succeeds or stalls
+                let mut last = i + 1;
+                while last + 1 < cmds.len()
+                    && cmds[last + 1].command.trim() == command
+                    && is_quick_follow_up(cmds[last], cmds[last + 1])
+                {
+                    last += 1;
+                }
+
+                let attempts = last - i + 1;
+                let entry = retries.entry(command).or_insert_with(|| RetryLoop {
+                    command: command.to_string(),
+                    loops: 0,
+                    max_attempts: 0,
+                    succeeded: 0,
+                });
+                entry.loops += 1;
+                entry.max_attempts = entry.max_attempts.max(attempts);
+                if cmds[last].exit_code == 0 {
+                    entry.succeeded += 1;
+                }
+                i = last + 1;
+                continue;
+            }
+
+            if let Some(pair) = correction(command, next.command.trim()) {
+                *typos.entry(pair).or_insert(0) += 1;
+            }
+            i += 1;
+        }
+    }
+
+    let mut typos: Vec<Typo> = typos
+        .into_iter()
+        .map(|((typed, corrected), count)| Typo {
+            typed,
+            corrected,
+            count,
+        })
+        .collect();
+    typos.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.typed.cmp(&b.typed)));
+
+    let mut retries: Vec<RetryLoop> = retries.into_values().collect();
+    retries.sort_by(|a, b| {
+        b.loops
+            .cmp(&a.loops)
+            .then_with(|| a.command.cmp(&b.command))
+    });
+
+    TypoReport { typos, retries }
+}
+
+/// Whether `next` was run soon after `prev` failed
+fn is_quick_follow_up(prev: &Command, next: &Command) -> bool {
+    let finished = prev.started_at + Duration::milliseconds(prev.duration_ms as i64);
+    prev.exit_code != 0 && next.started_at - finished <= Duration::seconds(MAX_GAP_SECS)
+}
+
+/// The typo and its fix if `corrected` is a small edit of `typed`
+///
+/// A mistyped program name (`gti status` → `git status`) is reported as just
+/// the program so every use of it adds up; other fixes keep the whole line.
+fn correction(typed: &str, corrected: &str) -> Option<(String, String)> {
+    let limit = corrected
+        .chars()
+        .count()
+        .saturating_sub(1)
+        .clamp(1, MAX_EDITS);
+    if edit_distance(typed, corrected) > limit {
+        return None;
+    }
+
+    let (typed_program, typed_rest) = typed.split_once(' ').unwrap_or((typed, ""));
+    let (program, rest) = corrected.split_once(' ').unwrap_or((corrected, ""));
+    if typed_program != program && typed_rest.trim() == rest.trim() {
+        return Some((typed_program.to_string(), program.to_string()));
+    }
+
+    Some((typed.to_string(), corrected.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn cmd(command: &str, exit_code: i32, second: u32) -> Command {
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: "/tmp".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap()
+                + Duration::seconds(second.into()),
+            duration_ms: 100,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_typo_report() {
+        let commands = vec![
+            cmd("gti status", 127, 0),
+            cmd("git status", 0, 5),
+            cmd("gti push", 127, 10),
+            cmd("git push", 0, 12),
+            cmd("git comit -m wip", 1, 20),
+            cmd("git commit -m wip", 0, 25),
+            // Too late to count as a fix
+            cmd("sl", 127, 30),
+            cmd("ls", 0, 300),
+            cmd("curl localhost", 7, 310),
+            cmd("curl localhost", 7, 312),
+            cmd("curl localhost", 0, 314),
+            // Unrelated follow-ups are not typos
+            cmd("make", 2, 320),
+            cmd("vim Makefile", 0, 322),
+        ];
+
+        let report = typo_report(&commands);
+
+        let typos: Vec<(&str, &str, usize)> = report
+            .typos
+            .iter()
+            .map(|t| (t.typed.as_str(), t.corrected.as_str(), t.count))
+            .collect();
+        assert_eq!(
+            typos,
+            vec![
+                ("gti", "git", 2),
+                ("git comit -m wip", "git commit -m wip", 1)
+            ]
+        );
+
+        assert_eq!(report.retries.len(), 1);
+        let retry = &report.retries[0];
+        assert_eq!(retry.command, "curl localhost");
+        assert_eq!(
+            (retry.loops, retry.max_attempts, retry.succeeded),
+            (1, 3, 1)
+        );
+    }
+}
@@ -1,10 +1,24 @@
-use crate::storage::Storage;
+use crate::cli::{self, OutputFormat};
 use anyhow::Result;
+use serde_json::json;
+use shelltape_core::storage::Storage;
 use std::fs;
+use std::path::Path;
+
+/// Shell RC files checked for hook installation: (name, RC file, hook file)
+const SHELL_RC_FILES: [(&str, &str, &str); 3] = [
+    ("bash", ".bashrc", "bash.sh"),
+    ("zsh", ".zshrc", "zsh.sh"),
+    ("fish", ".config/fish/config.fish", "fish.fish"),
+];
 
 /// Show status and storage information
-pub fn show_status() -> Result<()> {
-    let storage = Storage::new()?;
+pub fn show_status(storage: &Storage, format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Json {
+        cli::print_json(&status_json(storage))?;
+        return Ok(());
+    }
+
     let data_dir = storage.data_dir();
 
     println!("╔════════════════════════════════════════════════╗");
@@ -26,7 +40,7 @@ pub fn show_status() -> Result<()> {
     }
 
     // Check commands file
-    let commands_file = data_dir.join("commands.jsonl");
+    let commands_file = storage.history_file();
     let commands_exists = commands_file.exists();
 
     println!("📝 Commands File:");
@@ -47,22 +61,14 @@ pub fn show_status() -> Result<()> {
         }
 
         // Count commands
-        if let Ok(commands) = storage.read_all_commands() {
-            println!("  • Total Commands: {}", commands.len());
-
-            if !commands.is_empty() {
-                if let Some(oldest) = commands.iter().min_by_key(|c| c.started_at) {
-                    println!(
-                        "  • Oldest: {}",
-                        oldest.started_at.format("%Y-%m-%d %H:%M:%S")
-                    );
-                }
-                if let Some(newest) = commands.iter().max_by_key(|c| c.started_at) {
-                    println!(
-                        "  • Newest: {}",
-                        newest.started_at.format("%Y-%m-%d %H:%M:%S")
-                    );
-                }
+        if let Ok(aggregate) = storage.aggregate() {
+            println!("  • Total Commands: {}", aggregate.total);
+
+            if let Some(oldest) = aggregate.oldest {
+                println!("  • Oldest: {}", oldest.format("%Y-%m-%d %H:%M:%S"));
+            }
+            if let Some(newest) = aggregate.newest {
+                println!("  • Newest: {}", newest.format("%Y-%m-%d %H:%M:%S"));
             }
         }
     } else {
@@ -102,6 +108,59 @@ pub fn show_status() -> Result<()> {
     Ok(())
 }
 
+/// Build the status report as JSON
+fn status_json(storage: &Storage) -> serde_json::Value {
+    let file_info = |path: &Path| {
+        json!({
+            "path": path,
+            "exists": path.exists(),
+            "size_bytes": fs::metadata(path).map(|m| m.len()).ok(),
+        })
+    };
+
+    let mut commands = file_info(&storage.history_file());
+    if let Ok(aggregate) = storage.aggregate() {
+        commands["total_commands"] = json!(aggregate.total);
+        commands["oldest"] = json!(aggregate.oldest);
+        commands["newest"] = json!(aggregate.newest);
+    }
+
+    let mut sessions = file_info(storage.sessions_file());
+    if let Ok(all) = storage.read_all_sessions() {
+        sessions["total_sessions"] = json!(all.len());
+    }
+
+    let hooks: serde_json::Map<String, serde_json::Value> = dirs::home_dir()
+        .map(|home| {
+            SHELL_RC_FILES
+                .iter()
+                .filter_map(|(name, rc, hook)| {
+                    hook_installed(&home.join(rc), hook).map(|i| (name.to_string(), json!(i)))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    json!({
+        "data_dir": {
+            "path": storage.data_dir(),
+            "exists": storage.data_dir().exists(),
+        },
+        "commands_file": commands,
+        "sessions_file": sessions,
+        "shell_hooks": hooks,
+    })
+}
+
+/// Check whether an RC file sources shelltape (`None` if the file doesn't exist)
+fn hook_installed(rc_path: &Path, hook_file: &str) -> Option<bool> {
+    if !rc_path.exists() {
+        return None;
+    }
+    let content = fs::read_to_string(rc_path).ok()?;
+    Some(content.contains("shelltape") || content.contains(hook_file))
+}
+
 /// Check if shell hooks are installed
 fn check_shell_hooks() {
     let home = match dirs::home_dir() {
@@ -112,45 +171,14 @@ fn check_shell_hooks() {
         }
     };
 
-    // Check bash
-    let bashrc = home.join(".bashrc");
-    if bashrc.exists() {
-        if let Ok(content) = fs::read_to_string(&bashrc) {
-            let installed = content.contains("shelltape") || content.contains("bash.sh");
-            println!(
-                "  • Bash (~/.bashrc): {}",
-                if installed {
-                    "✓ Installed"
-                } else {
-                    "✗ Not installed"
-                }
-            );
-        }
-    }
-
-    // Check zsh
-    let zshrc = home.join(".zshrc");
-    if zshrc.exists() {
-        if let Ok(content) = fs::read_to_string(&zshrc) {
-            let installed = content.contains("shelltape") || content.contains("zsh.sh");
-            println!(
-                "  • Zsh (~/.zshrc): {}",
-                if installed {
-                    "✓ Installed"
-                } else {
-                    "✗ Not installed"
-                }
-            );
-        }
-    }
-
-    // Check fish
-    let fishrc = home.join(".config/fish/config.fish");
-    if fishrc.exists() {
-        if let Ok(content) = fs::read_to_string(&fishrc) {
-            let installed = content.contains("shelltape") || content.contains("fish.fish");
+    for (name, rc, hook) in SHELL_RC_FILES {
+        if let Some(installed) = hook_installed(&home.join(rc), hook) {
+            let mut label = name.to_string();
+            label[..1].make_ascii_uppercase();
             println!(
-                "  • Fish (~/.config/fish/config.fish): {}",
+                "  • {} (~/{}): {}",
+                label,
+                rc,
                 if installed {
                     "✓ Installed"
                 } else {
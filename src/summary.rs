@@ -0,0 +1,196 @@
+use crate::cli::{self, OutputFormat};
+use crate::list::truncate_end;
+use crate::timespec::parse_time;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, Timelike, Utc};
+use serde::Serialize;
+use shelltape_core::models::{Command, format_duration};
+use shelltape_core::storage::Storage;
+use std::collections::{BTreeSet, HashMap};
+
+/// How many entries to show in each ranked section
+const TOP_N: usize = 5;
+
+/// Digest of activity over a period
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub period: String,
+    pub since: DateTime<Utc>,
+    pub total_commands: usize,
+    pub failed_commands: usize,
+    /// Distinct local clock hours with at least one command
+    pub active_hours: usize,
+    pub first_command_at: Option<DateTime<Utc>>,
+    pub last_command_at: Option<DateTime<Utc>>,
+    pub top_directories: Vec<(String, usize)>,
+    /// Failing commands with how often they failed
+    pub failures: Vec<(String, usize)>,
+    /// Longest-running commands as (command, duration in ms)
+    pub longest: Vec<(String, u64)>,
+}
+
+/// Print a digest of today's (or the past week's) activity
+pub fn show_summary(storage: &Storage, week: bool, format: OutputFormat) -> Result<()> {
+    let (period, since) = if week {
+        ("Past 7 days", Utc::now() - Duration::days(7))
+    } else {
+        ("Today", parse_time("today")?)
+    };
+
+    let commands: Vec<Command> = storage
+        .read_all_commands()?
+        .into_iter()
+        .filter(|cmd| cmd.started_at >= since)
+        .collect();
+
+    let summary = summarize(period, since, &commands);
+
+    if format == OutputFormat::Json {
+        cli::print_json(&summary)?;
+        return Ok(());
+    }
+
+    print_summary(&summary);
+    Ok(())
+}
+
+/// Compute the digest for a set of commands
+fn summarize(period: &str, since: DateTime<Utc>, commands: &[Command]) -> Summary {
+    let hours: BTreeSet<_> = commands
+        .iter()
+        .map(|c| {
+            let local = c.started_at.with_timezone(&Local);
+            (local.date_naive(), local.hour())
+        })
+        .collect();
+
+    let mut dirs: HashMap<&str, usize> = HashMap::new();
+    let mut failures: HashMap<&str, usize> = HashMap::new();
+    for cmd in commands {
+        *dirs.entry(cmd.cwd.as_str()).or_insert(0) += 1;
+        if cmd.exit_code != 0 {
+            *failures.entry(cmd.command.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let mut longest: Vec<&Command> = commands.iter().collect();
+    longest.sort_by_key(|c| std::cmp::Reverse(c.duration_ms));
+
+    Summary {
+        period: period.to_string(),
+        since,
+        total_commands: commands.len(),
+        failed_commands: failures.values().sum(),
+        active_hours: hours.len(),
+        first_command_at: commands.iter().map(|c| c.started_at).min(),
+        last_command_at: commands.iter().map(|c| c.started_at).max(),
+        top_directories: ranked(dirs),
+        failures: ranked(failures),
+        longest: longest
+            .into_iter()
+            .take(TOP_N)
+            .map(|c| (c.command.clone(), c.duration_ms))
+            .collect(),
+    }
+}
+
+/// Sort counts descending (ties alphabetically) and keep the top entries
+fn ranked(counts: HashMap<&str, usize>) -> Vec<(String, usize)> {
+    let mut ranked: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(key, count)| (key.to_string(), count))
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(TOP_N);
+    ranked
+}
+
+/// Print the digest as text
+fn print_summary(summary: &Summary) {
+    println!(
+        "📋 {} (since {})",
+        summary.period,
+        summary.since.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+    );
+    println!();
+
+    if summary.total_commands == 0 {
+        println!("No commands recorded in this period");
+        return;
+    }
+
+    println!("📊 Overview:");
+    println!("  • Commands:     {}", summary.total_commands);
+    println!("  • Failed:       {}", summary.failed_commands);
+    println!("  • Active hours: {}", summary.active_hours);
+    if let (Some(first), Some(last)) = (summary.first_command_at, summary.last_command_at) {
+        println!(
+            "  • First/last:   {} → {}",
+            first.with_timezone(&Local).format("%a %H:%M"),
+            last.with_timezone(&Local).format("%a %H:%M")
+        );
+    }
+    println!();
+
+    println!("📁 Top Directories:");
+    for (dir, count) in &summary.top_directories {
+        println!("  [{:4}×] {}", count, dir);
+    }
+    println!();
+
+    if !summary.failures.is_empty() {
+        println!("✗ Notable Failures:");
+        for (cmd, count) in &summary.failures {
+            println!("  [{:4}×] {}", count, truncate_end(cmd, 60));
+        }
+        println!();
+    }
+
+    println!("⏱️  Longest Running:");
+    for (cmd, duration_ms) in &summary.longest {
+        println!(
+            "  {:>10}  {}",
+            format_duration(*duration_ms),
+            truncate_end(cmd, 60)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmd(command: &str, cwd: &str, exit_code: i32, duration_ms: u64) -> Command {
+        Command {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            exit_code,
+            started_at: Utc::now(),
+            duration_ms,
+            cwd: cwd.to_string(),
+            session_id: "session-1".to_string(),
+            shell: "bash".to_string(),
+            hostname: "localhost".to_string(),
+            username: "testuser".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let commands = vec![
+            cmd("cargo build", "/repo", 0, 30_000),
+            cmd("cargo test", "/repo", 101, 5_000),
+            cmd("cargo test", "/repo", 101, 6_000),
+            cmd("ls", "/tmp", 0, 3),
+        ];
+
+        let summary = summarize("Today", Utc::now(), &commands);
+        assert_eq!(summary.total_commands, 4);
+        assert_eq!(summary.failed_commands, 2);
+        assert_eq!(summary.active_hours, 1);
+        assert_eq!(summary.top_directories[0], ("/repo".to_string(), 3));
+        assert_eq!(summary.failures, vec![("cargo test".to_string(), 2)]);
+        assert_eq!(summary.longest[0], ("cargo build".to_string(), 30_000));
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::{Context, Result, anyhow, bail};
+use chacha20poly1305::aead::{Aead, Generate, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// File in the data directory holding the sync key
+const KEY_FILE: &str = "sync.key";
+
+/// Leading bytes of every encrypted blob, naming the format version
+const MAGIC: &[u8] = b"STE1";
+
+/// Length of an XChaCha20-Poly1305 nonce
+const NONCE_LEN: usize = 24;
+
+/// The symmetric key all machines syncing one history share
+pub struct SyncKey(Key);
+
+impl SyncKey {
+    /// A new random key
+    pub fn generate() -> Self {
+        SyncKey(Key::generate())
+    }
+
+    /// Parse a key given as 64 hex digits
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let bytes = decode_hex(hex.trim())
+            .filter(|bytes| bytes.len() == 32)
+            .ok_or_else(|| anyhow!("A sync key is 64 hex digits"))?;
+        Ok(SyncKey(Key::try_from(bytes.as_slice())?))
+    }
+
+    pub fn to_hex(&self) -> String {
+        encode_hex(&self.0)
+    }
+
+    /// A short digest that identifies the key without revealing it
+    pub fn fingerprint(&self) -> String {
+        let mut hex = sha256_hex(&self.0);
+        hex.truncate(16);
+        hex
+    }
+
+    /// Load the key from the data directory, if one was created or imported
+    pub fn load(data_dir: &Path) -> Result<Option<Self>> {
+        let path = data_dir.join(KEY_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let hex = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read: {}", path.display()))?;
+        Self::from_hex(&hex)
+            .with_context(|| format!("Invalid sync key in {}", path.display()))
+            .map(Some)
+    }
+
+    /// Save the key to the data directory, readable only by the owner
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join(KEY_FILE);
+        fs::write(&path, format!("{}\n", self.to_hex()))
+            .with_context(|| format!("Failed to write: {}", path.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        }
+
+        Ok(())
+    }
+
+    /// Encrypt `plaintext` under a fresh random nonce
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = XNonce::generate();
+        let ciphertext = XChaCha20Poly1305::new(&self.0)
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("Encryption failed"))?;
+
+        let mut blob = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        blob.extend_from_slice(MAGIC);
+        blob.extend_from_slice(&nonce);
+        blob.extend_from_slice(&ciphertext);
+        Ok(blob)
+    }
+
+    /// Decrypt a blob made by [`SyncKey::seal`], checking it wasn't altered
+    pub fn open(&self, blob: &[u8]) -> Result<Vec<u8>> {
+        let Some(rest) = blob.strip_prefix(MAGIC) else {
+            bail!("Not an encrypted shelltape file");
+        };
+        if rest.len() < NONCE_LEN {
+            bail!("Encrypted file is truncated");
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = XNonce::try_from(nonce)?;
+        XChaCha20Poly1305::new(&self.0)
+            .decrypt(&nonce, ciphertext)
+            .map_err(|_| anyhow!("Decryption failed (wrong sync key, or the file was modified)"))
+    }
+}
+
+/// SHA-256 of `data` as lowercase hex
+pub fn sha256_hex(data: &[u8]) -> String {
+    encode_hex(&Sha256::digest(data))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = SyncKey::generate();
+        let blob = key.seal(b"ls -la").unwrap();
+        assert!(blob.starts_with(MAGIC));
+        assert_eq!(key.open(&blob).unwrap(), b"ls -la");
+
+        // Each seal uses a new nonce
+        assert_ne!(key.seal(b"ls -la").unwrap(), blob);
+
+        let mut tampered = blob.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(key.open(&tampered).is_err());
+        assert!(SyncKey::generate().open(&blob).is_err());
+    }
+
+    #[test]
+    fn test_key_hex() {
+        let key = SyncKey::generate();
+        let parsed = SyncKey::from_hex(&format!("{}\n", key.to_hex())).unwrap();
+        assert_eq!(parsed.fingerprint(), key.fingerprint());
+        assert_eq!(key.fingerprint().len(), 16);
+
+        assert!(SyncKey::from_hex("abcd").is_err());
+        assert!(SyncKey::from_hex(&"zz".repeat(32)).is_err());
+    }
+}
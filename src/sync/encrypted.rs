@@ -0,0 +1,315 @@
+//! End-to-end encrypted sync
+//!
+//! Records are encrypted on this machine before they leave it, so the target
+//! only ever stores ciphertext and can be anything that serves files: a
+//! directory, an SSH host, or a static object store behind HTTP. Each push
+//! uploads one immutable, content-addressed segment holding the records the
+//! target lacks, and then the encrypted manifest listing every segment.
+//! Pulls merge the records of all segments into local history by ID.
+
+use super::SyncTarget;
+use super::crypto::{SyncKey, sha256_hex};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use shelltape_core::models::{Command, Session};
+use shelltape_core::storage::Storage;
+use std::collections::HashSet;
+use std::fs;
+
+/// Encrypted list of the segments on the target
+const MANIFEST_FILE: &str = "manifest.enc";
+
+/// Directory in the data directory caching downloaded segments
+const CACHE_DIR: &str = "sync-cache";
+
+/// Pushes retried when another machine replaced the manifest meanwhile
+const MAX_ATTEMPTS: usize = 3;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    segments: Vec<String>,
+}
+
+/// The records of one segment, or of all segments together
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Batch {
+    commands: Vec<Command>,
+    sessions: Vec<Session>,
+}
+
+impl Batch {
+    fn is_empty(&self) -> bool {
+        self.commands.is_empty() && self.sessions.is_empty()
+    }
+}
+
+/// Upload local records the target doesn't have yet
+pub fn push(storage: &Storage, target: &str) -> Result<()> {
+    let key = load_key(storage)?;
+    println!("Pushing to {}...", target);
+    let target = SyncTarget::parse(target);
+
+    for _ in 0..MAX_ATTEMPTS {
+        let (mut manifest, remote) = fetch_remote(storage, &target, &key)?;
+        let batch = missing(
+            storage.read_all_commands()?,
+            storage.read_all_sessions()?,
+            &remote,
+        );
+        if batch.is_empty() {
+            println!("✓ Nothing to push");
+            return Ok(());
+        }
+
+        let blob = key.seal(&serde_json::to_vec(&batch)?)?;
+        let name = sha256_hex(&blob);
+        target.upload(&segment_file(&name), &blob)?;
+        cache_segment(storage, &name, &blob)?;
+
+        manifest.segments.push(name.clone());
+        let sealed = key.seal(&serde_json::to_vec(&manifest)?)?;
+        target.upload(MANIFEST_FILE, &sealed)?;
+
+        // A concurrent push may have replaced the manifest with one lacking
+        // our segment; push what is still missing again in that case
+        if fetch_manifest(&target, &key)?.segments.contains(&name) {
+            println!("  • Commands: pushed {}", batch.commands.len());
+            println!("  • Sessions: pushed {}", batch.sessions.len());
+            println!("✓ Push complete");
+            return Ok(());
+        }
+        tracing::info!("Manifest changed during push, retrying");
+    }
+
+    bail!("The remote manifest kept changing during the push; try again")
+}
+
+/// Merge the records of every segment on the target into local history
+pub fn pull(storage: &Storage, target: &str) -> Result<()> {
+    let key = load_key(storage)?;
+    println!("Pulling from {}...", target);
+    let (_, remote) = fetch_remote(storage, &SyncTarget::parse(target), &key)?;
+
+    let commands = storage.merge_commands(remote.commands)?;
+    println!("  • Commands: pulled {}", commands);
+    let sessions = storage.merge_sessions(remote.sessions)?;
+    println!("  • Sessions: pulled {}", sessions);
+    println!("✓ Pull complete");
+
+    Ok(())
+}
+
+/// Compare local history with the target
+pub fn status(storage: &Storage, target: &str) -> Result<()> {
+    let key = load_key(storage)?;
+    println!("Target:          {}", target);
+    println!("Key fingerprint: {}", key.fingerprint());
+
+    let (manifest, remote) = fetch_remote(storage, &SyncTarget::parse(target), &key)?;
+    let commands = storage.read_all_commands()?;
+    let sessions = storage.read_all_sessions()?;
+
+    let local_ids: HashSet<&str> = commands.iter().map(|c| c.id.as_str()).collect();
+    let to_pull = remote
+        .commands
+        .iter()
+        .filter(|c| !local_ids.contains(c.id.as_str()))
+        .count();
+    let to_push = missing(commands.clone(), sessions, &remote);
+
+    println!("Segments:        {}", manifest.segments.len());
+    println!(
+        "Remote:          {} commands, {} sessions",
+        remote.commands.len(),
+        remote.sessions.len()
+    );
+    println!(
+        "Not pushed:      {} commands, {} sessions",
+        to_push.commands.len(),
+        to_push.sessions.len()
+    );
+    println!("Not pulled:      {} commands", to_pull);
+
+    Ok(())
+}
+
+/// Print the sync key, creating one first if needed, or import another
+/// machine's key
+pub fn key(storage: &Storage, set: Option<String>) -> Result<()> {
+    let data_dir = storage.data_dir();
+
+    if let Some(hex) = set {
+        let key = SyncKey::from_hex(&hex)?;
+        key.save(data_dir)?;
+        println!("✓ Sync key saved (fingerprint {})", key.fingerprint());
+        return Ok(());
+    }
+
+    let key = match SyncKey::load(data_dir)? {
+        Some(key) => key,
+        None => {
+            let key = SyncKey::generate();
+            key.save(data_dir)?;
+            eprintln!("Created a new sync key.");
+            key
+        }
+    };
+    println!("{}", key.to_hex());
+    eprintln!(
+        "Run `shelltape sync key --set <KEY>` on your other machines. Anyone with this key can read your synced history."
+    );
+
+    Ok(())
+}
+
+fn load_key(storage: &Storage) -> Result<SyncKey> {
+    SyncKey::load(storage.data_dir())?.ok_or_else(|| {
+        anyhow!(
+            "No sync key yet. Run `shelltape sync key` to create one, or `shelltape sync key --set <KEY>` to use another machine's"
+        )
+    })
+}
+
+fn segment_file(name: &str) -> String {
+    format!("segment-{}.enc", name)
+}
+
+fn fetch_manifest(target: &SyncTarget, key: &SyncKey) -> Result<Manifest> {
+    match target.fetch(MANIFEST_FILE)? {
+        Some(blob) => {
+            let json = key
+                .open(&blob)
+                .context("Failed to decrypt the remote manifest")?;
+            serde_json::from_slice(&json).context("Failed to parse the remote manifest")
+        }
+        None => Ok(Manifest::default()),
+    }
+}
+
+/// The manifest and the records of all segments on the target
+fn fetch_remote(
+    storage: &Storage,
+    target: &SyncTarget,
+    key: &SyncKey,
+) -> Result<(Manifest, Batch)> {
+    let manifest = fetch_manifest(target, key)?;
+    let cache = storage.data_dir().join(CACHE_DIR);
+
+    let mut all = Batch::default();
+    for name in &manifest.segments {
+        // Segments never change, so a cached copy is as good as the remote one
+        let blob = match fs::read(cache.join(name)) {
+            Ok(blob) => blob,
+            Err(_) => {
+                let blob = target
+                    .fetch(&segment_file(name))?
+                    .ok_or_else(|| anyhow!("Segment {} is listed but missing", name))?;
+                if sha256_hex(&blob) != *name {
+                    bail!("Segment {} does not match its name", name);
+                }
+                cache_segment(storage, name, &blob)?;
+                blob
+            }
+        };
+
+        let json = key
+            .open(&blob)
+            .with_context(|| format!("Failed to decrypt segment {}", name))?;
+        let batch: Batch = serde_json::from_slice(&json)
+            .with_context(|| format!("Failed to parse segment {}", name))?;
+        all.commands.extend(batch.commands);
+        all.sessions.extend(batch.sessions);
+    }
+
+    Ok((manifest, all))
+}
+
+fn cache_segment(storage: &Storage, name: &str, blob: &[u8]) -> Result<()> {
+    let cache = storage.data_dir().join(CACHE_DIR);
+    fs::create_dir_all(&cache)
+        .with_context(|| format!("Failed to create directory: {}", cache.display()))?;
+    fs::write(cache.join(name), blob).context("Failed to cache segment")
+}
+
+/// Local records whose IDs are not on the target
+fn missing(commands: Vec<Command>, sessions: Vec<Session>, remote: &Batch) -> Batch {
+    let remote_commands: HashSet<&str> = remote.commands.iter().map(|c| c.id.as_str()).collect();
+    let remote_sessions: HashSet<&str> = remote.sessions.iter().map(|s| s.id.as_str()).collect();
+
+    Batch {
+        commands: commands
+            .into_iter()
+            .filter(|c| !remote_commands.contains(c.id.as_str()))
+            .collect(),
+        sessions: sessions
+            .into_iter()
+            .filter(|s| !remote_sessions.contains(s.id.as_str()))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::tempdir;
+
+    fn command(id: &str) -> Command {
+        Command {
+            id: id.to_string(),
+            command: format!("echo {}", id),
+            exit_code: 0,
+            cwd: "/tmp".to_string(),
+            started_at: Utc::now(),
+            duration_ms: 1,
+            session_id: "s".to_string(),
+            shell: "bash".to_string(),
+            hostname: "host".to_string(),
+            username: "user".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_push_and_pull() {
+        let dir = tempdir().unwrap();
+        let remote = dir.path().join("remote");
+        let remote = remote.to_str().unwrap();
+        let laptop = Storage::with_dir(dir.path().join("laptop")).unwrap();
+        let desktop = Storage::with_dir(dir.path().join("desktop")).unwrap();
+
+        let key = SyncKey::generate();
+        key.save(laptop.data_dir()).unwrap();
+        key.save(desktop.data_dir()).unwrap();
+
+        laptop.append_command(&command("a")).unwrap();
+        push(&laptop, remote).unwrap();
+        desktop.append_command(&command("b")).unwrap();
+        push(&desktop, remote).unwrap();
+        // Nothing new, so no new segment
+        push(&desktop, remote).unwrap();
+
+        let target = SyncTarget::parse(remote);
+        let manifest = fetch_manifest(&target, &key).unwrap();
+        assert_eq!(manifest.segments.len(), 2);
+        let stored = target
+            .fetch(&segment_file(&manifest.segments[0]))
+            .unwrap()
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&stored).contains("echo a"));
+
+        pull(&laptop, remote).unwrap();
+        let ids: Vec<String> = laptop
+            .read_all_commands()
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec!["a", "b"]);
+
+        // Another key can't read the history
+        let stranger = Storage::with_dir(dir.path().join("stranger")).unwrap();
+        SyncKey::generate().save(stranger.data_dir()).unwrap();
+        assert!(pull(&stranger, remote).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+mod crypto;
+pub mod encrypted;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use shelltape_core::config::Config;
+use shelltape_core::models::{Command, Session};
+use shelltape_core::storage::{self, Storage};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+/// Files exchanged during a sync
+const COMMANDS_FILE: &str = "commands.jsonl";
+const SESSIONS_FILE: &str = "sessions.jsonl";
+
+/// Where a history is synced to and from
+#[derive(Debug, PartialEq, Eq)]
+enum SyncTarget {
+    /// A local (or mounted/rsync-able) directory
+    Dir(PathBuf),
+    /// A directory on a remote host, reached with `ssh`
+    Ssh { host: String, path: String },
+    /// An HTTP endpoint accepting GET and PUT, reached with `curl`
+    Http(String),
+}
+
+impl SyncTarget {
+    /// Parse a target given as a directory, `[user@]host:path`, or URL
+    fn parse(target: &str) -> Self {
+        if target.starts_with("http://") || target.starts_with("https://") {
+            return SyncTarget::Http(target.trim_end_matches('/').to_string());
+        }
+
+        // `host:path`, but not a Windows drive like `C:\\...`
+        if let Some((host, path)) = target.split_once(':')
+            && host.len() > 1
+            && !host.contains('/')
+        {
+            return SyncTarget::Ssh {
+                host: host.to_string(),
+                path: path.to_string(),
+            };
+        }
+
+        SyncTarget::Dir(PathBuf::from(target))
+    }
+
+    /// Fetch a file from the target, returning `None` if it doesn't exist
+    fn fetch(&self, file: &str) -> Result<Option<Vec<u8>>> {
+        match self {
+            SyncTarget::Dir(dir) => {
+                let path = dir.join(file);
+                if !path.exists() {
+                    return Ok(None);
+                }
+                fs::read(&path)
+                    .map(Some)
+                    .with_context(|| format!("Failed to read: {}", path.display()))
+            }
+            SyncTarget::Ssh { host, path } => {
+                let remote = format!("{}/{}", path.trim_end_matches('/'), file);
+                let script = format!("test -f {0} && cat {0} || true", shell_quote(&remote));
+                run_capture(std::process::Command::new("ssh").arg(host).arg(script)).map(Some)
+            }
+            SyncTarget::Http(url) => {
+                let output = std::process::Command::new("curl")
+                    .args(["-sS", "-w", "\n%{http_code}"])
+                    .arg(format!("{}/{}", url, file))
+                    .output()
+                    .context("Failed to run curl")?;
+                if !output.status.success() {
+                    bail!("curl failed: {}", String::from_utf8_lossy(&output.stderr));
+                }
+
+                let split = output.stdout.iter().rposition(|b| *b == b'\n');
+                let (body, status) = match split {
+                    Some(i) => (&output.stdout[..i], &output.stdout[i + 1..]),
+                    None => (&[][..], &output.stdout[..]),
+                };
+                match String::from_utf8_lossy(status).trim() {
+                    "404" => Ok(None),
+                    code if code.starts_with('2') => Ok(Some(body.to_vec())),
+                    code => bail!("GET {}/{} returned HTTP {}", url, file, code),
+                }
+            }
+        }
+    }
+
+    /// Upload a file to the target, replacing any existing copy
+    fn upload(&self, file: &str, content: &[u8]) -> Result<()> {
+        match self {
+            SyncTarget::Dir(dir) => {
+                fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+                // Write to a temporary file first so readers never see a partial file
+                let path = dir.join(file);
+                let tmp = dir.join(format!(".{}.tmp", file));
+                fs::write(&tmp, content)
+                    .with_context(|| format!("Failed to write: {}", tmp.display()))?;
+                fs::rename(&tmp, &path)
+                    .with_context(|| format!("Failed to replace: {}", path.display()))
+            }
+            SyncTarget::Ssh { host, path } => {
+                let dir = path.trim_end_matches('/');
+                let remote = format!("{}/{}", dir, file);
+                let tmp = format!("{}/.{}.tmp", dir, file);
+                let script = format!(
+                    "mkdir -p {} && cat > {} && mv {} {}",
+                    shell_quote(dir),
+                    shell_quote(&tmp),
+                    shell_quote(&tmp),
+                    shell_quote(&remote)
+                );
+                run_with_stdin(
+                    std::process::Command::new("ssh").arg(host).arg(script),
+                    content,
+                )
+            }
+            SyncTarget::Http(url) => run_with_stdin(
+                std::process::Command::new("curl")
+                    .args(["-fsS", "-X", "PUT", "--data-binary", "@-"])
+                    .arg(format!("{}/{}", url, file)),
+                content,
+            ),
+        }
+    }
+}
+
+/// The given sync target, or the configured `sync.target`
+pub fn resolve_target(target: Option<String>) -> Result<String> {
+    match target {
+        Some(target) => Ok(target),
+        None => {
+            let target = Config::load()?.sync.target;
+            if target.is_empty() {
+                bail!("No sync target given; pass one or set sync.target");
+            }
+            Ok(target)
+        }
+    }
+}
+
+/// Sync history with a remote target, exchanging plain JSONL files
+pub fn sync(storage: &Storage, target: String, pull_only: bool, push_only: bool) -> Result<()> {
+    println!("Syncing with {}...", target);
+    let target = SyncTarget::parse(&target);
+
+    let (pulled, pushed) = sync_file::<Command, _>(
+        &target,
+        COMMANDS_FILE,
+        storage.read_all_commands()?,
+        |c| &c.id,
+        |c| c.started_at,
+        |incoming| storage.merge_commands(incoming),
+        !push_only,
+        !pull_only,
+    )?;
+    println!("  • Commands: pulled {}, pushed {}", pulled, pushed);
+
+    let (pulled, pushed) = sync_file::<Session, _>(
+        &target,
+        SESSIONS_FILE,
+        storage.read_all_sessions()?,
+        |s| &s.id,
+        |s| s.started_at,
+        |incoming| storage.merge_sessions(incoming),
+        !push_only,
+        !pull_only,
+    )?;
+    println!("  • Sessions: pulled {}, pushed {}", pulled, pushed);
+
+    println!("✓ Sync complete");
+
+    Ok(())
+}
+
+/// Sync one JSONL file, returning `(pulled, pushed)` record counts
+#[allow(clippy::too_many_arguments)]
+fn sync_file<T, K>(
+    target: &SyncTarget,
+    file: &str,
+    local: Vec<T>,
+    id: impl Fn(&T) -> &String + Copy,
+    sort_key: impl Fn(&T) -> K,
+    merge_local: impl FnOnce(Vec<T>) -> Result<usize>,
+    pull: bool,
+    push: bool,
+) -> Result<(usize, usize)>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: Ord,
+{
+    let remote: Vec<T> = match target.fetch(file)? {
+        Some(content) => parse_jsonl(&String::from_utf8_lossy(&content), file)?,
+        None => Vec::new(),
+    };
+
+    let pushed = if push {
+        let (mut merged, added) = storage::merge_records(remote.clone(), local, id);
+        if added > 0 {
+            merged.sort_by_key(|r| sort_key(r));
+            target.upload(file, to_jsonl(&merged)?.as_bytes())?;
+        }
+        added
+    } else {
+        0
+    };
+
+    let pulled = if pull { merge_local(remote)? } else { 0 };
+
+    Ok((pulled, pushed))
+}
+
+/// Parse JSONL content fetched from a target
+fn parse_jsonl<T: DeserializeOwned>(content: &str, file: &str) -> Result<Vec<T>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse line {} of remote {}", i + 1, file))
+        })
+        .collect()
+}
+
+/// Serialize records as JSONL
+fn to_jsonl<T: Serialize>(records: &[T]) -> Result<String> {
+    let mut out = String::new();
+    for record in records {
+        out.push_str(&serde_json::to_string(record)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Quote a string for a POSIX shell
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Run a command and capture its stdout
+fn run_capture(cmd: &mut std::process::Command) -> Result<Vec<u8>> {
+    let output = cmd
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run {:?}", cmd.get_program()))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{:?} exited with status {}",
+            cmd.get_program(),
+            output.status
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Run a command, feeding `input` to its stdin
+fn run_with_stdin(cmd: &mut std::process::Command, input: &[u8]) -> Result<()> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {:?}", cmd.get_program()))?;
+
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("Failed to open stdin"))?
+        .write_all(input)?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("{:?} exited with status {}", cmd.get_program(), status);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            SyncTarget::parse("/mnt/backup/shelltape"),
+            SyncTarget::Dir(PathBuf::from("/mnt/backup/shelltape"))
+        );
+        assert_eq!(
+            SyncTarget::parse("me@box:.shelltape"),
+            SyncTarget::Ssh {
+                host: "me@box".to_string(),
+                path: ".shelltape".to_string()
+            }
+        );
+        assert_eq!(
+            SyncTarget::parse("https://example.com/history/"),
+            SyncTarget::Http("https://example.com/history".to_string())
+        );
+        assert_eq!(
+            SyncTarget::parse("C:\\shelltape"),
+            SyncTarget::Dir(PathBuf::from("C:\\shelltape"))
+        );
+    }
+
+    #[test]
+    fn test_dir_round_trip() {
+        let dir = tempdir().unwrap();
+        let target = SyncTarget::Dir(dir.path().join("remote"));
+
+        assert!(target.fetch(COMMANDS_FILE).unwrap().is_none());
+        target.upload(COMMANDS_FILE, b"{}\n").unwrap();
+        assert_eq!(
+            target.fetch(COMMANDS_FILE).unwrap().as_deref(),
+            Some(&b"{}\n"[..])
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::cli::{self, OutputFormat, TagAction};
+use anyhow::{Result, bail};
+use serde_json::json;
+use shelltape_core::storage::Storage;
+use std::collections::BTreeMap;
+
+/// Handle the `tag` subcommand
+pub fn tag_command(storage: &Storage, action: TagAction, format: OutputFormat) -> Result<()> {
+    match action {
+        TagAction::Add { id, last, tags } => {
+            let (id, tags) = split_target(id, last, tags)?;
+            let target = storage.find_command_or_last(id.as_deref())?;
+
+            let cmd = storage.update_command(&target.id, |cmd| {
+                for tag in &tags {
+                    if !cmd.has_tag(tag) {
+                        cmd.tags.push(tag.clone());
+                    }
+                }
+            })?;
+
+            println!("✓ Tagged `{}`: {}", cmd.command, cmd.tags.join(", "));
+        }
+        TagAction::Rm { id, last, tags } => {
+            let (id, tags) = split_target(id, last, tags)?;
+            let target = storage.find_command_or_last(id.as_deref())?;
+
+            let cmd = storage.update_command(&target.id, |cmd| {
+                cmd.tags
+                    .retain(|t| !tags.iter().any(|r| r.eq_ignore_ascii_case(t)));
+            })?;
+
+            if cmd.tags.is_empty() {
+                println!("✓ Removed tags from `{}` (none left)", cmd.command);
+            } else {
+                println!(
+                    "✓ Removed tags from `{}`; remaining: {}",
+                    cmd.command,
+                    cmd.tags.join(", ")
+                );
+            }
+        }
+        TagAction::List => {
+            let counts = tag_counts(storage)?;
+
+            if format == OutputFormat::Json {
+                let tags: Vec<_> = counts
+                    .iter()
+                    .map(|(tag, count)| json!({ "tag": tag, "count": count }))
+                    .collect();
+                cli::print_json(&tags)?;
+                return Ok(());
+            }
+
+            if counts.is_empty() {
+                println!("No tags yet. Add one with: shelltape tag add --last <tag>");
+                return Ok(());
+            }
+
+            for (tag, count) in counts {
+                println!("[{:4}×] {}", count, tag);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Count how many commands carry each tag (tags compared case-insensitively)
+fn tag_counts(storage: &Storage) -> Result<BTreeMap<String, usize>> {
+    let mut counts = BTreeMap::new();
+    for cmd in storage.read_all_commands()? {
+        for tag in cmd.tags {
+            *counts.entry(tag.to_lowercase()).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
+/// Separate the target ID from the tags
+///
+/// With `--last` every positional argument is a tag, including the one clap
+/// parsed as the ID. Tags may also be given comma-separated.
+fn split_target(
+    id: Option<String>,
+    last: bool,
+    tags: Vec<String>,
+) -> Result<(Option<String>, Vec<String>)> {
+    let (id, raw) = if last {
+        (None, id.into_iter().chain(tags).collect::<Vec<_>>())
+    } else {
+        (id, tags)
+    };
+
+    let tags = parse_tags(raw.iter().map(String::as_str));
+    if tags.is_empty() {
+        bail!("No tags given");
+    }
+
+    Ok((id, tags))
+}
+
+/// Split comma-separated tags, dropping blanks and case-insensitive duplicates
+pub fn parse_tags<'a>(raw: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in raw.into_iter().flat_map(|t| t.split(',')) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_target() {
+        let (id, tags) = split_target(
+            Some("abc".to_string()),
+            false,
+            vec!["deploy,prod".to_string(), "Prod".to_string()],
+        )
+        .unwrap();
+        assert_eq!(id.as_deref(), Some("abc"));
+        assert_eq!(tags, vec!["deploy", "prod"]);
+
+        let (id, tags) = split_target(Some("deploy".to_string()), true, Vec::new()).unwrap();
+        assert_eq!(id, None);
+        assert_eq!(tags, vec!["deploy"]);
+
+        assert!(split_target(Some("abc".to_string()), false, Vec::new()).is_err());
+    }
+}
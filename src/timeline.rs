@@ -0,0 +1,106 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::collections::HashMap;
+
+/// Print a chronological view of one day, grouped by session
+pub fn show_timeline(storage: &Storage, date: Option<String>, gap_minutes: i64) -> Result<()> {
+    let day = parse_day(date.as_deref().unwrap_or("today"))?;
+
+    let mut commands: Vec<Command> = storage
+        .read_all_commands()?
+        .into_iter()
+        .filter(|cmd| cmd.started_at.with_timezone(&Local).date_naive() == day)
+        .collect();
+
+    if commands.is_empty() {
+        println!("No commands recorded on {}", day.format("%A %Y-%m-%d"));
+        return Ok(());
+    }
+
+    commands.sort_by_key(|cmd| cmd.started_at);
+
+    // Group by session, keeping sessions ordered by their first command
+    let mut order: Vec<&str> = Vec::new();
+    let mut sessions: HashMap<&str, Vec<&Command>> = HashMap::new();
+    for cmd in &commands {
+        let entry = sessions.entry(cmd.session_id.as_str()).or_default();
+        if entry.is_empty() {
+            order.push(cmd.session_id.as_str());
+        }
+        entry.push(cmd);
+    }
+
+    println!("📅 {}", day.format("%A %Y-%m-%d"));
+    println!(
+        "   {} commands across {} sessions",
+        commands.len(),
+        order.len()
+    );
+    println!();
+
+    for session_id in order {
+        let cmds = &sessions[session_id];
+        let first = cmds[0].started_at.with_timezone(&Local);
+        let last = cmds[cmds.len() - 1];
+        let last_end = (last.started_at + chrono::Duration::milliseconds(last.duration_ms as i64))
+            .with_timezone(&Local);
+
+        let session_display = session_id.get(..8).unwrap_or(session_id);
+        println!(
+            "── Session {} ({} – {}, {} commands) ──",
+            session_display,
+            first.format("%H:%M"),
+            last_end.format("%H:%M"),
+            cmds.len()
+        );
+
+        let mut prev_end: Option<DateTime<Utc>> = None;
+        for cmd in cmds {
+            let start = cmd.started_at;
+
+            if let Some(prev_end) = prev_end {
+                let idle = (start - prev_end).num_minutes();
+                if idle >= gap_minutes {
+                    println!("           — {} idle —", format_idle(idle));
+                }
+            }
+
+            let status = if cmd.exit_code == 0 { "✓" } else { "✗" };
+            println!(
+                "  {}  {} {}",
+                start.with_timezone(&Local).format("%H:%M:%S"),
+                status,
+                cmd.command
+            );
+
+            prev_end = Some(start + chrono::Duration::milliseconds(cmd.duration_ms as i64));
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Parse a day given as `YYYY-MM-DD`, `today` or `yesterday`
+fn parse_day(input: &str) -> Result<NaiveDate> {
+    let today = Local::now().date_naive();
+    match input {
+        "today" => Ok(today),
+        "yesterday" => today
+            .pred_opt()
+            .ok_or_else(|| anyhow!("Invalid date: {}", input)),
+        _ => NaiveDate::parse_from_str(input, "%Y-%m-%d")
+            .map_err(|_| anyhow!("Invalid date (expected YYYY-MM-DD): {}", input)),
+    }
+}
+
+/// Format an idle gap in minutes for display
+fn format_idle(minutes: i64) -> String {
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{}h {:02}min", minutes / 60, minutes % 60)
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+
+/// A span of time; either end may be open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    pub since: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TimeRange {
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| time >= since) && self.until.is_none_or(|until| time < until)
+    }
+}
+
+/// Parse a time range: a day (`today`, `2024-05-01`) for the whole day,
+/// `last 2h` or `2h` for the time since, or `FROM..TO` with either end
+/// optional, where a day as `TO` includes that day
+pub fn parse_range(input: &str) -> Result<TimeRange> {
+    let input = input.trim();
+    if let Some((from, to)) = input.split_once("..") {
+        let (from, to) = (from.trim(), to.trim());
+        let since = (!from.is_empty()).then(|| parse_time(from)).transpose()?;
+        let until = match parse_day(to) {
+            _ if to.is_empty() => None,
+            Some(day) => Some(day_start(day.succ_opt().unwrap_or(day), to)?),
+            None => Some(parse_time(to)?),
+        };
+        return Ok(TimeRange { since, until });
+    }
+
+    if let Some(day) = parse_day(input) {
+        return Ok(TimeRange {
+            since: Some(day_start(day, input)?),
+            until: Some(day_start(day.succ_opt().unwrap_or(day), input)?),
+        });
+    }
+
+    let age = input.strip_prefix("last ").unwrap_or(input).trim();
+    Ok(TimeRange {
+        since: Some(parse_time(age)?),
+        until: None,
+    })
+}
+
+/// Parse a time filter: `today`, `yesterday`, `YYYY-MM-DD` or a relative age like `2h`, `3d`, `1w`
+pub fn parse_time(input: &str) -> Result<DateTime<Utc>> {
+    if let Some(day) = parse_day(input) {
+        return day_start(day, input);
+    }
+
+    let unit_at = input.char_indices().last().map_or(0, |(i, _)| i);
+    let (amount, unit) = input.split_at(unit_at);
+    let amount: i64 = amount.parse().map_err(|_| {
+        anyhow!(
+            "Invalid time (expected YYYY-MM-DD or e.g. 2h, 3d): {}",
+            input
+        )
+    })?;
+    let age = match unit {
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => bail!("Invalid time unit in '{}' (use m, h, d or w)", input),
+    };
+
+    Ok(Utc::now() - age)
+}
+
+fn parse_day(input: &str) -> Option<NaiveDate> {
+    let today = Local::now().date_naive();
+    match input {
+        "today" => Some(today),
+        "yesterday" => today.pred_opt(),
+        _ => NaiveDate::parse_from_str(input, "%Y-%m-%d").ok(),
+    }
+}
+
+/// Local midnight at the start of `day`
+fn day_start(day: NaiveDate, input: &str) -> Result<DateTime<Utc>> {
+    let midnight = day.and_hms_opt(0, 0, 0).unwrap();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("Invalid date: {}", input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        let now = Utc::now();
+
+        let two_hours = parse_time("2h").unwrap();
+        assert!((now - two_hours - Duration::hours(2)).num_seconds().abs() < 5);
+
+        let day = parse_time("2024-03-01").unwrap();
+        assert_eq!(
+            day.with_timezone(&Local).date_naive(),
+            NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+        );
+
+        assert!(parse_time("today").unwrap() <= now);
+        assert!(parse_time("soon").is_err());
+        assert!(parse_time("3y").is_err());
+        assert!(parse_time("2é").is_err());
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let local = |t: DateTime<Utc>| t.with_timezone(&Local).naive_local().to_string();
+
+        let days = parse_range("2024-05-01..2024-05-03").unwrap();
+        assert_eq!(local(days.since.unwrap()), "2024-05-01 00:00:00");
+        assert_eq!(local(days.until.unwrap()), "2024-05-04 00:00:00");
+
+        let day = parse_range("2024-05-01").unwrap();
+        assert_eq!(local(day.until.unwrap()), "2024-05-02 00:00:00");
+        assert!(day.contains(day.since.unwrap()));
+        assert!(!day.contains(day.until.unwrap()));
+
+        let recent = parse_range("last 2h").unwrap();
+        assert_eq!(recent.until, None);
+        assert!(recent.contains(Utc::now()));
+        assert!(!recent.contains(Utc::now() - Duration::hours(3)));
+
+        let open = parse_range("..1d").unwrap();
+        assert_eq!(open.since, None);
+        assert!(open.contains(Utc::now() - Duration::days(2)));
+
+        assert!(parse_range("last week").is_err());
+        assert!(parse_range("2024-05-01..later").is_err());
+    }
+}
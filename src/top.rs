@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table},
+};
+use shelltape_core::models::{Command, RunningCommand, format_duration};
+use shelltape_core::storage::Storage;
+use std::io;
+use std::time::Duration;
+
+/// How often the view refreshes
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Show a live view of running and recently finished commands
+pub fn run_top(storage: &Storage, recent: usize) -> Result<()> {
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).context("Failed to enter alternate screen")?;
+
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).context("Failed to create terminal")?;
+
+    let result = run_loop(&mut terminal, storage, recent);
+
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)
+        .context("Failed to leave alternate screen")?;
+    terminal.show_cursor().context("Failed to show cursor")?;
+
+    result
+}
+
+/// Refresh until the user quits
+fn run_loop<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    storage: &Storage,
+    recent: usize,
+) -> Result<()> {
+    loop {
+        let running = storage.read_running()?;
+        let finished = storage.get_recent_commands(recent)?;
+
+        terminal.draw(|f| draw(f, &running, &finished))?;
+
+        if event::poll(REFRESH_INTERVAL)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => break,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw running and finished commands
+fn draw(f: &mut Frame, running: &[RunningCommand], finished: &[Command]) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(running.len().max(1) as u16 + 3), // Running commands
+            Constraint::Min(0),                                  // Finished commands
+            Constraint::Length(1),                               // Status bar
+        ])
+        .split(f.area());
+
+    let now = Utc::now();
+    let header_style = Style::default().add_modifier(Modifier::BOLD);
+
+    let running_rows: Vec<Row> = running
+        .iter()
+        .map(|cmd| {
+            let elapsed = (now - cmd.started_at).num_milliseconds().max(0) as u64;
+            Row::new(vec![
+                format_duration(elapsed),
+                short_session(&cmd.session_id).to_string(),
+                cmd.cwd.clone(),
+                cmd.command.clone(),
+            ])
+            .style(Style::default().fg(Color::Yellow))
+        })
+        .collect();
+
+    let running_table = Table::new(
+        running_rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Percentage(30),
+            Constraint::Min(10),
+        ],
+    )
+    .header(Row::new(vec!["ELAPSED", "SESSION", "DIRECTORY", "COMMAND"]).style(header_style))
+    .block(
+        Block::default()
+            .title(format!(" Running ({}) ", running.len()))
+            .borders(Borders::ALL),
+    );
+
+    f.render_widget(running_table, chunks[0]);
+
+    let finished_rows: Vec<Row> = finished
+        .iter()
+        .map(|cmd| {
+            let (status, color) = if cmd.exit_code == 0 {
+                ("✓".to_string(), Color::Green)
+            } else {
+                (format!("✗ {}", cmd.exit_code), Color::Red)
+            };
+            Row::new(vec![
+                cmd.started_at
+                    .with_timezone(&chrono::Local)
+                    .format("%H:%M:%S")
+                    .to_string(),
+                status,
+                format_duration(cmd.duration_ms),
+                short_session(&cmd.session_id).to_string(),
+                cmd.command.clone(),
+            ])
+            .style(Style::default().fg(color))
+        })
+        .collect();
+
+    let finished_table = Table::new(
+        finished_rows,
+        [
+            Constraint::Length(9),
+            Constraint::Length(6),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Min(10),
+        ],
+    )
+    .header(
+        Row::new(vec!["STARTED", "STATUS", "DURATION", "SESSION", "COMMAND"]).style(header_style),
+    )
+    .block(
+        Block::default()
+            .title(" Recently Finished ")
+            .borders(Borders::ALL),
+    );
+
+    f.render_widget(finished_table, chunks[1]);
+
+    let status = Paragraph::new(Line::from(Span::styled(
+        format!(
+            " q: quit | refreshing every {}s ",
+            REFRESH_INTERVAL.as_secs()
+        ),
+        Style::default().bg(Color::DarkGray).fg(Color::White),
+    )));
+    f.render_widget(status, chunks[2]);
+}
+
+/// Shorten a session ID for display
+fn short_session(session_id: &str) -> &str {
+    session_id.get(..8).unwrap_or(session_id)
+}
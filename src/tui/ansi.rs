@@ -0,0 +1,195 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+/// Turn captured output into lines styled by its SGR (color) sequences
+///
+/// Styles carry over line breaks as they do in a terminal. Other escape
+/// sequences and carriage returns are dropped, as `strip_ansi` does.
+pub fn styled_lines(output: &str) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    let mut style = Style::default();
+    let mut chars = output.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' if chars.peek() == Some(&'[') => {
+                chars.next();
+                let mut params = String::new();
+                let mut end = None;
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        end = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if end == Some('m') {
+                    flush(&mut spans, &mut text, style);
+                    style = apply_sgr(style, &params);
+                }
+            }
+            '\x1b' if chars.peek() == Some(&']') => {
+                // OSC sequences: ESC ] ... terminated by BEL or ESC \
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            '\x1b' => {
+                chars.next();
+            }
+            '\r' => {}
+            '\n' => {
+                flush(&mut spans, &mut text, style);
+                lines.push(Line::from(std::mem::take(&mut spans)));
+            }
+            _ => text.push(c),
+        }
+    }
+    flush(&mut spans, &mut text, style);
+    if !spans.is_empty() {
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+/// Output lines with control characters shown in caret notation (`^[`,
+/// `^M`), as `cat -v` does
+pub fn raw_lines(output: &str) -> Vec<Line<'static>> {
+    output
+        .split_terminator('\n')
+        .map(|line| {
+            let mut text = String::with_capacity(line.len());
+            for c in line.chars() {
+                match c {
+                    '\t' => text.push(c),
+                    '\x00'..='\x1f' => {
+                        text.push('^');
+                        text.push((c as u8 + 0x40) as char);
+                    }
+                    '\x7f' => text.push_str("^?"),
+                    _ => text.push(c),
+                }
+            }
+            Line::from(text)
+        })
+        .collect()
+}
+
+fn flush(spans: &mut Vec<Span<'static>>, text: &mut String, style: Style) {
+    if !text.is_empty() {
+        spans.push(Span::styled(std::mem::take(text), style));
+    }
+}
+
+/// Apply the parameters of an SGR sequence (`ESC [ ... m`) to a style
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut codes = params.split(';').map(|p| p.parse::<u16>().unwrap_or(0));
+
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(basic_color(code - 30)),
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(basic_color(code - 40)),
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(bright_color(code - 90)),
+            100..=107 => style.bg(bright_color(code - 100)),
+            _ => style,
+        };
+    }
+    style
+}
+
+/// The color of `38;5;n` (256 colors) or `38;2;r;g;b` (true color)
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()? as u8)),
+        2 => Some(Color::Rgb(
+            codes.next()? as u8,
+            codes.next()? as u8,
+            codes.next()? as u8,
+        )),
+        _ => None,
+    }
+}
+
+fn basic_color(n: u16) -> Color {
+    match n {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        _ => Color::Gray,
+    }
+}
+
+fn bright_color(n: u16) -> Color {
+    match n {
+        0 => Color::DarkGray,
+        1 => Color::LightRed,
+        2 => Color::LightGreen,
+        3 => Color::LightYellow,
+        4 => Color::LightBlue,
+        5 => Color::LightMagenta,
+        6 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styled_lines() {
+        let output = "\x1b[1;31merror\x1b[0m: bad\r\n\x1b[38;5;208mwarn\ning\x1b[39m\x1b]0;title\x07 ok\x1b[K";
+        let lines = styled_lines(output);
+        assert_eq!(lines.len(), 3);
+
+        let spans = &lines[0].spans;
+        assert_eq!(spans[0].content, "error");
+        assert_eq!(
+            spans[0].style,
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            (spans[1].content.as_ref(), spans[1].style),
+            (": bad", Style::default())
+        );
+
+        // Colors carry over line breaks
+        assert_eq!(lines[1].spans[0].style.fg, Some(Color::Indexed(208)));
+        assert_eq!(lines[2].spans[0].style.fg, Some(Color::Indexed(208)));
+        assert_eq!(lines[2].spans[1].content, " ok");
+        assert_eq!(lines[2].spans[1].style.fg, Some(Color::Reset));
+
+        let raw = raw_lines("\x1b[31mred\x1b[0m\r\n");
+        assert_eq!(raw[0].spans[0].content, "^[[31mred^[[0m^M");
+    }
+}
@@ -1,8 +1,24 @@
-use crate::models::Command;
-use crate::storage::Storage;
+use crate::ansi::strip_ansi;
+use crate::clipboard;
+use crate::fuzzy;
+use crate::session;
+use crate::tag;
+use crate::timespec::{self, TimeRange};
+use crate::tui::pager::Pager;
 use anyhow::Result;
+use ratatui::style::Color;
+use regex::{Regex, RegexBuilder};
+use shelltape_core::config::Config;
+use shelltape_core::models::{Command, Stats};
+use shelltape_core::storage::{CommandPages, RecordPos, Storage};
 use std::collections::HashSet;
 
+/// Commands read per page as the list scrolls
+const PAGE_SIZE: usize = 500;
+
+/// Read the next page once the selection gets this close to the end
+const PREFETCH_MARGIN: usize = 50;
+
 /// View mode for the TUI
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ViewMode {
@@ -10,15 +26,66 @@ pub enum ViewMode {
     List,
     /// Detail view showing full command information
     Detail,
+    /// Statistics dashboard for the filtered commands
+    Stats,
+}
+
+/// How the search query matches commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Case-insensitive substring of the command, directory or output
+    Substring,
+    /// fzf-style subsequence of the command, best match first
+    Fuzzy,
+    /// Case-insensitive regex over the command and directory
+    Regex,
+}
+
+impl MatchMode {
+    /// The mode Tab switches to
+    pub fn next(self) -> Self {
+        match self {
+            MatchMode::Substring => MatchMode::Fuzzy,
+            MatchMode::Fuzzy => MatchMode::Regex,
+            MatchMode::Regex => MatchMode::Substring,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MatchMode::Substring => "Search",
+            MatchMode::Fuzzy => "Fuzzy",
+            MatchMode::Regex => "Regex",
+        }
+    }
 }
 
 /// The main TUI application state
 pub struct App {
     /// Storage instance
-    #[allow(dead_code)]
     pub storage: Storage,
-    /// All commands loaded from storage
+    /// Commands loaded so far, newest first, without their output and timing
     pub commands: Vec<Command>,
+    /// Older commands still to be read
+    pages: CommandPages,
+    /// Where to look for commands recorded since the list was loaded
+    cursor: u64,
+    /// IDs of `commands`, so a command read both ways is listed once
+    ids: HashSet<String>,
+    /// Whether new commands are added to the list as they are recorded
+    pub live: bool,
+    /// Only load commands with this tag
+    tag: Option<String>,
+    /// Whether a page is being read
+    pub loading: bool,
+    /// IDs of all commands matching the search, loaded or not
+    search_ids: Option<HashSet<String>>,
+    /// The applied search query, compiled in regex mode
+    search_regex: Option<Regex>,
+    /// Where each of `commands` sits in the commands file
+    positions: Vec<RecordPos>,
+    /// The full record of the selected command (index into `commands`)
+    selected_record: Option<(usize, Command)>,
     /// Indices of filtered commands (into `commands` vec)
     pub filtered_commands: Vec<usize>,
     /// Currently selected index (into `filtered_commands`)
@@ -29,62 +96,385 @@ pub struct App {
     pub search_query: String,
     /// Whether we're in search input mode
     pub search_mode: bool,
+    /// How the search query matches commands
+    pub match_mode: MatchMode,
+    /// Only show failed commands
+    pub failed_only: bool,
+    /// Only show commands run in this directory
+    pub dir_filter: Option<String>,
+    /// Only show commands from this session
+    pub session_filter: Option<String>,
+    /// Only show commands started in this range, as typed
+    pub date_range: Option<(String, TimeRange)>,
+    /// The date range being edited
+    pub date_input: Option<String>,
+    /// Why the edited date range could not be applied
+    pub date_error: Option<String>,
+    /// Tags of the selected command being edited, comma-separated
+    pub tag_input: Option<String>,
+    /// The selected command's output, open in the pager
+    pub pager: Option<Pager>,
+    /// Show output with its escape sequences visible instead of colored
+    pub raw_output: bool,
     /// Set of marked command indices (into `commands`)
     pub marked: HashSet<usize>,
     /// Current view mode
     pub view_mode: ViewMode,
     /// Whether to quit the app
     pub should_quit: bool,
+    /// Result of the last action, shown in the status bar until the next key
+    pub message: Option<String>,
+    /// The selected command's line, being confirmed (and maybe edited) to
+    /// run again
+    pub rerun_input: Option<String>,
+    /// A command to run again, with the line to run, once the TUI steps aside
+    pub pending_rerun: Option<(Command, String)>,
+    /// Background color for the selected row
+    pub highlight_color: Color,
+    /// Statistics shown in the dashboard, computed when it is opened
+    pub stats: Option<Stats>,
 }
 
 impl App {
     /// Create a new App instance
-    pub fn new() -> Result<Self> {
-        let storage = Storage::new()?;
-        let mut commands = storage.read_all_commands()?;
-
-        // Sort by most recent first
-        commands.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    pub fn new(storage: Storage, tag: Option<&str>) -> Result<Self> {
+        Self::with_config(storage, tag, &Config::load()?)
+    }
 
-        let filtered: Vec<usize> = (0..commands.len()).collect();
+    fn with_config(storage: Storage, tag: Option<&str>, config: &Config) -> Result<Self> {
+        // Taken first so nothing recorded meanwhile is missed
+        let cursor = storage.end_cursor()?;
+        // Only the newest page is read up front; the rest as the list scrolls
+        let pages = storage.command_pages()?;
 
-        Ok(Self {
+        let mut app = Self {
             storage,
-            commands,
-            filtered_commands: filtered,
+            commands: Vec::new(),
+            pages,
+            cursor,
+            ids: HashSet::new(),
+            live: true,
+            tag: tag.map(str::to_string),
+            loading: false,
+            search_ids: None,
+            search_regex: None,
+            positions: Vec::new(),
+            selected_record: None,
+            filtered_commands: Vec::new(),
             selected: 0,
             scroll: 0,
             search_query: String::new(),
             search_mode: false,
+            match_mode: MatchMode::Substring,
+            failed_only: false,
+            dir_filter: None,
+            session_filter: None,
+            date_range: None,
+            date_input: None,
+            date_error: None,
+            tag_input: None,
+            pager: None,
+            raw_output: false,
             marked: HashSet::new(),
             view_mode: ViewMode::List,
             should_quit: false,
-        })
+            message: None,
+            rerun_input: None,
+            pending_rerun: None,
+            highlight_color: config.ui.highlight_color.parse().unwrap_or(Color::DarkGray),
+            stats: None,
+        };
+        app.load_more()?;
+        Ok(app)
+    }
+
+    /// Whether older commands should be read for the selection to move on
+    pub fn wants_more(&self) -> bool {
+        let all_found = self
+            .search_ids
+            .as_ref()
+            .is_some_and(|ids| self.filtered_commands.len() >= ids.len());
+        !self.pages.is_done()
+            && !all_found
+            && self.selected + PREFETCH_MARGIN >= self.filtered_commands.len()
+    }
+
+    /// Whether every command has been read
+    pub fn fully_loaded(&self) -> bool {
+        self.pages.is_done()
+    }
+
+    /// Read pages until a page's worth of commands passes the filters, or
+    /// the history is exhausted
+    pub fn load_more(&mut self) -> Result<()> {
+        let shown = self.filtered_commands.len();
+        while !self.pages.is_done() && self.filtered_commands.len() < shown + PAGE_SIZE {
+            let page = self.pages.next_page(PAGE_SIZE)?;
+            for (cmd, pos) in page {
+                // Only browse tagged commands when a tag filter was given
+                if self.tag.as_deref().is_some_and(|tag| !cmd.has_tag(tag))
+                    || !self.ids.insert(cmd.id.clone())
+                {
+                    continue;
+                }
+                if self.matches_filter(&cmd) {
+                    self.filtered_commands.push(self.commands.len());
+                }
+                self.commands.push(cmd);
+                self.positions.push(pos);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add commands recorded since the last refresh to the top of the list
+    ///
+    /// The selection and marks stay on the commands they were on. Returns
+    /// how many commands were added.
+    pub fn refresh(&mut self) -> Result<usize> {
+        let (added, cursor) = self.storage.commands_since(self.cursor)?;
+        self.cursor = cursor;
+
+        let mut commands = Vec::new();
+        let mut positions = Vec::new();
+        for (mut cmd, pos) in added.into_iter().rev() {
+            if self.tag.as_deref().is_some_and(|tag| !cmd.has_tag(tag))
+                || !self.ids.insert(cmd.id.clone())
+            {
+                continue;
+            }
+            // The search scan predates this command, so match it here
+            if let Some(ids) = &mut self.search_ids
+                && cmd.matches_query(&self.search_query)
+            {
+                ids.insert(cmd.id.clone());
+            }
+            cmd.output = String::new();
+            cmd.timing = Vec::new();
+            commands.push(cmd);
+            positions.push(pos);
+        }
+        if commands.is_empty() {
+            return Ok(0);
+        }
+
+        // Everything already loaded moves down by the number added
+        let added = commands.len();
+        for idx in &mut self.filtered_commands {
+            *idx += added;
+        }
+        self.marked = self.marked.iter().map(|idx| idx + added).collect();
+        if let Some((idx, _)) = &mut self.selected_record {
+            *idx += added;
+        }
+        self.commands.splice(0..0, commands);
+        self.positions.splice(0..0, positions);
+
+        if self.fuzzy_ranked() {
+            // New commands take their place by score, not at the top
+            let selected = self.filtered_commands.get(self.selected).copied();
+            self.rank_fuzzy();
+            self.selected = selected
+                .and_then(|idx| self.filtered_commands.iter().position(|&i| i == idx))
+                .unwrap_or(0);
+        } else {
+            let shown: Vec<usize> = (0..added)
+                .filter(|&i| self.matches_filter(&self.commands[i]))
+                .collect();
+            if !self.filtered_commands.is_empty() {
+                self.selected += shown.len();
+            }
+            self.filtered_commands.splice(0..0, shown);
+        }
+        Ok(added)
+    }
+
+    /// Read every remaining command
+    pub fn load_all(&mut self) -> Result<()> {
+        while !self.fully_loaded() {
+            self.load_more()?;
+        }
+        Ok(())
+    }
+
+    fn matches_filter(&self, cmd: &Command) -> bool {
+        if !self.matches_quick_filters(cmd) {
+            return false;
+        }
+        if self.match_mode == MatchMode::Regex && !self.search_query.is_empty() {
+            // A pattern that does not compile matches nothing
+            return self
+                .search_regex
+                .as_ref()
+                .is_some_and(|re| re.is_match(&cmd.command) || re.is_match(&cmd.cwd));
+        }
+        match &self.search_ids {
+            _ if self.search_query.is_empty() => true,
+            _ if self.match_mode == MatchMode::Fuzzy => {
+                fuzzy::score(&self.search_query, &cmd.command).is_some()
+            }
+            Some(ids) => ids.contains(&cmd.id),
+            None => cmd.matches_query(&self.search_query),
+        }
+    }
+
+    /// Whether a command passes the failed, directory and session filters
+    fn matches_quick_filters(&self, cmd: &Command) -> bool {
+        (!self.failed_only || cmd.exit_code != 0)
+            && self.dir_filter.as_ref().is_none_or(|dir| cmd.cwd == *dir)
+            && self
+                .session_filter
+                .as_ref()
+                .is_none_or(|id| cmd.session_id == *id)
+            && self
+                .date_range
+                .as_ref()
+                .is_none_or(|(_, range)| range.contains(cmd.started_at))
+    }
+
+    /// Start editing the date range
+    pub fn start_date_edit(&mut self) {
+        let current = self.date_range.as_ref().map(|(text, _)| text.clone());
+        self.date_input = Some(current.unwrap_or_default());
+        self.date_error = None;
+    }
+
+    /// Apply the edited date range; an empty one shows every date
+    ///
+    /// An invalid range stays in the editor with the reason.
+    pub fn save_date_range(&mut self) -> Result<()> {
+        let Some(input) = self.date_input.as_ref() else {
+            return Ok(());
+        };
+        let text = input.trim().to_string();
+        self.date_range = if text.is_empty() {
+            None
+        } else {
+            match timespec::parse_range(&text) {
+                Ok(range) => Some((text, range)),
+                Err(e) => {
+                    self.date_error = Some(e.to_string());
+                    return Ok(());
+                }
+            }
+        };
+        self.date_input = None;
+        self.apply_filter()
+    }
+
+    /// Toggle showing only failed commands
+    pub fn toggle_failed_only(&mut self) -> Result<()> {
+        self.failed_only = !self.failed_only;
+        self.apply_filter()
+    }
+
+    /// Toggle showing only commands run in the current directory
+    pub fn toggle_dir_filter(&mut self) -> Result<()> {
+        self.dir_filter = match self.dir_filter {
+            Some(_) => None,
+            None => Some(std::env::current_dir()?.to_string_lossy().into_owned()),
+        };
+        self.apply_filter()
+    }
+
+    /// Toggle showing only the current session
+    ///
+    /// Outside a recorded shell, the selected command's session is used.
+    pub fn toggle_session_filter(&mut self) -> Result<()> {
+        self.session_filter = match self.session_filter {
+            Some(_) => None,
+            None => match session::current_session(&self.storage)? {
+                Some(id) => Some(id),
+                None => self.get_selected_command().map(|c| c.session_id.clone()),
+            },
+        };
+        self.apply_filter()
+    }
+
+    /// Names of the active quick filters, for the list title
+    pub fn quick_filter_labels(&self) -> Vec<&str> {
+        let mut labels = Vec::new();
+        if self.failed_only {
+            labels.push("failed");
+        }
+        if self.dir_filter.is_some() {
+            labels.push("this dir");
+        }
+        if self.session_filter.is_some() {
+            labels.push("this session");
+        }
+        if let Some((text, _)) = &self.date_range {
+            labels.push(text);
+        }
+        labels
+    }
+
+    /// Why the search query is not a valid regex, in regex mode
+    pub fn search_error(&self) -> Option<String> {
+        if self.match_mode != MatchMode::Regex || self.search_query.is_empty() {
+            return None;
+        }
+        let err = build_regex(&self.search_query).err()?;
+        // Syntax errors quote the pattern first; the last line says what is wrong
+        let message = err.to_string();
+        Some(message.lines().last().unwrap_or_default().to_string())
     }
 
     /// Apply the current search filter
-    pub fn apply_filter(&mut self) {
-        if self.search_query.is_empty() {
-            // No filter, show all commands
-            self.filtered_commands = (0..self.commands.len()).collect();
+    ///
+    /// An invalid regex, left in the search bar and applied by a quick
+    /// filter, shows nothing and says why in the status bar.
+    pub fn apply_filter(&mut self) -> Result<()> {
+        self.search_regex = match self.match_mode {
+            MatchMode::Regex if !self.search_query.is_empty() => {
+                build_regex(&self.search_query).ok()
+            }
+            _ => None,
+        };
+        if let Some(error) = self.search_error() {
+            self.message = Some(format!("Invalid regex: {}", error));
+        }
+        if self.fuzzy_ranked() {
+            // Ranking needs every candidate
+            self.search_ids = None;
+            self.load_all()?;
+            self.rank_fuzzy();
         } else {
-            let query = self.search_query.to_lowercase();
-            self.filtered_commands = self
-                .commands
-                .iter()
-                .enumerate()
-                .filter(|(_, cmd)| {
-                    cmd.command.to_lowercase().contains(&query)
-                        || cmd.cwd.to_lowercase().contains(&query)
-                        || cmd.output.to_lowercase().contains(&query)
-                })
-                .map(|(i, _)| i)
+            // Outputs are not in memory, so matches come from a scan of the file
+            self.search_ids = if self.search_query.is_empty() || self.match_mode == MatchMode::Regex
+            {
+                None
+            } else {
+                self.storage.search_command_ids(&self.search_query).ok()
+            };
+            self.filtered_commands = (0..self.commands.len())
+                .filter(|&i| self.matches_filter(&self.commands[i]))
                 .collect();
         }
 
         // Reset selection and scroll
         self.selected = 0;
         self.scroll = 0;
+        Ok(())
+    }
+
+    /// Whether the list is ordered by fuzzy score rather than by time
+    fn fuzzy_ranked(&self) -> bool {
+        self.match_mode == MatchMode::Fuzzy && !self.search_query.is_empty()
+    }
+
+    /// Order the loaded commands passing the quick filters by fuzzy score;
+    /// equal scores stay newest first
+    fn rank_fuzzy(&mut self) {
+        let mut scored: Vec<(usize, i64)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter(|(_, cmd)| self.matches_quick_filters(cmd))
+            .filter_map(|(i, cmd)| fuzzy::score(&self.search_query, &cmd.command).map(|s| (i, s)))
+            .collect();
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        self.filtered_commands = scored.into_iter().map(|(i, _)| i).collect();
     }
 
     /// Move selection down
@@ -147,10 +537,40 @@ impl App {
     }
 
     /// Get the currently selected command
+    ///
+    /// This is the full record, output included, once [`App::load_selected`]
+    /// has read it.
     pub fn get_selected_command(&self) -> Option<&Command> {
-        self.filtered_commands
-            .get(self.selected)
-            .and_then(|&idx| self.commands.get(idx))
+        let idx = *self.filtered_commands.get(self.selected)?;
+        match &self.selected_record {
+            Some((loaded, cmd)) if *loaded == idx => Some(cmd),
+            _ => self.commands.get(idx),
+        }
+    }
+
+    /// Read the full record of the selected command, if not read already
+    pub fn load_selected(&mut self) {
+        let Some(&idx) = self.filtered_commands.get(self.selected) else {
+            return;
+        };
+        if self
+            .selected_record
+            .as_ref()
+            .is_some_and(|(i, _)| *i == idx)
+        {
+            return;
+        }
+        self.selected_record = self.full_record(idx).map(|cmd| (idx, cmd));
+    }
+
+    /// The full record of `commands[idx]`, read from disk
+    fn full_record(&self, idx: usize) -> Option<Command> {
+        let id = &self.commands.get(idx)?.id;
+        // The file may have been rewritten since it was loaded
+        match self.storage.read_command_at(self.positions[idx]) {
+            Ok(cmd) if cmd.id == *id => Some(cmd),
+            _ => self.storage.find_command(id).ok(),
+        }
     }
 
     /// Export marked commands to a file
@@ -158,10 +578,10 @@ impl App {
         use chrono::Utc;
         use std::fs;
 
-        let marked_commands: Vec<&Command> = self
+        let marked_commands: Vec<Command> = self
             .marked
             .iter()
-            .filter_map(|&idx| self.commands.get(idx))
+            .filter_map(|&idx| self.full_record(idx))
             .collect();
 
         if marked_commands.is_empty() {
@@ -178,7 +598,7 @@ impl App {
         markdown.push_str(&format!("Total commands: {}\n\n", marked_commands.len()));
         markdown.push_str("---\n\n");
 
-        for cmd in marked_commands {
+        for cmd in &marked_commands {
             markdown.push_str(&format!(
                 "## {}\n\n",
                 cmd.started_at.format("%Y-%m-%d %H:%M:%S")
@@ -196,6 +616,13 @@ impl App {
                 cmd.exit_code, status
             ));
 
+            if let Some(note) = &cmd.note {
+                for line in note.lines() {
+                    markdown.push_str(&format!("> {}\n", line));
+                }
+                markdown.push('\n');
+            }
+
             markdown.push_str("**Command:**\n\n");
             markdown.push_str(&format!("```bash\n{}\n```\n\n", cmd.command));
 
@@ -216,10 +643,32 @@ impl App {
     pub fn toggle_view_mode(&mut self) {
         self.view_mode = match self.view_mode {
             ViewMode::List => ViewMode::Detail,
-            ViewMode::Detail => ViewMode::List,
+            ViewMode::Detail | ViewMode::Stats => ViewMode::List,
         };
     }
 
+    /// Toggle the statistics dashboard for the currently filtered commands
+    pub fn toggle_stats(&mut self) {
+        if self.view_mode == ViewMode::Stats {
+            self.view_mode = ViewMode::List;
+            return;
+        }
+
+        let commands: Vec<Command> = self
+            .filtered_commands
+            .iter()
+            .map(|&idx| self.commands[idx].clone())
+            .collect();
+        let sessions = commands
+            .iter()
+            .map(|c| c.session_id.as_str())
+            .collect::<HashSet<_>>()
+            .len();
+
+        self.stats = Some(Stats::from_commands(&commands, sessions));
+        self.view_mode = ViewMode::Stats;
+    }
+
     /// Add character to search query
     pub fn search_input(&mut self, c: char) {
         self.search_query.push(c);
@@ -231,9 +680,90 @@ impl App {
     }
 
     /// Clear search query
-    pub fn clear_search(&mut self) {
+    pub fn clear_search(&mut self) -> Result<()> {
         self.search_query.clear();
-        self.apply_filter();
+        self.apply_filter()
+    }
+
+    /// Start editing the selected command's tags
+    pub fn start_tag_edit(&mut self) {
+        if let Some(cmd) = self.get_selected_command() {
+            self.tag_input = Some(cmd.tags.join(", "));
+        }
+    }
+
+    /// Replace the selected command's tags with the edited ones
+    pub fn save_tags(&mut self) -> Result<()> {
+        let Some(input) = self.tag_input.take() else {
+            return Ok(());
+        };
+        let Some(&idx) = self.filtered_commands.get(self.selected) else {
+            return Ok(());
+        };
+        let tags = tag::parse_tags([input.as_str()]);
+        let id = self.commands[idx].id.clone();
+        let cmd = self.storage.update_command(&id, |cmd| cmd.tags = tags)?;
+
+        self.commands[idx].tags = cmd.tags.clone();
+        self.selected_record = Some((idx, cmd));
+        Ok(())
+    }
+
+    /// Copy the selected command line, or its output without escape
+    /// sequences, to the clipboard
+    pub fn copy_selected(&mut self, output: bool) {
+        let Some((what, text)) = self.copy_text(output) else {
+            return;
+        };
+        self.message = Some(match clipboard::copy_to_clipboard(&text) {
+            Ok(method) => format!("Copied {} to clipboard (via {})", what, method),
+            Err(e) => format!("Copy failed: {}", e),
+        });
+    }
+
+    /// What `copy_selected` copies, and the text
+    fn copy_text(&mut self, output: bool) -> Option<(&'static str, String)> {
+        self.load_selected();
+        let cmd = self.get_selected_command()?;
+        Some(if output {
+            ("output", strip_ansi(&cmd.output))
+        } else {
+            ("command", cmd.command.clone())
+        })
+    }
+
+    /// Ask to run the selected command again in its original directory
+    pub fn start_rerun(&mut self) {
+        let Some(cmd) = self.get_selected_command() else {
+            return;
+        };
+        if !std::path::Path::new(&cmd.cwd).is_dir() {
+            self.message = Some(format!("Directory {} no longer exists", cmd.cwd));
+            return;
+        }
+        self.rerun_input = Some(cmd.command.clone());
+    }
+
+    /// Queue the confirmed line to run; an empty one cancels
+    pub fn confirm_rerun(&mut self) {
+        let Some(line) = self.rerun_input.take() else {
+            return;
+        };
+        if line.trim().is_empty() {
+            return;
+        }
+        self.load_selected();
+        if let Some(original) = self.get_selected_command() {
+            self.pending_rerun = Some((original.clone(), line));
+        }
+    }
+
+    /// Open the selected command's output in the pager
+    pub fn open_pager(&mut self) {
+        self.load_selected();
+        if let Some(cmd) = self.get_selected_command() {
+            self.pager = Some(Pager::new(&cmd.command, &cmd.output));
+        }
     }
 
     /// Quit the application
@@ -241,3 +771,309 @@ impl App {
         self.should_quit = true;
     }
 }
+
+fn build_regex(pattern: &str) -> std::result::Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use tempfile::TempDir;
+
+    fn cmd(minute: i64, command: &str, exit_code: i32) -> Command {
+        Command {
+            id: minute.to_string(),
+            command: command.to_string(),
+            exit_code,
+            cwd: "/src".to_string(),
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap()
+                + Duration::minutes(minute),
+            session_id: "s1".to_string(),
+            shell: "bash".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// An app over a fresh history holding `commands`, oldest first
+    fn app(commands: &[Command]) -> (TempDir, App) {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::with_dir(dir.path().to_path_buf()).unwrap();
+        storage.append_commands(commands).unwrap();
+        let app = App::with_config(storage, None, &Config::default()).unwrap();
+        (dir, app)
+    }
+
+    /// The IDs of the commands shown, in order
+    fn ids(app: &App) -> Vec<&str> {
+        app.filtered_commands
+            .iter()
+            .map(|&i| app.commands[i].id.as_str())
+            .collect()
+    }
+
+    /// The command lines shown, in order
+    fn shown(app: &App) -> Vec<&str> {
+        app.filtered_commands
+            .iter()
+            .map(|&i| app.commands[i].command.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_invalid_regex_applied_by_quick_filter() {
+        let (_dir, mut app) = app(&[
+            cmd(1, "make build", 0),
+            cmd(2, "make test", 2),
+            cmd(3, "cargo test", 1),
+        ]);
+        assert_eq!(shown(&app), ["cargo test", "make test", "make build"]);
+
+        // Typed in regex mode, then left with Esc: the query stays unapplied
+        app.match_mode = MatchMode::Regex;
+        for c in "foo(".chars() {
+            app.search_input(c);
+        }
+        app.toggle_failed_only().unwrap();
+        assert!(shown(&app).is_empty());
+        assert!(app.message.as_ref().unwrap().starts_with("Invalid regex: "));
+
+        app.search_query = "^make".to_string();
+        app.message = None;
+        app.toggle_failed_only().unwrap();
+        app.toggle_failed_only().unwrap();
+        assert_eq!(shown(&app), ["make test"]);
+        assert_eq!(app.message, None);
+    }
+
+    #[test]
+    fn test_refresh_keeps_selection_and_marks() {
+        let (_dir, mut app) = app(&[cmd(1, "make build", 0), cmd(2, "make test", 2)]);
+        app.select_next();
+        app.toggle_mark();
+        assert_eq!(app.get_selected_command().unwrap().command, "make build");
+
+        app.storage
+            .append_commands(&[cmd(3, "cargo test", 1), cmd(4, "ls", 0)])
+            .unwrap();
+        assert_eq!(app.refresh().unwrap(), 2);
+        assert_eq!(shown(&app), ["ls", "cargo test", "make test", "make build"]);
+        assert_eq!(app.get_selected_command().unwrap().command, "make build");
+        let marked: Vec<&str> = app
+            .marked
+            .iter()
+            .map(|&i| app.commands[i].command.as_str())
+            .collect();
+        assert_eq!(marked, ["make build"]);
+        assert_eq!(app.refresh().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_refresh_keeps_fuzzy_ranking() {
+        let (_dir, mut app) = app(&[
+            cmd(1, "make build", 0),
+            cmd(2, "mytest", 0),
+            cmd(3, "make test", 0),
+        ]);
+        app.match_mode = MatchMode::Fuzzy;
+        app.search_query = "test".to_string();
+        app.apply_filter().unwrap();
+        assert_eq!(shown(&app), ["make test", "mytest"]);
+        app.select_next();
+        app.toggle_mark();
+
+        // New commands are ranked among the others, not put on top
+        app.storage
+            .append_commands(&[cmd(4, "cargo test", 0), cmd(5, "t e s t", 0)])
+            .unwrap();
+        app.refresh().unwrap();
+        assert_eq!(
+            shown(&app),
+            ["cargo test", "make test", "mytest", "t e s t"]
+        );
+        assert_eq!(app.get_selected_command().unwrap().command, "mytest");
+        let marked: Vec<usize> = app.marked.iter().copied().collect();
+        assert_eq!(marked, [app.filtered_commands[app.selected]]);
+    }
+
+    #[test]
+    fn test_fuzzy_ranking() {
+        let (_dir, mut app) = app(&[
+            cmd(1, "t e s t", 0),
+            cmd(2, "make test", 0),
+            cmd(3, "mytest", 0),
+            cmd(4, "make build", 0),
+            cmd(5, "cargo test", 0),
+        ]);
+        app.match_mode = MatchMode::Fuzzy;
+        app.search_query = "test".to_string();
+        app.apply_filter().unwrap();
+        // Word-start substrings first, equal scores newest first, then
+        // scattered matches
+        assert_eq!(
+            shown(&app),
+            ["cargo test", "make test", "mytest", "t e s t"]
+        );
+
+        app.search_query = "mk bld".to_string();
+        app.apply_filter().unwrap();
+        assert_eq!(shown(&app), ["make build"]);
+    }
+
+    #[test]
+    fn test_match_mode_cycle() {
+        assert_eq!(MatchMode::Substring.next(), MatchMode::Fuzzy);
+        assert_eq!(MatchMode::Fuzzy.next(), MatchMode::Regex);
+        assert_eq!(MatchMode::Regex.next(), MatchMode::Substring);
+
+        let (_dir, mut app) = app(&[
+            cmd(1, "make test", 0),
+            cmd(2, "mytest", 0),
+            cmd(3, "git commit -m tidy", 0),
+        ]);
+        app.search_query = "^m".to_string();
+        let mut results = Vec::new();
+        for _ in 0..3 {
+            app.apply_filter().unwrap();
+            results.push((app.match_mode.label(), shown(&app).join(", ")));
+            app.match_mode = app.match_mode.next();
+        }
+        assert_eq!(
+            results,
+            [
+                ("Search", String::new()),
+                ("Fuzzy", String::new()),
+                ("Regex", "mytest, make test".to_string()),
+            ]
+        );
+        assert_eq!(app.match_mode, MatchMode::Substring);
+    }
+
+    #[test]
+    fn test_quick_filters_with_search() {
+        let here = std::env::current_dir()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let (_dir, mut app) = app(&[
+            cmd(1, "make test", 2),
+            Command {
+                cwd: here.clone(),
+                ..cmd(2, "make build", 0)
+            },
+            Command {
+                session_id: "s2".to_string(),
+                ..cmd(3, "make test", 0)
+            },
+            Command {
+                cwd: here,
+                ..cmd(4, "make test", 1)
+            },
+            cmd(5, "cargo test", 1),
+        ]);
+        app.search_query = "make".to_string();
+        app.apply_filter().unwrap();
+        assert_eq!(app.filtered_commands.len(), 4);
+
+        app.toggle_failed_only().unwrap();
+        assert_eq!(ids(&app), ["4", "1"]);
+        app.toggle_failed_only().unwrap();
+
+        app.toggle_dir_filter().unwrap();
+        assert_eq!(ids(&app), ["4", "2"]);
+        app.toggle_dir_filter().unwrap();
+
+        app.session_filter = Some("s2".to_string());
+        app.apply_filter().unwrap();
+        assert_eq!(ids(&app), ["3"]);
+
+        // Filters combine with each other and with every match mode
+        app.session_filter = None;
+        app.toggle_failed_only().unwrap();
+        app.toggle_dir_filter().unwrap();
+        assert_eq!(ids(&app), ["4"]);
+        app.match_mode = MatchMode::Fuzzy;
+        app.search_query = "mk tst".to_string();
+        app.apply_filter().unwrap();
+        assert_eq!(ids(&app), ["4"]);
+        app.match_mode = MatchMode::Regex;
+        app.search_query = "^cargo".to_string();
+        app.apply_filter().unwrap();
+        assert!(ids(&app).is_empty());
+        assert_eq!(app.quick_filter_labels(), ["failed", "this dir"]);
+    }
+
+    #[test]
+    fn test_copy_text() {
+        let (_dir, mut app) = app(&[Command {
+            output: "\x1b[32mok\x1b[0m 3 passed\r\n".to_string(),
+            ..cmd(1, "cargo test", 0)
+        }]);
+        // The list holds no output; copying it reads the full record
+        assert!(app.commands[0].output.is_empty());
+        assert_eq!(
+            app.copy_text(true),
+            Some(("output", "ok 3 passed\n".to_string()))
+        );
+        assert_eq!(
+            app.copy_text(false),
+            Some(("command", "cargo test".to_string()))
+        );
+
+        app.search_query = "nothing".to_string();
+        app.apply_filter().unwrap();
+        assert_eq!(app.copy_text(false), None);
+    }
+
+    #[test]
+    fn test_rerun_confirm_and_cancel() {
+        let here = std::env::current_dir()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let (_dir, mut app) = app(&[
+            Command {
+                cwd: "/no/such/dir".to_string(),
+                ..cmd(1, "make build", 0)
+            },
+            Command {
+                cwd: here,
+                output: "ok\n".to_string(),
+                ..cmd(2, "make test", 1)
+            },
+        ]);
+
+        // The line is offered for editing, then queued with the full record
+        app.start_rerun();
+        assert_eq!(app.rerun_input.as_deref(), Some("make test"));
+        app.rerun_input.as_mut().unwrap().push_str(" -j4");
+        app.confirm_rerun();
+        assert_eq!(app.rerun_input, None);
+        let (original, line) = app.pending_rerun.take().unwrap();
+        assert_eq!(
+            (original.id.as_str(), line.as_str()),
+            ("2", "make test -j4")
+        );
+        assert_eq!(original.output, "ok\n");
+
+        // An emptied line cancels, as Esc does
+        app.start_rerun();
+        app.rerun_input = Some("  ".to_string());
+        app.confirm_rerun();
+        assert!(app.rerun_input.is_none() && app.pending_rerun.is_none());
+        app.start_rerun();
+        let esc = crossterm::event::KeyEvent::from(crossterm::event::KeyCode::Esc);
+        crate::tui::events::handle_key_event(&mut app, esc).unwrap();
+        assert!(app.rerun_input.is_none() && app.pending_rerun.is_none());
+
+        // A command whose directory is gone is not offered
+        app.select_next();
+        app.start_rerun();
+        assert_eq!(app.rerun_input, None);
+        assert_eq!(
+            app.message.as_deref(),
+            Some("Directory /no/such/dir no longer exists")
+        );
+    }
+}
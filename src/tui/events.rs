@@ -1,11 +1,20 @@
-use crate::tui::app::{App, ViewMode};
+use crate::tui::app::{App, MatchMode, ViewMode};
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal;
+use std::time::Duration;
 
 /// Handle keyboard input events
 pub fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<()> {
+    app.message = None;
+
     // Global quit key
-    if key.code == KeyCode::Char('q') && !app.search_mode {
+    let typing = app.search_mode
+        || app.tag_input.is_some()
+        || app.date_input.is_some()
+        || app.rerun_input.is_some()
+        || app.pager.as_ref().is_some_and(|p| p.searching);
+    if key.code == KeyCode::Char('q') && !typing {
         app.quit();
         return Ok(());
     }
@@ -13,10 +22,19 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<()> {
     // Handle events based on current mode
     if app.search_mode {
         handle_search_mode(app, key)?;
+    } else if app.tag_input.is_some() {
+        handle_tag_mode(app, key)?;
+    } else if app.date_input.is_some() {
+        handle_date_mode(app, key)?;
+    } else if app.rerun_input.is_some() {
+        handle_rerun_mode(app, key);
+    } else if app.pager.is_some() {
+        handle_pager_mode(app, key)?;
     } else {
         match app.view_mode {
             ViewMode::List => handle_list_mode(app, key)?,
             ViewMode::Detail => handle_detail_mode(app, key)?,
+            ViewMode::Stats => handle_stats_mode(app, key)?,
         }
     }
 
@@ -29,10 +47,21 @@ fn handle_search_mode(app: &mut App, key: KeyEvent) -> Result<()> {
         KeyCode::Esc => {
             app.search_mode = false;
         }
-        KeyCode::Enter => {
-            app.apply_filter();
+        // An invalid regex stays in the search bar to be fixed
+        KeyCode::Enter if app.search_error().is_none() => {
+            app.apply_filter()?;
             app.search_mode = false;
         }
+        KeyCode::Tab => {
+            app.match_mode = app.match_mode.next();
+        }
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.match_mode = if app.match_mode == MatchMode::Regex {
+                MatchMode::Substring
+            } else {
+                MatchMode::Regex
+            };
+        }
         KeyCode::Char(c) => {
             app.search_input(c);
         }
@@ -45,6 +74,150 @@ fn handle_search_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
+/// Handle key events while editing tags
+fn handle_tag_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(input) = app.tag_input.as_mut() else {
+        return Ok(());
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.tag_input = None;
+        }
+        KeyCode::Enter => {
+            app.save_tags()?;
+        }
+        KeyCode::Char(c) => {
+            input.push(c);
+        }
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Handle key events while editing the date range
+fn handle_date_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(input) = app.date_input.as_mut() else {
+        return Ok(());
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.date_input = None;
+        }
+        KeyCode::Enter => {
+            app.save_date_range()?;
+        }
+        KeyCode::Char(c) => {
+            input.push(c);
+            app.date_error = None;
+        }
+        KeyCode::Backspace => {
+            input.pop();
+            app.date_error = None;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Handle key events while confirming a rerun, where the line can be edited
+fn handle_rerun_mode(app: &mut App, key: KeyEvent) {
+    let Some(input) = app.rerun_input.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.rerun_input = None;
+        }
+        KeyCode::Enter => {
+            app.confirm_rerun();
+        }
+        KeyCode::Char(c) => {
+            input.push(c);
+        }
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        _ => {}
+    }
+}
+
+/// Handle key events in the output pager
+fn handle_pager_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(pager) = app.pager.as_mut() else {
+        return Ok(());
+    };
+
+    if pager.searching {
+        match key.code {
+            KeyCode::Esc => {
+                pager.searching = false;
+            }
+            KeyCode::Enter => {
+                pager.searching = false;
+                pager.search();
+            }
+            KeyCode::Char(c) => {
+                pager.query.push(c);
+            }
+            KeyCode::Backspace => {
+                pager.query.pop();
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    // Everything but the frame, search bar and status bar
+    let page_size = terminal::size().map_or(10, |(_, rows)| rows.saturating_sub(7) as usize);
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('o') => {
+            app.pager = None;
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            pager.scroll_down(1);
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            pager.scroll_up(1);
+        }
+        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            pager.scroll_down(page_size / 2);
+        }
+        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            pager.scroll_up(page_size / 2);
+        }
+        KeyCode::PageDown | KeyCode::Char(' ') => {
+            pager.scroll_down(page_size);
+        }
+        KeyCode::PageUp | KeyCode::Char('b') => {
+            pager.scroll_up(page_size);
+        }
+        KeyCode::Char('g') | KeyCode::Home => {
+            pager.scroll = 0;
+        }
+        KeyCode::Char('G') | KeyCode::End => {
+            pager.scroll_to_end();
+        }
+        KeyCode::Char('/') => {
+            pager.searching = true;
+            pager.query.clear();
+        }
+        KeyCode::Char('n') => {
+            pager.next_match();
+        }
+        KeyCode::Char('N') => {
+            pager.previous_match();
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
 /// Handle key events in list mode
 fn handle_list_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
@@ -59,6 +232,7 @@ fn handle_list_mode(app: &mut App, key: KeyEvent) -> Result<()> {
             app.select_first();
         }
         KeyCode::Char('G') | KeyCode::End => {
+            app.load_all()?;
             app.select_last();
         }
         KeyCode::PageDown | KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -76,6 +250,7 @@ fn handle_list_mode(app: &mut App, key: KeyEvent) -> Result<()> {
             app.select_next(); // Move to next after marking
         }
         KeyCode::Char('a') => {
+            app.load_all()?;
             app.mark_all();
         }
         KeyCode::Char('c') => {
@@ -88,23 +263,73 @@ fn handle_list_mode(app: &mut App, key: KeyEvent) -> Result<()> {
             app.search_query.clear();
         }
         KeyCode::Esc => {
-            app.clear_search();
+            app.clear_search()?;
+        }
+
+        // Quick filters
+        KeyCode::Char('f') => {
+            app.toggle_failed_only()?;
+        }
+        KeyCode::Char('.') => {
+            app.toggle_dir_filter()?;
+        }
+        KeyCode::Char('s') => {
+            app.toggle_session_filter()?;
+        }
+        KeyCode::Char('d') => {
+            app.start_date_edit();
+        }
+
+        // Tags
+        KeyCode::Char('t') => {
+            app.start_tag_edit();
+        }
+
+        // Output
+        KeyCode::Char('o') => {
+            app.open_pager();
+        }
+        KeyCode::Char('R') => {
+            app.raw_output = !app.raw_output;
+        }
+
+        // Clipboard
+        KeyCode::Char('y') => {
+            app.copy_selected(false);
+        }
+
+        // Run again
+        KeyCode::Char('r') => {
+            app.start_rerun();
+        }
+        KeyCode::Char('Y') => {
+            app.copy_selected(true);
+        }
+
+        // Live updates
+        KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.refresh()?;
+        }
+        KeyCode::Char('L') => {
+            app.live = !app.live;
         }
 
         // View
         KeyCode::Enter => {
             app.toggle_view_mode();
         }
+        KeyCode::Char('S') => {
+            app.load_all()?;
+            app.toggle_stats();
+        }
 
         // Export
-        KeyCode::Char('e') => {
-            if !app.marked.is_empty() {
-                let home = dirs::home_dir().unwrap_or_default();
-                let output_path = home.join("shelltape-export.md");
-
-                if let Err(e) = app.export_marked(&output_path.to_string_lossy()) {
-                    eprintln!("Export failed: {}", e);
-                }
+        KeyCode::Char('e') if !app.marked.is_empty() => {
+            let home = dirs::home_dir().unwrap_or_default();
+            let output_path = home.join("shelltape-export.md");
+
+            if let Err(e) = app.export_marked(&output_path.to_string_lossy()) {
+                eprintln!("Export failed: {}", e);
             }
         }
 
@@ -126,13 +351,45 @@ fn handle_detail_mode(app: &mut App, key: KeyEvent) -> Result<()> {
         KeyCode::Char('k') | KeyCode::Up => {
             app.select_previous();
         }
+        KeyCode::Char('t') => {
+            app.start_tag_edit();
+        }
+        KeyCode::Char('o') => {
+            app.open_pager();
+        }
+        KeyCode::Char('R') => {
+            app.raw_output = !app.raw_output;
+        }
+        KeyCode::Char('y') => {
+            app.copy_selected(false);
+        }
+        KeyCode::Char('Y') => {
+            app.copy_selected(true);
+        }
+        KeyCode::Char('r') => {
+            app.start_rerun();
+        }
         _ => {}
     }
 
     Ok(())
 }
 
-/// Read the next event from the terminal
-pub fn read_event() -> Result<Event> {
-    Ok(event::read()?)
+/// Handle key events in the statistics dashboard
+fn handle_stats_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    if matches!(key.code, KeyCode::Char('S') | KeyCode::Enter | KeyCode::Esc) {
+        app.toggle_stats();
+    }
+
+    Ok(())
+}
+
+/// Read the next event from the terminal, or `None` if none arrives
+/// within `timeout`
+pub fn read_event(timeout: Duration) -> Result<Option<Event>> {
+    if event::poll(timeout)? {
+        Ok(Some(event::read()?))
+    } else {
+        Ok(None)
+    }
 }
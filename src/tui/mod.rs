@@ -1,9 +1,12 @@
+mod ansi;
 mod app;
 mod events;
+mod pager;
 mod ui;
 
 pub use app::App;
 
+use crate::rerun;
 use anyhow::{Context, Result};
 use crossterm::{
     event::Event,
@@ -11,10 +14,15 @@ use crossterm::{
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
-use std::io;
+use shelltape_core::storage::Storage;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// How often live mode checks storage for new commands
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Run the TUI application
-pub fn run() -> Result<()> {
+pub fn run(storage: Storage, tag: Option<String>) -> Result<()> {
     // Setup terminal
     enable_raw_mode().context("Failed to enable raw mode")?;
     let mut stdout = io::stdout();
@@ -24,7 +32,7 @@ pub fn run() -> Result<()> {
     let mut terminal = Terminal::new(backend).context("Failed to create terminal")?;
 
     // Create app
-    let mut app = App::new()?;
+    let mut app = App::new(storage, tag.as_deref())?;
 
     // Main loop
     let result = run_app(&mut terminal, &mut app);
@@ -52,18 +60,59 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
+/// Hand the terminal back for `run`, then wait for Enter so its output can
+/// be read before the TUI is drawn over it
+fn run_outside<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    run: impl FnOnce() -> Result<i32>,
+) -> Result<Result<i32>> {
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    execute!(io::stdout(), LeaveAlternateScreen).context("Failed to leave alternate screen")?;
+    terminal.show_cursor().context("Failed to show cursor")?;
+
+    let result = run();
+    match &result {
+        Ok(code) => eprint!("\n[exit code {}] ", code),
+        Err(e) => eprint!("\nRerun failed: {:#}\n", e),
+    }
+    eprint!("Press Enter to return to shelltape");
+    io::stderr().flush()?;
+    io::stdin().read_line(&mut String::new())?;
+
+    execute!(io::stdout(), EnterAlternateScreen).context("Failed to enter alternate screen")?;
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    terminal.clear().context("Failed to clear terminal")?;
+    Ok(result)
+}
+
 /// Main application loop
 fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    let mut last_refresh = Instant::now();
     loop {
-        // Draw UI
+        // Pick up commands recorded in other shells
+        if app.live && last_refresh.elapsed() >= REFRESH_INTERVAL {
+            app.refresh()?;
+            last_refresh = Instant::now();
+        }
+
+        // Read older commands as the selection nears the end of the list
+        if app.wants_more() {
+            app.loading = true;
+            terminal.draw(|f| ui::draw(f, app))?;
+            app.load_more()?;
+            app.loading = false;
+        }
+
+        // Draw UI, with the selected command's output read from disk
+        app.load_selected();
         terminal.draw(|f| ui::draw(f, app))?;
 
-        // Handle events
-        let event = events::read_event()?;
+        // Handle events, waking up in time for the next refresh
+        let event = events::read_event(REFRESH_INTERVAL.saturating_sub(last_refresh.elapsed()))?;
 
         // Only handle KeyPress events, ignore KeyRelease and KeyRepeat
         // This prevents duplicate events on Windows and other platforms
-        if let Event::Key(key) = event {
+        if let Some(Event::Key(key)) = event {
             use crossterm::event::KeyEventKind;
 
             // Only process Press events, ignore Release and Repeat
@@ -74,6 +123,20 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
             }
         }
 
+        // Run a command again outside the TUI, then show its new record
+        if let Some((original, command)) = app.pending_rerun.take() {
+            let storage = app.storage.clone();
+            let cwd = original.cwd.clone();
+            let result = run_outside(terminal, || {
+                rerun::run_again(storage, &original, command, cwd)
+            })?;
+            app.message = Some(match result {
+                Ok(code) => format!("Rerun finished with exit code {}", code),
+                Err(e) => format!("Rerun failed: {:#}", e),
+            });
+            app.refresh()?;
+        }
+
         // Check if we should quit
         if app.should_quit {
             break;
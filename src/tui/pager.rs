@@ -0,0 +1,165 @@
+use crate::ansi::strip_ansi;
+use regex::{Regex, RegexBuilder};
+
+/// Lines kept above a match when jumping to it
+const MATCH_CONTEXT: usize = 3;
+
+/// A match of the pager search: line index and byte range within the line
+pub type Match = (usize, std::ops::Range<usize>);
+
+/// Scrollable view of one command's output, searchable with `/`
+pub struct Pager {
+    /// The command whose output is shown
+    pub title: String,
+    /// Output lines, stripped of escape sequences
+    pub lines: Vec<String>,
+    /// Index of the first visible line
+    pub scroll: usize,
+    /// Whether the search query is being typed
+    pub searching: bool,
+    /// Current search query, matched case-insensitively as plain text
+    pub query: String,
+    /// Every match of the query, in order
+    pub matches: Vec<Match>,
+    /// Index of the current match (into `matches`)
+    pub current: usize,
+}
+
+impl Pager {
+    /// Open the pager on a command's output
+    pub fn new(title: &str, output: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            lines: output.lines().map(strip_ansi).collect(),
+            scroll: 0,
+            searching: false,
+            query: String::new(),
+            matches: Vec::new(),
+            current: 0,
+        }
+    }
+
+    /// Scroll down by `n` lines, stopping at the last one
+    pub fn scroll_down(&mut self, n: usize) {
+        self.scroll = (self.scroll + n).min(self.lines.len().saturating_sub(1));
+    }
+
+    /// Scroll up by `n` lines
+    pub fn scroll_up(&mut self, n: usize) {
+        self.scroll = self.scroll.saturating_sub(n);
+    }
+
+    /// Scroll to the last line
+    pub fn scroll_to_end(&mut self) {
+        self.scroll_down(self.lines.len());
+    }
+
+    /// Find every match of the query and jump to the first one at or below
+    /// the top of the screen
+    pub fn search(&mut self) {
+        self.matches = match self.regex() {
+            Some(re) => self
+                .lines
+                .iter()
+                .enumerate()
+                .flat_map(|(i, line)| re.find_iter(line).map(move |m| (i, m.range())))
+                .collect(),
+            None => Vec::new(),
+        };
+        self.current = self
+            .matches
+            .iter()
+            .position(|(line, _)| *line >= self.scroll)
+            .unwrap_or(0);
+        self.show_current();
+    }
+
+    /// Jump to the next match, wrapping around at the end
+    pub fn next_match(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + 1) % self.matches.len();
+            self.show_current();
+        }
+    }
+
+    /// Jump to the previous match, wrapping around at the start
+    pub fn previous_match(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + self.matches.len() - 1) % self.matches.len();
+            self.show_current();
+        }
+    }
+
+    /// The matches on line `idx`, in order
+    pub fn line_matches(&self, idx: usize) -> impl Iterator<Item = (usize, &Match)> {
+        let start = self.matches.partition_point(|(line, _)| *line < idx);
+        self.matches[start..]
+            .iter()
+            .take_while(move |(line, _)| *line == idx)
+            .enumerate()
+            .map(move |(i, m)| (start + i, m))
+    }
+
+    fn show_current(&mut self) {
+        if let Some((line, _)) = self.matches.get(self.current) {
+            self.scroll = line.saturating_sub(MATCH_CONTEXT);
+        }
+    }
+
+    fn regex(&self) -> Option<Regex> {
+        if self.query.is_empty() {
+            return None;
+        }
+        RegexBuilder::new(&regex::escape(&self.query))
+            .case_insensitive(true)
+            .build()
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_and_navigation() {
+        let output = (0..20)
+            .map(|i| {
+                if i % 5 == 0 {
+                    format!("\x1b[31mError\x1b[0m {} error", i)
+                } else {
+                    format!("line {}", i)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        let mut pager = Pager::new("make", &output);
+        assert_eq!(pager.lines[0], "Error 0 error");
+
+        pager.scroll_down(7);
+        pager.query = "ERROR".to_string();
+        pager.search();
+        assert_eq!(pager.matches.len(), 8);
+        // The first match below the top of the screen is current
+        assert_eq!(pager.matches[pager.current], (10, 0..5));
+        assert_eq!(pager.scroll, 7);
+
+        pager.next_match();
+        assert_eq!(pager.matches[pager.current], (10, 9..14));
+        let on_line: Vec<usize> = pager.line_matches(10).map(|(i, _)| i).collect();
+        assert_eq!(on_line, [4, 5]);
+
+        pager.current = pager.matches.len() - 1;
+        pager.next_match();
+        assert_eq!((pager.current, pager.scroll), (0, 0));
+        pager.previous_match();
+        assert_eq!(pager.matches[pager.current].0, 15);
+        assert_eq!(pager.scroll, 12);
+
+        pager.scroll_to_end();
+        assert_eq!(pager.scroll, 19);
+        pager.query = "a.b".to_string();
+        pager.search();
+        assert!(pager.matches.is_empty());
+    }
+}
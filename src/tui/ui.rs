@@ -1,11 +1,18 @@
+use crate::tui::ansi;
 use crate::tui::app::{App, ViewMode};
+use crate::tui::pager::Pager;
+use chrono::Local;
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, Borders, List, ListItem, Paragraph, Sparkline, Wrap,
+    },
 };
+use shelltape_core::models::DURATION_BUCKETS;
+use shelltape_core::models::format_duration;
 
 /// Draw the entire UI
 pub fn draw(f: &mut Frame, app: &App) {
@@ -21,6 +28,11 @@ pub fn draw(f: &mut Frame, app: &App) {
     draw_search_bar(f, app, chunks[0]);
 
     match app.view_mode {
+        _ if app.pager.is_some() => {
+            if let Some(pager) = &app.pager {
+                draw_pager(f, pager, chunks[1]);
+            }
+        }
         ViewMode::List => {
             // Split main area for list and preview
             let main_chunks = Layout::default()
@@ -37,6 +49,9 @@ pub fn draw(f: &mut Frame, app: &App) {
         ViewMode::Detail => {
             draw_detail_view(f, app, chunks[1]);
         }
+        ViewMode::Stats => {
+            draw_stats_view(f, app, chunks[1]);
+        }
     }
 
     draw_status_bar(f, app, chunks[2]);
@@ -44,18 +59,53 @@ pub fn draw(f: &mut Frame, app: &App) {
 
 /// Draw the search bar
 fn draw_search_bar(f: &mut Frame, app: &App, area: Rect) {
-    let style = if app.search_mode {
+    let pager_search = app.pager.as_ref().filter(|p| p.searching);
+    let search_error = app.search_error().filter(|_| app.search_mode);
+    let style = if search_error.is_some() || app.date_error.is_some() {
+        Style::default().fg(Color::Red)
+    } else if app.search_mode
+        || app.tag_input.is_some()
+        || app.date_input.is_some()
+        || app.rerun_input.is_some()
+        || pager_search.is_some()
+    {
         Style::default().fg(Color::Yellow)
     } else {
         Style::default()
     };
 
-    let text = if app.search_mode {
-        format!("Search: {}_", app.search_query)
+    let text = if let Some(pager) = pager_search {
+        format!("Search output: {}_", pager.query)
+    } else if let Some(input) = &app.rerun_input {
+        let cwd = app.get_selected_command().map_or("", |c| c.cwd.as_str());
+        format!("Run in {}: {}_", cwd, input)
+    } else if let Some(input) = &app.date_input {
+        match &app.date_error {
+            Some(error) => format!("Dates: {}_ ({})", input, error),
+            None => format!(
+                "Dates: {}_ (today, yesterday, last 2h, 2024-05-01..2024-05-03; empty for all)",
+                input
+            ),
+        }
+    } else if let Some(input) = &app.tag_input {
+        format!("Tags: {}_ (comma-separated)", input)
+    } else if let Some(error) = search_error {
+        format!("Regex: {}_ (invalid: {})", app.search_query, error)
+    } else if app.search_mode {
+        format!(
+            "{}: {}_ (Tab: {})",
+            app.match_mode.label(),
+            app.search_query,
+            app.match_mode.next().label().to_lowercase()
+        )
     } else if app.search_query.is_empty() {
         "Press / to search".to_string()
     } else {
-        format!("Filter: {} (press / to edit)", app.search_query)
+        format!(
+            "{}: {} (press / to edit)",
+            app.match_mode.label(),
+            app.search_query
+        )
     };
 
     let paragraph = Paragraph::new(text)
@@ -94,7 +144,7 @@ fn draw_command_list(f: &mut Frame, app: &App, area: Rect) {
 
             let style = if display_idx == app.selected {
                 Style::default()
-                    .bg(Color::DarkGray)
+                    .bg(app.highlight_color)
                     .add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
@@ -107,9 +157,16 @@ fn draw_command_list(f: &mut Frame, app: &App, area: Rect) {
     let list = List::new(items).block(
         Block::default()
             .title(format!(
-                " Commands ({}/{}) ",
+                " Commands ({}/{}{}){}{}{} ",
                 app.filtered_commands.len(),
-                app.commands.len()
+                app.commands.len(),
+                if app.fully_loaded() { "" } else { "+" },
+                match app.quick_filter_labels().as_slice() {
+                    [] => String::new(),
+                    labels => format!(" [{}]", labels.join(", ")),
+                },
+                if app.live { " live" } else { "" },
+                if app.loading { " loading…" } else { "" }
             ))
             .borders(Borders::ALL),
     );
@@ -126,32 +183,43 @@ fn draw_preview(f: &mut Frame, app: &App, area: Rect) {
             format!("{:.2}s", cmd.duration_ms as f64 / 1000.0)
         };
 
-        let output_display = if cmd.output.trim().is_empty() {
-            "(no output captured)".to_string()
-        } else if cmd.output.len() > 200 {
-            let preview = cmd.output.chars().take(200).collect::<String>();
-            format!("{}... (truncated)", preview.trim())
-        } else {
-            cmd.output.trim().to_string()
-        };
-
         let session_display = if cmd.session_id.len() >= 8 {
             &cmd.session_id[..8]
         } else {
             &cmd.session_id
         };
 
-        format!(
-            "Command: {}\n\nDirectory: {}\nDuration: {}\nExit Code: {}\nSession: {}\n\nOutput:\n{}",
-            cmd.command, cmd.cwd, duration_display, cmd.exit_code, session_display, output_display
-        )
+        let tags_display = if cmd.tags.is_empty() {
+            String::new()
+        } else {
+            format!("\nTags: {}", cmd.tags.join(", "))
+        };
+
+        let header = format!(
+            "Command: {}\n\nDirectory: {}\nDuration: {}\nExit Code: {}\nSession: {}{}\n\nOutput:",
+            cmd.command, cmd.cwd, duration_display, cmd.exit_code, session_display, tags_display,
+        );
+        let mut lines: Vec<Line> = header.lines().map(|l| Line::from(l.to_string())).collect();
+
+        // Only what fits the pane; the pager and detail view show the rest
+        let room = (area.height as usize).saturating_sub(lines.len() + 2);
+        let output = output_lines(app, &cmd.output);
+        if output.is_empty() {
+            lines.push(Line::from("(no output captured)"));
+        } else if output.len() > room {
+            lines.extend(output.into_iter().take(room.saturating_sub(1)));
+            lines.push(Line::from("... (truncated, press o for the full output)"));
+        } else {
+            lines.extend(output);
+        }
+        lines
     } else {
-        "No command selected".to_string()
+        vec![Line::from("No command selected")]
     };
 
     let paragraph = Paragraph::new(content)
         .block(Block::default().title(" Preview ").borders(Borders::ALL))
-        .wrap(Wrap { trim: true });
+        .wrap(Wrap { trim: false });
 
     f.render_widget(paragraph, area);
 }
@@ -171,7 +239,7 @@ fn draw_detail_view(f: &mut Frame, app: &App, area: Rect) {
             "✗ Failed"
         };
 
-        format!(
+        let header = format!(
             "╔═══════════════════════════════════════════════════════════════╗\n\
              ║ COMMAND DETAILS                                               ║\n\
              ╚═══════════════════════════════════════════════════════════════╝\n\n\
@@ -181,10 +249,11 @@ fn draw_detail_view(f: &mut Frame, app: &App, area: Rect) {
              Session:   {}\n\n\
              Shell:     {}\n\
              Hostname:  {}\n\
-             User:      {}\n\n\
+             User:      {}\n\
+             {}{}\n\
              Directory:\n  {}\n\n\
              Command:\n  {}\n\n\
-             Output:\n{}",
+             Output:",
             cmd.started_at.format("%Y-%m-%d %H:%M:%S"),
             duration_display,
             status,
@@ -193,21 +262,38 @@ fn draw_detail_view(f: &mut Frame, app: &App, area: Rect) {
             cmd.shell,
             cmd.hostname,
             cmd.username,
+            if cmd.tags.is_empty() {
+                String::new()
+            } else {
+                format!("Tags:      {}\n", cmd.tags.join(", "))
+            },
+            match &cmd.note {
+                Some(note) => format!(
+                    "\nNote:\n{}\n",
+                    note.lines()
+                        .map(|line| format!("  {}", line))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ),
+                None => String::new(),
+            },
             cmd.cwd,
             cmd.command,
-            if cmd.output.trim().is_empty() {
-                "  (no output captured)".to_string()
-            } else {
-                cmd.output
-                    .trim()
-                    .lines()
-                    .map(|line| format!("  {}", line.trim_end()))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            }
-        )
+        );
+        let mut lines: Vec<Line> = header.lines().map(|l| Line::from(l.to_string())).collect();
+
+        let output = output_lines(app, &cmd.output);
+        if output.is_empty() {
+            lines.push(Line::from("  (no output captured)"));
+        }
+        for line in output {
+            let mut spans = vec![Span::raw("  ")];
+            spans.extend(line.spans);
+            lines.push(Line::from(spans));
+        }
+        lines
     } else {
-        "No command selected".to_string()
+        vec![Line::from("No command selected")]
     };
 
     let paragraph = Paragraph::new(content)
@@ -221,16 +307,184 @@ fn draw_detail_view(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
+/// Output as styled lines, or with its escape sequences visible in raw view
+///
+/// Blank lines at either end are left out.
+fn output_lines(app: &App, output: &str) -> Vec<Line<'static>> {
+    let output = output.trim_start_matches(['\r', '\n']).trim_end();
+    if app.raw_output {
+        ansi::raw_lines(output)
+    } else {
+        ansi::styled_lines(output)
+    }
+}
+
+/// Draw the output pager, with search matches highlighted
+fn draw_pager(f: &mut Frame, pager: &Pager, area: Rect) {
+    let height = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = pager
+        .lines
+        .iter()
+        .enumerate()
+        .skip(pager.scroll)
+        .take(height)
+        .map(|(idx, text)| {
+            let mut spans = Vec::new();
+            let mut end = 0;
+            for (i, (_, range)) in pager.line_matches(idx) {
+                let style = if i == pager.current {
+                    Style::default().bg(Color::LightRed).fg(Color::Black)
+                } else {
+                    Style::default().bg(Color::Yellow).fg(Color::Black)
+                };
+                spans.push(Span::raw(&text[end..range.start]));
+                spans.push(Span::styled(&text[range.clone()], style));
+                end = range.end;
+            }
+            spans.push(Span::raw(&text[end..]));
+            Line::from(spans)
+        })
+        .collect();
+
+    let position = if pager.lines.is_empty() {
+        "(no output captured)".to_string()
+    } else {
+        format!(
+            "{}/{}",
+            (pager.scroll + height).min(pager.lines.len()),
+            pager.lines.len()
+        )
+    };
+    let matches = match pager.matches.len() {
+        _ if pager.query.is_empty() => String::new(),
+        0 => format!(" · no match for \"{}\"", pager.query),
+        n => format!(" · match {}/{}", pager.current + 1, n),
+    };
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" Output: {} ", pager.title))
+            .title_bottom(format!(" {}{} ", position, matches))
+            .borders(Borders::ALL),
+    );
+
+    f.render_widget(paragraph, area);
+}
+
+/// Draw the statistics dashboard
+fn draw_stats_view(f: &mut Frame, app: &App, area: Rect) {
+    let Some(stats) = &app.stats else {
+        return;
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let mut lines = vec![
+        Line::from(format!("Commands:      {}", stats.total_commands)),
+        Line::from(format!("Sessions:      {}", stats.total_sessions)),
+        Line::from(format!("Success rate:  {:.1}%", stats.success_rate)),
+        Line::from(""),
+        Line::from(format!(
+            "Duration:      p50 {} · p90 {} · p99 {}",
+            format_duration(stats.durations.p50),
+            format_duration(stats.durations.p90),
+            format_duration(stats.durations.p99)
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Most used (p50 / p90)",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    for ((cmd, count), (_, p)) in stats
+        .most_used_commands
+        .iter()
+        .zip(&stats.command_durations)
+    {
+        lines.push(Line::from(format!(
+            "{:4}× {:>7} / {:<7} {}",
+            count,
+            format_duration(p.p50),
+            format_duration(p.p90),
+            cmd
+        )));
+    }
+
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(5)])
+        .split(chunks[0]);
+
+    let overview = Paragraph::new(lines)
+        .block(Block::default().title(" Statistics ").borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+    f.render_widget(overview, left[0]);
+
+    // One bar per day, ending today; days without commands are left blank
+    let days = left[1].width.saturating_sub(2) as usize;
+    let series: Vec<Option<u64>> = stats
+        .success_rate_series(Local::now().date_naive(), days)
+        .into_iter()
+        .map(|rate| rate.map(|r| r.round() as u64))
+        .collect();
+    let sparkline = Sparkline::default()
+        .block(
+            Block::default()
+                .title(format!(" Success Rate by Day (last {} days) ", days))
+                .borders(Borders::ALL),
+        )
+        .data(series)
+        .max(100)
+        .style(Style::default().fg(Color::Green));
+    f.render_widget(sparkline, left[1]);
+
+    let bars: Vec<Bar> = DURATION_BUCKETS
+        .iter()
+        .zip(&stats.duration_histogram)
+        .map(|((_, label), &count)| {
+            Bar::default()
+                .label(Line::from(*label))
+                .value(count as u64)
+                .style(Style::default().fg(Color::Cyan))
+        })
+        .collect();
+    let histogram = BarChart::default()
+        .block(
+            Block::default()
+                .title(" Duration Distribution ")
+                .borders(Borders::ALL),
+        )
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(6)
+        .bar_gap(1);
+    f.render_widget(histogram, chunks[1]);
+}
+
 /// Draw the status bar
 fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
-    let help_text = if app.search_mode {
+    let help_text = if app.tag_input.is_some() {
+        " ESC: cancel | Enter: save tags | Type tags, comma-separated "
+    } else if app.date_input.is_some() {
+        " ESC: cancel | Enter: apply | Type a date range "
+    } else if app.rerun_input.is_some() {
+        " ESC: cancel | Enter: run and record | Edit the command line first if needed "
+    } else if app.search_mode {
+        " ESC: exit search | Enter: apply | Tab: match mode | Ctrl-R: regex | Type to search "
+    } else if app.pager.as_ref().is_some_and(|p| p.searching) {
         " ESC: exit search | Enter: apply | Type to search "
+    } else if app.pager.is_some() {
+        " j/k/↑/↓: scroll | Space/b: page | g/G: top/bottom | /: search | n/N: next/prev match | o/ESC: close | q: quit "
     } else {
         match app.view_mode {
             ViewMode::List => {
-                " j/k/↑/↓: navigate | Space: mark | a: mark all | c: clear marks | /: search | Enter: detail | e: export | q: quit "
+                " j/k/↑/↓: navigate | Space: mark | a: mark all | c: clear marks | /: search | f: failed | .: this dir | s: this session | d: dates | t: tags | y/Y: copy | r: rerun | o: output | R: raw | Ctrl-L: refresh | L: live | Enter: detail | S: stats | e: export | q: quit "
             }
-            ViewMode::Detail => " Enter: back to list | q: quit ",
+            ViewMode::Detail => {
+                " Enter: back to list | t: tags | y/Y: copy command/output | r: rerun | o: output | R: raw | q: quit "
+            }
+            ViewMode::Stats => " S/Enter: back to list | q: quit ",
         }
     };
 
@@ -241,7 +495,10 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
         String::new()
     };
 
-    let status_text = format!("{}{}", help_text, marked_info);
+    let status_text = match &app.message {
+        Some(message) => format!(" {}{} ", message, marked_info),
+        None => format!("{}{}", help_text, marked_info),
+    };
 
     let spans = vec![Span::styled(
         status_text,
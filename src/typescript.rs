@@ -0,0 +1,185 @@
+use crate::recording::{self, Chunk, Recording};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use shelltape_core::storage::Storage;
+use std::path::Path;
+
+/// Import a `script(1)` typescript, splitting it into commands at prompts
+///
+/// The timing file written by `script -t`/`--log-timing` gives commands real
+/// start times, durations and replayable output.
+pub fn import_typescript(
+    storage: &Storage,
+    file: &Path,
+    timing: Option<&Path>,
+    prompt: Option<&str>,
+) -> Result<()> {
+    let content =
+        std::fs::read(file).with_context(|| format!("Failed to read: {}", file.display()))?;
+
+    let (header, data) = split_header(&content);
+    let started = match header.and_then(parse_header_time) {
+        Some(started) => started,
+        None => std::fs::metadata(file)
+            .and_then(|m| m.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now()),
+    };
+    let chunks = match timing {
+        Some(path) => parse_timing(
+            &std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read: {}", path.display()))?,
+        )?,
+        None => Vec::new(),
+    };
+
+    let (data, footer) = split_footer(data);
+    let recording = Recording {
+        started,
+        data: data.to_vec(),
+        chunks,
+        shell: None,
+        command: header.and_then(|h| header_field(h, "COMMAND")),
+        exit_code: footer
+            .and_then(|f| header_field(f, "COMMAND_EXIT_CODE"))
+            .and_then(|code| code.parse().ok()),
+    };
+    recording::import(storage, file, recording, prompt)
+}
+
+/// Split off the `Script started on ...` line, if present
+fn split_header(content: &[u8]) -> (Option<&str>, &[u8]) {
+    if content.starts_with(b"Script started on ")
+        && let Some(newline) = content.iter().position(|b| *b == b'\n')
+    {
+        let header = std::str::from_utf8(&content[..newline]).ok();
+        return (header, &content[newline + 1..]);
+    }
+    (None, content)
+}
+
+/// Split off the `Script done on ...` line, if present
+fn split_footer(data: &[u8]) -> (&[u8], Option<&str>) {
+    const FOOTER: &[u8] = b"\nScript done on ";
+    match data.windows(FOOTER.len()).rposition(|w| w == FOOTER) {
+        Some(i) => (
+            &data[..i + 1],
+            std::str::from_utf8(&data[i + 1..]).ok().map(str::trim_end),
+        ),
+        None => (data, None),
+    }
+}
+
+/// A `NAME="value"` field of a header or footer line
+fn header_field(line: &str, name: &str) -> Option<String> {
+    let start = line
+        .find(&format!("[{}=\"", name))
+        .or_else(|| line.find(&format!(" {}=\"", name)))?;
+    let value = &line[start + name.len() + 3..];
+    Some(value[..value.find('"')?].to_string())
+}
+
+/// The start time in a typescript header
+///
+/// util-linux writes `2024-05-02 10:00:00+02:00`; older versions and the
+/// BSDs write `ctime` style dates in local time.
+fn parse_header_time(header: &str) -> Option<DateTime<Utc>> {
+    let rest = header.strip_prefix("Script started on ")?;
+    let rest = rest.split(" [").next().unwrap_or(rest).trim();
+
+    if let Ok(time) = DateTime::parse_from_str(rest, "%Y-%m-%d %H:%M:%S%:z") {
+        return Some(time.with_timezone(&Utc));
+    }
+    let naive = NaiveDateTime::parse_from_str(rest, "%a %b %e %H:%M:%S %Y").ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Parse a timing file, keeping only output chunks
+///
+/// Classic files have `<delay> <bytes>` lines; advanced ones
+/// (`--logging-format advanced`) prefix each line with its stream type.
+fn parse_timing(timing: &str) -> Result<Vec<Chunk>> {
+    let mut chunks = Vec::new();
+    let mut at = 0.0;
+    let mut offset = 0;
+
+    for (i, line) in timing.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (delay, len) = match fields.as_slice() {
+            [] => continue,
+            [delay, len] => (*delay, *len),
+            ["O", delay, len, ..] => (*delay, *len),
+            // Input, signal and header entries
+            [_, delay, ..] => {
+                at += delay.parse::<f64>().unwrap_or_default();
+                continue;
+            }
+            _ => bail!("Invalid line {} in timing file", i + 1),
+        };
+        let delay: f64 = delay
+            .parse()
+            .with_context(|| format!("Invalid delay on line {} of timing file", i + 1))?;
+        let len: usize = len
+            .parse()
+            .with_context(|| format!("Invalid length on line {} of timing file", i + 1))?;
+
+        at += delay;
+        chunks.push(Chunk { at, offset, len });
+        offset += len;
+    }
+
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TYPESCRIPT: &str =
+        "Script started on 2026-10-17 09:00:00+00:00 [COMMAND=\"htop\" <not executed on terminal>]
+CPU 12%
+Script done on 2026-10-17 09:00:09+00:00 [COMMAND_EXIT_CODE=\"1\"]
+";
+
+    #[test]
+    fn test_header_and_footer() {
+        let (header, data) = split_header(TYPESCRIPT.as_bytes());
+        let header = header.unwrap();
+        assert_eq!(
+            parse_header_time(header),
+            Some(Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap())
+        );
+        assert_eq!(header_field(header, "COMMAND").as_deref(), Some("htop"));
+
+        let (data, footer) = split_footer(data);
+        assert_eq!(data, b"CPU 12%\n");
+        assert_eq!(
+            header_field(footer.unwrap(), "COMMAND_EXIT_CODE").as_deref(),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn test_parse_timing() {
+        let chunks = parse_timing("0.5 10\nI 0.1 1\nO 0.4 5\n").unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                Chunk {
+                    at: 0.5,
+                    offset: 0,
+                    len: 10
+                },
+                Chunk {
+                    at: 1.0,
+                    offset: 10,
+                    len: 5
+                },
+            ]
+        );
+        assert!(parse_timing("soon 5").is_err());
+    }
+}
@@ -21,12 +21,14 @@ pub fn uninstall(shell: Option<Shell>) -> Result<()> {
         .join(".shelltape");
 
     if shelltape_dir.exists() {
-        let hook_file_path = shelltape_dir.join(shell.hook_file());
-        if hook_file_path.exists() {
-            fs::remove_file(&hook_file_path).with_context(|| {
-                format!("Failed to remove hook file: {}", hook_file_path.display())
-            })?;
-            println!("  [OK] Removed hook file from {}", hook_file_path.display());
+        for file in std::iter::once(shell.hook_file()).chain(shell.widget_file()) {
+            let hook_file_path = shelltape_dir.join(file);
+            if hook_file_path.exists() {
+                fs::remove_file(&hook_file_path).with_context(|| {
+                    format!("Failed to remove hook file: {}", hook_file_path.display())
+                })?;
+                println!("  [OK] Removed hook file from {}", hook_file_path.display());
+            }
         }
     }
 
@@ -55,14 +57,14 @@ fn remove_from_rc_file(shell: Shell) -> Result<()> {
     let content = fs::read_to_string(&rc_path)
         .with_context(|| format!("Failed to read: {}", rc_path.display()))?;
 
-    let hook_line = match shell {
-        Shell::Bash | Shell::Zsh => format!("source ~/.shelltape/{}", shell.hook_file()),
-        Shell::Fish => format!("source ~/.shelltape/{}", shell.hook_file()),
-        Shell::Powershell => format!(". ~\\.shelltape\\{}", shell.hook_file()),
-    };
+    // The hook and, if installed, the Ctrl-R widget
+    let hook_lines: Vec<String> = std::iter::once(shell.hook_line())
+        .chain(shell.widget_line())
+        .collect();
+    let is_hook_line = |line: &str| hook_lines.iter().any(|hook| line.contains(hook.as_str()));
 
     // Check if hook line exists
-    if !content.contains(&hook_line) {
+    if !content.lines().any(is_hook_line) {
         println!(
             "  [INFO] Shelltape hooks not found in {}",
             rc_path.display()
@@ -82,7 +84,7 @@ fn remove_from_rc_file(shell: Shell) -> Result<()> {
         if line.contains("# Shelltape") && i + 1 < lines.len() {
             let next_line = lines[i + 1];
             // If the next line is the hook source, skip both
-            if next_line.contains(&hook_line) {
+            if is_hook_line(next_line) {
                 i += 2; // Skip comment and hook line
                 // Also skip any empty lines after
                 while i < lines.len() && lines[i].trim().is_empty() {
@@ -93,7 +95,7 @@ fn remove_from_rc_file(shell: Shell) -> Result<()> {
         }
 
         // Check if this is the hook line without comment
-        if line.contains(&hook_line) {
+        if is_hook_line(line) {
             i += 1;
             continue;
         }
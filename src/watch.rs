@@ -0,0 +1,109 @@
+use anyhow::Result;
+use shelltape_core::models::Command;
+use shelltape_core::storage::Storage;
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+/// How often the history is checked for new records
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Filters applied to followed records
+pub struct WatchFilter {
+    /// Only show commands containing this string (command or directory)
+    pub query: Option<String>,
+    /// Only show commands that failed
+    pub failed: bool,
+    /// Only show commands from this session
+    pub session: Option<String>,
+}
+
+impl WatchFilter {
+    /// Check whether a command passes the filter
+    fn matches(&self, cmd: &Command) -> bool {
+        if self.failed && cmd.exit_code == 0 {
+            return false;
+        }
+
+        if let Some(session) = &self.session
+            && !cmd.session_id.starts_with(session.as_str())
+        {
+            return false;
+        }
+
+        if let Some(query) = &self.query {
+            let query = query.to_lowercase();
+            if !cmd.command.to_lowercase().contains(&query)
+                && !cmd.cwd.to_lowercase().contains(&query)
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Follow the history and print new records as they are written
+pub fn watch_commands(
+    storage: &Storage,
+    filter: WatchFilter,
+    json: bool,
+    backlog: usize,
+) -> Result<()> {
+    // Print the last few matching records before following
+    if backlog > 0 {
+        let mut recent: Vec<Command> = storage
+            .read_all_commands()?
+            .into_iter()
+            .filter(|cmd| filter.matches(cmd))
+            .collect();
+        recent.sort_by_key(|c| c.started_at);
+        let skip = recent.len().saturating_sub(backlog);
+        for cmd in &recent[skip..] {
+            print_command(cmd, json)?;
+        }
+    }
+
+    let mut cursor = storage.end_cursor()?;
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        // Rewrites (clean, prune, sync) start over from the top
+        let (added, next) = storage.commands_since(cursor)?;
+        cursor = next;
+        for (cmd, _) in added.iter().filter(|(cmd, _)| filter.matches(cmd)) {
+            print_command(cmd, json)?;
+        }
+    }
+}
+
+/// Print a single record as one line
+fn print_command(cmd: &Command, json: bool) -> Result<()> {
+    let mut stdout = std::io::stdout();
+
+    if json {
+        writeln!(stdout, "{}", serde_json::to_string(cmd)?)?;
+    } else {
+        let status = if cmd.exit_code == 0 {
+            "✓".to_string()
+        } else {
+            format!("✗ {}", cmd.exit_code)
+        };
+        writeln!(
+            stdout,
+            "{} {:<5} {:>8}ms  {}  ({})",
+            cmd.started_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            status,
+            cmd.duration_ms,
+            cmd.command,
+            cmd.cwd
+        )?;
+    }
+
+    // Flush so piped consumers see records immediately
+    stdout.flush()?;
+    Ok(())
+}
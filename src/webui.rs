@@ -0,0 +1,217 @@
+/// The single-page browser UI of `serve --ui`
+///
+/// It is a static page: it holds no history and is served without the
+/// token. The page reads the token from the `#token=` fragment of the URL
+/// printed at startup (fragments never reach the server) and sends it with
+/// every API request.
+pub const INDEX: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Shelltape</title>
+<style>
+* { box-sizing: border-box; }
+body { font-family: system-ui, sans-serif; margin: 0; color: #1f2328; height: 100vh; display: flex; flex-direction: column; }
+header { display: flex; gap: 1rem; align-items: center; padding: 0.6rem 1rem; border-bottom: 1px solid #d1d9e0; }
+header h1 { font-size: 1.1rem; margin: 0; }
+header input[type=search] { flex: 1; padding: 0.4rem 0.6rem; font-size: 1rem; }
+#status { color: #59636e; font-size: 0.85rem; }
+.panes { flex: 1; display: flex; min-height: 0; }
+nav { width: 16rem; overflow-y: auto; border-right: 1px solid #d1d9e0; }
+#commands { width: 40%; overflow-y: auto; border-right: 1px solid #d1d9e0; }
+#detail { flex: 1; overflow-y: auto; padding: 0 1rem; }
+.item { padding: 0.4rem 0.8rem; border-bottom: 1px solid #eef1f4; cursor: pointer; }
+.item:hover { background: #f6f8fa; }
+.item.selected { background: #ddf4ff; }
+.item .meta { color: #59636e; font-size: 0.8rem; }
+.item code { white-space: nowrap; overflow: hidden; text-overflow: ellipsis; display: block; }
+.failed .status { color: #cf222e; }
+.status { color: #1a7f37; font-weight: 600; }
+.tag { background: #ddf4ff; border-radius: 1rem; padding: 0 0.5rem; margin-right: 0.3rem; }
+pre { background: #f6f8fa; border-radius: 6px; padding: 0.6rem; overflow-x: auto; white-space: pre-wrap; word-break: break-all; }
+dl { display: grid; grid-template-columns: max-content 1fr; gap: 0.2rem 1rem; }
+dt { color: #59636e; }
+dd { margin: 0; }
+blockquote { margin: 0.5rem 0; padding-left: 0.8rem; border-left: 3px solid #d1d9e0; color: #59636e; white-space: pre-wrap; }
+#login { margin: 4rem auto; max-width: 28rem; }
+#login input { width: 100%; padding: 0.4rem; font-size: 1rem; }
+</style>
+</head>
+<body>
+<header>
+<h1>Shelltape</h1>
+<input type="search" id="search" placeholder="Search commands and output">
+<label><input type="checkbox" id="failed"> Failed only</label>
+<span id="status"></span>
+</header>
+<form id="login" hidden>
+<p>Enter the API token printed by <code>shelltape serve</code>:</p>
+<input type="password" id="token" autocomplete="off">
+</form>
+<div class="panes" id="panes" hidden>
+<nav id="sessions"></nav>
+<div id="commands"></div>
+<div id="detail"></div>
+</div>
+<script>
+const LIMIT = 200;
+const $ = id => document.getElementById(id);
+let token = sessionStorage.getItem('shelltape-token') || '';
+let session = null;
+let selected = null;
+let commands = [];
+
+const fragment = new URLSearchParams(location.hash.slice(1));
+if (fragment.get('token')) {
+  token = fragment.get('token');
+  sessionStorage.setItem('shelltape-token', token);
+  history.replaceState(null, '', location.pathname);
+}
+
+function el(tag, props, ...children) {
+  const node = document.createElement(tag);
+  Object.assign(node, props || {});
+  for (const child of children) {
+    if (child != null) node.append(child);
+  }
+  return node;
+}
+
+function stripAnsi(text) {
+  return text.replace(/\x1b\[[0-9;?]*[ -\/]*[@-~]|\x1b\][^\x07\x1b]*(\x07|\x1b\\)|\x1b[@-Z\\-_]/g, '').replace(/\r\n/g, '\n');
+}
+
+function formatDuration(ms) {
+  if (ms < 1000) return ms + 'ms';
+  if (ms < 60000) return (ms / 1000).toFixed(1) + 's';
+  return Math.floor(ms / 60000) + 'm ' + Math.round(ms % 60000 / 1000) + 's';
+}
+
+function formatTime(time) {
+  return new Date(time).toLocaleString();
+}
+
+async function api(path) {
+  const response = await fetch(path, { headers: { Authorization: 'Bearer ' + token } });
+  if (response.status === 401) {
+    sessionStorage.removeItem('shelltape-token');
+    token = '';
+    showLogin();
+    throw new Error('Unauthorized');
+  }
+  const body = await response.json();
+  if (!response.ok) throw new Error(body.error || response.statusText);
+  return body;
+}
+
+function showLogin() {
+  $('login').hidden = false;
+  $('panes').hidden = true;
+  $('token').focus();
+}
+
+async function loadSessions() {
+  const sessions = await api('/api/sessions?limit=100');
+  const nav = $('sessions');
+  nav.replaceChildren();
+  const all = el('div', { className: 'item' + (session ? '' : ' selected') }, el('strong', { textContent: 'All commands' }));
+  all.onclick = () => { session = null; loadSessions(); loadCommands(); };
+  nav.append(all);
+  for (const s of sessions) {
+    const item = el('div', { className: 'item' + (s.id === session ? ' selected' : '') },
+      el('code', { textContent: s.name || s.id.slice(0, 8) }),
+      el('div', { className: 'meta', textContent: formatTime(s.started_at) + ' · ' + s.hostname + ' · ' + s.commands + ' cmds' + (s.failed ? ', ' + s.failed + ' failed' : '') }));
+    item.onclick = () => { session = s.id; loadSessions(); loadCommands(); };
+    nav.append(item);
+  }
+}
+
+async function loadCommands() {
+  const query = $('search').value.trim();
+  $('status').textContent = 'Loading…';
+  try {
+    if (session) {
+      const detail = await api('/api/sessions/' + encodeURIComponent(session));
+      const needle = query.toLowerCase();
+      commands = detail.commands.filter(c => !needle
+        || [c.command, c.cwd, c.output].some(f => f.toLowerCase().includes(needle)));
+    } else if (query) {
+      commands = await api('/api/search?limit=' + LIMIT + '&q=' + encodeURIComponent(query));
+    } else {
+      commands = await api('/api/commands?limit=' + LIMIT);
+    }
+    renderCommands();
+  } catch (e) {
+    $('status').textContent = e.message;
+  }
+}
+
+function renderCommands() {
+  const shown = commands.filter(c => !$('failed').checked || c.exit_code !== 0);
+  const list = $('commands');
+  list.replaceChildren();
+  for (const cmd of shown) {
+    const item = el('div', { className: 'item' + (cmd.exit_code ? ' failed' : '') + (selected === cmd.id ? ' selected' : '') },
+      el('code', { textContent: cmd.command }),
+      el('div', { className: 'meta' },
+        el('span', { className: 'status', textContent: (cmd.exit_code ? '✗ ' : '✓ ') + cmd.exit_code }),
+        ' ' + formatTime(cmd.started_at) + ' · ' + formatDuration(cmd.duration_ms) + ' · ' + cmd.cwd));
+    item.onclick = () => { selected = cmd.id; renderCommands(); renderDetail(cmd); };
+    list.append(item);
+  }
+  $('status').textContent = shown.length + ' shown' + (commands.length >= LIMIT && !session ? ' (newest ' + LIMIT + ')' : '');
+}
+
+function renderDetail(cmd) {
+  const rows = [
+    ['Started', formatTime(cmd.started_at)],
+    ['Duration', formatDuration(cmd.duration_ms)],
+    ['Exit code', String(cmd.exit_code)],
+    ['Directory', cmd.cwd],
+    ['Session', cmd.session_id],
+    ['Host', cmd.username + '@' + cmd.hostname],
+    ['Shell', cmd.shell],
+    ['ID', cmd.id],
+  ];
+  const list = el('dl');
+  for (const [name, value] of rows) {
+    list.append(el('dt', { textContent: name }), el('dd', { textContent: value }));
+  }
+  const tags = (cmd.tags || []).map(t => el('span', { className: 'tag', textContent: t }));
+  const output = cmd.output ? stripAnsi(cmd.output) : '(no output captured)';
+  $('detail').replaceChildren(
+    el('pre', { textContent: cmd.command }),
+    list,
+    tags.length ? el('p', {}, ...tags) : null,
+    cmd.note ? el('blockquote', { textContent: cmd.note }) : null,
+    el('h3', { textContent: 'Output' }),
+    el('pre', { textContent: output }));
+}
+
+async function start() {
+  $('login').hidden = true;
+  $('panes').hidden = false;
+  try {
+    await loadSessions();
+    await loadCommands();
+  } catch (e) {
+    $('status').textContent = e.message;
+  }
+}
+
+let timer;
+$('search').addEventListener('input', () => { clearTimeout(timer); timer = setTimeout(loadCommands, 250); });
+$('failed').addEventListener('change', renderCommands);
+$('login').addEventListener('submit', event => {
+  event.preventDefault();
+  token = $('token').value.trim();
+  sessionStorage.setItem('shelltape-token', token);
+  start();
+});
+
+if (token) start(); else showLogin();
+</script>
+</body>
+</html>
+"#;